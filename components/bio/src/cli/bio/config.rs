@@ -14,6 +14,8 @@ use structopt::StructOpt;
 /// Commands relating to a Service's runtime config
 pub enum ServiceConfig {
     Apply(ServiceConfigApply),
+    History(ServiceConfigHistory),
+    Rollback(ServiceConfigRollback),
     Show(ServiceConfigShow),
 }
 
@@ -34,6 +36,10 @@ pub struct ServiceConfigApply {
     /// Name of a user key to use for encryption
     #[structopt(short = "u", long = "user")]
     user:           Option<String>,
+    /// Render the service's templates with the configuration and show the files and hooks that
+    /// would change, without applying it
+    #[structopt(name = "DRY_RUN", long = "dry-run")]
+    dry_run:        bool,
     #[structopt(flatten)]
     remote_sup:     RemoteSup,
     #[structopt(flatten)]
@@ -46,6 +52,54 @@ pub struct ServiceConfigApply {
 pub struct ServiceConfigShow {
     #[structopt(flatten)]
     pkg_ident:  PkgIdent,
+    /// Display the merged configuration of every layer, along with the layer and source that set
+    /// each key
+    #[structopt(name = "EFFECTIVE", short = "e", long = "effective")]
+    effective:  bool,
+    /// Only display the keys set by a layer of the merged configuration
+    #[structopt(name = "LAYER",
+                long = "layer",
+                possible_values = &["default", "environment", "user", "gossip"])]
+    layer:      Option<String>,
+    /// Only display the keys of the merged configuration that differ from the package's default
+    /// configuration
+    #[structopt(name = "DIFF", long = "diff")]
+    diff:       bool,
     #[structopt(flatten)]
     remote_sup: RemoteSup,
 }
+
+/// Displays the configurations most recently applied to a Service Group
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(name = "history", no_version, rename_all = "screamingsnake")]
+pub struct ServiceConfigHistory {
+    /// Target service group service.group[@organization] (ex: redis.default or
+    /// foo.default@bazcorp)
+    #[structopt()]
+    service_group: ServiceGroup,
+    /// Output will be rendered in json. (Includes each configuration)
+    #[structopt(name = "TO_JSON", short = "j", long = "json")]
+    to_json:       bool,
+    #[structopt(flatten)]
+    remote_sup:    RemoteSup,
+}
+
+/// Re-applies an earlier configuration to a Service Group with a new version number
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(name = "rollback", no_version, rename_all = "screamingsnake")]
+pub struct ServiceConfigRollback {
+    /// Target service group service.group[@organization] (ex: redis.default or
+    /// foo.default@bazcorp)
+    #[structopt()]
+    service_group:  ServiceGroup,
+    /// The version number of the configuration to roll back to, as shown by `bio config history`
+    #[structopt()]
+    version_number: u64,
+    /// Name of a user key to use for encryption
+    #[structopt(short = "u", long = "user")]
+    user:           Option<String>,
+    #[structopt(flatten)]
+    remote_sup:     RemoteSup,
+    #[structopt(flatten)]
+    cache_key_path: CacheKeyPath,
+}
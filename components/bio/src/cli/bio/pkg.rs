@@ -35,6 +35,26 @@ use structopt::{clap::{AppSettings,
                        ArgGroup},
                 StructOpt};
 
+/// Verify installed packages against the checksums recorded when they were built
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(name = "audit",
+            group = ArgGroup::with_name("prefix").required(true),
+            no_version,
+            rename_all = "screamingsnake")]
+pub struct PkgAudit {
+    /// Audit all installed packages
+    #[structopt(name = "ALL", short = "a", long = "all", group = "prefix")]
+    all:            bool,
+    /// A package identifier (ex: core/redis, core/busybox-static/1.42.2)
+    #[structopt(name = "PKG_IDENT", group = "prefix")]
+    pkg_ident:      Option<PackageIdent>,
+    /// Output will be rendered in json
+    #[structopt(name = "TO_JSON", short = "j", long = "json")]
+    to_json:        bool,
+    #[structopt(flatten)]
+    cache_key_path: CacheKeyPath,
+}
+
 /// List all versions of installed packages
 #[derive(ConfigOpt, StructOpt, Debug)]
 #[structopt(name = "list", group = ArgGroup::with_name("prefix").required(true), no_version)]
@@ -74,6 +94,7 @@ pub struct PkgBinds {
 #[allow(clippy::large_enum_variant)]
 /// Commands relating to Biome packages
 pub enum Pkg {
+    Audit(PkgAudit),
     Binds(PkgBinds),
     Binlink(PkgBinlink),
     Build(PkgBuild),
@@ -83,6 +104,7 @@ pub enum Pkg {
     Delete(PkgDelete),
     Demote(PkgDemote),
     Dependencies(PkgDependencies),
+    Diff(PkgDiff),
     Download(PkgDownload),
     Env(PkgEnv),
     Exec(PkgExec),
@@ -90,6 +112,7 @@ pub enum Pkg {
                   any(target_arch = "x86_64", target_arch = "aarch64")),
               all(target_os = "windows", target_arch = "x86_64"),))]
     Export(ExportCommand),
+    Gc(PkgGc),
     Hash(PkgHash),
     Header(PkgHeader),
     Info(PkgInfo),
@@ -98,6 +121,7 @@ pub enum Pkg {
     Path(PkgPath),
     Promote(PkgPromote),
     Provides(PkgProvides),
+    Repo(PkgRepo),
     Search(PkgSearch),
     Sign(PkgSign),
     Uninstall(PkgUninstall),
@@ -194,6 +218,26 @@ pub struct PkgProvides {
     full_paths:    bool,
 }
 
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(no_version)]
+/// Commands relating to static package repositories
+pub enum PkgRepo {
+    Index(PkgRepoIndex),
+}
+
+/// Index the artifacts of a static package repository
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(name = "index", no_version, rename_all = "screamingsnake")]
+pub struct PkgRepoIndex {
+    /// The repository directory, laid out like a `bio pkg download` directory (ex:
+    /// /srv/biome)
+    #[structopt(name = "DIR", validator = dir_exists)]
+    dir:     PathBuf,
+    /// Add newly indexed artifacts to the specified release channel, in addition to unstable
+    #[structopt(name = "CHANNEL", short = "c", long = "channel")]
+    channel: Vec<String>,
+}
+
 /// Search for a package in Builder
 #[derive(ConfigOpt, StructOpt)]
 #[structopt(name = "search", no_version, rename_all = "screamingsnake")]
@@ -231,6 +275,34 @@ pub struct PkgSign {
     cache_key_path: CacheKeyPath,
 }
 
+/// Uninstall packages that are not used by a loaded service, a binlink or the keep list
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(name = "gc", no_version, rename_all = "screamingsnake")]
+pub struct PkgGc {
+    /// Just show what would be removed, don't actually do it
+    #[structopt(name = "DRYRUN", short = "d", long = "dryrun")]
+    dryrun:                bool,
+    /// Identifier of one or more packages that should be kept along with their dependencies.
+    /// (ex: core/redis, core/busybox-static/1.42.2/21120102031201)
+    #[structopt(name = "KEEP", long = "keep")]
+    keep:                  Vec<PackageIdent>,
+    /// Keep packages binlinked into this directory
+    #[structopt(name = "BINLINK_DIR",
+                long = "binlink-dir",
+                default_value = DEFAULT_BINLINK_DIR,
+                env = BINLINK_DIR_ENVVAR)]
+    binlink_dir:           PathBuf,
+    /// Also remove cached artifacts of packages that are not installed
+    #[structopt(name = "ARTIFACTS", long = "artifacts")]
+    artifacts:             bool,
+    /// Also remove source cache entries that have not been used for 30 days
+    #[structopt(name = "SRC", long = "src")]
+    src:                   bool,
+    /// Do not run any uninstall hooks
+    #[structopt(name = "IGNORE_UNINSTALL_HOOK", long = "ignore-uninstall-hook")]
+    ignore_uninstall_hook: bool,
+}
+
 /// Safely uninstall a package and dependencies from the local filesystem
 #[derive(ConfigOpt, StructOpt)]
 #[structopt(name = "uninstall", no_version, rename_all = "screamingsnake")]
@@ -400,6 +472,22 @@ pub struct PkgDependencies {
     reverse:    bool,
 }
 
+/// Compare the metadata, hooks and files of two package releases
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(name = "diff", no_version, rename_all = "screamingsnake")]
+pub struct PkgDiff {
+    /// The package to compare from: an installed package identifier (ex: core/redis/6.2.5) or a
+    /// path to a Biome Artifact (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)
+    #[structopt(name = "FROM")]
+    from:    String,
+    /// The package to compare to: an installed package identifier or a path to a Biome Artifact
+    #[structopt(name = "TO")]
+    to:      String,
+    /// Output will be rendered in json
+    #[structopt(name = "TO_JSON", short = "j", long = "json")]
+    to_json: bool,
+}
+
 /// Download Biome artifacts (including dependencies and keys) from Builder
 #[derive(ConfigOpt, StructOpt)]
 #[structopt(name = "download", no_version, rename_all = "screamingsnake")]
@@ -436,6 +524,18 @@ pub struct PkgDownload {
     /// Ignore packages specified that are not present on the target Builder
     #[structopt(name = "IGNORE_MISSING_SEEDS", long = "ignore-missing-seeds")]
     ignore_missing_seed: bool,
+    /// Also write the downloaded artifacts and their public keys to a signed bundle at this path
+    /// (ex: /home/redis.bundle) for installing without access to Builder
+    #[structopt(name = "BUNDLE", long = "bundle")]
+    bundle:              Option<PathBuf>,
+    /// Origin key used to sign the bundle
+    #[structopt(name = "ORIGIN",
+                long = "origin",
+                requires = "BUNDLE",
+                validator = valid_origin)]
+    origin:              Option<String>,
+    #[structopt(flatten)]
+    cache_key_path:      CacheKeyPath,
 }
 
 /// Executes a command using the 'PATH' context of an installed package
@@ -505,8 +605,13 @@ pub struct PkgInstall {
     channel:               String,
     /// One or more Biome package identifiers (ex: acme/redis) and/or filepaths to a Biome
     /// Artifact (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)
-    #[structopt(required = true)]
+    #[structopt(required_unless = "BUNDLE")]
     pkg_ident_or_artifact: Vec<String>,
+    /// Install from a signed bundle created with `bio pkg download --bundle` without contacting
+    /// Builder. Installs the packages the bundle was created for unless package identifiers are
+    /// given
+    #[structopt(long = "bundle", validator = file_exists, conflicts_with = "OFFLINE")]
+    bundle:                Option<PathBuf>,
     /// Binlink all binaries from installed package(s) into BINLINK_DIR
     #[structopt(short = "b", long = "binlink")]
     binlink:               bool,
@@ -40,21 +40,26 @@ pub struct PlanInit {
 #[derive(ConfigOpt, StructOpt)]
 #[structopt(name = "render", no_version)]
 pub struct PlanRender {
-    /// Path to default.toml
+    /// Path to default.toml, defaults to the one of the package when rendering a package
     #[structopt(name = "DEFAULT_TOML",
                 short = "d",
                 long = "default-toml",
                 default_value = "./default.toml")]
     default_toml:  PathBuf,
-    /// Path to config to render
-    #[structopt(name = "TEMPLATE_PATH", validator = file_exists)]
-    template_path: PathBuf,
+    /// Path to config to render, or a plan directory or installed package identifier to render
+    /// all of its config templates and hooks
+    #[structopt(name = "TEMPLATE_PATH")]
+    template_path: String,
     /// Path to user.toml, defaults to none
     #[structopt(name = "USER_TOML", short = "u", long = "user-toml")]
     user_toml:     Option<PathBuf>,
     /// Path to json file with mock data for template, defaults to none        
     #[structopt(name = "MOCK_DATA", short = "m", long = "mock-data")]
     mock_data:     Option<PathBuf>,
+    /// Path to a TOML or JSON file describing the Supervisor, census groups, binds and gossiped
+    /// config to render with, defaults to none
+    #[structopt(name = "FIXTURE", short = "f", long = "fixture", validator = file_exists)]
+    fixture:       Option<PathBuf>,
     /// Prints config to STDOUT
     #[structopt(name = "PRINT", short = "p", long = "print")]
     print:         bool,
@@ -67,6 +72,9 @@ pub struct PlanRender {
     /// Don't write anything to disk, ignores --render-dir        
     #[structopt(name = "NO_RENDER", short = "n", long = "no-render")]
     no_render:     bool,
+    /// Fail when a template references an undefined variable
+    #[structopt(name = "STRICT", long = "strict")]
+    strict:        bool,
     /// Don't print any helper messages.  When used with `--print` will only print config file
     #[structopt(name = "QUIET", short = "q", long = "no-verbose", long = "quiet")]
     quiet:         bool,
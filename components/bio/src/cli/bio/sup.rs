@@ -31,13 +31,15 @@ use biome_common::{cli::{RING_ENVVAR,
 use biome_core::{env::Config,
                    fs::HAB_CTL_KEYS_CACHE,
                    package::PackageIdent,
+                   schedule::Schedule,
                    util as core_util};
 use rants::{error::Error as RantsError,
             Address as NatsAddress};
 use serde::{Deserialize,
             Serialize};
 use std::{fmt,
-          net::IpAddr,
+          net::{IpAddr,
+                SocketAddr},
           path::PathBuf,
           str::FromStr};
 use structopt::{clap::AppSettings,
@@ -76,6 +78,12 @@ pub enum BioSup {
         #[structopt(flatten)]
         remote_sup: RemoteSup,
     },
+    /// Switch to the next Supervisor update without waiting for the Supervisor's update window
+    #[structopt(no_version, name = "update-now")]
+    UpdateNow {
+        #[structopt(flatten)]
+        remote_sup: RemoteSup,
+    },
     #[cfg(not(target_os = "macos"))]
     #[structopt(flatten)]
     Sup(Sup),
@@ -220,6 +228,13 @@ pub struct SupRun {
     /// The period of time in seconds between Supervisor update checks
     #[structopt(long = "auto-update-period", default_value = "60")]
     pub auto_update_period: DurationProxy,
+    /// The minutes in which the Supervisor updates itself, as a cron-like schedule optionally
+    /// followed by a time zone (ex: "* 2-4 * * SAT,SUN UTC")
+    ///
+    /// A newer Supervisor found outside of the window is switched to once the window opens. Use
+    /// `bio sup update-now` to switch to it without waiting for the window.
+    #[structopt(long = "auto-update-window")]
+    pub auto_update_window: Option<Schedule>,
     /// The period of time in seconds between service update checks
     #[structopt(long = "service-update-period", default_value = "60")]
     pub service_update_period: DurationProxy,
@@ -261,6 +276,13 @@ pub struct SupRun {
     /// Biome artifact (ex: /home/core-redis-3.0.7-21120102031201-x86_64-linux.hart).
     #[structopt()]
     pub pkg_ident_or_artifact: Option<InstallSource>,
+    /// Install packages from a signed bundle created with `bio pkg download --bundle`
+    ///
+    /// The bundled packages are installed without contacting Builder. If PKG_IDENT_OR_ARTIFACT is
+    /// given, it is resolved against the bundle and loaded. Otherwise, the packages the bundle was
+    /// created for are installed, and loaded if there is only one of them.
+    #[structopt(long = "bundle")]
+    pub bundle: Option<PathBuf>,
     /// Verbose output showing file and line/column numbers
     #[structopt(short = "v")]
     pub verbose: bool,
@@ -327,6 +349,22 @@ pub struct SupRun {
     /// automatic package cleanup is performed.
     #[structopt(long = "keep-latest-packages", env = "HAB_KEEP_LATEST_PACKAGES")]
     pub keep_latest_packages: Option<usize>,
+    /// Periodically audit the packages of loaded services
+    ///
+    /// Every PACKAGE_AUDIT_PERIOD seconds, the Supervisor verifies the installed files of each
+    /// loaded service's package and its dependencies against the checksums recorded when they
+    /// were built. The results are served from the HTTP gateway's `/packages/audit` endpoint. If
+    /// this argument is not specified, no periodic audit is performed.
+    #[structopt(long = "package-audit-period")]
+    pub package_audit_period: Option<DurationProxy>,
+    /// The listen address for the DNS responder
+    ///
+    /// When set, the Supervisor answers A, AAAA and SRV queries for names like
+    /// `redis.default.svc.local` (or `leader.redis.default.svc.local`) from the alive members of
+    /// the census, so applications on the host can discover services of the ring. If this
+    /// argument is not specified, no DNS responder is started.
+    #[structopt(long = "listen-dns")]
+    pub listen_dns: Option<SocketAddr>,
     /// Paths to files or directories of service config files to load on startup
     ///
     /// See `bio svc bulkload --help` for details
@@ -13,13 +13,18 @@ use biome_common::{FeatureFlag,
                      FEATURE_FLAGS};
 use biome_core::{os::process::ShutdownTimeout,
                    package::PackageIdent,
+                   schedule::Schedule,
                    service::{BindingMode,
                              HealthCheckInterval,
+                             JobConcurrency,
+                             JobSchedule,
                              ServiceBind,
-                             ServiceGroup},
+                             ServiceGroup,
+                             UpdateBatchSize},
                    ChannelIdent};
 use biome_sup_protocol::{ctl,
-                           types::UpdateCondition};
+                           types::{RolloutAction,
+                                   UpdateCondition}};
 use log::warn;
 use serde::Deserialize;
 use std::{convert::TryFrom,
@@ -46,10 +51,15 @@ pub enum Svc {
     #[structopt(no_version)]
     Update(Update),
     Start(SvcStart),
+    Rollout(SvcRollout),
+    #[structopt(name = "run-job")]
+    RunJob(SvcRunJob),
     #[structopt(aliases = &["stat", "statu"])]
     Status(SvcStatus),
     Stop(SvcStop),
     Unload(SvcUnload),
+    #[structopt(name = "update-now")]
+    UpdateNow(SvcUpdateNow),
 }
 
 #[derive(ConfigOpt, StructOpt)]
@@ -80,6 +90,39 @@ pub struct SvcStart {
     remote_sup: RemoteSup,
 }
 
+/// Pause, resume, approve or abort the canary or batched update of a service group
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(name = "rollout", no_version, rename_all = "screamingsnake")]
+pub struct SvcRollout {
+    /// The action to take on the update in progress
+    #[structopt(name = "ACTION", possible_values = RolloutAction::VARIANTS)]
+    pub action:     RolloutAction,
+    #[structopt(flatten)]
+    pub pkg_ident:  PkgIdent,
+    #[structopt(flatten)]
+    pub remote_sup: RemoteSup,
+}
+
+/// Apply the next package update of a service without waiting for its update window
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(name = "update-now", no_version, rename_all = "screamingsnake")]
+pub struct SvcUpdateNow {
+    #[structopt(flatten)]
+    pub pkg_ident:  PkgIdent,
+    #[structopt(flatten)]
+    pub remote_sup: RemoteSup,
+}
+
+/// Run a job service now, in addition to its schedule
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(name = "run-job", no_version, rename_all = "screamingsnake")]
+pub struct SvcRunJob {
+    #[structopt(flatten)]
+    pub pkg_ident:  PkgIdent,
+    #[structopt(flatten)]
+    pub remote_sup: RemoteSup,
+}
+
 /// Query the status of Biome services
 #[derive(ConfigOpt, StructOpt)]
 #[structopt(name = "status", no_version, rename_all = "screamingsnake")]
@@ -89,6 +132,10 @@ pub struct SvcStatus {
     pub pkg_ident:  Option<PackageIdent>,
     #[structopt(flatten)]
     pub remote_sup: RemoteSup,
+    /// List the newest release in the channel each service tracks and when the channel was
+    /// last checked instead of the state of the services
+    #[structopt(long = "updates")]
+    pub updates:    bool,
 }
 
 /// Stop a running Biome service.
@@ -168,7 +215,7 @@ pub struct SharedLoad {
     #[structopt(long = "strategy",
                 short = "s",
                 default_value = "none",
-                possible_values = &["none", "at-once", "rolling"])]
+                possible_values = &["none", "at-once", "rolling", "canary", "batched", "notify"])]
     #[serde(default)]
     pub strategy:              biome_sup_protocol::types::UpdateStrategy,
     /// The condition dictating when this service should update
@@ -212,6 +259,74 @@ pub struct SharedLoad {
     /// The default value can be set in the packages plan file.
     #[structopt(long = "shutdown-timeout")]
     pub shutdown_timeout:      Option<ShutdownTimeout>,
+    /// The time in seconds to drain the service for before stopping it
+    ///
+    /// While it drains, the service is reported as not ready to its peers and its pre-stop hook
+    /// runs. Draining ends early when the pre-stop hook exits.
+    #[structopt(long = "drain-timeout")]
+    pub drain_timeout:         Option<u32>,
+    /// Fail rendering of configuration and hook templates that reference undefined variables
+    /// instead of rendering them as empty strings
+    ///
+    /// Templates that fail to render block the reconfiguration or restart of the service.
+    #[structopt(long = "strict-templates")]
+    #[serde(default)]
+    pub strict_templates:      bool,
+    /// The time in seconds to watch the health of the service after a package update
+    ///
+    /// If the health check turns critical or the service crash loops within this window, the
+    /// update is rolled back to the previous package and the failed release is not retried.
+    #[structopt(long = "rollback-window")]
+    pub rollback_window:       Option<u32>,
+    /// The number of members, including the update leader, that a canary update updates before
+    /// waiting for approval (default: 1)
+    ///
+    /// The canaries are approved with `bio svc rollout approve` or automatically once they have
+    /// stayed healthy for the rollback window.
+    #[structopt(long = "canary-count")]
+    pub canary_count:          Option<u32>,
+    /// The number of members, or percentage of the service group, that a canary or batched
+    /// update updates at a time (ex: 3, 25%) (default: 1)
+    #[structopt(long = "update-batch-size")]
+    pub update_batch_size:     Option<UpdateBatchSize>,
+    /// The maximum number of members that may be unavailable at once while a canary or batched
+    /// update is in progress (default: the update batch size)
+    #[structopt(long = "max-unavailable")]
+    pub max_unavailable:       Option<u32>,
+    /// The minutes in which package updates are applied, as a cron-like schedule optionally
+    /// followed by a time zone (ex: "* 2-4 * * SAT,SUN UTC")
+    ///
+    /// Updates found outside of the window are deferred until it opens. Use `bio svc update-now`
+    /// to apply an update without waiting for the window.
+    #[structopt(long = "update-window")]
+    pub update_window:         Option<Schedule>,
+    /// Run the post-update hook only on the leader of the service group rather than on every
+    /// member that is updated
+    #[structopt(long = "post-update-on-leader")]
+    #[serde(default)]
+    pub post_update_on_leader: bool,
+    /// Run the service as a job instead of keeping it running: "once" runs it to completion
+    /// after it starts, while a cron-like schedule optionally followed by a time zone
+    /// (ex: "0 3 * * * UTC") runs it in every matching minute
+    ///
+    /// Use `bio svc run-job` to run a job on demand.
+    #[structopt(long = "job")]
+    pub job:                   Option<JobSchedule>,
+    /// The time in seconds after which a job run is stopped and recorded as timed out
+    #[structopt(long = "job-timeout")]
+    pub job_timeout:           Option<u32>,
+    /// What a job does when a run is due while the previous run is still going
+    ///
+    /// forbid: skips the new run. replace: stops the previous run and starts the new one.
+    #[structopt(long = "job-concurrency",
+                default_value = "forbid",
+                possible_values = &["forbid", "replace"])]
+    #[serde(default)]
+    pub job_concurrency:       JobConcurrency,
+    /// Run the job only on the leader of the service group rather than on every member
+    #[structopt(long = "job-on-leader")]
+    #[serde(default)]
+    pub job_on_leader:         bool,
     #[cfg(target_os = "windows")]
     /// Password of the service user
     #[structopt(long = "password")]
@@ -381,7 +496,19 @@ pub fn shared_load_cli_to_ctl(ident: PackageIdent,
                  health_check_interval:
                      Some(HealthCheckInterval { seconds: shared_load.health_check_interval, }),
                  shutdown_timeout: shared_load.shutdown_timeout.map(u32::from),
-                 update_condition: Some(shared_load.update_condition as i32) })
+                 update_condition: Some(shared_load.update_condition as i32),
+                 strict_templates: Some(shared_load.strict_templates),
+                 rollback_window: shared_load.rollback_window,
+                 canary_count: shared_load.canary_count,
+                 update_batch_size: shared_load.update_batch_size.map(|b| b.to_string()),
+                 max_unavailable: shared_load.max_unavailable,
+                 update_window: shared_load.update_window.map(|w| w.to_string()),
+                 post_update_on_leader: Some(shared_load.post_update_on_leader),
+                 drain_timeout: shared_load.drain_timeout,
+                 job: shared_load.job.map(|j| j.to_string()),
+                 job_timeout: shared_load.job_timeout,
+                 job_concurrency: Some(shared_load.job_concurrency.to_string()),
+                 job_on_leader: Some(shared_load.job_on_leader) })
 }
 
 impl TryFrom<Load> for biome_sup_protocol::ctl::SvcLoad {
@@ -436,7 +563,7 @@ pub struct Update {
     /// The update strategy
     #[structopt(long = "strategy",
                 short = "s",
-                possible_values = &["none", "at-once", "rolling"])]
+                possible_values = &["none", "at-once", "rolling", "canary", "batched", "notify"])]
     pub strategy: Option<biome_sup_protocol::types::UpdateStrategy>,
 
     /// The condition dictating when this service should update
@@ -478,6 +605,76 @@ pub struct Update {
     #[structopt(long = "shutdown-timeout")]
     pub shutdown_timeout: Option<ShutdownTimeout>,
 
+    /// The time in seconds to drain the service for before stopping it; 0 turns off draining
+    #[structopt(long = "drain-timeout")]
+    pub drain_timeout: Option<u32>,
+
+    /// Whether rendering of configuration and hook templates that reference undefined
+    /// variables fails
+    #[structopt(long = "strict-templates")]
+    pub strict_templates: Option<bool>,
+
+    /// The time in seconds to watch the health of the service after a package update before
+    /// keeping it; 0 turns off rolling back failed updates
+    #[structopt(long = "rollback-window")]
+    pub rollback_window: Option<u32>,
+
+    /// The number of members that a canary update updates before waiting for approval
+    #[structopt(long = "canary-count")]
+    pub canary_count: Option<u32>,
+
+    /// The number of members, or percentage of the service group, that a canary or batched
+    /// update updates at a time (ex: 3, 25%)
+    #[structopt(long = "update-batch-size")]
+    pub update_batch_size: Option<UpdateBatchSize>,
+
+    /// The maximum number of members that may be unavailable at once while a canary or batched
+    /// update is in progress
+    #[structopt(long = "max-unavailable")]
+    pub max_unavailable: Option<u32>,
+
+    /// The minutes in which package updates are applied, as a cron-like schedule optionally
+    /// followed by a time zone (ex: "* 2-4 * * SAT,SUN UTC"); "* * * * *" applies updates as
+    /// soon as they are found
+    #[structopt(long = "update-window")]
+    pub update_window: Option<Schedule>,
+
+    /// Hold the service at the release it is currently running; available updates are reported
+    /// but not applied until the hold is released
+    #[structopt(long = "hold", conflicts_with = "RELEASE")]
+    pub hold: bool,
+
+    /// Release a hold placed with --hold so that updates are applied again
+    #[structopt(long = "release")]
+    pub release: bool,
+
+    /// Whether the post-update hook only runs on the leader of the service group
+    #[structopt(long = "post-update-on-leader")]
+    pub post_update_on_leader: Option<bool>,
+
+    /// Run the service as a job, "once" or on a cron-like schedule optionally followed by a
+    /// time zone (ex: "0 3 * * * UTC"), instead of keeping it running
+    #[structopt(long = "job", conflicts_with = "NO_JOB")]
+    pub job: Option<JobSchedule>,
+
+    /// Keep the service running again instead of running it as a job
+    #[structopt(long = "no-job")]
+    pub no_job: bool,
+
+    /// The time in seconds after which a job run is stopped; 0 lets runs take as long as they
+    /// need
+    #[structopt(long = "job-timeout")]
+    pub job_timeout: Option<u32>,
+
+    /// What a job does when a run is due while the previous run is still going
+    #[structopt(long = "job-concurrency",
+                possible_values = &["forbid", "replace"])]
+    pub job_concurrency: Option<JobConcurrency>,
+
+    /// Whether the job only runs on the leader of the service group
+    #[structopt(long = "job-on-leader")]
+    pub job_on_leader: Option<bool>,
+
     /// Password of the service user
     #[cfg(target_os = "windows")]
     #[structopt(long = "password")]
@@ -501,6 +698,30 @@ impl TryFrom<Update> for ctl::SvcUpdate {
                                    update_strategy: u.strategy.map(|v| v as i32),
                                    update_condition: u.update_condition.map(|v| v as i32),
                                    shutdown_timeout: u.shutdown_timeout.map(Into::into),
+                                   strict_templates: u.strict_templates,
+                                   rollback_window: u.rollback_window,
+                                   canary_count: u.canary_count,
+                                   update_batch_size: u.update_batch_size.map(|b| b.to_string()),
+                                   max_unavailable: u.max_unavailable,
+                                   update_window: u.update_window.map(|w| w.to_string()),
+                                   hold: if u.hold {
+                                       Some(true)
+                                   } else if u.release {
+                                       Some(false)
+                                   } else {
+                                       None
+                                   },
+                                   post_update_on_leader: u.post_update_on_leader,
+                                   drain_timeout: u.drain_timeout,
+                                   // An empty schedule turns off job mode
+                                   job: if u.no_job {
+                                       Some(String::new())
+                                   } else {
+                                       u.job.map(|j| j.to_string())
+                                   },
+                                   job_timeout: u.job_timeout,
+                                   job_concurrency: u.job_concurrency.map(|c| c.to_string()),
+                                   job_on_leader: u.job_on_leader,
                                    #[cfg(windows)]
                                    svc_encrypted_password: u.password,
                                    #[cfg(not(windows))]
@@ -520,7 +741,20 @@ impl TryFrom<Update> for ctl::SvcUpdate {
                                 update_strategy: None,
                                 health_check_interval: None,
                                 shutdown_timeout: None,
-                                update_condition: None, } = &msg
+                                update_condition: None,
+                                strict_templates: None,
+                                rollback_window: None,
+                                canary_count: None,
+                                update_batch_size: None,
+                                max_unavailable: None,
+                                update_window: None,
+                                hold: None,
+                                post_update_on_leader: None,
+                                drain_timeout: None,
+                                job: None,
+                                job_timeout: None,
+                                job_concurrency: None,
+                                job_on_leader: None, } = &msg
         {
             Err(Error::ArgumentError("No fields specified for update".to_string()))
         } else {
@@ -49,7 +49,8 @@ pub struct AuthToken {
 #[configopt(derive(Serialize))]
 #[structopt(no_version)]
 pub struct BldrUrl {
-    /// Specify an alternate Builder endpoint. If not specified, the value will be
+    /// Specify an alternate Builder endpoint, or a static package repository (file:// or
+    /// static+https:// URL). If not specified, the value will be
     /// taken from the HAB_BLDR_URL environment variable if defined. (default: https://bldr.habitat.sh)
     // TODO (DM): This should probably use `env` and `default_value`
     #[structopt(name = "BLDR_URL", short = "u", long = "url")]
@@ -1,3 +1,4 @@
+pub mod audit;
 pub mod binlink;
 pub mod build;
 pub mod bulkupload;
@@ -5,10 +6,12 @@ pub mod channels;
 pub mod delete;
 pub mod demote;
 pub mod dependencies;
+pub mod diff;
 pub mod download;
 pub mod env;
 pub mod exec;
 pub mod export;
+pub mod gc;
 pub mod hash;
 pub mod header;
 pub mod info;
@@ -16,6 +19,7 @@ pub mod list;
 pub mod path;
 pub mod promote;
 pub mod provides;
+pub mod repo;
 pub mod search;
 pub mod sign;
 pub mod uninstall;
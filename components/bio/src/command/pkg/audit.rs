@@ -0,0 +1,70 @@
+use crate::{command::pkg::list::{self,
+                                 ListingType},
+            common::ui::{Status,
+                         UIWriter,
+                         UI},
+            error::{Error,
+                    Result},
+            hcore::{crypto::keys::KeyCache,
+                    package::{PackageAudit,
+                              PackageInstall}}};
+use std::path::Path;
+
+/// Audit the installed packages in `listing` against the checksums recorded in their `FILES`
+/// metadata, failing if any of them was modified or could not be audited.
+pub fn start(ui: &mut UI,
+             listing: &ListingType,
+             fs_root_path: &Path,
+             key_cache: &KeyCache,
+             to_json: bool)
+             -> Result<()> {
+    let mut audits = Vec::new();
+    let mut failures = 0;
+    for ident in list::package_list(listing)? {
+        let audit = PackageInstall::load(&ident, Some(fs_root_path)).and_then(|install| {
+                                                                        PackageAudit::run(&install,
+                                                                                          key_cache)
+                                                                    });
+        match audit {
+            Ok(audit) => {
+                if !audit.is_clean() {
+                    failures += 1;
+                }
+                if !to_json {
+                    report(ui, &audit)?;
+                }
+                audits.push(audit);
+            }
+            Err(e) => {
+                failures += 1;
+                ui.warn(format!("Unable to audit {}: {}", ident, e))?;
+            }
+        }
+    }
+
+    if to_json {
+        println!("{}", serde_json::to_string_pretty(&audits)?);
+    }
+    if failures > 0 {
+        return Err(Error::PackageAuditFailed(failures));
+    }
+    Ok(())
+}
+
+fn report(ui: &mut UI, audit: &PackageAudit) -> Result<()> {
+    if audit.is_clean() {
+        ui.status(Status::Verified,
+                  format!("{} signed with {}", audit.ident, audit.signer))?;
+        return Ok(());
+    }
+    ui.warn(format!("{} does not match its recorded checksums", audit.ident))?;
+    for (kind, paths) in &[("modified", &audit.modified),
+                           ("missing", &audit.missing),
+                           ("extra", &audit.extra)]
+    {
+        for path in paths.iter() {
+            ui.info(format!("  {:<8} {}", kind, path.display()))?;
+        }
+    }
+    Ok(())
+}
@@ -9,8 +9,10 @@ use crate::{common::ui::{Status,
 use std::{collections::BTreeMap,
           env,
           fs,
+          io,
           path::{Path,
-                 PathBuf}};
+                 PathBuf},
+          str::FromStr};
 #[cfg(windows)]
 use std::{fmt::Write,
           fs::File,
@@ -232,6 +234,45 @@ pub fn binlink_all_in_pkg(ui: &mut UI,
     Ok(())
 }
 
+/// Returns the installed packages that binaries in `dest_path` are binlinked from.
+pub fn binlinked_packages(dest_path: &Path, fs_root_path: &Path) -> Result<Vec<PackageIdent>> {
+    let dst_path = fs_root_path.join(dest_path.strip_prefix("/")?);
+    let entries = match fs::read_dir(&dst_path) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    // Binlink targets are absolute paths below the package root, which only includes
+    // `fs_root_path` on Windows
+    let pkg_roots = [hfs::pkg_root_path(Some(fs_root_path)),
+                     hfs::pkg_root_path(None::<&Path>)];
+    let mut idents = Vec::new();
+    for entry in entries {
+        let binlink = match Binlink::from_file(&entry?.path()) {
+            Ok(binlink) => binlink,
+            Err(_) => continue,
+        };
+        let relative = match pkg_roots.iter()
+                                      .find_map(|r| binlink.target.strip_prefix(r).ok())
+        {
+            Some(relative) => relative,
+            None => continue,
+        };
+        let parts = relative.iter()
+                            .take(4)
+                            .map(|p| p.to_string_lossy())
+                            .collect::<Vec<_>>();
+        if parts.len() == 4 {
+            if let Ok(ident) = PackageIdent::from_str(&parts.join("/")) {
+                idents.push(ident);
+            }
+        }
+    }
+    idents.sort_unstable_by(|a, b| a.by_parts_cmp(b));
+    idents.dedup();
+    Ok(idents)
+}
+
 fn is_dest_on_path(dest_dir: &Path) -> bool {
     if let Some(val) = env::var_os("PATH") {
         env::split_paths(&val).any(|p| p == dest_dir)
@@ -443,6 +484,27 @@ mod test {
                                                                              .target);
     }
 
+    #[test]
+    fn binlinked_packages_finds_source_packages() {
+        let rootfs = TempDir::new().unwrap();
+        let mut tools = HashMap::new();
+        tools.insert("bin", vec!["magicate.exe"]);
+        let ident = fake_bin_pkg_install("acme/cooltools", tools, rootfs.path());
+        let mut tools = HashMap::new();
+        tools.insert("bin", vec!["securitize.exe"]);
+        fake_bin_pkg_install("acme/securetools", tools, rootfs.path());
+        let dst_path = Path::new("/opt/bin");
+
+        assert!(binlinked_packages(dst_path, rootfs.path()).unwrap()
+                                                           .is_empty());
+
+        let mut ui = UI::with_sinks();
+        start(&mut ui, &ident, "magicate.exe", dst_path, rootfs.path(), true).unwrap();
+
+        assert_eq!(vec![ident],
+                   binlinked_packages(dst_path, rootfs.path()).unwrap());
+    }
+
     fn fake_bin_pkg_install<P>(ident: &str,
                                binaries: HashMap<&str, Vec<&str>>,
                                rootfs: P)
@@ -0,0 +1,393 @@
+use crate::{error::{Error,
+                    Result},
+            hcore::{crypto::Blake2bHash,
+                    package::{metadata::MetaFile,
+                              PackageArchive,
+                              PackageIdent,
+                              PackageInstall},
+                    util::text_diff}};
+use serde::Serialize;
+use std::{collections::{BTreeMap,
+                        BTreeSet},
+          fmt,
+          fs,
+          path::{Path,
+                 PathBuf},
+          str::FromStr};
+use walkdir::WalkDir;
+
+/// The metadata files whose contents are compared between two packages.
+const METAFILES: &[MetaFile] = &[MetaFile::Deps,
+                                 MetaFile::TDeps,
+                                 MetaFile::Exports,
+                                 MetaFile::Binds,
+                                 MetaFile::BindsOptional,
+                                 MetaFile::Config,
+                                 MetaFile::RuntimeEnvironment];
+
+const HOOKS_DIR: &str = "hooks";
+
+/// Either side of a comparison: an installed package or a Biome Artifact on disk.
+pub enum PackageSource {
+    Ident(PackageIdent),
+    Archive(PathBuf),
+}
+
+impl FromStr for PackageSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path = Path::new(s);
+        if path.is_file() {
+            Ok(PackageSource::Archive(path.to_path_buf()))
+        } else {
+            Ok(PackageSource::Ident(s.parse()?))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FileSummary {
+    pub size:     u64,
+    pub checksum: String,
+}
+
+/// Everything about a package release that takes part in the comparison.
+struct PackageContents {
+    ident: PackageIdent,
+    files: BTreeMap<PathBuf, FileSummary>,
+    /// The contents of metadata files and hooks
+    texts: BTreeMap<PathBuf, String>,
+}
+
+impl PackageContents {
+    fn load(source: &PackageSource, fs_root_path: &Path) -> Result<Self> {
+        match source {
+            PackageSource::Ident(ident) => Self::from_install(ident, fs_root_path),
+            PackageSource::Archive(path) => Self::from_archive(path),
+        }
+    }
+
+    fn from_archive(path: &Path) -> Result<Self> {
+        let mut archive = PackageArchive::new(path)?;
+        let ident = archive.ident()?;
+        let mut files = BTreeMap::new();
+        let mut texts = BTreeMap::new();
+        for file in archive.files(is_compared_text)? {
+            if let Some(contents) = file.contents {
+                texts.insert(file.path.clone(),
+                             String::from_utf8_lossy(&contents).into_owned());
+            }
+            files.insert(file.path,
+                         FileSummary { size:     file.size,
+                                       checksum: file.checksum.to_string(), });
+        }
+        Ok(PackageContents { ident,
+                             files,
+                             texts })
+    }
+
+    fn from_install(ident: &PackageIdent, fs_root_path: &Path) -> Result<Self> {
+        let pkg_install = PackageInstall::load(ident, Some(fs_root_path))?;
+        let installed_path = pkg_install.installed_path();
+        let mut files = BTreeMap::new();
+        let mut texts = BTreeMap::new();
+        for entry in WalkDir::new(installed_path) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path().strip_prefix(installed_path)?.to_path_buf();
+            if is_compared_text(&path) {
+                let contents = fs::read(entry.path())?;
+                texts.insert(path.clone(),
+                             String::from_utf8_lossy(&contents).into_owned());
+            }
+            files.insert(path,
+                    FileSummary { size:     entry.metadata()?.len(),
+                                  checksum: Blake2bHash::from_file(entry.path())?.to_string(), });
+        }
+        Ok(PackageContents { ident: pkg_install.ident().clone(),
+                             files,
+                             texts })
+    }
+}
+
+fn is_compared_text(path: &Path) -> bool {
+    path.starts_with(HOOKS_DIR) || METAFILES.iter().any(|m| path == Path::new(&m.to_string()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    fn marker(self) -> char {
+        match self {
+            ChangeKind::Added => 'A',
+            ChangeKind::Removed => 'D',
+            ChangeKind::Modified => 'M',
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TextChange {
+    pub name: String,
+    pub kind: ChangeKind,
+    pub diff: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub from: Option<FileSummary>,
+    pub to:   Option<FileSummary>,
+}
+
+/// The differences between two package releases.
+#[derive(Debug, Serialize)]
+pub struct PackageDiff {
+    pub from:     String,
+    pub to:       String,
+    pub metadata: Vec<TextChange>,
+    pub hooks:    Vec<TextChange>,
+    pub files:    Vec<FileChange>,
+}
+
+impl PackageDiff {
+    fn new(from: &PackageContents, to: &PackageContents) -> Self {
+        let mut metadata = Vec::new();
+        let mut hooks = Vec::new();
+        let names = from.texts
+                        .keys()
+                        .chain(to.texts.keys())
+                        .collect::<BTreeSet<_>>();
+        for name in names {
+            let old = from.texts.get(name);
+            let new = to.texts.get(name);
+            let kind = match (old, new) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Removed,
+                (Some(old), Some(new)) if old != new => ChangeKind::Modified,
+                _ => continue,
+            };
+            let label = name.to_string_lossy();
+            let diff =
+                text_diff::unified_diff(old.map_or("", String::as_str),
+                                        new.map_or("", String::as_str),
+                                        &format!("{}/{}", from.ident, label),
+                                        &format!("{}/{}", to.ident, label)).unwrap_or_default();
+            let change = TextChange { name: label.into_owned(),
+                                      kind,
+                                      diff };
+            if name.starts_with(HOOKS_DIR) {
+                hooks.push(change);
+            } else {
+                metadata.push(change);
+            }
+        }
+
+        let mut files = Vec::new();
+        let paths = from.files
+                        .keys()
+                        .chain(to.files.keys())
+                        .collect::<BTreeSet<_>>();
+        for path in paths {
+            let old = from.files.get(path);
+            let new = to.files.get(path);
+            let kind = match (old, new) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Removed,
+                (Some(old), Some(new)) if old != new => ChangeKind::Modified,
+                _ => continue,
+            };
+            files.push(FileChange { path: path.to_string_lossy().into_owned(),
+                                    kind,
+                                    from: old.cloned(),
+                                    to: new.cloned() });
+        }
+
+        PackageDiff { from: from.ident.to_string(),
+                      to: to.ident.to_string(),
+                      metadata,
+                      hooks,
+                      files }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.metadata.is_empty() && self.hooks.is_empty() && self.files.is_empty()
+    }
+}
+
+impl fmt::Display for PackageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Comparing {} to {}", self.from, self.to)?;
+        if self.is_empty() {
+            return writeln!(f, "No differences found");
+        }
+        for (title, changes) in &[("Metadata", &self.metadata), ("Hooks", &self.hooks)] {
+            if changes.is_empty() {
+                continue;
+            }
+            writeln!(f, "\n{}:", title)?;
+            for change in changes.iter() {
+                writeln!(f, "  {} {}", change.kind.marker(), change.name)?;
+                for line in change.diff.lines() {
+                    writeln!(f, "      {}", line)?;
+                }
+            }
+        }
+        if !self.files.is_empty() {
+            writeln!(f, "\nFiles:")?;
+            for change in &self.files {
+                let sizes = match (&change.from, &change.to) {
+                    (Some(from), Some(to)) => format!("{} -> {} bytes", from.size, to.size),
+                    (Some(from), None) => format!("{} bytes", from.size),
+                    (None, Some(to)) => format!("{} bytes", to.size),
+                    (None, None) => String::new(),
+                };
+                writeln!(f, "  {} {} ({})", change.kind.marker(), change.path, sizes)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare the metadata, hooks and files of two package releases, each of which may be an
+/// installed package or a Biome Artifact.
+pub fn start(from: &PackageSource,
+             to: &PackageSource,
+             fs_root_path: &Path,
+             to_json: bool)
+             -> Result<()> {
+    let from = PackageContents::load(from, fs_root_path)?;
+    let to = PackageContents::load(to, fs_root_path)?;
+    let diff = PackageDiff::new(&from, &to);
+
+    if to_json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
+    print!("{}", diff);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn contents(ident: &str, files: &[(&str, &str)]) -> PackageContents {
+        let mut summaries = BTreeMap::new();
+        let mut texts = BTreeMap::new();
+        for (path, text) in files {
+            let path = PathBuf::from(path);
+            if is_compared_text(&path) {
+                texts.insert(path.clone(), text.to_string());
+            }
+            summaries.insert(path,
+                             FileSummary { size:     text.len() as u64,
+                                           checksum: format!("{:x}", text.len()), });
+        }
+        PackageContents { ident: ident.parse().unwrap(),
+                          files: summaries,
+                          texts }
+    }
+
+    #[test]
+    fn only_metadata_and_hooks_are_compared_as_text() {
+        assert!(is_compared_text(Path::new("DEPS")));
+        assert!(is_compared_text(Path::new("RUNTIME_ENVIRONMENT")));
+        assert!(is_compared_text(Path::new("hooks/run")));
+        assert!(!is_compared_text(Path::new("MANIFEST")));
+        assert!(!is_compared_text(Path::new("bin/hooks")));
+    }
+
+    #[test]
+    fn identical_packages_have_no_differences() {
+        let from = contents("core/redis/4.0.14/20200101000000",
+                            &[("DEPS", "core/glibc\n"), ("bin/redis", "binary")]);
+        let to = contents("core/redis/4.0.14/20200202000000",
+                          &[("DEPS", "core/glibc\n"), ("bin/redis", "binary")]);
+        let diff = PackageDiff::new(&from, &to);
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(),
+                   "Comparing core/redis/4.0.14/20200101000000 to \
+                    core/redis/4.0.14/20200202000000\nNo differences found\n");
+    }
+
+    #[test]
+    fn changes_are_sorted_into_metadata_hooks_and_files() {
+        let from = contents("core/redis/4.0.14/20200101000000",
+                            &[("DEPS", "core/glibc\n"),
+                              ("hooks/init", "echo init\n"),
+                              ("bin/redis", "binary"),
+                              ("bin/redis-cli", "cli")]);
+        let to = contents("core/redis/5.0.7/20200202000000",
+                          &[("DEPS", "core/glibc\ncore/openssl\n"),
+                            ("hooks/run", "redis-server\n"),
+                            ("bin/redis", "new binary"),
+                            ("bin/redis-cli", "cli")]);
+        let diff = PackageDiff::new(&from, &to);
+
+        assert_eq!(diff.metadata.len(), 1);
+        assert_eq!(diff.metadata[0].name, "DEPS");
+        assert_eq!(diff.metadata[0].kind, ChangeKind::Modified);
+        assert_eq!(diff.hooks
+                       .iter()
+                       .map(|c| (c.name.as_str(), c.kind))
+                       .collect::<Vec<_>>(),
+                   vec![("hooks/init", ChangeKind::Removed),
+                        ("hooks/run", ChangeKind::Added)]);
+        assert_eq!(diff.files
+                       .iter()
+                       .map(|c| (c.path.as_str(), c.kind))
+                       .collect::<Vec<_>>(),
+                   vec![("DEPS", ChangeKind::Modified),
+                        ("bin/redis", ChangeKind::Modified),
+                        ("hooks/init", ChangeKind::Removed),
+                        ("hooks/run", ChangeKind::Added)]);
+        assert_eq!(diff.files[1].from.as_ref().unwrap().size, 6);
+        assert_eq!(diff.files[1].to.as_ref().unwrap().size, 10);
+    }
+
+    #[test]
+    fn differences_are_printed_by_section() {
+        let from = contents("core/redis/4.0.14/20200101000000",
+                            &[("DEPS", "core/glibc\n"), ("bin/redis", "binary")]);
+        let to = contents("core/redis/5.0.7/20200202000000",
+                          &[("DEPS", "core/openssl\n"),
+                            ("hooks/run", "redis-server\n"),
+                            ("bin/redis", "binary")]);
+        let expected = "Comparing core/redis/4.0.14/20200101000000 to \
+                        core/redis/5.0.7/20200202000000
+
+Metadata:
+  M DEPS
+      --- core/redis/4.0.14/20200101000000/DEPS
+      +++ core/redis/5.0.7/20200202000000/DEPS
+      @@ -1,1 +1,1 @@
+      -core/glibc
+      +core/openssl
+
+Hooks:
+  A hooks/run
+      --- core/redis/4.0.14/20200101000000/hooks/run
+      +++ core/redis/5.0.7/20200202000000/hooks/run
+      @@ -0,0 +1,1 @@
+      +redis-server
+
+Files:
+  M DEPS (11 -> 13 bytes)
+  A hooks/run (13 bytes)
+";
+        assert_eq!(PackageDiff::new(&from, &to).to_string(), expected);
+    }
+}
@@ -26,8 +26,10 @@
 //! * Download the artifact
 //! * Verify it is un-altered
 //! * Fetch the signing keys
+//! * Optionally, write everything to a signed bundle for installing without access to Builder
 
-use std::{collections::{HashMap,
+use std::{collections::{BTreeSet,
+                        HashMap,
                         HashSet},
           fs::DirBuilder,
           path::{Path,
@@ -36,20 +38,22 @@ use std::{collections::{HashMap,
 use crate::{api_client::{self,
                          retry_builder_api,
                          APIFailure,
-                         BuilderAPIClient,
-                         Client,
                          Error::{APIClientError,
                                  APIError},
                          Package,
+                         PackageSource,
                          API_RETRY_COUNT,
                          API_RETRY_DELAY},
             common::error::Error as CommonError,
             hcore::{crypto::{artifact,
-                             keys::{KeyCache,
-                                    NamedRevision}},
+                             keys::{Key,
+                                    KeyCache,
+                                    NamedRevision,
+                                    SecretOriginSigningKey}},
                     fs::cache_root_path,
                     package::{Identifiable,
                               PackageArchive,
+                              PackageBundle,
                               PackageIdent,
                               PackageTarget},
                     ChannelIdent,
@@ -104,6 +108,10 @@ pub struct PackageSet {
 /// dependencies will be downloaded on the system in the
 /// <download_path>/artifacts directory. Any signing keys will also be
 /// downloaded and put in the <download_path/keys> directory.
+///
+/// If a `bundle` path and signing key are given, the downloaded artifacts and their signing keys
+/// are also written to a single signed bundle that `bio pkg install --bundle` can install from
+/// without access to Builder.
 
 /// Also, in the future we may want to accept an alternate builder to 'filter' what we pull down by
 /// That would greatly optimize the 'sync' to on prem builder case, as we could point to that
@@ -117,7 +125,8 @@ pub async fn start<U>(ui: &mut U,
                       download_path: Option<&PathBuf>,
                       token: Option<&str>,
                       verify: bool,
-                      ignore_missing_seeds: bool)
+                      ignore_missing_seeds: bool,
+                      bundle: Option<(&Path, &SecretOriginSigningKey)>)
                       -> Result<()>
     where U: UIWriter
 {
@@ -152,7 +161,7 @@ pub async fn start<U>(ui: &mut U,
 
     // We deliberately use None to specify the default path as this is used for cert paths, which
     // we don't want to override.
-    let api_client = Client::new(url, product, version, None)?;
+    let api_client = api_client::package_source(url, product, version, None)?;
 
     let task = DownloadTask { package_sets,
                               url,
@@ -162,9 +171,13 @@ pub async fn start<U>(ui: &mut U,
                               verify,
                               ignore_missing_seeds };
 
-    let download_count = task.execute(ui).await?;
+    let downloaded_artifacts = task.execute(ui).await?;
+
+    debug!("Expanded package count: {}", downloaded_artifacts.len());
 
-    debug!("Expanded package count: {}", download_count);
+    if let Some((bundle_path, signing_key)) = bundle {
+        task.write_bundle(ui, &downloaded_artifacts, bundle_path, signing_key)?;
+    }
 
     Ok(())
 }
@@ -172,7 +185,7 @@ pub async fn start<U>(ui: &mut U,
 struct DownloadTask<'a> {
     package_sets:         &'a [PackageSet],
     url:                  &'a str,
-    api_client:           BuilderAPIClient,
+    api_client:           Box<dyn PackageSource>,
     token:                Option<&'a str>,
     download_path:        &'a Path,
     verify:               bool,
@@ -180,7 +193,7 @@ struct DownloadTask<'a> {
 }
 
 impl<'a> DownloadTask<'a> {
-    async fn execute<T>(&self, ui: &mut T) -> Result<usize>
+    async fn execute<T>(&self, ui: &mut T) -> Result<Vec<PackageArchive>>
         where T: UIWriter
     {
         // This was written intentionally with an eye towards data parallelism
@@ -193,9 +206,47 @@ impl<'a> DownloadTask<'a> {
         let expanded_idents = self.expand_sources(ui).await?;
 
         // Phase 2: Download artifacts
-        let downloaded_artifacts = self.download_artifacts(ui, &expanded_idents).await?;
+        self.download_artifacts(ui, &expanded_idents).await
+    }
 
-        Ok(downloaded_artifacts.len())
+    /// Write the downloaded artifacts, and the public keys needed to verify them, to a signed
+    /// bundle.
+    fn write_bundle<T>(&self,
+                       ui: &mut T,
+                       artifacts: &[PackageArchive],
+                       bundle_path: &Path,
+                       signing_key: &SecretOriginSigningKey)
+                       -> Result<()>
+        where T: UIWriter
+    {
+        let cache = KeyCache::new(self.path_for_keys());
+        let mut key_paths = BTreeSet::new();
+        for artifact in artifacts {
+            let signer = artifact::artifact_signer(&artifact.path)?;
+            let key = cache.public_signing_key(&signer)?;
+            key_paths.insert(cache.path_in_cache(&key));
+        }
+        let artifact_paths = artifacts.iter()
+                                      .map(|a| a.path.clone())
+                                      .collect::<Vec<_>>();
+        let seeds = self.package_sets
+                        .iter()
+                        .flat_map(|set| set.idents.iter().cloned())
+                        .collect::<Vec<_>>();
+
+        ui.status(Status::Creating,
+                  format!("bundle {} signed by {}",
+                          bundle_path.display(),
+                          signing_key.named_revision()))?;
+        PackageBundle::create(bundle_path,
+                              &seeds,
+                              &artifact_paths,
+                              &key_paths.into_iter().collect::<Vec<_>>(),
+                              signing_key)?;
+        ui.end(format!("Bundle of {} artifacts written to {}",
+                       artifact_paths.len(),
+                       bundle_path.display()))?;
+        Ok(())
     }
 
     // For each source, use the builder/depot to expand it to a fully qualifed form
@@ -0,0 +1,461 @@
+use super::{binlink,
+            uninstall::{self,
+                        UninstallHookMode},
+            ExecutionStrategy};
+use crate::{common::{consts::{DEFAULT_HAB_LAUNCHER_PKG_IDENT,
+                              DEFAULT_HAB_PKG_IDENT,
+                              DEFAULT_HAB_SUP_PKG_IDENT},
+                     package_graph::PackageGraph,
+                     ui::{Status,
+                          UIWriter,
+                          UI}},
+            error::Result,
+            hcore::{fs as hfs,
+                    package::{self,
+                              Identifiable,
+                              PackageArchive,
+                              PackageIdent,
+                              PackageInstall}}};
+use std::{collections::HashSet,
+          fs,
+          path::Path,
+          str::FromStr,
+          time::{Duration,
+                 SystemTime}};
+use walkdir::WalkDir;
+
+/// Entries of the source cache that have not been modified for this long are considered stale
+const SRC_CACHE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// What to clean up in addition to unreachable installed packages
+#[derive(Clone, Copy, Default)]
+pub struct GcCaches {
+    /// Remove cached artifacts of packages that are not installed after collection
+    pub artifacts: bool,
+    /// Remove stale entries of the source cache
+    pub src:       bool,
+}
+
+/// Uninstall every package that is not reachable from a loaded or persisted service, a binlinked
+/// binary, the `keep` list or Biome itself.
+///
+/// A package is reachable if it is one of those roots or if any of its reverse dependencies is.
+pub async fn start(ui: &mut UI,
+                   fs_root_path: &Path,
+                   binlink_dir: &Path,
+                   keep: &[PackageIdent],
+                   caches: GcCaches,
+                   execution_strategy: ExecutionStrategy,
+                   uninstall_hook_mode: UninstallHookMode)
+                   -> Result<()> {
+    ui.begin("Collecting unused packages")?;
+    let mut graph = PackageGraph::from_root_path(fs_root_path)?;
+    let installed = package::all_packages(&hfs::pkg_root_path(Some(fs_root_path)))?;
+
+    let roots = roots(ui, fs_root_path, binlink_dir, keep, &installed).await?;
+    let garbage = unreachable(&graph, &installed, &roots);
+    let (garbage, skipped) = removal_order(&mut graph, garbage);
+    for ident in &skipped {
+        ui.status(Status::Skipping,
+                  format!("{}. It is part of or required by a dependency cycle", ident))?;
+    }
+    // Packages skipped above stay installed, so their cached artifacts are retained as well.
+    let retained = installed.iter()
+                            .filter(|ident| !garbage.contains(ident))
+                            .collect::<HashSet<_>>();
+
+    let mut reclaimed = 0;
+    let mut removed = 0;
+    for ident in &garbage {
+        let install = PackageInstall::load(ident, Some(fs_root_path))?;
+        let size = dir_size(install.installed_path());
+        if uninstall::delete_unused(ui,
+                                    fs_root_path,
+                                    &install,
+                                    execution_strategy,
+                                    uninstall_hook_mode).await?
+           || matches!(execution_strategy, ExecutionStrategy::DryRun)
+        {
+            reclaimed += size;
+            removed += 1;
+        }
+    }
+
+    if caches.artifacts {
+        reclaimed += clean_artifact_cache(ui, fs_root_path, &retained, execution_strategy)?;
+    }
+    if caches.src {
+        reclaimed += clean_src_cache(ui, fs_root_path, execution_strategy)?;
+    }
+
+    match execution_strategy {
+        ExecutionStrategy::DryRun => {
+            ui.end(format!("Would uninstall {} package(s) and reclaim {} (Dry run)",
+                           removed,
+                           format_size(reclaimed)))?;
+        }
+        ExecutionStrategy::Run => {
+            ui.end(format!("Uninstalled {} package(s) and reclaimed {}",
+                           removed,
+                           format_size(reclaimed)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the installed packages that are neither one of the `roots` nor a dependency of one.
+fn unreachable(graph: &PackageGraph,
+               installed: &[PackageIdent],
+               roots: &HashSet<PackageIdent>)
+               -> Vec<PackageIdent> {
+    installed.iter()
+             .filter(|ident| {
+                 !roots.contains(*ident)
+                 && !graph.ordered_reverse_deps(ident)
+                          .into_iter()
+                          .any(|rdep| roots.contains(rdep))
+             })
+             .cloned()
+             .collect()
+}
+
+/// Orders `garbage` so that dependents come before their dependencies, so a package is never
+/// removed while an installed package still depends on it. The ordered packages are removed from
+/// `graph`. Returns the order along with the packages that cannot be ordered because they are
+/// part of a dependency cycle or a dependency of a package that is.
+fn removal_order(graph: &mut PackageGraph,
+                 mut garbage: Vec<PackageIdent>)
+                 -> (Vec<PackageIdent>, Vec<PackageIdent>) {
+    let mut order = Vec::with_capacity(garbage.len());
+    while !garbage.is_empty() {
+        let (ready, rest): (Vec<_>, Vec<_>) =
+            garbage.into_iter()
+                   .partition(|ident| graph.count_rdeps(ident).unwrap_or(0) == 0);
+        if ready.is_empty() {
+            return (order, rest);
+        }
+        for ident in &ready {
+            graph.remove(ident);
+        }
+        order.extend(ready);
+        garbage = rest;
+    }
+    (order, Vec::new())
+}
+
+/// Returns the installed packages that must be kept along with their dependencies.
+async fn roots(ui: &mut UI,
+               fs_root_path: &Path,
+               binlink_dir: &Path,
+               keep: &[PackageIdent],
+               installed: &[PackageIdent])
+               -> Result<HashSet<PackageIdent>> {
+    let mut patterns = Vec::new();
+
+    ui.status(Status::Determining, "list of loaded services in supervisor")?;
+    for ident in uninstall::supervisor_services().await? {
+        ui.status(Status::Found, format!("loaded service {}", ident))?;
+        patterns.push(ident);
+    }
+
+    // The Supervisor starts the services of its spec files again even while it is stopped, so
+    // the release each of them would start is kept along with any release it is held at.
+    ui.status(Status::Determining,
+              "list of services in supervisor spec files")?;
+    for (ident, hold) in persisted_services(ui, fs_root_path)? {
+        if let Some(latest) = installed.iter()
+                                       .filter(|installed| installed.satisfies(&ident))
+                                       .max_by(|a, b| a.by_parts_cmp(b))
+        {
+            ui.status(Status::Found, format!("persisted service {}", latest))?;
+            patterns.push(latest.clone());
+        }
+        if let Some(hold) = hold {
+            ui.status(Status::Found, format!("held service {}", hold))?;
+            patterns.push(hold);
+        }
+    }
+
+    ui.status(Status::Determining,
+              format!("list of binlinks in {}", binlink_dir.display()))?;
+    for ident in binlink::binlinked_packages(binlink_dir, fs_root_path)? {
+        ui.status(Status::Found, format!("binlinked package {}", ident))?;
+        patterns.push(ident);
+    }
+
+    patterns.extend_from_slice(keep);
+
+    let mut roots = installed.iter()
+                             .filter(|ident| patterns.iter().any(|p| ident.satisfies(p)))
+                             .cloned()
+                             .collect::<HashSet<_>>();
+
+    // `bio pkg uninstall` refuses to remove biome/bio, so keep all of its releases along with
+    // their dependencies. Only the latest Supervisor and Launcher are kept.
+    let bio = PackageIdent::from_str(DEFAULT_HAB_PKG_IDENT)?;
+    roots.extend(installed.iter()
+                          .filter(|ident| ident.satisfies(&bio))
+                          .cloned());
+    for biome in &[DEFAULT_HAB_SUP_PKG_IDENT, DEFAULT_HAB_LAUNCHER_PKG_IDENT] {
+        let biome = PackageIdent::from_str(biome)?;
+        if let Some(latest) = installed.iter()
+                                       .filter(|ident| ident.satisfies(&biome))
+                                       .max_by(|a, b| a.by_parts_cmp(b))
+        {
+            roots.insert(latest.clone());
+        }
+    }
+    Ok(roots)
+}
+
+/// Returns the ident of each service spec file of the Supervisors under `fs_root_path`, along
+/// with the release the service is held at, if any.
+fn persisted_services(ui: &mut UI,
+                      fs_root_path: &Path)
+                      -> Result<Vec<(PackageIdent, Option<PackageIdent>)>> {
+    let pattern = fs_root_path.join("hab")
+                              .join("sup")
+                              .join("*")
+                              .join("specs")
+                              .join("*.spec");
+    let mut services = Vec::new();
+    for path in glob::glob(&pattern.display().to_string()).expect("Failed to read glob pattern")
+                                                          .filter_map(std::result::Result::ok)
+    {
+        let spec = match fs::read_to_string(&path).map_err(|e| e.to_string())
+                                                  .and_then(|spec| {
+                                                      spec.parse::<toml::Value>()
+                                                          .map_err(|e| e.to_string())
+                                                  }) {
+            Ok(spec) => spec,
+            Err(e) => {
+                ui.warn(format!("Skipping spec file {}: {}", path.display(), e))?;
+                continue;
+            }
+        };
+        let ident_of = |key| {
+            spec.get(key)
+                .and_then(toml::Value::as_str)
+                .and_then(|ident| PackageIdent::from_str(ident).ok())
+        };
+        match ident_of("ident") {
+            Some(ident) => services.push((ident, ident_of("hold"))),
+            None => {
+                ui.warn(format!("Skipping spec file {} without a valid ident",
+                                path.display()))?
+            }
+        }
+    }
+    Ok(services)
+}
+
+/// Remove cached artifacts of packages that are not in `retained`, returning the reclaimed space.
+fn clean_artifact_cache(ui: &mut UI,
+                        fs_root_path: &Path,
+                        retained: &HashSet<&PackageIdent>,
+                        execution_strategy: ExecutionStrategy)
+                        -> Result<u64> {
+    let cache_path = hfs::cache_artifact_path(Some(fs_root_path));
+    let entries = match fs::read_dir(&cache_path) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(0),
+    };
+    let mut reclaimed = 0;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().map_or(true, |e| e != "hart") {
+            continue;
+        }
+        let ident = match PackageArchive::new(&path).and_then(|mut a| a.ident()) {
+            Ok(ident) => ident,
+            Err(e) => {
+                ui.warn(format!("Skipping cached artifact {}: {}", path.display(), e))?;
+                continue;
+            }
+        };
+        if retained.contains(&ident) {
+            continue;
+        }
+        reclaimed += remove_path(ui, &path, execution_strategy)?;
+    }
+    Ok(reclaimed)
+}
+
+/// Remove entries of the source cache that are older than `SRC_CACHE_MAX_AGE`, returning the
+/// reclaimed space.
+fn clean_src_cache(ui: &mut UI,
+                   fs_root_path: &Path,
+                   execution_strategy: ExecutionStrategy)
+                   -> Result<u64> {
+    let cache_path = hfs::cache_src_path(Some(fs_root_path));
+    let entries = match fs::read_dir(&cache_path) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(0),
+    };
+    let now = SystemTime::now();
+    let mut reclaimed = 0;
+    for entry in entries {
+        let entry = entry?;
+        let stale = entry.metadata()?
+                         .modified()
+                         .ok()
+                         .and_then(|modified| now.duration_since(modified).ok())
+                         .map_or(false, |age| age > SRC_CACHE_MAX_AGE);
+        if stale {
+            reclaimed += remove_path(ui, &entry.path(), execution_strategy)?;
+        }
+    }
+    Ok(reclaimed)
+}
+
+fn remove_path(ui: &mut UI, path: &Path, execution_strategy: ExecutionStrategy) -> Result<u64> {
+    let size = dir_size(path);
+    match execution_strategy {
+        ExecutionStrategy::DryRun => ui.status(Status::DryRunDeleting, path.display())?,
+        ExecutionStrategy::Run => {
+            ui.status(Status::Deleting, path.display())?;
+            if path.is_dir() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+    Ok(size)
+}
+
+/// The total size of the files at or below `path`
+fn dir_size(path: &Path) -> u64 {
+    WalkDir::new(path).into_iter()
+                      .filter_map(|entry| entry.ok())
+                      .filter_map(|entry| entry.metadata().ok())
+                      .filter(|metadata| metadata.is_file())
+                      .map(|metadata| metadata.len())
+                      .sum()
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn ident(s: &str) -> PackageIdent { s.parse().unwrap() }
+
+    fn graph(packages: &[(&str, &[&str])]) -> PackageGraph {
+        let mut graph = PackageGraph::default();
+        for (package, deps) in packages {
+            let deps = deps.iter().map(|d| ident(d)).collect::<Vec<_>>();
+            graph.extend(&ident(package), &deps);
+        }
+        graph
+    }
+
+    const BASE: &str = "core/glibc/2.29/20200101000000";
+    const LIB1: &str = "core/openssl/1.0.2/20200101000000";
+    const LIB2: &str = "core/zlib/1.2.11/20200101000000";
+    const APP1: &str = "core/redis/5.0.7/20200101000000";
+    const APP2: &str = "core/nginx/1.17.4/20200101000000";
+
+    fn installed() -> Vec<PackageIdent> {
+        [BASE, LIB1, LIB2, APP1, APP2].iter()
+                                      .map(|i| ident(i))
+                                      .collect()
+    }
+
+    #[test]
+    fn dependencies_of_roots_are_reachable() {
+        let graph = graph(&[(BASE, &[]),
+                            (LIB1, &[BASE]),
+                            (LIB2, &[BASE]),
+                            (APP1, &[LIB1, BASE]),
+                            (APP2, &[LIB2, BASE])]);
+        let roots = vec![ident(APP1)].into_iter().collect();
+
+        assert_eq!(unreachable(&graph, &installed(), &roots),
+                   vec![ident(LIB2), ident(APP2)]);
+    }
+
+    #[test]
+    fn nothing_is_unreachable_from_a_shared_dependency() {
+        let graph = graph(&[(BASE, &[]),
+                            (LIB1, &[BASE]),
+                            (LIB2, &[BASE]),
+                            (APP1, &[LIB1, BASE]),
+                            (APP2, &[LIB2, BASE])]);
+        let roots = vec![ident(APP1), ident(APP2)].into_iter().collect();
+
+        assert!(unreachable(&graph, &installed(), &roots).is_empty());
+    }
+
+    #[test]
+    fn dependents_are_removed_before_their_dependencies() {
+        let mut graph = graph(&[(BASE, &[]),
+                                (LIB1, &[BASE]),
+                                (LIB2, &[BASE]),
+                                (APP1, &[LIB1, BASE]),
+                                (APP2, &[LIB2, BASE])]);
+        let garbage = installed();
+
+        let (order, skipped) = removal_order(&mut graph, garbage);
+        assert_eq!(order,
+                   vec![ident(APP1),
+                        ident(APP2),
+                        ident(LIB1),
+                        ident(LIB2),
+                        ident(BASE)]);
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn persisted_services_are_read_from_every_supervisor_spec_file() {
+        let fs_root = TempDir::new().unwrap();
+        for (sup, file, spec) in
+            &[("default", "redis.spec", format!("ident = \"{}\"", APP1)),
+              ("default", "nginx.spec", format!("ident = \"core/nginx\"\nhold = \"{}\"", APP2)),
+              ("custom", "broken.spec", "ident = ".to_string()),
+              ("custom", "notes.txt", "ident = \"core/zlib\"".to_string())]
+        {
+            let specs = fs_root.path()
+                               .join("hab")
+                               .join("sup")
+                               .join(sup)
+                               .join("specs");
+            fs::create_dir_all(&specs).unwrap();
+            fs::write(specs.join(file), spec).unwrap();
+        }
+
+        let mut services = persisted_services(&mut UI::with_sinks(), fs_root.path()).unwrap();
+        services.sort_by_key(|(ident, _)| ident.to_string());
+        assert_eq!(services,
+                   vec![(ident("core/nginx"), Some(ident(APP2))),
+                        (ident(APP1), None)]);
+    }
+
+    #[test]
+    fn packages_in_a_dependency_cycle_are_skipped() {
+        let mut graph = graph(&[(BASE, &[]),
+                                (LIB1, &[LIB2, BASE]),
+                                (LIB2, &[LIB1]),
+                                (APP1, &[LIB1])]);
+        let garbage = vec![ident(BASE), ident(LIB1), ident(LIB2), ident(APP1)];
+
+        let (order, skipped) = removal_order(&mut graph, garbage);
+        assert_eq!(order, vec![ident(APP1)]);
+        assert_eq!(skipped, vec![ident(BASE), ident(LIB1), ident(LIB2)]);
+    }
+}
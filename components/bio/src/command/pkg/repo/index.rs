@@ -0,0 +1,37 @@
+use crate::{api_client::RepoIndex,
+            common::ui::{Status,
+                         UIWriter,
+                         UI},
+            error::Result,
+            hcore::{crypto::{artifact,
+                             keys::KeyCache},
+                    ChannelIdent}};
+use std::path::Path;
+
+pub fn start(ui: &mut UI, dir: &Path, channels: &[ChannelIdent]) -> Result<()> {
+    ui.begin(format!("Indexing package repository {}", dir.display()))?;
+    let index = RepoIndex::build(dir, channels)?;
+
+    // Installing from the repository fails for any artifact whose signing key it does not serve,
+    // so call those out now rather than at install time.
+    let key_cache = KeyCache::new(dir.join("keys"));
+    for package in &index.packages {
+        let path = dir.join("artifacts").join(&package.file);
+        match artifact::verify(&path, &key_cache) {
+            Ok((signer, _)) => {
+                ui.status(Status::Verified,
+                          format!("{} signed with {}", package.package.ident, signer))?;
+            }
+            Err(e) => {
+                ui.warn(format!("{} cannot be verified with the repository's keys: {}",
+                                package.file, e))?;
+            }
+        }
+    }
+
+    index.write(dir)?;
+    ui.end(format!("Indexed {} artifact(s) in {}.",
+                   index.packages.len(),
+                   dir.display()))?;
+    Ok(())
+}
@@ -8,7 +8,9 @@ use biome_common::ui::UI;
 use biome_core::package::PackageIdent;
 use std::path::Path;
 
-pub use uninstall_impl::{uninstall,
+pub use uninstall_impl::{delete_unused,
+                         supervisor_services,
+                         uninstall,
                          uninstall_all_but_latest,
                          UninstallHookMode,
                          UninstallSafety};
@@ -259,7 +259,8 @@ fn launcher_is_running(fs_root_path: &Path) -> bool {
     pid_file_path.is_file()
 }
 
-async fn supervisor_services() -> Result<Vec<PackageIdent>> {
+/// Returns the packages of the services loaded by a Supervisor running out of this biome root.
+pub async fn supervisor_services() -> Result<Vec<PackageIdent>> {
     if !launcher_is_running(&FS_ROOT_PATH) {
         return Ok(vec![]);
     }
@@ -306,6 +307,27 @@ impl UninstallSafetyImpl<'_> {
     }
 }
 
+/// Delete a single package that no other installed package depends on, regardless of whether
+/// it is loaded by the supervisor. Callers are responsible for checking both.
+///
+/// Returns the same values as `maybe_delete`.
+pub async fn delete_unused<U>(ui: &mut U,
+                              fs_root_path: &Path,
+                              install: &PackageInstall,
+                              strategy: ExecutionStrategy,
+                              uninstall_hook_mode: UninstallHookMode)
+                              -> Result<bool>
+    where U: UIWriter
+{
+    maybe_delete(ui,
+                 fs_root_path,
+                 install,
+                 strategy,
+                 &[],
+                 uninstall_hook_mode,
+                 UninstallSafetyImpl::Force).await
+}
+
 /// Delete a package from disk, depending upon the ExecutionStrategy supplied
 ///
 /// Returns:
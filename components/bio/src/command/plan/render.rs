@@ -1,115 +1,177 @@
+use chrono::Utc;
 use serde_json::{self,
-                 json,
                  Value as Json};
 use std::{fs::{create_dir_all,
+               read_dir,
                read_to_string,
                File},
           io::Write,
-          path::Path};
-use toml::Value;
+          path::{Path,
+                 PathBuf},
+          result,
+          str::FromStr};
 
-use crate::{common::{templating::TemplateRenderer,
+use crate::{common::{self,
+                     templating::{config::{secret,
+                                           Cfg,
+                                           CfgRenderer},
+                                  fixture::Fixture,
+                                  package::Pkg,
+                                  RenderContext,
+                                  TemplateRenderer},
                      ui::{Status,
                           UIWriter,
                           UI}},
-            error::Result};
+            error::{Error,
+                    Result},
+            hcore::{fs::FS_ROOT_PATH,
+                    package::{FullyQualifiedPackageIdent,
+                              PackageIdent,
+                              PackageInstall}}};
 
+/// Render a single template, or every configuration template and hook of a plan directory or an
+/// installed package.
 #[allow(clippy::too_many_arguments)]
-pub fn start(ui: &mut UI,
-             template_path: &Path,
-             default_toml_path: &Path,
-             user_toml_path: Option<&Path>,
-             mock_data_path: Option<&Path>,
-             print: bool,
-             render: bool,
-             render_dir: &Path,
-             quiet: bool)
-             -> Result<()> {
-    // Strip the file name out of our passed template
-    let file_name = Path::new(template_path.file_name().expect("valid template file"));
+pub async fn start(ui: &mut UI,
+                   target: &str,
+                   default_toml_path: Option<&Path>,
+                   user_toml_path: Option<&Path>,
+                   mock_data_path: Option<&Path>,
+                   fixture_path: Option<&Path>,
+                   print: bool,
+                   render: bool,
+                   render_dir: &Path,
+                   strict: bool,
+                   quiet: bool)
+                   -> Result<()> {
+    let template_path = Path::new(target);
+    // A single template is rendered for the plan in the current directory
+    let (package_path, pkg) = if template_path.is_file() {
+        (PathBuf::from("."), Pkg::from_plan(plan_ident(Path::new("."))))
+    } else if template_path.is_dir() {
+        (template_path.to_path_buf(), Pkg::from_plan(plan_ident(template_path)))
+    } else {
+        let ident =
+            PackageIdent::from_str(target).map_err(|_| Error::FileNotFound(target.to_string()))?;
+        let package = PackageInstall::load(&ident, Some(&*FS_ROOT_PATH))?;
+        (package.installed_path.clone(), Pkg::from_install(&package).await?)
+    };
 
     if !quiet {
-        ui.begin(format!("Rendering: {} into: {} as: {}",
+        ui.begin(format!("Rendering: {} into: {}",
                          template_path.display(),
-                         render_dir.display(),
-                         file_name.display()))?;
+                         render_dir.display()))?;
         ui.br()?;
     }
 
-    // read our template from file
-    let template = read_to_string(template_path)?;
-
-    // create a "data" json struct
-    let mut data = json!({});
-
-    if !quiet {
-        // import default.toml values, convert to JSON
-        ui.begin(format!("Importing default.toml: {}", &default_toml_path.display()))?;
-    }
-
-    // we should always have a default.toml, would be nice to "autodiscover" based on package name,
-    // for now assume we're working in the plan dir if --default-toml not passed
-    let default_toml = read_to_string(default_toml_path)?;
-
-    // merge default into data struct
-    merge(&mut data, toml_to_json(&default_toml)?);
-
-    // import default.toml values, convert to JSON
-    let user_toml = match user_toml_path {
+    // The default configuration is the package's own unless another one is passed. The
+    // configuration of the service on this system is never used, only `--user-toml`.
+    let user = match user_toml_path {
         Some(path) => {
             if !quiet {
-                // print helper message, maybe only print if '--verbose'? how?
                 ui.begin(format!("Importing user.toml: {}", path.display()))?;
             }
-            read_to_string(path)?
+            Some(toml::from_str(&read_to_string(path)?)?)
         }
-        None => String::new(),
+        None => None,
     };
-    // merge default into data struct
-    merge(&mut data, toml_to_json(&user_toml)?);
+    let mut cfg = Cfg::with_user(&pkg, Some(&package_path), user)?;
+    if let Some(path) = default_toml_path {
+        if !quiet {
+            ui.begin(format!("Importing default.toml: {}", path.display()))?;
+        }
+        cfg.default = Some(toml::from_str(&read_to_string(path)?)?);
+    }
 
-    // read mock data if provided
-    let mock_data = match mock_data_path {
+    let fixture = match fixture_path {
         Some(path) => {
             if !quiet {
-                // print helper message, maybe only print if '--verbose'? how?
-                ui.begin(format!("Importing override file: {}", path.display()))?;
+                ui.begin(format!("Importing fixture: {}", path.display()))?;
             }
-            read_to_string(path)?
+            Fixture::load(path)?
         }
-        // return an empty json block if '--mock-data' isn't defined.
-        // this allows us to merge an empty JSON block
-        None => "{}".to_string(),
+        None => Fixture::default(),
     };
-    // merge mock data into data
-    merge(&mut data, serde_json::from_str(&mock_data)?);
-
-    // create a template renderer
-    let mut renderer = TemplateRenderer::new();
-    // register our template
-    renderer.register_template_string(&template, &template)
-            .expect("Could not register template content");
-    // render our JSON override in our template.
-    let rendered_template = renderer.render(&template, &data)?;
-
-    if print {
+    if !fixture.gossip.is_empty() {
+        cfg.set_gossip(1, fixture.gossip.clone())?;
+    }
+
+    let mut data = serde_json::to_value(RenderContext::new(&pkg, &cfg))?;
+    merge(&mut data, fixture.census_context(pkg.ident.as_ref())?);
+
+    // read mock data if provided
+    if let Some(path) = mock_data_path {
         if !quiet {
-            ui.br()?;
-            ui.warn(format!("###======== Rendered template: {}",
-                            &template_path.display()))?;
+            ui.begin(format!("Importing override file: {}", path.display()))?;
         }
+        merge(&mut data, serde_json::from_str(&read_to_string(path)?)?);
+    }
 
-        println!("{}", rendered_template);
+    let renderer = || {
+        if strict {
+            TemplateRenderer::strict()
+        } else {
+            TemplateRenderer::new()
+        }
+    };
 
-        if !quiet {
-            ui.warn(format!("========### End rendered template: {}",
-                            &template_path.display()))?;
+    let mut rendered = Vec::new();
+    if template_path.is_file() {
+        // Strip the file name out of our passed template
+        let file_name = template_path.file_name().expect("valid template file");
+        let name = file_name.to_string_lossy();
+        let mut renderer = renderer();
+        renderer.register_template_file(&name, template_path)
+                .map_err(common::Error::from)?;
+        let mut concealed = data.clone();
+        if let Some(cfg) = concealed.get_mut("cfg") {
+            secret::conceal(cfg)?;
+        }
+        rendered.push((PathBuf::from(file_name), renderer.render(&name, &concealed)?));
+    } else {
+        let config = CfgRenderer::with_renderer(package_path.join("config"), renderer())?;
+        for (file, content) in config.render_concealed(&data)?.files {
+            rendered.push((Path::new("config").join(file), content));
+        }
+
+        let hooks_path = package_path.join("hooks");
+        if hooks_path.is_dir() {
+            let mut hooks = read_dir(&hooks_path)?.filter_map(result::Result::ok)
+                                                  .map(|entry| entry.path())
+                                                  .filter(|path| path.is_file())
+                                                  .collect::<Vec<_>>();
+            hooks.sort();
+            let mut renderer = renderer();
+            for hook in hooks {
+                let name = hook.file_name()
+                               .expect("hook file name")
+                               .to_string_lossy()
+                               .to_string();
+                renderer.register_template_file(&name, &hook)
+                        .map_err(common::Error::from)?;
+                rendered.push((Path::new("hooks").join(&name), renderer.render(&name, &data)?));
+            }
         }
     }
 
-    if render {
-        // Render our template file
-        create_with_template(ui, render_dir, file_name, &rendered_template, quiet)?;
+    for (path, content) in rendered {
+        if print {
+            if !quiet {
+                ui.br()?;
+                ui.warn(format!("###======== Rendered template: {}", path.display()))?;
+            }
+
+            println!("{}", content);
+
+            if !quiet {
+                ui.warn(format!("========### End rendered template: {}", path.display()))?;
+            }
+        }
+
+        if render {
+            // Render our template file
+            create_with_template(ui, render_dir, &path, &content, quiet)?;
+        }
     }
 
     if !quiet {
@@ -118,11 +180,36 @@ pub fn start(ui: &mut UI,
     Ok(())
 }
 
-fn toml_to_json(cfg: &str) -> Result<Json> {
-    let toml_value = cfg.parse::<Value>()?;
-    let toml_string = serde_json::to_string(&toml_value)?;
-    let json = serde_json::from_str(&format!(r#"{{ "cfg": {} }}"#, &toml_string))?;
-    Ok(json)
+/// The identifier of the package a plan builds, read from the plan in `plan_path`. Packages that
+/// haven't been built have no release, so the current time stands in for it as it would in a
+/// build.
+fn plan_ident(plan_path: &Path) -> FullyQualifiedPackageIdent {
+    let plan = ["plan.sh", "plan.ps1"].iter()
+                                      .find_map(|file| read_to_string(plan_path.join(file)).ok())
+                                      .unwrap_or_default();
+    // Only plain assignments are read; values computed by the plan are left to their defaults
+    let var = |name: &str| {
+        plan.lines()
+            .find_map(|line| {
+                let line = line.trim().trim_start_matches('$');
+                let value = line.strip_prefix(name)?.trim_start().strip_prefix('=')?;
+                Some(value.trim().trim_matches(&['"', '\''][..]).to_string())
+            })
+            .filter(|value| !value.is_empty() && !value.contains('$'))
+    };
+    let name = var("pkg_name").unwrap_or_else(|| {
+                                  plan_path.canonicalize()
+                                           .ok()
+                                           .and_then(|path| {
+                                               path.file_name()
+                                                   .map(|name| name.to_string_lossy().to_string())
+                                           })
+                                           .unwrap_or_else(|| "plan".to_string())
+                              });
+    FullyQualifiedPackageIdent::new(var("pkg_origin").unwrap_or_else(|| "local".to_string()),
+                                    name,
+                                    var("pkg_version").unwrap_or_else(|| "0.0.0".to_string()),
+                                    Utc::now().format("%Y%m%d%H%M%S").to_string())
 }
 
 // merge two Json structs
@@ -149,9 +236,157 @@ fn create_with_template(ui: &mut UI,
         ui.status(Status::Creating, format!("file: {}", path.display()))?;
     }
 
-    create_dir_all(render_dir)?;
+    create_dir_all(path.parent().unwrap_or(render_dir))?;
 
     // Write file to disk
     File::create(path).and_then(|mut file| file.write(template.as_bytes()))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn plan_dir(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().expect("create temp dir");
+        for (file, content) in files {
+            let path = dir.path().join(file);
+            create_dir_all(path.parent().unwrap()).expect("create plan directory");
+            fs::write(path, content).expect("write plan file");
+        }
+        dir
+    }
+
+    #[test]
+    fn plan_ident_reads_plain_assignments() {
+        let dir = plan_dir(&[("plan.sh",
+                              "pkg_name=myapp\npkg_name_suffix=ignored\n  \
+                               pkg_origin=\"core\"\npkg_version='1.2.3'\n")]);
+        let ident = plan_ident(dir.path());
+        assert_eq!(ident.as_ref().origin, "core");
+        assert_eq!(ident.as_ref().name, "myapp");
+        assert_eq!(ident.version(), "1.2.3");
+        assert_eq!(ident.release().len(), "20201019000000".len());
+    }
+
+    #[test]
+    fn plan_ident_reads_powershell_plans() {
+        let dir = plan_dir(&[("plan.ps1", "$pkg_name=\"winapp\"\n$pkg_origin=\"core\"\n")]);
+        let ident = plan_ident(dir.path());
+        assert_eq!(ident.as_ref().origin, "core");
+        assert_eq!(ident.as_ref().name, "winapp");
+    }
+
+    #[test]
+    fn plan_ident_defaults_computed_or_missing_values() {
+        let dir = plan_dir(&[("myapp/plan.sh", "pkg_version=$(cat VERSION)\npkg_origin=\n")]);
+        let ident = plan_ident(&dir.path().join("myapp"));
+        assert_eq!(ident.as_ref().origin, "local");
+        assert_eq!(ident.as_ref().name, "myapp");
+        assert_eq!(ident.version(), "0.0.0");
+    }
+
+    #[tokio::test]
+    async fn plan_templates_and_hooks_are_rendered() {
+        let plan = plan_dir(&[("plan.sh", "pkg_name=render-test-app\npkg_origin=core\n"),
+                              ("default.toml", "port = 80\nhost = \"localhost\"\n"),
+                              ("user.toml", "port = 81\n"),
+                              ("config/app.conf",
+                               "{{cfg.host}}:{{cfg.port}} leader={{svc.me.leader}}"),
+                              ("hooks/run", "exec {{pkg.name}}")]);
+        let out = TempDir::new().expect("create temp dir");
+        let mut ui = UI::with_sinks();
+        start(&mut ui,
+              &plan.path().to_string_lossy(),
+              None,
+              Some(&plan.path().join("user.toml")),
+              None,
+              None,
+              false,
+              true,
+              out.path(),
+              true,
+              true).await
+                   .expect("plan renders");
+
+        assert_eq!(fs::read_to_string(out.path().join("config").join("app.conf")).unwrap(),
+                   "localhost:81 leader=false");
+        assert_eq!(fs::read_to_string(out.path().join("hooks").join("run")).unwrap(),
+                   "exec render-test-app");
+    }
+
+    #[tokio::test]
+    async fn secrets_are_never_resolved() {
+        let plan = plan_dir(&[("plan.sh", "pkg_name=render-test-app\n"),
+                              ("user.toml",
+                               "[password]\nsecret = \"env:HAB_TEST_RENDER_SECRET\"\n"),
+                              ("config/app.conf", "password={{cfg.password}}"),
+                              ("app.conf", "password={{cfg.password}}")]);
+        let out = TempDir::new().expect("create temp dir");
+        let mut ui = UI::with_sinks();
+        start(&mut ui,
+              &plan.path().join("app.conf").to_string_lossy(),
+              None,
+              Some(&plan.path().join("user.toml")),
+              None,
+              None,
+              false,
+              true,
+              &out.path().join("file"),
+              true,
+              true).await
+                   .expect("template renders");
+        start(&mut ui,
+              &plan.path().to_string_lossy(),
+              None,
+              Some(&plan.path().join("user.toml")),
+              None,
+              None,
+              false,
+              true,
+              &out.path().join("plan"),
+              true,
+              true).await
+                   .expect("plan renders");
+
+        let concealed = "password=<secret env:HAB_TEST_RENDER_SECRET>";
+        let file = out.path().join("file").join("app.conf");
+        assert_eq!(fs::read_to_string(file).unwrap(), concealed);
+        let plan_file = out.path().join("plan").join("config").join("app.conf");
+        assert_eq!(fs::read_to_string(plan_file).unwrap(), concealed);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_written_unless_rendering() {
+        let plan = plan_dir(&[("plan.sh", "pkg_name=render-test-app\n"),
+                              ("config/app.conf", "{{pkg.name}}")]);
+        let out = TempDir::new().expect("create temp dir");
+        let mut ui = UI::with_sinks();
+        start(&mut ui,
+              &plan.path().to_string_lossy(),
+              None,
+              None,
+              None,
+              None,
+              false,
+              false,
+              &out.path().join("rendered"),
+              false,
+              true).await
+                   .expect("plan renders");
+
+        assert!(!out.path().join("rendered").exists());
+    }
+
+    #[test]
+    fn mock_data_is_merged_into_the_context() {
+        let mut data = serde_json::json!({ "cfg": { "port": 80, "host": "a" }, "pkg": "x" });
+        merge(&mut data,
+              serde_json::json!({ "cfg": { "port": 81 }, "pkg": { "name": "y" } }));
+        assert_eq!(data,
+                   serde_json::json!({ "cfg": { "port": 81, "host": "a" },
+                                       "pkg": { "name": "y" } }));
+    }
+}
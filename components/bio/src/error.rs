@@ -35,6 +35,8 @@ pub enum Error {
     CommandNotFoundInPkg((String, String)),
     CliConfig(cli_config::Error),
     ConfigOpt(configopt::Error),
+    ConfigSchemaViolation(String, usize),
+    ConfigVersionNotFound(String, u64),
     CryptoCLI(String),
     CtlClient(SrvClientError),
     CtrlcError(ctrlc::Error),
@@ -62,6 +64,7 @@ pub enum Error {
     NameLookup,
     NetErr(net::NetErr),
     PackageArchiveMalformed(String),
+    PackageAuditFailed(usize),
     PackageSetParseError(String),
     ParseIntError(num::ParseIntError),
     ParseUrlError(url::ParseError),
@@ -113,6 +116,14 @@ impl fmt::Display for Error {
             }
             Error::CliConfig(ref err) => format!("{}", err),
             Error::ConfigOpt(ref err) => format!("{}", err),
+            Error::ConfigSchemaViolation(ref service_group, ref count) => {
+                format!("Configuration for {} has {} schema violation(s) and was not applied",
+                        service_group, count)
+            }
+            Error::ConfigVersionNotFound(ref service_group, ref version) => {
+                format!("Configuration version {} for {} is not in its configuration history",
+                        version, service_group)
+            }
             Error::CryptoCLI(ref e) => e.to_string(),
             Error::CtlClient(ref e) => e.to_string(),
             Error::CtrlcError(ref err) => format!("{}", err),
@@ -183,6 +194,9 @@ impl fmt::Display for Error {
                 format!("Package archive was unreadable or contained unexpected contents: {:?}",
                         e)
             }
+            Error::PackageAuditFailed(ref count) => {
+                format!("{} package(s) failed the integrity audit", count)
+            }
             Error::PackageSetParseError(ref e) => {
                 format!("Package set file could not be parsed: {:?}", e)
             }
@@ -1,5 +1,7 @@
 #![recursion_limit = "128"]
 
+use chrono::{DateTime,
+             Utc};
 use clap::{value_t,
            ArgMatches,
            ErrorKind as ClapErrorKind,
@@ -21,7 +23,10 @@ use bio::{cli::{self,
                       svc::{self,
                             BulkLoad as SvcBulkLoad,
                             Load as SvcLoad,
-                            Svc},
+                            Svc,
+                            SvcRollout,
+                            SvcRunJob,
+                            SvcUpdateNow},
                       util::{bldr_auth_token_from_args_env_or_load,
                              bldr_url_from_args_env_load_or_default},
                       Bio,
@@ -94,7 +99,10 @@ use std::{collections::HashMap,
           result,
           str::FromStr,
           string::ToString,
-          thread};
+          thread,
+          time::{Duration,
+                 SystemTime,
+                 UNIX_EPOCH}};
 use tabwriter::TabWriter;
 
 #[cfg(not(target_os = "macos"))]
@@ -121,6 +129,8 @@ lazy_static! {
              "pid",
              "group",]
     };
+    static ref UPDATES_HEADER: Vec<&'static str> =
+        { vec!["package", "latest", "update", "last checked", "group",] };
 }
 
 #[tokio::main]
@@ -260,6 +270,9 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
                         BioSup::Restart { remote_sup } => {
                             return sub_sup_restart(remote_sup.inner()).await;
                         }
+                        BioSup::UpdateNow { remote_sup } => {
+                            return sub_sup_update_now(remote_sup.inner()).await;
+                        }
                     }
                 }
                 Bio::Svc(svc) => {
@@ -275,9 +288,15 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
                             return sub_svc_load(svc_load).await;
                         }
                         Svc::Update(svc_update) => return sub_svc_update(svc_update).await,
+                        Svc::Rollout(svc_rollout) => return sub_svc_rollout(svc_rollout).await,
+                        Svc::UpdateNow(svc_update_now) => {
+                            return sub_svc_update_now(svc_update_now).await;
+                        }
+                        Svc::RunJob(svc_run_job) => return sub_svc_run_job(svc_run_job).await,
                         Svc::Status(svc_status) => {
                             return sub_svc_status(svc_status.pkg_ident,
-                                                  svc_status.remote_sup.inner()).await;
+                                                  svc_status.remote_sup.inner(),
+                                                  svc_status.updates).await;
                         }
                         _ => {
                             // All other commands will be caught by the CLI parsing logic below.
@@ -371,6 +390,8 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
         ("config", Some(m)) => {
             match m.subcommand() {
                 ("apply", Some(m)) => sub_svc_set(m).await?,
+                ("history", Some(m)) => sub_svc_config_history(m).await?,
+                ("rollback", Some(m)) => sub_svc_config_rollback(m).await?,
                 ("show", Some(m)) => sub_svc_config(m).await?,
                 _ => unreachable!(),
             }
@@ -452,19 +473,28 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
         }
         ("pkg", Some(matches)) => {
             match matches.subcommand() {
+                ("audit", Some(m)) => sub_pkg_audit(ui, m)?,
                 ("binds", Some(m)) => sub_pkg_binds(m)?,
                 ("binlink", Some(m)) => sub_pkg_binlink(ui, m)?,
                 ("build", Some(m)) => sub_pkg_build(ui, m, feature_flags).await?,
                 ("channels", Some(m)) => sub_pkg_channels(ui, m).await?,
                 ("config", Some(m)) => sub_pkg_config(m)?,
                 ("dependencies", Some(m)) => sub_pkg_dependencies(m)?,
+                ("diff", Some(m)) => sub_pkg_diff(m)?,
                 ("download", Some(m)) => sub_pkg_download(ui, m, feature_flags).await?,
                 ("env", Some(m)) => sub_pkg_env(m)?,
+                ("gc", Some(m)) => sub_pkg_gc(ui, m).await?,
                 ("hash", Some(m)) => sub_pkg_hash(m)?,
                 ("install", Some(m)) => sub_pkg_install(ui, m, feature_flags).await?,
                 ("list", Some(m)) => sub_pkg_list(m)?,
                 ("path", Some(m)) => sub_pkg_path(m)?,
                 ("provides", Some(m)) => sub_pkg_provides(m)?,
+                ("repo", Some(m)) => {
+                    match m.subcommand() {
+                        ("index", Some(sc)) => sub_pkg_repo_index(ui, sc)?,
+                        _ => unreachable!(),
+                    }
+                }
                 ("search", Some(m)) => sub_pkg_search(m).await?,
                 ("sign", Some(m)) => sub_pkg_sign(ui, m)?,
                 ("uninstall", Some(m)) => sub_pkg_uninstall(ui, m).await?,
@@ -482,7 +512,7 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
         ("plan", Some(matches)) => {
             match matches.subcommand() {
                 ("init", Some(m)) => sub_plan_init(ui, m)?,
-                ("render", Some(m)) => sub_plan_render(ui, m)?,
+                ("render", Some(m)) => sub_plan_render(ui, m).await?,
                 _ => unreachable!(),
             }
         }
@@ -871,6 +901,15 @@ fn sub_pkg_dependencies(m: &ArgMatches<'_>) -> Result<()> {
     command::pkg::dependencies::start(&ident, scope, direction, &FS_ROOT_PATH)
 }
 
+fn sub_pkg_diff(m: &ArgMatches<'_>) -> Result<()> {
+    let from = required_value_of(m, "FROM").parse()?;
+    let to = required_value_of(m, "TO").parse()?;
+    let to_json = m.is_present("TO_JSON");
+    init()?;
+
+    command::pkg::diff::start(&from, &to, &FS_ROOT_PATH, to_json)
+}
+
 async fn sub_pkg_download(ui: &mut UI,
                           m: &ArgMatches<'_>,
                           _feature_flags: FeatureFlag)
@@ -895,9 +934,19 @@ async fn sub_pkg_download(ui: &mut UI,
 
     let verify = verify_from_matches(m);
     let ignore_missing_seeds = ignore_missing_seeds_from_matches(m);
+    let bundle_path = m.value_of("BUNDLE").map(Path::new);
 
     init()?;
 
+    let bundle_key = match bundle_path {
+        Some(_) => {
+            let origin = origin_param_or_env(m)?;
+            let key_cache = key_cache_from_matches(m)?;
+            Some(key_cache.latest_secret_origin_signing_key(&origin)?)
+        }
+        None => None,
+    };
+
     command::pkg::download::start(ui,
                                   &url,
                                   PRODUCT,
@@ -906,7 +955,8 @@ async fn sub_pkg_download(ui: &mut UI,
                                   download_dir.as_ref(),
                                   token.as_deref(),
                                   verify,
-                                  ignore_missing_seeds).await?;
+                                  ignore_missing_seeds,
+                                  bundle_path.zip(bundle_key.as_ref())).await?;
     Ok(())
 }
 
@@ -964,6 +1014,35 @@ async fn sub_pkg_uninstall(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
                                    uninstall_hook_mode).await
 }
 
+async fn sub_pkg_gc(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let execute_strategy = if m.is_present("DRYRUN") {
+        command::pkg::ExecutionStrategy::DryRun
+    } else {
+        command::pkg::ExecutionStrategy::Run
+    };
+    // unwrap safe as we've validated the input
+    let keep = m.values_of("KEEP")
+                .unwrap_or_default()
+                .map(|i| PackageIdent::from_str(i).unwrap())
+                .collect::<Vec<_>>();
+    let binlink_dir = Path::new(required_value_of(m, "BINLINK_DIR"));
+    let caches = command::pkg::gc::GcCaches { artifacts: m.is_present("ARTIFACTS"),
+                                              src:       m.is_present("SRC"), };
+    let uninstall_hook_mode = if m.is_present("IGNORE_UNINSTALL_HOOK") {
+        UninstallHookMode::Ignore
+    } else {
+        UninstallHookMode::default()
+    };
+
+    command::pkg::gc::start(ui,
+                            &FS_ROOT_PATH,
+                            binlink_dir,
+                            &keep,
+                            caches,
+                            execute_strategy,
+                            uninstall_hook_mode).await
+}
+
 async fn sub_bldr_channel_create(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     let url = bldr_url_from_matches(m)?;
     let origin = origin_param_or_env(m)?;
@@ -1054,19 +1133,26 @@ fn sub_plan_init(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     command::plan::init::start(ui, &origin, minimal, scaffolding_ident, name)
 }
 
-fn sub_plan_render(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+async fn sub_plan_render(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     let template_path = required_value_of(m, "TEMPLATE_PATH");
-    let template_path = Path::new(template_path);
 
-    let default_toml_path = required_value_of(m, "DEFAULT_TOML");
-    let default_toml_path = Path::new(default_toml_path);
+    // The default configuration of a package directory or installed package is its own, unless
+    // one is explicitly passed
+    let default_toml_path = if m.occurrences_of("DEFAULT_TOML") > 0 {
+        m.value_of("DEFAULT_TOML").map(Path::new)
+    } else {
+        None
+    };
 
     let user_toml_path = m.value_of("USER_TOML").map(Path::new);
 
     let mock_data_path = m.value_of("MOCK_DATA").map(Path::new);
 
+    let fixture_path = m.value_of("FIXTURE").map(Path::new);
+
     let print = m.is_present("PRINT");
     let render = !m.is_present("NO_RENDER");
+    let strict = m.is_present("STRICT");
     let quiet = m.is_present("QUIET");
 
     let render_dir = required_value_of(m, "RENDER_DIR");
@@ -1077,10 +1163,12 @@ fn sub_plan_render(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
                                  default_toml_path,
                                  user_toml_path,
                                  mock_data_path,
+                                 fixture_path,
                                  print,
                                  render,
                                  render_dir,
-                                 quiet)
+                                 strict,
+                                 quiet).await
 }
 
 async fn sub_pkg_install(ui: &mut UI,
@@ -1089,7 +1177,6 @@ async fn sub_pkg_install(ui: &mut UI,
                          -> Result<()> {
     let url = bldr_url_from_matches(m)?;
     let channel = channel_from_matches_or_default(m);
-    let install_sources = install_sources_from_matches(m)?;
     let token = maybe_auth_token(m);
     let install_mode =
         if feature_flags.contains(FeatureFlag::OFFLINE_INSTALL) && m.is_present("OFFLINE") {
@@ -1112,6 +1199,37 @@ async fn sub_pkg_install(ui: &mut UI,
 
     init()?;
 
+    if let Some(bundle_path) = m.value_of("BUNDLE") {
+        let idents = match m.values_of("PKG_IDENT_OR_ARTIFACT") {
+            Some(values) => {
+                values.map(|v| PackageIdent::from_str(v).map_err(Error::from))
+                      .collect::<Result<Vec<_>>>()?
+            }
+            None => Vec::new(),
+        };
+        let pkg_installs =
+            common::command::package::install::start_from_bundle(ui,
+                                                                 Path::new(bundle_path),
+                                                                 &idents,
+                                                                 PRODUCT,
+                                                                 VERSION,
+                                                                 &FS_ROOT_PATH,
+                                                                 &cache_artifact_path(Some(FS_ROOT_PATH.as_path())),
+                                                                 install_hook_mode).await?;
+        if let Some(dest_dir) = binlink_dest_dir_from_matches(m) {
+            let force = m.is_present("FORCE");
+            for pkg_install in pkg_installs.iter() {
+                command::pkg::binlink::binlink_all_in_pkg(ui,
+                                                          pkg_install.ident(),
+                                                          &dest_dir,
+                                                          &FS_ROOT_PATH,
+                                                          force)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let install_sources = install_sources_from_matches(m)?;
     for install_source in install_sources.iter() {
         let pkg_install =
             common::command::package::install::start(ui,
@@ -1144,6 +1262,15 @@ fn sub_pkg_path(m: &ArgMatches<'_>) -> Result<()> {
     command::pkg::path::start(&ident, &FS_ROOT_PATH)
 }
 
+fn sub_pkg_audit(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let listing_type = ListingType::from(m);
+    let key_cache = key_cache_from_matches(m)?;
+    let to_json = m.is_present("TO_JSON");
+    init()?;
+
+    command::pkg::audit::start(ui, &listing_type, &FS_ROOT_PATH, &key_cache, to_json)
+}
+
 fn sub_pkg_list(m: &ArgMatches<'_>) -> Result<()> {
     let listing_type = ListingType::from(m);
 
@@ -1262,6 +1389,17 @@ fn sub_pkg_verify(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     command::pkg::verify::start(ui, src, &key_cache)
 }
 
+fn sub_pkg_repo_index(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let dir = Path::new(required_value_of(m, "DIR"));
+    let channels = m.values_of("CHANNEL")
+                    .unwrap_or_default()
+                    .map(ChannelIdent::from)
+                    .collect::<Vec<_>>();
+    init()?;
+
+    command::pkg::repo::index::start(ui, dir, &channels)
+}
+
 fn sub_pkg_header(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     let src = Path::new(required_value_of(m, "SOURCE"));
     init()?;
@@ -1309,9 +1447,6 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
     let remote_sup_addr = SrvClient::ctl_addr(remote_sup_addr.as_ref())?;
     let service_group = required_value_of(m, "SERVICE_GROUP").parse::<ServiceGroup>()?;
     let mut ui = ui::ui();
-    let mut validate = sup_proto::ctl::SvcValidateCfg { service_group:
-                                                            Some(service_group.clone().into()),
-                                                        ..Default::default() };
     let mut buf = Vec::with_capacity(sup_proto::butterfly::MAX_SVC_CFG_SIZE);
     let cfg_len = match m.value_of("FILE") {
         Some("-") | None => io::stdin().read_to_end(&mut buf)?,
@@ -1325,7 +1460,72 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
                          sup_proto::butterfly::MAX_SVC_CFG_SIZE))?;
         process::exit(1);
     }
-    validate.cfg = Some(buf.clone());
+    if m.is_present("DRY_RUN") {
+        return svc_cfg_dry_run(&mut ui, &remote_sup_addr, service_group, buf).await;
+    }
+    let version = value_t!(m, "VERSION_NUMBER", u64).unwrap();
+    svc_cfg_apply(m, &mut ui, &remote_sup_addr, service_group, version, buf).await
+}
+
+/// Render the templates of a running service with a configuration and show what would change if
+/// it were applied.
+async fn svc_cfg_dry_run(ui: &mut UI,
+                         remote_sup_addr: &ResolvedListenCtlAddr,
+                         service_group: ServiceGroup,
+                         buf: Vec<u8>)
+                         -> Result<()> {
+    let msg = sup_proto::ctl::SvcCfgDryRun { service_group: Some(service_group.clone().into()),
+                                             cfg: Some(buf),
+                                             ..Default::default() };
+    ui.begin(format!("Rendering configuration for {} without applying it",
+                     service_group))?;
+    let mut response = SrvClient::request(Some(remote_sup_addr), msg).await?;
+    while let Some(message_result) = response.next().await {
+        let reply = message_result?;
+        match reply.message_id() {
+            "ServiceCfgDryRun" => {
+                let dry_run = reply.parse::<sup_proto::types::ServiceCfgDryRun>()
+                                   .map_err(SrvClientError::Decode)?;
+                for diff in &dry_run.diffs {
+                    print!("{}", diff);
+                }
+                if dry_run.diffs.is_empty() {
+                    ui.info("No configuration file or hook would change")?;
+                }
+                if !dry_run.changed_hooks.is_empty() {
+                    ui.info(format!("Changed hooks: {}", dry_run.changed_hooks.join(", ")))?;
+                }
+                if let Some(ref reason) = dry_run.restart_reason {
+                    ui.end(format!("The service would restart because {}", reason))?;
+                } else if dry_run.reconfigure() {
+                    ui.end("The service would be reconfigured")?;
+                } else {
+                    ui.end("The service would neither be reconfigured nor restarted")?;
+                }
+            }
+            "NetErr" => {
+                let m = reply.parse::<sup_proto::net::NetErr>()
+                             .map_err(SrvClientError::Decode)?;
+                return Err(SrvClientError::from(m).into());
+            }
+            _ => return Err(SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into()),
+        }
+    }
+    Ok(())
+}
+
+/// Validate a configuration against a running service and gossip it to its service group.
+async fn svc_cfg_apply(m: &ArgMatches<'_>,
+                       ui: &mut UI,
+                       remote_sup_addr: &ResolvedListenCtlAddr,
+                       service_group: ServiceGroup,
+                       version: u64,
+                       buf: Vec<u8>)
+                       -> Result<()> {
+    let group: sup_proto::types::ServiceGroup = service_group.clone().into();
+    let validate = sup_proto::ctl::SvcValidateCfg { service_group: Some(group.clone()),
+                                                    cfg: Some(buf.clone()),
+                                                    ..Default::default() };
     let key_cache = key_cache_from_matches(m)?;
 
     let mut set = sup_proto::ctl::SvcSetCfg::default();
@@ -1344,8 +1544,8 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
         }
         _ => set.cfg = Some(buf.to_vec()),
     }
-    set.service_group = Some(service_group.into());
-    set.version = Some(value_t!(m, "VERSION_NUMBER", u64).unwrap());
+    set.service_group = Some(group);
+    set.version = Some(version);
     ui.begin(format!("Setting new configuration version {} for {}",
                      set.version
                         .as_ref()
@@ -1356,7 +1556,8 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
                         .map(ToString::to_string)
                         .unwrap_or_else(|| "UNKNOWN".to_string()),))?;
     ui.status(Status::Creating, "service configuration")?;
-    let mut response = SrvClient::request(Some(&remote_sup_addr), validate).await?;
+    let mut violations = 0;
+    let mut response = SrvClient::request(Some(remote_sup_addr), validate).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         match reply.message_id() {
@@ -1367,6 +1568,7 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
                 match ErrCode::try_from(m.code) {
                     Ok(ErrCode::InvalidPayload) => {
                         ui.warn(m)?;
+                        violations += 1;
                     }
                     _ => return Err(SrvClientError::from(m).into()),
                 }
@@ -1374,8 +1576,11 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
             _ => return Err(SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into()),
         }
     }
+    if violations > 0 {
+        return Err(Error::ConfigSchemaViolation(service_group.to_string(), violations));
+    }
     ui.status(Status::Applying, format!("via peer {}", remote_sup_addr))?;
-    let mut response = SrvClient::request(Some(&remote_sup_addr), set).await?;
+    let mut response = SrvClient::request(Some(remote_sup_addr), set).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         match reply.message_id() {
@@ -1392,17 +1597,108 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
     Ok(())
 }
 
+async fn sub_svc_config_history(m: &ArgMatches<'_>) -> Result<()> {
+    let service_group = required_value_of(m, "SERVICE_GROUP").parse::<ServiceGroup>()?;
+    let remote_sup_addr = remote_sup_from_input(m)?;
+    let remote_sup_addr = SrvClient::ctl_addr(remote_sup_addr.as_ref())?;
+    let entries = svc_cfg_history(&remote_sup_addr, service_group).await?;
+    if m.is_present("TO_JSON") {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+    for entry in entries.iter().rev() {
+        let applied_at =
+            DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(entry.applied_at()));
+        println!("Version {} applied {} from {}",
+                 entry.incarnation(),
+                 applied_at.to_rfc3339(),
+                 entry.from_id());
+        for change in &entry.diff {
+            println!("    {}", change);
+        }
+    }
+    Ok(())
+}
+
+async fn sub_svc_config_rollback(m: &ArgMatches<'_>) -> Result<()> {
+    let service_group = required_value_of(m, "SERVICE_GROUP").parse::<ServiceGroup>()?;
+    let version = value_t!(m, "VERSION_NUMBER", u64).unwrap();
+    let remote_sup_addr = remote_sup_from_input(m)?;
+    let remote_sup_addr = SrvClient::ctl_addr(remote_sup_addr.as_ref())?;
+    let mut ui = ui::ui();
+    let entries = svc_cfg_history(&remote_sup_addr, service_group.clone()).await?;
+    let cfg =
+        entries.iter()
+               .find(|e| e.incarnation() == version)
+               .map(|e| e.cfg().as_bytes().to_vec())
+               .ok_or_else(|| Error::ConfigVersionNotFound(service_group.to_string(), version))?;
+    // The rolled back configuration must supersede the current one, whose version may not be in
+    // the history if it was rejected by the service's schema.
+    let latest = entries.iter()
+                        .map(|e| e.incarnation())
+                        .max()
+                        .unwrap_or_default();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)
+                               .map(|d| d.as_secs())
+                               .unwrap_or_default();
+    let new_version = (latest + 1).max(now);
+    ui.status(Status::Applying,
+              format!("configuration version {} as version {}",
+                      version, new_version))?;
+    svc_cfg_apply(m,
+                  &mut ui,
+                  &remote_sup_addr,
+                  service_group,
+                  new_version,
+                  cfg).await
+}
+
+/// Retrieve the configurations most recently applied to a running service, oldest first.
+async fn svc_cfg_history(remote_sup_addr: &ResolvedListenCtlAddr,
+                         service_group: ServiceGroup)
+                         -> Result<Vec<sup_proto::types::ServiceCfgHistoryEntry>> {
+    let msg = sup_proto::ctl::SvcGetCfgHistory { service_group: Some(service_group.into()), };
+    let mut entries = Vec::new();
+    let mut response = SrvClient::request(Some(remote_sup_addr), msg).await?;
+    while let Some(message_result) = response.next().await {
+        let reply = message_result?;
+        match reply.message_id() {
+            "ServiceCfgHistoryEntry" => {
+                entries.push(reply.parse::<sup_proto::types::ServiceCfgHistoryEntry>()
+                                  .map_err(SrvClientError::Decode)?);
+            }
+            "NetOk" => (),
+            "NetErr" => {
+                let m = reply.parse::<sup_proto::net::NetErr>()
+                             .map_err(SrvClientError::Decode)?;
+                return Err(SrvClientError::from(m).into());
+            }
+            _ => return Err(SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into()),
+        }
+    }
+    Ok(entries)
+}
+
 async fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
     let ident = required_pkg_ident_from_input(m)?;
     let remote_sup_addr = remote_sup_from_input(m)?;
-    let msg = sup_proto::ctl::SvcGetDefaultCfg { ident: Some(ident.into()), };
+    let layer = m.value_of("LAYER");
+    let diff = m.is_present("DIFF");
+    let effective = m.is_present("EFFECTIVE") || layer.is_some() || diff;
+    let msg = sup_proto::ctl::SvcGetDefaultCfg { ident:     Some(ident.into()),
+                                                 effective: Some(effective), };
     let mut response = SrvClient::request(remote_sup_addr.as_ref(), msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         match reply.message_id() {
             "ServiceCfg" => {
-                reply.parse::<sup_proto::types::ServiceCfg>()
-                     .map_err(SrvClientError::Decode)?;
+                let cfg = reply.parse::<sup_proto::types::ServiceCfg>()
+                               .map_err(SrvClientError::Decode)?;
+                if effective {
+                    print_svc_cfg_keys(&cfg, layer, diff)?;
+                } else {
+                    print!("{}", cfg.default());
+                }
             }
             "NetErr" => {
                 let m = reply.parse::<sup_proto::net::NetErr>()
@@ -1415,6 +1711,54 @@ async fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
     Ok(())
 }
 
+/// Print the keys of a service's merged configuration with the layer and source that set them,
+/// optionally only those set by `layer` or those that differ from the package's defaults.
+fn print_svc_cfg_keys(cfg: &sup_proto::types::ServiceCfg,
+                      layer: Option<&str>,
+                      diff: bool)
+                      -> Result<()> {
+    let keys = cfg.keys
+                  .iter()
+                  .filter(|k| layer.map_or(true, |layer| k.layer() == layer))
+                  .filter(|k| !diff || k.default_value.as_deref() != Some(k.value()));
+    let mut out = TabWriter::new(io::stdout());
+    if diff {
+        for key in keys {
+            match key.default_value {
+                Some(ref default) => {
+                    writeln!(out,
+                             "~ {} = {} -> {}\t{}\t{}",
+                             key.key(),
+                             default,
+                             key.value(),
+                             key.layer(),
+                             key.source())?
+                }
+                None => {
+                    writeln!(out,
+                             "+ {} = {}\t{}\t{}",
+                             key.key(),
+                             key.value(),
+                             key.layer(),
+                             key.source())?
+                }
+            }
+        }
+    } else {
+        writeln!(out, "KEY\tVALUE\tLAYER\tSOURCE")?;
+        for key in keys {
+            writeln!(out,
+                     "{}\t{}\t{}\t{}",
+                     key.key(),
+                     key.value(),
+                     key.layer(),
+                     key.source())?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
 async fn sub_svc_load(svc_load: SvcLoad) -> Result<()> {
     let remote_sup_addr = svc_load.remote_sup.clone();
     let msg = biome_sup_protocol::ctl::SvcLoad::try_from(svc_load)?;
@@ -1452,6 +1796,22 @@ async fn sub_svc_update(u: bio::cli::bio::svc::Update) -> Result<()> {
     gateway_util::send(ctl_addr.inner(), msg).await
 }
 
+async fn sub_svc_rollout(r: SvcRollout) -> Result<()> {
+    let msg = sup_proto::ctl::SvcRollout { ident:  Some(r.pkg_ident.pkg_ident().into()),
+                                           action: Some(r.action as i32), };
+    gateway_util::send(r.remote_sup.inner(), msg).await
+}
+
+async fn sub_svc_update_now(u: SvcUpdateNow) -> Result<()> {
+    let msg = sup_proto::ctl::SvcUpdateNow { ident: Some(u.pkg_ident.pkg_ident().into()), };
+    gateway_util::send(u.remote_sup.inner(), msg).await
+}
+
+async fn sub_svc_run_job(r: SvcRunJob) -> Result<()> {
+    let msg = sup_proto::ctl::SvcRunJob { ident: Some(r.pkg_ident.pkg_ident().into()), };
+    gateway_util::send(r.remote_sup.inner(), msg).await
+}
+
 async fn sub_svc_start(m: &ArgMatches<'_>) -> Result<()> {
     let ident = required_pkg_ident_from_input(m)?;
     let msg = sup_proto::ctl::SvcStart { ident: Some(ident.into()), };
@@ -1460,7 +1820,8 @@ async fn sub_svc_start(m: &ArgMatches<'_>) -> Result<()> {
 }
 
 async fn sub_svc_status(pkg_ident: Option<PackageIdent>,
-                        remote_sup: Option<&ResolvedListenCtlAddr>)
+                        remote_sup: Option<&ResolvedListenCtlAddr>,
+                        updates: bool)
                         -> Result<()> {
     let msg = sup_proto::ctl::SvcStatus { ident: pkg_ident.map(Into::into), };
 
@@ -1469,13 +1830,13 @@ async fn sub_svc_status(pkg_ident: Option<PackageIdent>,
     // Ensure there is at least one result from the server otherwise produce an error
     if let Some(message_result) = response.next().await {
         let reply = message_result?;
-        print_svc_status(&mut out, &reply, true)?;
+        print_svc_status(&mut out, &reply, true, updates)?;
     } else {
         return Err(SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into());
     }
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
-        print_svc_status(&mut out, &reply, false)?;
+        print_svc_status(&mut out, &reply, false, updates)?;
     }
     out.flush()?;
     Ok(())
@@ -1616,6 +1977,12 @@ async fn sub_sup_restart(remote_sup: Option<&ResolvedListenCtlAddr>) -> Result<(
     Ok(())
 }
 
+#[cfg(not(target_os = "macos"))]
+async fn sub_sup_update_now(remote_sup: Option<&ResolvedListenCtlAddr>) -> Result<()> {
+    let msg = sup_proto::ctl::SupUpdateNow::default();
+    gateway_util::send(remote_sup, msg).await
+}
+
 #[cfg(not(target_os = "macos"))]
 fn sub_sup_secret_generate() -> Result<()> {
     let mut ui = ui::ui();
@@ -1938,7 +2305,8 @@ fn excludes_from_matches(matches: &ArgMatches<'_>) -> Vec<PackageIdent> {
 
 fn print_svc_status<T>(out: &mut T,
                        reply: &SrvMessage,
-                       print_header: bool)
+                       print_header: bool,
+                       updates: bool)
                        -> result::Result<(), SrvClientError>
     where T: io::Write
 {
@@ -1961,6 +2329,9 @@ fn print_svc_status<T>(out: &mut T,
             return Ok(());
         }
     };
+    if updates {
+        return print_svc_update(out, status, print_header);
+    }
     let svc_desired_state = status.desired_state
                                   .map_or("<none>".to_string(), |s| s.to_string());
     let (svc_state, svc_pid, svc_elapsed) = {
@@ -1995,6 +2366,42 @@ fn print_svc_status<T>(out: &mut T,
              svc_elapsed,
              svc_pid,
              status.service_group,)?;
+    if let Some(rollout) = status.update_rollout {
+        writeln!(out, "  {}", rollout)?;
+        if rollout.state == "halted" {
+            writeln!(out,
+                     "  a member rolled the update back; abort it or publish a new release")?;
+        }
+    }
+    if let Some(held_at) = status.held_at {
+        match status.available_update {
+            Some(update) => writeln!(out, "  held at {}; update to {} available", held_at, update)?,
+            None => writeln!(out, "  held at {}", held_at)?,
+        }
+    }
+    Ok(())
+}
+
+fn print_svc_update<T>(out: &mut T,
+                       status: sup_proto::types::ServiceStatus,
+                       print_header: bool)
+                       -> result::Result<(), SrvClientError>
+    where T: io::Write
+{
+    if print_header {
+        writeln!(out, "{}", UPDATES_HEADER.join("\t")).unwrap();
+    }
+    let latest = status.latest_in_channel
+                       .map_or("<none>".to_string(), |l| l.to_string());
+    let update = status.available_update
+                       .map_or("<none>".to_string(), |u| u.to_string());
+    let last_checked = match status.last_update_check {
+        Some(t) => DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(t)).to_rfc3339(),
+        None => "<never>".to_string(),
+    };
+    writeln!(out,
+             "{}\t{}\t{}\t{}\t{}",
+             status.ident, latest, update, last_checked, status.service_group)?;
     Ok(())
 }
 
@@ -2173,4 +2580,52 @@ mod test {
             binlink_dest_dir_from_matches(pkg_install_matches)
         }
     }
+
+    mod print_svc_update {
+        use super::*;
+        use biome_core::service::ServiceGroup;
+
+        fn status() -> sup_proto::types::ServiceStatus {
+            let ident = PackageIdent::from_str("core/redis/4.0.14/20190319155852").unwrap();
+            let service_group = ServiceGroup::from_str("redis.default").unwrap();
+            sup_proto::types::ServiceStatus { ident: ident.into(),
+                                              service_group: service_group.into(),
+                                              ..Default::default() }
+        }
+
+        fn output(status: sup_proto::types::ServiceStatus, print_header: bool) -> String {
+            let mut out = Vec::new();
+            super::super::print_svc_update(&mut out, status, print_header).unwrap();
+            String::from_utf8(out).unwrap()
+        }
+
+        fn row(columns: &[&str]) -> String { format!("{}\n", columns.join("\t")) }
+
+        #[test]
+        fn unchecked_services_have_no_latest_release_or_update() {
+            assert_eq!(output(status(), true),
+                       row(&UPDATES_HEADER)
+                       + &row(&["core/redis/4.0.14/20190319155852",
+                                "<none>",
+                                "<none>",
+                                "<never>",
+                                "redis.default"]));
+        }
+
+        #[test]
+        fn available_updates_are_a_column_of_their_own() {
+            let latest = PackageIdent::from_str("core/redis/5.0.0/20200101000000").unwrap();
+            let mut status = status();
+            status.latest_in_channel = Some(latest.clone().into());
+            status.available_update = Some(latest.into());
+            status.last_update_check = Some(0);
+
+            assert_eq!(output(status, false),
+                       row(&["core/redis/4.0.14/20190319155852",
+                             "core/redis/5.0.0/20200101000000",
+                             "core/redis/5.0.0/20200101000000",
+                             "1970-01-01T00:00:00+00:00",
+                             "redis.default"]));
+        }
+    }
 }
@@ -40,6 +40,7 @@ use reqwest::{header::CONTENT_LENGTH,
               Body,
               IntoUrl,
               RequestBuilder,
+              Response,
               StatusCode};
 use serde::{Deserialize,
             Serialize};
@@ -193,26 +194,7 @@ impl BuilderAPIClient {
         fs::create_dir_all(dst_path)?;
         let file_name = response::get_header(&resp, X_FILENAME)?;
         let dst_file_path = dst_path.join(file_name);
-        let w = AtomicWriter::new_with_permissions(&dst_file_path, permissions)?;
-        let content_length = response::get_header(&resp, CONTENT_LENGTH);
-        let mut body = Cursor::new(resp.bytes().await?);
-        // Blocking IO is used because of `DisplayProgress` which relies on the `Write` trait.
-        task::spawn_blocking(move || {
-            w.with_writer(|mut f| {
-                 // There will be no CONTENT_LENGTH header if an on prem
-                 // builder is using chunked transfer encoding
-                 match (progress, content_length) {
-                     (Some(mut progress), Ok(content_length)) => {
-                         let size = content_length.parse().map_err(Error::ParseIntError)?;
-                         progress.size(size);
-                         let mut writer = BroadcastWriter::new(&mut f, progress);
-                         io::copy(&mut body, &mut writer).map_err(Error::IO)
-                     }
-                     _ => io::copy(&mut body, &mut f).map_err(Error::IO),
-                 }
-             })?;
-            Ok(dst_file_path)
-        }).await?
+        write_response_body(resp, dst_file_path, permissions, progress).await
     }
 
     async fn upload_body(src_path: &Path,
@@ -1485,6 +1467,35 @@ macro_rules! retry_builder_api {
 
 fn origin_keys_path(origin: &Origin) -> String { format!("depot/origins/{}/keys", origin) }
 
+/// Write the body of a successful response to `dst_file_path`, reporting progress if the response
+/// has a known length.
+pub(crate) async fn write_response_body(resp: Response,
+                                        dst_file_path: PathBuf,
+                                        permissions: Permissions,
+                                        progress: Option<Box<dyn DisplayProgress>>)
+                                        -> Result<PathBuf> {
+    let w = AtomicWriter::new_with_permissions(&dst_file_path, permissions)?;
+    let content_length = response::get_header(&resp, CONTENT_LENGTH);
+    let mut body = Cursor::new(resp.bytes().await?);
+    // Blocking IO is used because of `DisplayProgress` which relies on the `Write` trait.
+    task::spawn_blocking(move || {
+        w.with_writer(|mut f| {
+             // There will be no CONTENT_LENGTH header if an on prem
+             // builder is using chunked transfer encoding
+             match (progress, content_length) {
+                 (Some(mut progress), Ok(content_length)) => {
+                     let size = content_length.parse().map_err(Error::ParseIntError)?;
+                     progress.size(size);
+                     let mut writer = BroadcastWriter::new(&mut f, progress);
+                     io::copy(&mut body, &mut writer).map_err(Error::IO)
+                 }
+                 _ => io::copy(&mut body, &mut f).map_err(Error::IO),
+             }
+         })?;
+        Ok(dst_file_path)
+    }).await?
+}
+
 fn package_download(package: &PackageIdent) -> String {
     format!("{}/download", package_path(package))
 }
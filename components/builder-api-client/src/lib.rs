@@ -7,7 +7,9 @@ use biome_http_client as bio_http;
 
 pub mod builder;
 pub mod error;
+pub mod package_source;
 pub mod response;
+pub mod static_repo;
 
 use std::str::FromStr;
 
@@ -29,7 +31,11 @@ pub use crate::{builder::{BuilderAPIClient,
                           API_RETRY_DELAY},
                 error::{APIFailure,
                         Error,
-                        Result}};
+                        Result},
+                package_source::{package_source,
+                                 PackageSource},
+                static_repo::{RepoIndex,
+                              StaticRepoClient}};
 
 pub trait DisplayProgress: Write + Send + Sync {
     fn size(&mut self, size: u64);
@@ -141,7 +147,7 @@ mod json {
     }
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Package {
     pub ident:    PackageIdent,
     pub checksum: String,
@@ -0,0 +1,120 @@
+//! The operations needed to resolve, download and verify packages, independent of where the
+//! packages are served from.
+//!
+//! Most of the time packages come from a Builder depot through `BuilderAPIClient`, but they can
+//! also be served from a static package repository (see `StaticRepoClient`) on the local
+//! filesystem or a plain HTTP file server. Use `package_source` to get the right implementation
+//! for a URL.
+
+use crate::{builder::BuilderAPIClient,
+            error::Result,
+            static_repo::StaticRepoClient,
+            DisplayProgress,
+            Package};
+use async_trait::async_trait;
+use biome_core::{origin::Origin,
+                   package::{PackageArchive,
+                             PackageIdent,
+                             PackageTarget},
+                   ChannelIdent};
+use std::path::{Path,
+                PathBuf};
+
+#[async_trait]
+pub trait PackageSource: Send + Sync {
+    /// Returns the identifier of the latest package in `channel` that satisfies `package`.
+    async fn show_package(&self,
+                          (package, target): (&PackageIdent, PackageTarget),
+                          channel: &ChannelIdent,
+                          token: Option<&str>)
+                          -> Result<PackageIdent> {
+        Ok(self.show_package_metadata((package, target), channel, token)
+               .await?
+               .ident)
+    }
+
+    /// Returns the latest package in `channel` that satisfies `package`.
+    async fn show_package_metadata(&self,
+                                   (package, target): (&PackageIdent, PackageTarget),
+                                   channel: &ChannelIdent,
+                                   token: Option<&str>)
+                                   -> Result<Package>;
+
+    /// Download the artifact of a fully qualified package into `dst_path`.
+    async fn fetch_package<'a>(&'a self,
+                               (ident, target): (&'a PackageIdent, PackageTarget),
+                               token: Option<&'a str>,
+                               dst_path: &'a Path,
+                               progress: Option<Box<dyn DisplayProgress>>)
+                               -> Result<PackageArchive>;
+
+    /// Download a revision of an origin's public signing key into `dst_path`.
+    async fn fetch_origin_key<'a>(&'a self,
+                                  origin: &'a str,
+                                  revision: &'a str,
+                                  token: Option<&'a str>,
+                                  dst_path: &'a Path,
+                                  progress: Option<Box<dyn DisplayProgress>>)
+                                  -> Result<PathBuf>;
+
+    /// Returns the names of the channels of an origin.
+    async fn list_channels(&self,
+                           origin: &Origin,
+                           include_sandbox_channels: bool)
+                           -> Result<Vec<String>>;
+}
+
+#[async_trait]
+impl PackageSource for BuilderAPIClient {
+    async fn show_package_metadata(&self,
+                                   package: (&PackageIdent, PackageTarget),
+                                   channel: &ChannelIdent,
+                                   token: Option<&str>)
+                                   -> Result<Package> {
+        BuilderAPIClient::show_package_metadata(self, package, channel, token).await
+    }
+
+    async fn fetch_package<'a>(&'a self,
+                               package: (&'a PackageIdent, PackageTarget),
+                               token: Option<&'a str>,
+                               dst_path: &'a Path,
+                               progress: Option<Box<dyn DisplayProgress>>)
+                               -> Result<PackageArchive> {
+        BuilderAPIClient::fetch_package(self, package, token, dst_path, progress).await
+    }
+
+    async fn fetch_origin_key<'a>(&'a self,
+                                  origin: &'a str,
+                                  revision: &'a str,
+                                  token: Option<&'a str>,
+                                  dst_path: &'a Path,
+                                  progress: Option<Box<dyn DisplayProgress>>)
+                                  -> Result<PathBuf> {
+        BuilderAPIClient::fetch_origin_key(self, origin, revision, token, dst_path, progress).await
+    }
+
+    async fn list_channels(&self,
+                           origin: &Origin,
+                           include_sandbox_channels: bool)
+                           -> Result<Vec<String>> {
+        BuilderAPIClient::list_channels(self, origin, include_sandbox_channels).await
+    }
+}
+
+/// Returns the package source for `url`: a static package repository for `file://` and
+/// `static+http(s)://` URLs, and a Builder depot otherwise.
+pub fn package_source(url: &str,
+                      product: &str,
+                      version: &str,
+                      fs_root_path: Option<&Path>)
+                      -> Result<Box<dyn PackageSource>> {
+    match StaticRepoClient::from_url(url, product, version, fs_root_path)? {
+        Some(repo) => Ok(Box::new(repo)),
+        None => {
+            Ok(Box::new(BuilderAPIClient::new(url,
+                                              product,
+                                              version,
+                                              fs_root_path)?))
+        }
+    }
+}
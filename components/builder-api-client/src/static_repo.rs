@@ -0,0 +1,390 @@
+//! A package repository made of static files, usable in place of a Builder depot.
+//!
+//! A repository is a directory with the following layout, served either from the local
+//! filesystem (`file:///srv/biome`) or from a plain HTTP file server
+//! (`static+https://mirror.example.com/biome`):
+//!
+//! ```text
+//! index.json
+//! artifacts/<origin>-<name>-<version>-<release>-<target>.hart
+//! keys/<origin>-<revision>.pub
+//! ```
+//!
+//! `artifacts/` and `keys/` are laid out the same way `bio pkg download` writes them, so a
+//! download directory becomes a repository once it is indexed with `bio pkg repo index`. The
+//! index records the metadata and channels of every artifact so the latest package in a channel
+//! can be resolved without a Builder API.
+
+use crate::{bio_http::ApiClient,
+            builder::write_response_body,
+            error::{Error,
+                    Result},
+            package_source::PackageSource,
+            response,
+            DisplayProgress,
+            Package};
+use async_trait::async_trait;
+use biome_core::{fs::{AtomicWriter,
+                        Permissions,
+                        DEFAULT_CACHED_ARTIFACT_PERMISSIONS,
+                        DEFAULT_PUBLIC_KEY_PERMISSIONS},
+                   origin::Origin,
+                   package::{Identifiable,
+                             PackageArchive,
+                             PackageIdent,
+                             PackageTarget},
+                   ChannelIdent};
+use log::debug;
+use reqwest::StatusCode;
+use serde::{Deserialize,
+            Serialize};
+use std::{collections::{BTreeSet,
+                        HashMap},
+          fs::{self,
+               File},
+          io::{self,
+               Write},
+          path::{Path,
+                 PathBuf}};
+use url::Url;
+
+/// The name of the index file at the root of a repository.
+pub const REPO_INDEX_FILE: &str = "index.json";
+/// The version of the index format written by this crate.
+pub const REPO_FORMAT_VERSION: u8 = 1;
+/// The prefix that marks an HTTP(S) URL as pointing at a static repository instead of Builder.
+pub const STATIC_URL_PREFIX: &str = "static+";
+
+const ARTIFACTS_DIR: &str = "artifacts";
+const KEYS_DIR: &str = "keys";
+
+/// The index of a static package repository.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RepoIndex {
+    pub format_version: u8,
+    pub packages:       Vec<RepoPackage>,
+}
+
+/// An artifact in a static package repository.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RepoPackage {
+    #[serde(flatten)]
+    pub package:  Package,
+    pub target:   PackageTarget,
+    /// The file name of the artifact in the `artifacts` directory
+    pub file:     String,
+    pub channels: BTreeSet<String>,
+}
+
+impl RepoIndex {
+    /// Build the index of the repository in `dir` from the artifacts it contains.
+    ///
+    /// Artifacts that are already part of the repository's current index keep their channels.
+    /// New artifacts are added to the `unstable` channel and to each of `channels`.
+    pub fn build(dir: &Path, channels: &[ChannelIdent]) -> Result<Self> {
+        let mut existing = match File::open(dir.join(REPO_INDEX_FILE)) {
+                               Ok(file) => serde_json::from_reader::<_, RepoIndex>(file)?.packages,
+                               Err(ref e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+                               Err(e) => return Err(Error::IO(e)),
+                           }.into_iter()
+                            .map(|p| (p.file.clone(), p))
+                            .collect::<HashMap<_, _>>();
+
+        let mut paths = fs::read_dir(dir.join(ARTIFACTS_DIR))?.map(|e| e.map(|e| e.path()))
+                                                              .collect::<io::Result<Vec<_>>>()?;
+        paths.retain(|p| p.extension().map_or(false, |e| e == "hart"));
+        paths.sort();
+
+        let mut packages = Vec::with_capacity(paths.len());
+        for path in paths {
+            let mut archive = PackageArchive::new(&path)?;
+            let file = archive.file_name();
+            let checksum = archive.checksum()?;
+            if let Some(package) = existing.remove(&file)
+                                           .filter(|p| p.package.checksum == checksum)
+            {
+                packages.push(package);
+                continue;
+            }
+            debug!("Indexing new artifact {}", path.display());
+            let package =
+                Package { ident: archive.ident()?,
+                          checksum,
+                          manifest: archive.manifest()?.to_string(),
+                          deps: archive.deps()?,
+                          tdeps: archive.tdeps()?,
+                          exposes: archive.exposes()?.into_iter().map(u32::from).collect(),
+                          config: archive.config().unwrap_or_default().to_string() };
+            let channels = channels.iter()
+                                   .map(ToString::to_string)
+                                   .chain(Some(ChannelIdent::unstable().to_string()))
+                                   .collect();
+            packages.push(RepoPackage { package,
+                                        target: archive.target()?,
+                                        file,
+                                        channels });
+        }
+        Ok(RepoIndex { format_version: REPO_FORMAT_VERSION,
+                       packages })
+    }
+
+    /// Write the index to the root of the repository in `dir`.
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        let w = AtomicWriter::new_with_permissions(&dir.join(REPO_INDEX_FILE),
+                                                   DEFAULT_CACHED_ARTIFACT_PERMISSIONS)?;
+        let content = serde_json::to_vec_pretty(self)?;
+        w.with_writer(|f| f.write_all(&content))?;
+        Ok(())
+    }
+
+    /// Returns the latest package in `channel` for `target` that satisfies `ident`.
+    pub fn latest(&self,
+                  ident: &PackageIdent,
+                  target: PackageTarget,
+                  channel: &ChannelIdent)
+                  -> Option<&RepoPackage> {
+        self.packages
+            .iter()
+            .filter(|p| {
+                p.target == target
+                && p.channels.contains(channel.as_str())
+                && p.package.ident.satisfies(ident)
+            })
+            .max_by(|a, b| a.package.ident.cmp(&b.package.ident))
+    }
+}
+
+enum RepoLocation {
+    Local(PathBuf),
+    Remote(ApiClient),
+}
+
+/// A client for a static package repository.
+pub struct StaticRepoClient(RepoLocation);
+
+impl StaticRepoClient {
+    /// Returns a client for the static repository at `url`, or `None` if `url` does not point at
+    /// a static repository.
+    pub fn from_url(url: &str,
+                    product: &str,
+                    version: &str,
+                    fs_root_path: Option<&Path>)
+                    -> Result<Option<Self>> {
+        if let Some(remote) = url.strip_prefix(STATIC_URL_PREFIX) {
+            let client = ApiClient::new(remote, product, version, fs_root_path)
+                .map_err(Error::BiomeHttpClient)?;
+            return Ok(Some(StaticRepoClient(RepoLocation::Remote(client))));
+        }
+        match Url::parse(url) {
+            Ok(parsed) if parsed.scheme() == "file" => {
+                let path = parsed.to_file_path()
+                                 .map_err(|_| {
+                                     Error::BiomeCore(biome_core::Error::InvalidUrl(url.to_string()))
+                                 })?;
+                Ok(Some(StaticRepoClient(RepoLocation::Local(path))))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn index(&self) -> Result<RepoIndex> {
+        let content = match &self.0 {
+            RepoLocation::Local(dir) => {
+                fs::read(dir.join(REPO_INDEX_FILE)).map_err(|e| not_found_or(e, REPO_INDEX_FILE))?
+            }
+            RepoLocation::Remote(client) => {
+                let resp = client.get(REPO_INDEX_FILE).send().await?;
+                let resp = response::ok_if(resp, &[StatusCode::OK]).await?;
+                resp.bytes().await.map_err(Error::BadResponseBody)?.to_vec()
+            }
+        };
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Copy the file at `path` within the repository to `dst_file_path`.
+    async fn fetch_file(&self,
+                        path: &str,
+                        dst_file_path: PathBuf,
+                        permissions: Permissions,
+                        progress: Option<Box<dyn DisplayProgress>>)
+                        -> Result<PathBuf> {
+        debug!("Fetching {} to path: {}", path, dst_file_path.display());
+        if let Some(parent) = dst_file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match &self.0 {
+            RepoLocation::Local(dir) => {
+                let mut src = File::open(dir.join(path)).map_err(|e| not_found_or(e, path))?;
+                let w = AtomicWriter::new_with_permissions(&dst_file_path, permissions)?;
+                w.with_writer(|f| io::copy(&mut src, f))?;
+                Ok(dst_file_path)
+            }
+            RepoLocation::Remote(client) => {
+                let resp = client.get(path).send().await?;
+                let resp = response::ok_if(resp, &[StatusCode::OK]).await?;
+                write_response_body(resp, dst_file_path, permissions, progress).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PackageSource for StaticRepoClient {
+    async fn show_package_metadata(&self,
+                                   (package, target): (&PackageIdent, PackageTarget),
+                                   channel: &ChannelIdent,
+                                   _token: Option<&str>)
+                                   -> Result<Package> {
+        debug!("Retrieving package metadata for {}, target {} from static repository",
+               package, target);
+        self.index()
+            .await?
+            .latest(package, target, channel)
+            .map(|p| p.package.clone())
+            .ok_or_else(|| {
+                Error::APIError(StatusCode::NOT_FOUND,
+                                format!("{} for {} not found in the '{}' channel",
+                                        package, target, channel))
+            })
+    }
+
+    async fn fetch_package<'a>(&'a self,
+                               (ident, target): (&'a PackageIdent, PackageTarget),
+                               _token: Option<&'a str>,
+                               dst_path: &'a Path,
+                               progress: Option<Box<dyn DisplayProgress>>)
+                               -> Result<PackageArchive> {
+        if !ident.fully_qualified() {
+            return Err(Error::IdentNotFullyQualified);
+        }
+        let index = self.index().await?;
+        let file = index.packages
+                        .iter()
+                        .find(|p| p.target == target && p.package.ident == *ident)
+                        .map(|p| p.file.clone())
+                        .ok_or_else(|| {
+                            Error::APIError(StatusCode::NOT_FOUND,
+                                            format!("{} for {} not found", ident, target))
+                        })?;
+        let path = self.fetch_file(&format!("{}/{}", ARTIFACTS_DIR, file),
+                                   dst_path.join(&file),
+                                   DEFAULT_CACHED_ARTIFACT_PERMISSIONS,
+                                   progress)
+                       .await?;
+        Ok(PackageArchive::new(path)?)
+    }
+
+    async fn fetch_origin_key<'a>(&'a self,
+                                  origin: &'a str,
+                                  revision: &'a str,
+                                  _token: Option<&'a str>,
+                                  dst_path: &'a Path,
+                                  progress: Option<Box<dyn DisplayProgress>>)
+                                  -> Result<PathBuf> {
+        let file = format!("{}-{}.pub", origin, revision);
+        self.fetch_file(&format!("{}/{}", KEYS_DIR, file),
+                        dst_path.join(&file),
+                        DEFAULT_PUBLIC_KEY_PERMISSIONS,
+                        progress)
+            .await
+    }
+
+    async fn list_channels(&self,
+                           origin: &Origin,
+                           _include_sandbox_channels: bool)
+                           -> Result<Vec<String>> {
+        let channels = self.index()
+                           .await?
+                           .packages
+                           .into_iter()
+                           .filter(|p| p.package.ident.origin == origin.as_ref())
+                           .flat_map(|p| p.channels)
+                           .collect::<BTreeSet<_>>();
+        Ok(channels.into_iter().collect())
+    }
+}
+
+/// Report a missing repository file the same way Builder reports a missing resource.
+fn not_found_or(err: io::Error, path: &str) -> Error {
+    if err.kind() == io::ErrorKind::NotFound {
+        Error::APIError(StatusCode::NOT_FOUND, format!("{} not found", path))
+    } else {
+        Error::IO(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const FIXTURE: &str = "happyhumans-possums-8.1.4-20160427165340-x86_64-linux.hart";
+
+    fn repo_with_fixture() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(ARTIFACTS_DIR)).unwrap();
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("../core/tests/fixtures")
+                                                       .join(FIXTURE);
+        fs::copy(src, dir.path().join(ARTIFACTS_DIR).join(FIXTURE)).unwrap();
+        dir
+    }
+
+    #[test]
+    fn index_keeps_channels_of_known_artifacts() {
+        let dir = repo_with_fixture();
+        let stable = ChannelIdent::stable();
+        let index = RepoIndex::build(dir.path(), &[stable.clone()]).unwrap();
+        index.write(dir.path()).unwrap();
+        assert_eq!(index.packages.len(), 1);
+        assert_eq!(index.packages[0].file, FIXTURE);
+
+        let ident: PackageIdent = "happyhumans/possums".parse().unwrap();
+        let target = index.packages[0].target;
+        let latest = index.latest(&ident, target, &stable).unwrap();
+        assert_eq!(latest.package.ident.to_string(),
+                   "happyhumans/possums/8.1.4/20160427165340");
+        assert!(index.latest(&ident, target, &ChannelIdent::from("dev"))
+                     .is_none());
+
+        // Re-indexing with other channels must not move artifacts that are already indexed
+        let reindexed = RepoIndex::build(dir.path(), &[ChannelIdent::from("dev")]).unwrap();
+        assert!(reindexed.latest(&ident, target, &stable).is_some());
+        assert!(reindexed.latest(&ident, target, &ChannelIdent::from("dev"))
+                         .is_none());
+    }
+
+    #[tokio::test]
+    async fn local_repo_serves_indexed_artifacts() {
+        let dir = repo_with_fixture();
+        RepoIndex::build(dir.path(), &[]).unwrap()
+                                         .write(dir.path())
+                                         .unwrap();
+        let url = Url::from_directory_path(dir.path()).unwrap();
+        let client = StaticRepoClient::from_url(url.as_str(), "test", "0.0.0", None).unwrap()
+                                                                                    .unwrap();
+
+        let ident: PackageIdent = "happyhumans/possums".parse().unwrap();
+        let target: PackageTarget = "x86_64-linux".parse().unwrap();
+        let unstable = ChannelIdent::unstable();
+        let latest = client.show_package((&ident, target), &unstable, None)
+                           .await
+                           .unwrap();
+        assert!(latest.fully_qualified());
+
+        let dst = TempDir::new().unwrap();
+        let mut archive = client.fetch_package((&latest, target), None, dst.path(), None)
+                                .await
+                                .unwrap();
+        assert_eq!(archive.ident().unwrap(), latest);
+
+        match client.show_package((&ident, target), &ChannelIdent::stable(), None)
+                    .await
+        {
+            Err(Error::APIError(StatusCode::NOT_FOUND, _)) => {}
+            other => {
+                panic!("Expected a not found error, got {:?}",
+                       other.map(|i| i.to_string()))
+            }
+        }
+    }
+}
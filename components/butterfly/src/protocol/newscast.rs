@@ -79,7 +79,8 @@ impl From<CService> for Rumor {
                                 pkg:             Some(value.pkg),
                                 pkg_incarnation: Some(value.pkg_incarnation),
                                 cfg:             Some(value.cfg),
-                                sys:             Some(value.sys.into()), };
+                                sys:             Some(value.sys.into()),
+                                rollout_control: value.rollout_control.map(Into::into), };
         Rumor { r#type:  RumorType::Service as i32,
                 tag:     Vec::default(),
                 from_id: Some(value.member_id),
@@ -28,10 +28,13 @@ pub struct Service {
     pub service_group:   ServiceGroup,
     pub incarnation:     u64,
     pub initialized:     bool,
+    /// Whether the service reports that it is ready to serve
+    pub ready:           bool,
     pub pkg:             String,
     pub pkg_incarnation: u64,
     pub cfg:             Vec<u8>,
     pub sys:             SysInfo,
+    pub rollout_control: Option<RolloutControl>,
 }
 
 impl fmt::Display for Service {
@@ -47,7 +50,7 @@ impl Serialize for Service {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut strukt = serializer.serialize_struct("service", 7)?;
+        let mut strukt = serializer.serialize_struct("service", 10)?;
         let cfg: toml::value::Table =
             toml::from_str(str::from_utf8(&self.cfg).unwrap_or_default()).unwrap_or_default();
         strukt.serialize_field("member_id", &self.member_id)?;
@@ -58,6 +61,8 @@ impl Serialize for Service {
         strukt.serialize_field("cfg", &cfg)?;
         strukt.serialize_field("sys", &self.sys)?;
         strukt.serialize_field("initialized", &self.initialized)?;
+        strukt.serialize_field("ready", &self.ready)?;
+        strukt.serialize_field("rollout_control", &self.rollout_control)?;
         strukt.end()
     }
 }
@@ -101,9 +106,11 @@ impl Service {
                   service_group,
                   incarnation: 0,
                   initialized: false,
+                  ready: true,
                   pkg: package.to_string(),
                   pkg_incarnation: 0,
                   sys,
+                  rollout_control: None,
                   cfg: cfg.map(|v| {
                               // Directly serializing a toml::value::Table can lead to an error
                               // Wrapping it in a toml::value::Value makes this operation safe
@@ -133,12 +140,15 @@ impl FromProto<newscast::Rumor> for Service {
                                 .and_then(|s| ServiceGroup::from_str(&s).map_err(Error::from))?,
                      incarnation:     payload.incarnation.unwrap_or(0),
                      initialized:     payload.initialized.unwrap_or(false),
+                     // Members that do not report their readiness are taken to be ready
+                     ready:           payload.ready.unwrap_or(true),
                      pkg:             payload.pkg.ok_or(Error::ProtocolMismatch("pkg"))?,
                      pkg_incarnation: payload.pkg_incarnation.unwrap_or(0),
                      cfg:             payload.cfg.unwrap_or_default(),
                      sys:             payload.sys
                                              .ok_or(Error::ProtocolMismatch("sys"))
-                                             .and_then(SysInfo::from_proto)?, })
+                                             .and_then(SysInfo::from_proto)?,
+                     rollout_control: payload.rollout_control.map(RolloutControl::from), })
     }
 }
 
@@ -148,10 +158,12 @@ impl From<Service> for newscast::Service {
                             service_group:   Some(value.service_group.to_string()),
                             incarnation:     Some(value.incarnation),
                             initialized:     Some(value.initialized),
+                            ready:           Some(value.ready),
                             pkg:             Some(value.pkg),
                             pkg_incarnation: Some(value.pkg_incarnation),
                             cfg:             Some(value.cfg),
-                            sys:             Some(value.sys.into()), }
+                            sys:             Some(value.sys.into()),
+                            rollout_control: value.rollout_control.map(Into::into), }
     }
 }
 
@@ -174,6 +186,39 @@ impl Rumor for Service {
     fn key(&self) -> &str { self.service_group.as_ref() }
 }
 
+/// Operator controls for a canary or batched rolling update, gossiped with the service rumor of
+/// the member they were issued on. `rollout` is the package incarnation of the update leader the
+/// controls apply to, so they are ignored once a new rollout begins, and `sequence` orders
+/// controls issued on different members of the service group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RolloutControl {
+    pub rollout:  u64,
+    pub sequence: u64,
+    pub paused:   bool,
+    pub approved: bool,
+    pub aborted:  bool,
+}
+
+impl From<newscast::RolloutControl> for RolloutControl {
+    fn from(proto: newscast::RolloutControl) -> Self {
+        RolloutControl { rollout:  proto.rollout.unwrap_or_default(),
+                         sequence: proto.sequence.unwrap_or_default(),
+                         paused:   proto.paused.unwrap_or_default(),
+                         approved: proto.approved.unwrap_or_default(),
+                         aborted:  proto.aborted.unwrap_or_default(), }
+    }
+}
+
+impl From<RolloutControl> for newscast::RolloutControl {
+    fn from(value: RolloutControl) -> Self {
+        newscast::RolloutControl { rollout:  Some(value.rollout),
+                                   sequence: Some(value.sequence),
+                                   paused:   Some(value.paused),
+                                   approved: Some(value.approved),
+                                   aborted:  Some(value.aborted), }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SysInfo {
     pub ip:                String,
@@ -235,9 +280,14 @@ mod tests {
                                  PackageIdent},
                        service::ServiceGroup};
 
-    use super::Service;
-    use crate::rumor::{service::SysInfo,
-                       Rumor};
+    use super::{RolloutControl,
+                Service};
+    use crate::{protocol::{newscast,
+                           FromProto,
+                           Message},
+                rumor::{service::SysInfo,
+                        Rumor,
+                        RumorPayload}};
 
     fn create_service(member_id: &str) -> Service {
         let pkg = PackageIdent::from_str("core/neurosis/1.2.3/20161208121212").unwrap();
@@ -347,4 +397,41 @@ mod tests {
         map.insert("a".into(), toml::value::Value::Table(sub_map));
         Service::new("member_id_val", &package, sg, SysInfo::default(), Some(map));
     }
+
+    #[test]
+    fn rollout_control_survives_the_wire() {
+        let mut service = create_service("adam");
+        let bytes = service.write_to_bytes().unwrap();
+        assert_eq!(Service::from_bytes(&bytes).unwrap().rollout_control, None);
+
+        let control = RolloutControl { rollout:  3,
+                                       sequence: 2,
+                                       paused:   true,
+                                       approved: false,
+                                       aborted:  false, };
+        service.rollout_control = Some(control);
+        let bytes = service.write_to_bytes().unwrap();
+        assert_eq!(Service::from_bytes(&bytes).unwrap().rollout_control,
+                   Some(control));
+    }
+
+    #[test]
+    fn readiness_survives_the_wire() {
+        let mut service = create_service("adam");
+        assert!(service.ready);
+        service.ready = false;
+        let bytes = service.write_to_bytes().unwrap();
+        assert!(!Service::from_bytes(&bytes).unwrap().ready);
+    }
+
+    #[test]
+    fn services_that_do_not_report_readiness_are_ready() {
+        let mut service = create_service("adam");
+        service.ready = false;
+        let mut rumor = newscast::Rumor::from(service);
+        if let Some(RumorPayload::Service(ref mut payload)) = rumor.payload {
+            payload.ready = None;
+        }
+        assert!(Service::from_proto(rumor).unwrap().ready);
+    }
 }
@@ -1552,9 +1552,11 @@ mod tests {
                   incarnation:     Default::default(),
                   pkg_incarnation: Default::default(),
                   initialized:     Default::default(),
+                  ready:           Default::default(),
                   pkg:             Default::default(),
                   cfg:             Default::default(),
-                  sys:             Default::default(), }
+                  sys:             Default::default(),
+                  rollout_control: Default::default(), }
     }
 
     #[test]
@@ -23,10 +23,9 @@
 use crate::{api_client::{self,
                          retry_builder_api,
                          APIFailure,
-                         BuilderAPIClient,
-                         Client,
                          Error::{APIClientError,
                                  APIError},
+                         PackageSource,
                          API_RETRY_COUNT,
                          API_RETRY_DELAY},
             error::{Error,
@@ -49,6 +48,7 @@ use biome_core::{self,
                              FullyQualifiedPackageIdent,
                              Identifiable,
                              PackageArchive,
+                             PackageBundle,
                              PackageIdent,
                              PackageInstall,
                              PackageTarget},
@@ -312,7 +312,7 @@ pub async fn start<U>(ui: &mut U,
     key_cache.setup()?;
     debug!("install key cache: {}", key_cache.as_ref().display());
 
-    let api_client = Client::new(url, product, version, Some(fs_root_path))?;
+    let api_client = api_client::package_source(url, product, version, Some(fs_root_path))?;
     let task = InstallTask { install_mode,
                              local_package_usage,
                              api_client,
@@ -364,6 +364,71 @@ pub fn type_erased_start<'a, U>(
                    install_hook_mode))
 }
 
+/// Install packages from a signed bundle produced by `bio pkg download --bundle`, without
+/// contacting Builder.
+///
+/// The bundle's signature is verified against the key cache before its artifacts and public keys
+/// are added to the local caches. Each of `idents` is then resolved to the newest matching
+/// package in the bundle and installed, along with its transitive dependencies, in offline mode.
+/// If no identifiers are given, the packages the bundle was created for are installed.
+#[allow(clippy::too_many_arguments)]
+pub async fn start_from_bundle<U>(ui: &mut U,
+                                  bundle_path: &Path,
+                                  idents: &[PackageIdent],
+                                  product: &str,
+                                  version: &str,
+                                  fs_root_path: &Path,
+                                  artifact_cache_path: &Path,
+                                  install_hook_mode: InstallHookMode)
+                                  -> Result<Vec<PackageInstall>>
+    where U: UIWriter
+{
+    let key_cache = KeyCache::new(cache_key_path(fs_root_path));
+    key_cache.setup()?;
+
+    ui.begin(format!("Installing from bundle {}", bundle_path.display()))?;
+    ui.status(Status::Verifying, bundle_path.display())?;
+    let bundle = PackageBundle::open(bundle_path, &key_cache)?;
+    debug!("Verified bundle {} signed by {}",
+           bundle_path.display(),
+           bundle.signer());
+    bundle.unpack(artifact_cache_path, &key_cache)?;
+    ui.status(Status::Cached,
+              format!("{} artifacts from bundle", bundle.manifest().packages.len()))?;
+
+    let idents = if idents.is_empty() {
+        bundle.manifest().seeds()?
+    } else {
+        idents.to_vec()
+    };
+    let target = PackageTarget::active_target();
+    let url = biome_core::url::default_bldr_url();
+    let mut installs = Vec::with_capacity(idents.len());
+    for ident in idents {
+        let bundled_ident = bundle.manifest()
+                                  .latest_satisfying(&ident, target)?
+                                  .ok_or_else(|| {
+                                      Error::PackageNotFound(format!("{} for {} in bundle {}",
+                                                                     ident,
+                                                                     target,
+                                                                     bundle_path.display()))
+                                  })?;
+        installs.push(start(ui,
+                            &url,
+                            &ChannelIdent::stable(),
+                            &InstallSource::Ident(bundled_ident, target),
+                            product,
+                            version,
+                            fs_root_path,
+                            artifact_cache_path,
+                            None,
+                            &InstallMode::Offline,
+                            &LocalPackageUsage::default(),
+                            install_hook_mode).await?);
+    }
+    Ok(installs)
+}
+
 pub async fn check_install_hooks<T, P>(ui: &mut T,
                                        package: &PackageInstall,
                                        fs_root_path: P)
@@ -417,7 +482,7 @@ fn read_install_hook_status(path: PathBuf) -> Result<Option<i32>> {
 struct InstallTask<'a> {
     install_mode:        &'a InstallMode,
     local_package_usage: &'a LocalPackageUsage,
-    api_client:          BuilderAPIClient,
+    api_client:          Box<dyn PackageSource>,
     channel:             &'a ChannelIdent,
     fs_root_path:        &'a Path,
     /// The path to the local artifact cache (e.g., /hab/cache/artifacts)
@@ -1,7 +1,8 @@
 use crate::{api_client,
             hcore::{self,
                     package::{FullyQualifiedPackageIdent,
-                              PackageIdent}}};
+                              PackageIdent}},
+            templating::config::schema::Violation};
 #[cfg(windows)]
 use biome_core::os::process::windows_child::ExitStatus;
 #[cfg(not(windows))]
@@ -64,6 +65,9 @@ pub enum Error {
     BadGlyphStyle(String),
     CantUploadGossipToml,
     ChannelNotFound,
+    /// Occurs when a configuration layer does not match the package's configuration schema. The
+    /// first field describes where the configuration came from.
+    ConfigSchemaViolation(String, Vec<Violation>),
     CryptoKeyError(String),
     EditorEnv(env::VarError),
     EditStatus,
@@ -78,6 +82,10 @@ pub enum Error {
         error:         CommandExecutionError,
     },
     InvalidEventStreamToken(String),
+    /// Occurs when a fixture describing the census for offline rendering can't be used
+    InvalidRenderFixture(String),
+    /// Occurs when a secret reference in the configuration names an unknown provider
+    InvalidSecretReference(String),
     /// Occurs when making lower level IO calls.
     IO(io::Error),
     /// Errors when joining paths :)
@@ -97,6 +105,9 @@ pub enum Error {
     /// When an error occurs serializing rendering context
     RenderContextSerialization(serde_json::Error),
     RootRequired,
+    /// Occurs when the value of a secret reference cannot be read. The first field is the
+    /// reference, never the secret itself.
+    SecretResolutionFailed(String, String),
     StatusFileCorrupt(PathBuf),
     StrFromUtf8Error(str::Utf8Error),
     StringFromUtf8Error(string::FromUtf8Error),
@@ -116,6 +127,9 @@ pub enum Error {
     /// When an error occurs parsing toml
     TomlParser(toml::de::Error),
     TomlSerializeError(toml::ser::Error),
+    /// Occurs when gossiped configuration holds a secret reference that reads a file or runs a
+    /// command. The fields are the key and the reference.
+    UntrustedSecretReference(String, String),
     WireDecode(String),
 }
 
@@ -155,6 +169,14 @@ impl fmt::Display for Error {
                 "Can't upload gossip.toml, it's a reserved file name".to_string()
             }
             Error::ChannelNotFound => "Channel not found".to_string(),
+            Error::ConfigSchemaViolation(ref source, ref violations) => {
+                format!("Configuration from {} does not match the package's schema: {}",
+                        source,
+                        violations.iter()
+                                  .map(ToString::to_string)
+                                  .collect::<Vec<_>>()
+                                  .join("; "))
+            }
             Error::CryptoKeyError(ref s) => format!("Missing or invalid key: {}", s),
             Error::EditorEnv(ref e) => format!("Missing EDITOR environment variable: {}", e),
             Error::EditStatus => "Failed edit text command".to_string(),
@@ -176,6 +198,12 @@ impl fmt::Display for Error {
             Error::InvalidEventStreamToken(ref s) => {
                 format!("Invalid event stream token provided: '{}'", s)
             }
+            Error::InvalidRenderFixture(ref e) => format!("Invalid render fixture, {}", e),
+            Error::InvalidSecretReference(ref s) => {
+                format!("Invalid secret reference '{}', expected 'file:<path>', 'env:<name>' or \
+                         'exec:<command>'",
+                        s)
+            }
             Error::IO(ref err) => format!("{}", err),
             Error::JoinPathsError(ref err) => format!("{}", err),
             Error::NamedPipeTimeoutOnStart(ref group, ref hook, ref err) => {
@@ -210,6 +238,9 @@ impl fmt::Display for Error {
             Error::RootRequired => {
                 "Root or administrator permissions required to complete operation".to_string()
             }
+            Error::SecretResolutionFailed(ref reference, ref reason) => {
+                format!("Unable to resolve secret '{}': {}", reference, reason)
+            }
             Error::StatusFileCorrupt(ref path) => {
                 format!("Unable to decode contents of INSTALL_STATUS file, {}",
                         path.display())
@@ -221,6 +252,10 @@ impl fmt::Display for Error {
             Error::TomlMergeError(ref e) => format!("Failed to merge TOML: {}", e),
             Error::TomlParser(ref err) => format!("Failed to parse TOML: {}", err),
             Error::TomlSerializeError(ref e) => format!("Can't serialize TOML: {}", e),
+            Error::UntrustedSecretReference(ref key, ref reference) => {
+                format!("Secret reference '{}' of {} is not allowed in gossiped configuration",
+                        reference, key)
+            }
             Error::WireDecode(ref m) => format!("Failed to decode wire message: {}", m),
         };
         write!(f, "{}", msg)
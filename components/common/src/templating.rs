@@ -1,8 +1,10 @@
 pub mod config;
 mod context;
+pub mod fixture;
 pub mod helpers;
 pub mod hooks;
 pub mod package;
+mod strict;
 pub mod test_helpers;
 
 pub use self::context::RenderContext;
@@ -16,6 +18,7 @@ use crate::{error::{Error,
             FeatureFlag};
 use handlebars::{Handlebars,
                  RenderError,
+                 TemplateError,
                  TemplateFileError};
 use lazy_static::lazy_static;
 use log::debug;
@@ -69,12 +72,18 @@ pub async fn compile_for_package_install(package: &PackageInstall,
 
 pub type RenderResult<T> = result::Result<T, RenderError>;
 
-pub struct TemplateRenderer(Handlebars);
+pub struct TemplateRenderer {
+    handlebars: Handlebars,
+    /// Whether references to undefined variables are render errors
+    strict:     bool,
+}
 
 impl TemplateRenderer {
     pub fn new() -> Self {
         let mut handlebars = Handlebars::new();
         handlebars.register_helper("eachAlive", Box::new(helpers::EACH_ALIVE));
+        handlebars.register_helper("eachMember", Box::new(helpers::EACH_MEMBER));
+        handlebars.register_helper("eachReady", Box::new(helpers::EACH_READY));
         handlebars.register_helper("pkgPathFor", Box::new(helpers::PKG_PATH_FOR));
         handlebars.register_helper("strConcat", Box::new(helpers::STR_CONCAT));
         handlebars.register_helper("strJoin", Box::new(helpers::STR_JOIN));
@@ -84,18 +93,55 @@ impl TemplateRenderer {
         handlebars.register_helper("toJson", Box::new(helpers::TO_JSON));
         handlebars.register_helper("toToml", Box::new(helpers::TO_TOML));
         handlebars.register_helper("toYaml", Box::new(helpers::TO_YAML));
+        handlebars.register_helper("toIni", Box::new(helpers::TO_INI));
+        handlebars.register_helper("toProperties", Box::new(helpers::TO_PROPERTIES));
+        handlebars.register_helper("toEnv", Box::new(helpers::TO_ENV));
+        handlebars.register_helper("base64Encode", Box::new(helpers::BASE64_ENCODE));
+        handlebars.register_helper("base64Decode", Box::new(helpers::BASE64_DECODE));
+        handlebars.register_helper("hexEncode", Box::new(helpers::HEX_ENCODE));
+        handlebars.register_helper("hexDecode", Box::new(helpers::HEX_DECODE));
+        handlebars.register_helper("sha256", Box::new(helpers::SHA256));
+        handlebars.register_helper("default", Box::new(helpers::DEFAULT));
+        handlebars.register_helper("coalesce", Box::new(helpers::COALESCE));
+        handlebars.register_helper("add", Box::new(helpers::ADD));
+        handlebars.register_helper("subtract", Box::new(helpers::SUBTRACT));
+        handlebars.register_helper("multiply", Box::new(helpers::MULTIPLY));
+        handlebars.register_helper("divide", Box::new(helpers::DIVIDE));
+        handlebars.register_helper("modulo", Box::new(helpers::MODULO));
+        handlebars.register_helper("cidrContains", Box::new(helpers::CIDR_CONTAINS));
+        handlebars.register_helper("cidrHost", Box::new(helpers::CIDR_HOST));
+        handlebars.register_helper("cidrNetmask", Box::new(helpers::CIDR_NETMASK));
+        handlebars.register_helper("cidrNetwork", Box::new(helpers::CIDR_NETWORK));
+
+        handlebars.register_helper(strict::STRICT_CHECK, Box::new(strict::STRICT_CHECK_HELPER));
 
         handlebars.register_escape_fn(never_escape);
-        TemplateRenderer(handlebars)
+        TemplateRenderer { handlebars,
+                           strict: false }
+    }
+
+    /// Create a renderer in which a reference to an undefined variable is a render error
+    /// reporting the template, line and path of the variable, instead of an empty string.
+    pub fn strict() -> Self {
+        TemplateRenderer { strict: true,
+                           ..Self::new() }
     }
 
+    pub fn is_strict(&self) -> bool { self.strict }
+
     pub fn render<T>(&self, template: &str, ctx: &T) -> Result<String>
         where T: Serialize
     {
         let raw = serde_json::to_value(ctx).map_err(Error::RenderContextSerialization)?;
         debug!("Rendering template with context, {}, {}", template, raw);
-        self.0
-            .render(template, &raw)
+        self.render_value(template, &raw)
+    }
+
+    /// Render a template with an already serialized context. Unlike `render`, the context is
+    /// never logged, so it may contain resolved secrets.
+    pub fn render_value(&self, template: &str, raw: &serde_json::Value) -> Result<String> {
+        self.handlebars
+            .render(template, raw)
             .map_err(|e| Error::TemplateRenderError(format!("{}", e)))
     }
 
@@ -136,9 +182,24 @@ impl TemplateRenderer {
                 });
         }
 
-        self.0.register_template_string(name, template_string)?;
+        self.register_template_string(name, template_string)?;
         Ok(())
     }
+
+    /// Register a template, rewriting it first if the renderer is strict.
+    pub fn register_template_string<S>(&mut self,
+                                       name: &str,
+                                       source: S)
+                                       -> result::Result<(), TemplateError>
+        where S: AsRef<str>
+    {
+        if self.strict {
+            self.handlebars
+                .register_template_string(name, strict::strict_source(source.as_ref()))
+        } else {
+            self.handlebars.register_template_string(name, source)
+        }
+    }
 }
 
 impl fmt::Debug for TemplateRenderer {
@@ -150,11 +211,11 @@ impl fmt::Debug for TemplateRenderer {
 impl Deref for TemplateRenderer {
     type Target = Handlebars;
 
-    fn deref(&self) -> &Handlebars { &self.0 }
+    fn deref(&self) -> &Handlebars { &self.handlebars }
 }
 
 impl DerefMut for TemplateRenderer {
-    fn deref_mut(&mut self) -> &mut Handlebars { &mut self.0 }
+    fn deref_mut(&mut self) -> &mut Handlebars { &mut self.handlebars }
 }
 
 /// Disables HTML escaping which is enabled by default in Handlebars.
@@ -182,6 +243,7 @@ mod test {
                           any(target_arch = "x86_64", target_arch = "aarch64")),
                       all(target_os = "windows", target_arch = "x86_64"),)))]
     use biome_core::package::metadata::MetaFile;
+    use serde_json::json;
     use std::{collections::BTreeMap,
               env,
               fs::File,
@@ -412,6 +474,23 @@ mod test {
         assert_eq!(each_alive_render, each_if_render);
     }
 
+    #[test]
+    fn each_ready_helper_skips_members_that_are_not_ready() {
+        let mut renderer = TemplateRenderer::new();
+        renderer.register_template_string("t",
+                                          "{{~#eachReady svc.members as \
+                                           |member|~}}{{member.member_id}} {{/eachReady}}")
+                .unwrap();
+
+        let data = json!({"svc": {"members": [
+            {"member_id": "a", "alive": true, "ready": true},
+            {"member_id": "b", "alive": true, "ready": false},
+            {"member_id": "c", "alive": false, "ready": true},
+            {"member_id": "d", "alive": true},
+        ]}});
+        assert_eq!(renderer.render("t", &data).unwrap(), "a ");
+    }
+
     #[tokio::test]
     async fn render_package_install() {
         let root = TempDir::new().expect("create temp dir").into_path();
@@ -1,4 +1,6 @@
 /// Collect all the configuration data that is exposed to users, and render it.
+use self::schema::{Schema,
+                   Violation};
 use crate::{error::{Error,
                     Result},
             hcore::{self,
@@ -7,22 +9,27 @@ use crate::{error::{Error,
                          USER_CONFIG_FILE}},
             outputln,
             templating::{package::Pkg,
-                         TemplateRenderer}};
+                         TemplateRenderer},
+            util::flatten::dotted_keys};
 use log::{debug,
           trace};
 use serde::{Deserialize,
             Serialize,
             Serializer};
 use std::{self,
-          borrow::Cow,
           clone::Clone,
+          collections::BTreeMap,
           env,
+          fmt,
           fs::File,
           io::prelude::*,
           path::{Path,
                  PathBuf},
           result};
 
+pub mod schema;
+pub mod secret;
+
 static LOGKEY: &str = "CF";
 static ENV_VAR_PREFIX: &str = "HAB";
 /// The maximum TOML table merge depth allowed before failing the operation. The value here is
@@ -60,6 +67,40 @@ impl From<UserConfigPath> for PathBuf {
     }
 }
 
+/// The layers a service's configuration is merged from, in increasing order of precedence.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum CfgLayer {
+    Default,
+    Environment,
+    User,
+    Gossip,
+}
+
+impl fmt::Display for CfgLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            CfgLayer::Default => "default",
+            CfgLayer::Environment => "environment",
+            CfgLayer::User => "user",
+            CfgLayer::Gossip => "gossip",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// A key of the merged configuration and the layer that set it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CfgKeySource {
+    /// The dotted path of the key, e.g. `tls.cert`
+    pub key:     String,
+    pub value:   toml::Value,
+    pub layer:   CfgLayer,
+    /// Where the layer was loaded from, see `Cfg::layer_source`
+    pub source:  String,
+    /// The value of the key in the package's default configuration, if it has one
+    pub default: Option<toml::Value>,
+}
+
 /// Trait for getting paths to directories where various configuration
 /// files are expected to be.
 pub trait PackageConfigPaths {
@@ -100,63 +141,134 @@ pub struct Cfg {
     /// The path to an optional dev-time configuration directory that
     /// is being used.
     override_config_dir:    Option<PathBuf>,
+    /// The directory the default configuration and schema were loaded from
+    default_config_dir:     PathBuf,
+    /// The environment variable the environment configuration was loaded from
+    environment_var:        String,
+    /// Schema loaded from a Package's `schema.toml` that every layer must match
+    schema:                 Option<Schema>,
 }
 
 impl Cfg {
     pub fn new<P>(package: &P, config_from: Option<&PathBuf>) -> Result<Cfg>
         where P: PackageConfigPaths
     {
-        let override_config_dir = config_from.cloned();
-        let default = {
-            let pkg_root = match override_config_dir {
-                Some(ref path) => Cow::Borrowed(path),
-                None => Cow::Owned(package.default_config_dir()),
-            };
-            Self::load_default(pkg_root.as_ref())?
-        };
         let user_config_path = Self::determine_user_config_path(package);
         let user = Self::load_user(user_config_path.get_path())?;
         let environment = Self::load_environment(&package.name())?;
-        Ok(Self { default,
-                  user,
-                  gossip: None,
-                  environment,
-                  gossip_incarnation: 0,
-                  user_config_path,
-                  override_config_dir })
+        Self::with_layers(package, config_from, user_config_path, user, environment)
+    }
+
+    /// Creates the configuration of a package from its default configuration and the given
+    /// `user` layer only. Neither the `user.toml` of the service on this system nor the
+    /// environment is used, so that templates can be rendered the same way on any system.
+    pub fn with_user<P>(package: &P,
+                        config_from: Option<&PathBuf>,
+                        user: Option<toml::value::Table>)
+                        -> Result<Cfg>
+        where P: PackageConfigPaths
+    {
+        let user_config_path = UserConfigPath::Recommended(package.recommended_user_config_dir());
+        Self::with_layers(package, config_from, user_config_path, user, None)
+    }
+
+    fn with_layers<P>(package: &P,
+                      config_from: Option<&PathBuf>,
+                      user_config_path: UserConfigPath,
+                      user: Option<toml::value::Table>,
+                      environment: Option<toml::value::Table>)
+                      -> Result<Cfg>
+        where P: PackageConfigPaths
+    {
+        let override_config_dir = config_from.cloned();
+        let default_config_dir =
+            override_config_dir.clone()
+                               .unwrap_or_else(|| package.default_config_dir());
+        let default = Self::load_default(&default_config_dir)?;
+        let schema = Schema::load(&default_config_dir)?;
+        let environment_var = Self::environment_var(&package.name());
+        let cfg = Self { default,
+                         user,
+                         gossip: None,
+                         environment,
+                         gossip_incarnation: 0,
+                         user_config_path,
+                         override_config_dir,
+                         default_config_dir,
+                         environment_var,
+                         schema };
+        cfg.check_schema()?;
+        Ok(cfg)
     }
 
-    /// Validates a service configuration against a configuration interface.
+    /// The schema describing the keys which are configurable, their types and the values they
+    /// accept. Packages which don't ship a `schema.toml` have no schema, and any configuration is
+    /// accepted for them.
+    pub fn schema(&self) -> Option<&Schema> { self.schema.as_ref() }
+
+    /// Validates a new gossip layer against the package's schema, both on its own and merged
+    /// with the other layers, without applying it.
     ///
-    /// Returns `None` if valid and `Some` containing a list of errors if invalid.
-    pub fn validate(interface: &toml::value::Table,
-                    cfg: &toml::value::Table)
-                    -> Option<Vec<String>> {
-        let mut errors = vec![];
-        for key in cfg.keys() {
-            if !interface.contains_key(key) {
-                errors.push(format!("Unknown key: {}", key));
+    /// Returns every violation found, which is empty if the configuration is valid or the
+    /// package has no schema.
+    pub fn validate_gossip(&self, gossip: &toml::value::Table) -> Vec<Violation> {
+        let untrusted = secret::references(gossip);
+        if !untrusted.is_empty() {
+            let message = |reference: String| {
+                format!("secret reference '{}' is only allowed in local configuration",
+                        reference)
+            };
+            return untrusted.into_iter()
+                            .map(|(path, reference)| {
+                                Violation { path,
+                                            message: message(reference) }
+                            })
+                            .collect();
+        }
+        let schema = match self.schema {
+            Some(ref schema) => schema,
+            None => return Vec::new(),
+        };
+        let violations = schema.validate_layer(gossip);
+        if !violations.is_empty() {
+            return violations;
+        }
+        let mut candidate = self.clone();
+        candidate.gossip = Some(gossip.clone());
+        schema.validate(&candidate.merged())
+    }
+
+    /// Checks every layer and the merged configuration against the package's schema, if it has
+    /// one.
+    fn check_schema(&self) -> Result<()> {
+        let schema = match self.schema {
+            Some(ref schema) => schema,
+            None => return Ok(()),
+        };
+        for (layer, cfg) in self.layers() {
+            if let Some(cfg) = cfg {
+                let violations = schema.validate_layer(cfg);
+                if !violations.is_empty() {
+                    return Err(Error::ConfigSchemaViolation(self.layer_source(layer), violations));
+                }
             }
         }
-        if errors.is_empty() {
-            None
-        } else {
-            Some(errors)
+        let violations = schema.validate(&self.merged());
+        if !violations.is_empty() {
+            return Err(Error::ConfigSchemaViolation("all layers merged".to_string(), violations));
         }
+        Ok(())
     }
 
-    /// A structured interface which describes configuration keys which are configurable and their
-    /// optional default values.
-    pub fn interface(&self) -> Option<&toml::value::Table> {
-        // JW TODO: For now let's use `default.toml` as it is for the interface. In the future,
-        // we will need to be able to describe more than just the key value relationship that
-        // `default.toml` provides. We will need to be able to describe things like:
-        //
-        // * Keys which have no default
-        // * Key with a default value
-        // * Keys which only accept certain values
-        // * Allowed types for a key
-        self.default.as_ref()
+    /// Applies `update` to the configuration only if the result matches the package's schema.
+    fn update_checked<F>(&mut self, update: F) -> Result<()>
+        where F: FnOnce(&mut Self)
+    {
+        let mut candidate = self.clone();
+        update(&mut candidate);
+        candidate.check_schema()?;
+        *self = candidate;
+        Ok(())
     }
 
     /// Updates the default layer of the configuration when a service
@@ -172,26 +284,35 @@ impl Cfg {
     pub fn update_defaults_from_package<P>(&mut self, package: &P) -> Result<bool>
         where P: PackageConfigPaths
     {
-        let incoming_defaults = {
-            let pkg_root = match self.override_config_dir {
-                Some(ref path) => Cow::Borrowed(path),
-                None => Cow::Owned(package.default_config_dir()),
-            };
-            Self::load_default(pkg_root.as_ref())?
-        };
-
-        if incoming_defaults != self.default {
-            self.default = incoming_defaults;
+        let default_config_dir = self.override_config_dir
+                                     .clone()
+                                     .unwrap_or_else(|| package.default_config_dir());
+        let incoming_defaults = Self::load_default(&default_config_dir)?;
+        let incoming_schema = Schema::load(&default_config_dir)?;
+        self.default_config_dir = default_config_dir;
+
+        if incoming_defaults != self.default || incoming_schema != self.schema {
+            self.update_checked(|cfg| {
+                    cfg.default = incoming_defaults;
+                    cfg.schema = incoming_schema;
+                })?;
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
-    /// Updates the service configuration with data from a census group
-    pub fn set_gossip(&mut self, incarnation: u64, gossip: toml::value::Table) {
+    /// Updates the service configuration with data from a census group.
+    ///
+    /// The incarnation is recorded even if the configuration does not match the package's
+    /// schema or holds secret references, in which case the previous gossip layer is kept and an
+    /// error is returned.
+    pub fn set_gossip(&mut self, incarnation: u64, gossip: toml::value::Table) -> Result<()> {
         self.gossip_incarnation = incarnation;
-        self.gossip = Some(gossip);
+        if let Some((key, reference)) = secret::references(&gossip).into_iter().next() {
+            return Err(Error::UntrustedSecretReference(key, reference));
+        }
+        self.update_checked(|cfg| cfg.gossip = Some(gossip))
     }
 
     /// Returns a subset of the overall configuration which intersects with the given package
@@ -292,16 +413,21 @@ impl Cfg {
         Self::load_toml_file(path, USER_CONFIG_FILE)
     }
 
-    /// Reloads the user configuration file.
+    /// Reloads the user configuration file. The current user configuration is kept if the new
+    /// one does not match the package's schema.
     pub fn reload_user(&mut self) -> Result<()> {
         let user = Self::load_user(self.user_config_path.get_path())?;
-        self.user = user;
-        Ok(())
+        self.update_checked(|cfg| cfg.user = user)
+    }
+
+    /// The name of the environment variable holding the environment configuration of a package.
+    fn environment_var(package_name: &str) -> String {
+        format!("{}_{}", ENV_VAR_PREFIX, package_name).to_ascii_uppercase()
+                                                      .replace('-', "_")
     }
 
     fn load_environment(package_name: &str) -> Result<Option<toml::value::Table>> {
-        let var_name = format!("{}_{}", ENV_VAR_PREFIX, package_name).to_ascii_uppercase()
-                                                                     .replace('-', "_");
+        let var_name = Self::environment_var(package_name);
         match env::var(&var_name) {
             Ok(config) => {
                 // If we've got an environment variable, we'll parsing
@@ -357,12 +483,70 @@ impl Cfg {
             }
         }
     }
-}
 
-impl Serialize for Cfg {
-    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
-        where S: Serializer
-    {
+    /// Every layer of the configuration, in increasing order of precedence.
+    fn layers(&self) -> [(CfgLayer, &Option<toml::value::Table>); 4] {
+        [(CfgLayer::Default, &self.default),
+         (CfgLayer::Environment, &self.environment),
+         (CfgLayer::User, &self.user),
+         (CfgLayer::Gossip, &self.gossip)]
+    }
+
+    /// Describes where a layer of the configuration was loaded from: a file for the default and
+    /// user layers, an environment variable for the environment layer, and the incarnation of the
+    /// census group's service config for the gossip layer.
+    pub fn layer_source(&self, layer: CfgLayer) -> String {
+        match layer {
+            CfgLayer::Default => {
+                self.default_config_dir
+                    .join("default.toml")
+                    .display()
+                    .to_string()
+            }
+            CfgLayer::Environment => format!("${}", self.environment_var),
+            CfgLayer::User => {
+                self.user_config_path
+                    .get_path()
+                    .join(USER_CONFIG_FILE)
+                    .display()
+                    .to_string()
+            }
+            CfgLayer::Gossip => format!("gossip (incarnation {})", self.gossip_incarnation),
+        }
+    }
+
+    /// Returns every key of the merged configuration, ordered by key, along with the layer that
+    /// set it. Only the leaves of nested tables are returned.
+    ///
+    /// A key is set by the layer with the highest precedence that contains it, since merging a
+    /// layer replaces every value it contains.
+    pub fn provenance(&self) -> Vec<CfgKeySource> {
+        let layers =
+            self.layers()
+                .iter()
+                .map(|&(layer, cfg)| (layer, cfg.as_ref().map(dotted_keys).unwrap_or_default()))
+                .collect::<Vec<_>>();
+        let merged = self.merged();
+        dotted_keys(&merged).into_iter()
+                            .map(|(key, value)| {
+                                let layer = layers.iter()
+                                                  .rev()
+                                                  .find(|(_, cfg)| cfg.contains_key(&key))
+                                                  .map(|(layer, _)| *layer)
+                                                  .unwrap_or(CfgLayer::Default);
+                                let default = layers[0].1.get(&key).map(|v| (*v).clone());
+                                CfgKeySource { source: self.layer_source(layer),
+                                               key,
+                                               value: value.clone(),
+                                               layer,
+                                               default }
+                            })
+                            .collect()
+    }
+
+    /// Merges all layers, with later layers taking precedence: default, environment, user and
+    /// gossip.
+    fn merged(&self) -> toml::value::Table {
         let mut table = toml::value::Table::new();
         if let Some(ref default_cfg) = self.default {
             if let Err(err) = toml_merge(&mut table, default_cfg) {
@@ -384,11 +568,25 @@ impl Serialize for Cfg {
                 outputln!("Error merging gossip-cfg into config, {}", err);
             }
         }
+        table
+    }
+}
 
-        serializer.collect_map(&table)
+impl Serialize for Cfg {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.collect_map(&self.merged())
     }
 }
 
+/// The configuration files rendered from a package's templates.
+#[derive(Debug, Default)]
+pub struct RenderedCfg {
+    /// The content of each file, by its path relative to the configuration directory
+    pub files: BTreeMap<String, String>,
+}
+
 #[derive(Debug)]
 /// Renders configuration templates into config files.
 pub struct CfgRenderer(TemplateRenderer);
@@ -398,14 +596,64 @@ impl CfgRenderer {
     /// configuration directory, if it exists.
     pub fn new<T>(templates_path: T) -> Result<Self>
         where T: AsRef<Path>
+    {
+        Self::with_renderer(templates_path, TemplateRenderer::new())
+    }
+
+    /// Create a new `CfgRenderer` registering the templates of a configuration directory, if it
+    /// exists, with the given renderer, such as a strict one.
+    pub fn with_renderer<T>(templates_path: T, renderer: TemplateRenderer) -> Result<Self>
+        where T: AsRef<Path>
     {
         if templates_path.as_ref().is_dir() {
-            load_templates(templates_path.as_ref(),
-                           &PathBuf::new(),
-                           TemplateRenderer::new()).map(CfgRenderer)
+            load_templates(templates_path.as_ref(), &PathBuf::new(), renderer).map(CfgRenderer)
         } else {
-            Ok(CfgRenderer(TemplateRenderer::new()))
+            Ok(CfgRenderer(renderer))
+        }
+    }
+
+    /// Render all configuration templates without writing them, resolving any secret references
+    /// they use.
+    pub fn render<T>(&self, ctx: &T) -> Result<RenderedCfg>
+        where T: Serialize
+    {
+        self.render_with(ctx, secret::resolve)
+    }
+
+    /// Render all configuration templates without writing them, replacing any secret references
+    /// they use with a placeholder naming the reference. The result holds no secret, so it can be
+    /// shown to users.
+    pub fn render_concealed<T>(&self, ctx: &T) -> Result<RenderedCfg>
+        where T: Serialize
+    {
+        self.render_with(ctx, secret::conceal)
+    }
+
+    fn render_with<T>(&self,
+                      ctx: &T,
+                      replace_secrets: fn(&mut serde_json::Value) -> Result<secret::Resolved>)
+                      -> Result<RenderedCfg>
+        where T: Serialize
+    {
+        // Secret references are only replaced here, after the context has been logged, so that
+        // their plaintext only ends up in the rendered files. Only the service's own
+        // configuration is resolved, never census data gossiped by other members.
+        let mut raw = serde_json::to_value(ctx).map_err(Error::RenderContextSerialization)?;
+        debug!("Rendering configuration templates with context, {}", raw);
+        let resolved = match raw.get_mut("cfg") {
+            Some(cfg) => replace_secrets(cfg)?,
+            None => secret::Resolved::default(),
+        };
+        let mut files = BTreeMap::new();
+        for template in self.0.get_templates().keys() {
+            // A failed rendering may quote the values it was given, and its error is logged
+            let content =
+                self.0
+                    .render_value(template, &raw)
+                    .map_err(|e| Error::TemplateRenderError(resolved.redact(&e.to_string())))?;
+            files.insert(template.to_string(), content);
         }
+        Ok(RenderedCfg { files })
     }
 
     /// Compile and write all configuration files to the configuration directory.
@@ -424,11 +672,23 @@ impl CfgRenderer {
         // error resulting in the end-user not knowing what the fuck happned at all. We need to go
         // through this and pipe the service group through to let people know which service is
         // having issues and be more descriptive about what happened.
+        let rendered = self.render(ctx)?;
+        Self::write(service_group_name, pkg, render_path, &rendered)
+    }
 
+    /// Write configuration files rendered by `render` to the configuration directory.
+    ///
+    /// Returns `true` if the configuration has changed.
+    pub fn write<P>(service_group_name: &str,
+                    pkg: &Pkg,
+                    render_path: P,
+                    rendered: &RenderedCfg)
+                    -> Result<bool>
+        where P: AsRef<Path>
+    {
         let mut changed = false;
-        for template in self.0.get_templates().keys() {
-            let compiled = self.0.render(template, ctx)?;
-            let compiled_hash = Blake2bHash::from_bytes(&compiled);
+        for (template, compiled) in &rendered.files {
+            let compiled_hash = Blake2bHash::from_bytes(compiled);
             let cfg_dest = render_path.as_ref().join(template);
             let file_hash = match Blake2bHash::from_file(&cfg_dest) {
                 Ok(file_hash) => Some(file_hash),
@@ -446,7 +706,7 @@ impl CfgRenderer {
                                                &cfg_dest,
                                                &pkg.svc_user,
                                                &pkg.svc_group)?;
-                    write_templated_file(&cfg_dest, &compiled, &pkg.svc_user, &pkg.svc_group)?;
+                    write_templated_file(&cfg_dest, compiled, &pkg.svc_user, &pkg.svc_group)?;
                     outputln!(
                         preamble service_group_name,
                         "Created configuration file {}",
@@ -464,7 +724,7 @@ impl CfgRenderer {
                     } else {
                         debug!("Configuration {} has changed; templating new data",
                                cfg_dest.display());
-                        write_templated_file(&cfg_dest, &compiled, &pkg.svc_user, &pkg.svc_group)?;
+                        write_templated_file(&cfg_dest, compiled, &pkg.svc_user, &pkg.svc_group)?;
                         outputln!(
                             preamble service_group_name,
                             "Modified configuration file {}",
@@ -885,6 +1145,17 @@ mod test {
         assert!(cfg.user.is_none());
     }
 
+    #[test]
+    fn with_user_ignores_the_configuration_of_this_system() {
+        let cfg_data = CfgTestData::new();
+        write_toml(&cfg_data.rucp, "foo = 42");
+
+        let cfg = Cfg::with_user(&cfg_data.pkg, None, Some(toml_from_str("bar = 1")))
+            .expect("create config");
+        assert_eq!(cfg.user, Some(toml_from_str("bar = 1")));
+        assert!(cfg.environment.is_none());
+    }
+
     #[test]
     fn load_deprecated_user_toml() {
         let cfg_data = CfgTestData::new();
@@ -943,6 +1214,112 @@ mod test {
         assert_eq!(cfg.user, Some(toml_from_str(toml)));
     }
 
+    fn write_schema(cfg_data: &CfgTestData) {
+        write_toml(&cfg_data.pkg.default_config_dir().join(schema::SCHEMA_FILE),
+                   "[port]\ntype = \"integer\"\nrequired = true\n");
+    }
+
+    #[test]
+    fn user_toml_violating_schema_is_rejected() {
+        let cfg_data = CfgTestData::new();
+        write_schema(&cfg_data);
+        write_toml(&cfg_data.rucp, "port = \"eighty\"");
+
+        match Cfg::new(&cfg_data.pkg, None) {
+            Err(Error::ConfigSchemaViolation(_, violations)) => {
+                assert_eq!(violations.len(), 1);
+                assert_eq!(violations[0].path, "port");
+            }
+            other => panic!("Expected a schema violation; got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn required_key_without_default_must_be_configured() {
+        let cfg_data = CfgTestData::new();
+        write_schema(&cfg_data);
+        assert!(Cfg::new(&cfg_data.pkg, None).is_err());
+
+        write_toml(&cfg_data.rucp, "port = 80");
+        assert!(Cfg::new(&cfg_data.pkg, None).is_ok());
+    }
+
+    #[test]
+    fn invalid_reloaded_user_toml_keeps_current_configuration() {
+        let cfg_data = CfgTestData::new();
+        write_schema(&cfg_data);
+        write_toml(&cfg_data.rucp, "port = 80");
+        let mut cfg = Cfg::new(&cfg_data.pkg, None).expect("create config");
+
+        write_toml(&cfg_data.rucp, "port = 80\nhost = \"localhost\"");
+        assert!(cfg.reload_user().is_err());
+        assert_eq!(cfg.user, Some(toml_from_str("port = 80")));
+    }
+
+    #[test]
+    fn invalid_gossip_is_rejected_but_incarnation_is_recorded() {
+        let cfg_data = CfgTestData::new();
+        write_schema(&cfg_data);
+        write_toml(&cfg_data.rucp, "port = 80");
+        let mut cfg = Cfg::new(&cfg_data.pkg, None).expect("create config");
+
+        let gossip = toml_from_str("port = 8.5");
+        assert_eq!(cfg.validate_gossip(&gossip).len(), 1);
+        assert!(cfg.set_gossip(2, gossip).is_err());
+        assert_eq!(cfg.gossip_incarnation, 2);
+        assert_eq!(cfg.gossip, None);
+
+        cfg.set_gossip(3, toml_from_str("port = 8080"))
+           .expect("valid gossip");
+        assert_eq!(cfg.gossip, Some(toml_from_str("port = 8080")));
+    }
+
+    #[test]
+    fn gossip_cannot_hold_secret_references() {
+        let cfg_data = CfgTestData::new();
+        write_toml(&cfg_data.rucp,
+                   "password = { secret = \"file:/run/secrets/db\" }");
+        let mut cfg = Cfg::new(&cfg_data.pkg, None).expect("create config");
+
+        let gossip = toml_from_str("[db]\npassword = { secret = \"file:/etc/shadow\" }");
+        let violations = cfg.validate_gossip(&gossip);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "db.password");
+        assert!(cfg.set_gossip(2, gossip).is_err());
+        assert!(cfg.set_gossip(3, toml_from_str("cmd = { secret = \"exec:id\" }"))
+                   .is_err());
+        let gossip = toml_from_str("token = { secret = \"env:HAB_CTL_SECRET\" }");
+        assert_eq!(cfg.validate_gossip(&gossip).len(), 1);
+        assert!(cfg.set_gossip(4, gossip).is_err());
+        assert_eq!(cfg.gossip, None);
+    }
+
+    #[test]
+    fn provenance_names_the_layer_that_set_each_key() {
+        let cfg_data = CfgTestData::new();
+        write_toml(&cfg_data.pkg.default_config_dir().join("default.toml"),
+                   "port = 80\nhost = \"localhost\"\n[tls]\nverify = true\n");
+        write_toml(&cfg_data.rucp, "port = 8080");
+        let mut cfg = Cfg::new(&cfg_data.pkg, None).expect("create config");
+        cfg.set_gossip(3,
+                       toml_from_str("tls = { verify = false, cert = \"/cert\" }"))
+           .expect("valid gossip");
+
+        let provenance = cfg.provenance();
+        let layers = provenance.iter()
+                               .map(|k| (k.key.as_str(), k.layer))
+                               .collect::<Vec<_>>();
+        assert_eq!(layers,
+                   vec![("host", CfgLayer::Default),
+                        ("port", CfgLayer::User),
+                        ("tls.cert", CfgLayer::Gossip),
+                        ("tls.verify", CfgLayer::Gossip)]);
+        assert_eq!(provenance[1].source, cfg_data.rucp.display().to_string());
+        assert_eq!(provenance[1].default, Some(toml::Value::Integer(80)));
+        assert_eq!(provenance[2].source, "gossip (incarnation 3)");
+        assert_eq!(provenance[2].default, None);
+    }
+
     #[test]
     fn serialize_config() {
         let concrete_path = TempDir::new().expect("create temp dir");
@@ -1129,6 +1506,48 @@ mod test {
         load_templates(&file, &PathBuf::new(), TemplateRenderer::new())?;
     }
 
+    #[test]
+    fn render_resolves_or_conceals_secrets() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let config_dir = tmp.path().join("config");
+        fs::create_dir_all(&config_dir).expect("create config dir");
+        create_with_content(config_dir.join("app.conf"),
+                            "password = {{cfg.db.password}}");
+        let secret = tmp.path().join("db-password");
+        create_with_content(&secret, "hunter2\n");
+
+        let renderer = CfgRenderer::new(&config_dir).expect("create cfg renderer");
+        let ctx = serde_json::json!({ "cfg": { "db": { "password": {
+                      "secret": format!("file:{}", secret.display())
+                  } } } });
+        let rendered = renderer.render(&ctx).expect("render");
+        assert_eq!(rendered.files["app.conf"], "password = hunter2");
+
+        let concealed = renderer.render_concealed(&ctx).expect("render");
+        assert_eq!(concealed.files["app.conf"],
+                   format!("password = <secret file:{}>", secret.display()));
+    }
+
+    #[test]
+    fn census_data_is_never_resolved() {
+        let tmp = TempDir::new().expect("create temp dir");
+        let config_dir = tmp.path().join("config");
+        fs::create_dir_all(&config_dir).expect("create config dir");
+        create_with_content(config_dir.join("app.conf"),
+                            "{{bind.db.first.cfg.password.secret}}");
+        env::set_var("HAB_TEST_CENSUS_SECRET", "hunter2");
+
+        let renderer = CfgRenderer::new(&config_dir).expect("create cfg renderer");
+        let ctx = serde_json::json!({ "cfg": {}, "bind": { "db": { "first": { "cfg": {
+                      "password": { "secret": "env:HAB_TEST_CENSUS_SECRET" }
+                  } } } } });
+        let rendered = renderer.render(&ctx);
+        env::remove_var("HAB_TEST_CENSUS_SECRET");
+
+        assert_eq!(rendered.expect("render").files["app.conf"],
+                   "env:HAB_TEST_CENSUS_SECRET");
+    }
+
     #[tokio::test]
     async fn test_compile_recursive_config_dir() {
         let root = TempDir::new().expect("create temp dir").into_path();
@@ -0,0 +1,390 @@
+//! Typed schemas for package configuration.
+//!
+//! A package may ship a `schema.toml` next to its `default.toml` that describes every configurable
+//! key. Each top-level entry of the schema is the name of a key, mapped to a table describing it:
+//!
+//! ```toml
+//! [port]
+//! type = "integer"
+//! min = 1
+//! max = 65535
+//!
+//! [log_level]
+//! type = "string"
+//! enum = ["debug", "info", "warn", "error"]
+//!
+//! [tls]
+//! type = "table"
+//! [tls.keys.cert]
+//! type = "string"
+//! required = true
+//! ```
+//!
+//! * `type` is one of `string`, `integer`, `float`, `boolean`, `datetime`, `array`, `table` or
+//!   `any` (the default). A `float` also accepts integers.
+//! * `required` keys must be present once all configuration layers are merged, which makes it
+//!   possible to describe keys that have no default.
+//! * `enum` lists the only values the key accepts.
+//! * `min` and `max` bound numbers, and the length of strings and arrays.
+//! * `items` describes every element of an array.
+//! * `keys` describes the keys of a table. Unless `additional_keys` is `true`, a table with
+//!   described keys rejects any other key. A table without described keys accepts anything.
+//!
+//! Unknown top-level keys are always rejected.
+
+use super::secret;
+use crate::error::{Error,
+                   Result};
+use serde::Deserialize;
+use std::{collections::BTreeMap,
+          fmt,
+          fs,
+          io,
+          path::Path};
+
+/// The name of the file a package's configuration schema is read from.
+pub const SCHEMA_FILE: &str = "schema.toml";
+
+/// The type of value a configuration key accepts.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    #[default]
+    Any,
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Datetime,
+    Array,
+    Table,
+}
+
+impl ValueType {
+    fn accepts(self, value: &toml::Value) -> bool {
+        matches!((self, value),
+                 (ValueType::Any, _)
+                 | (ValueType::String, toml::Value::String(_))
+                 | (ValueType::Integer, toml::Value::Integer(_))
+                 | (ValueType::Float, toml::Value::Float(_))
+                 | (ValueType::Float, toml::Value::Integer(_))
+                 | (ValueType::Boolean, toml::Value::Boolean(_))
+                 | (ValueType::Datetime, toml::Value::Datetime(_))
+                 | (ValueType::Array, toml::Value::Array(_))
+                 | (ValueType::Table, toml::Value::Table(_)))
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueType::Any => "any",
+            ValueType::String => "string",
+            ValueType::Integer => "integer",
+            ValueType::Float => "float",
+            ValueType::Boolean => "boolean",
+            ValueType::Datetime => "datetime",
+            ValueType::Array => "array",
+            ValueType::Table => "table",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Describes a single configuration key.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct KeySchema {
+    #[serde(default, rename = "type")]
+    pub value_type:      ValueType,
+    #[serde(default)]
+    pub required:        bool,
+    #[serde(default, rename = "enum")]
+    pub allowed:         Vec<toml::Value>,
+    pub min:             Option<f64>,
+    pub max:             Option<f64>,
+    pub items:           Option<Box<KeySchema>>,
+    #[serde(default)]
+    pub keys:            BTreeMap<String, KeySchema>,
+    #[serde(default)]
+    pub additional_keys: bool,
+    pub description:     Option<String>,
+}
+
+/// A package's configuration schema.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct Schema {
+    keys: BTreeMap<String, KeySchema>,
+}
+
+/// A configuration value that does not match the schema.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Violation {
+    /// The dotted path of the offending key, e.g. `tls.cert` or `servers[2]`
+    pub path:    String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl Schema {
+    /// Load the schema from `dir`, returning `None` if the package does not ship one.
+    pub fn load<P>(dir: P) -> Result<Option<Self>>
+        where P: AsRef<Path>
+    {
+        let path = dir.as_ref().join(SCHEMA_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(Some(toml::from_str(&content).map_err(Error::TomlParser)?)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Validate a fully merged configuration, including the presence of required keys.
+    pub fn validate(&self, cfg: &toml::value::Table) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        validate_table(&self.keys, false, cfg, "", true, &mut violations);
+        violations
+    }
+
+    /// Validate a single configuration layer, such as a `user.toml`. Required keys may be
+    /// provided by another layer, so their absence is not a violation.
+    pub fn validate_layer(&self, cfg: &toml::value::Table) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        validate_table(&self.keys, false, cfg, "", false, &mut violations);
+        violations
+    }
+}
+
+impl KeySchema {
+    fn validate(&self,
+                value: &toml::Value,
+                path: &str,
+                check_required: bool,
+                violations: &mut Vec<Violation>) {
+        let mut violation = |message: String| {
+            violations.push(Violation { path: path.to_string(),
+                                        message })
+        };
+        // The value of a secret is unknown until templates are rendered, but it is always a
+        // string.
+        if secret::is_reference(value) {
+            if !matches!(self.value_type, ValueType::Any | ValueType::String) {
+                violation(format!("expected {}, found a secret reference", self.value_type));
+            }
+            return;
+        }
+        if !self.value_type.accepts(value) {
+            violation(format!("expected {}, found {}", self.value_type, value.type_str()));
+            return;
+        }
+        if !self.allowed.is_empty() && !self.allowed.contains(value) {
+            let allowed = self.allowed
+                              .iter()
+                              .map(ToString::to_string)
+                              .collect::<Vec<_>>()
+                              .join(", ");
+            violation(format!("{} is not one of {}", value, allowed));
+        }
+        let size = match value {
+            toml::Value::Integer(i) => Some(*i as f64),
+            toml::Value::Float(f) => Some(*f),
+            toml::Value::String(s) => Some(s.chars().count() as f64),
+            toml::Value::Array(a) => Some(a.len() as f64),
+            _ => None,
+        };
+        if let Some(size) = size {
+            let what = match value {
+                toml::Value::String(_) | toml::Value::Array(_) => "length",
+                _ => "value",
+            };
+            if let Some(min) = self.min.filter(|min| size < *min) {
+                violation(format!("{} {} is less than the minimum of {}", what, size, min));
+            }
+            if let Some(max) = self.max.filter(|max| size > *max) {
+                violation(format!("{} {} is greater than the maximum of {}", what, size, max));
+            }
+        }
+        match value {
+            toml::Value::Array(array) => {
+                if let Some(ref items) = self.items {
+                    for (i, item) in array.iter().enumerate() {
+                        items.validate(item,
+                                       &format!("{}[{}]", path, i),
+                                       check_required,
+                                       violations);
+                    }
+                }
+            }
+            toml::Value::Table(table) => {
+                validate_table(&self.keys,
+                               self.additional_keys || self.keys.is_empty(),
+                               table,
+                               path,
+                               check_required,
+                               violations);
+            }
+            _ => (),
+        }
+    }
+}
+
+fn validate_table(keys: &BTreeMap<String, KeySchema>,
+                  additional_keys: bool,
+                  table: &toml::value::Table,
+                  prefix: &str,
+                  check_required: bool,
+                  violations: &mut Vec<Violation>) {
+    let path = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+    for (key, value) in table {
+        match keys.get(key) {
+            Some(schema) => schema.validate(value, &path(key), check_required, violations),
+            None if !additional_keys => {
+                violations.push(Violation { path:    path(key),
+                                            message: "unknown key".to_string(), })
+            }
+            None => (),
+        }
+    }
+    if check_required {
+        for (key, schema) in keys {
+            if schema.required && !table.contains_key(key) {
+                violations.push(Violation { path:    path(key),
+                                            message: "required key is missing".to_string(), });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn schema() -> Schema {
+        toml::from_str(
+                       r#"
+            [port]
+            type = "integer"
+            min = 1
+            max = 65535
+
+            [log_level]
+            type = "string"
+            enum = ["debug", "info"]
+
+            [ratio]
+            type = "float"
+
+            [password]
+            type = "string"
+            min = 8
+
+            [servers]
+            type = "array"
+            max = 2
+            items = { type = "string" }
+
+            [tls]
+            type = "table"
+            [tls.keys.cert]
+            type = "string"
+            required = true
+            [tls.keys.verify]
+            type = "boolean"
+
+            [extra]
+            type = "table"
+        "#,
+        ).expect("valid schema")
+    }
+
+    fn table(content: &str) -> toml::value::Table { toml::from_str(content).expect("valid TOML") }
+
+    fn paths(violations: &[Violation]) -> Vec<&str> {
+        violations.iter().map(|v| v.path.as_str()).collect()
+    }
+
+    #[test]
+    fn valid_configuration_has_no_violations() {
+        let cfg = table(
+                        r#"
+            port = 8080
+            log_level = "info"
+            ratio = 1
+            servers = ["a", "b"]
+            tls = { cert = "/path/to/cert", verify = true }
+            extra = { anything = "goes" }
+        "#,
+        );
+        assert!(schema().validate(&cfg).is_empty());
+    }
+
+    #[test]
+    fn type_enum_and_range_violations_are_reported_by_path() {
+        let cfg = table(
+                        r#"
+            port = 0
+            log_level = "trace"
+            servers = ["a", 1, "c"]
+            tls = { cert = "/path/to/cert", verify = "yes" }
+        "#,
+        );
+        let violations = schema().validate(&cfg);
+        assert_eq!(paths(&violations),
+                   vec!["port", "log_level", "servers", "servers[1]", "tls.verify"]);
+        assert_eq!(violations[0].to_string(),
+                   "port: value 0 is less than the minimum of 1");
+        assert_eq!(violations[3].to_string(),
+                   "servers[1]: expected string, found integer");
+    }
+
+    #[test]
+    fn secret_references_are_accepted_for_strings() {
+        let cfg = table(r#"
+            password = { secret = "file:/run/secrets/db" }
+            port = { secret = "env:PORT" }
+        "#);
+        let violations = schema().validate_layer(&cfg);
+        assert_eq!(violations,
+                   vec![Violation { path:    "port".to_string(),
+                                    message: "expected integer, found a secret reference"
+                                                 .to_string(), }]);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let cfg = table(
+                        r#"
+            prot = 8080
+            tls = { cert = "/path/to/cert", key = "/path/to/key" }
+        "#,
+        );
+        let violations = schema().validate(&cfg);
+        assert_eq!(paths(&violations), vec!["prot", "tls.key"]);
+        assert!(violations.iter().all(|v| v.message == "unknown key"));
+    }
+
+    #[test]
+    fn required_keys_are_only_checked_in_merged_configuration() {
+        let cfg = table("tls = { verify = false }");
+        assert!(schema().validate_layer(&cfg).is_empty());
+        assert_eq!(paths(&schema().validate(&cfg)), vec!["tls.cert"]);
+    }
+
+    #[test]
+    fn schema_with_unknown_attribute_fails_to_parse() {
+        assert!(toml::from_str::<Schema>("[port]\ntype = \"integer\"\nminimum = 1\n").is_err());
+        assert!(toml::from_str::<Schema>("[port]\ntype = \"int\"\n").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! References to secrets in service configuration.
+//!
+//! Any configuration value may be a reference to a secret instead of the secret itself. A
+//! reference is a table with a single `secret` key naming a provider and its argument:
+//!
+//! ```toml
+//! [db]
+//! password = { secret = "file:/run/secrets/db" }
+//! token = { secret = "env:API_TOKEN" }
+//! key = { secret = "exec:/usr/local/bin/fetch-secret db-key" }
+//! ```
+//!
+//! * `file:<path>` reads the contents of a file.
+//! * `env:<name>` reads an environment variable of the Supervisor.
+//! * `exec:<command> [args...]` runs a command and reads its standard output.
+//!
+//! A single trailing newline is removed from the resolved value.
+//!
+//! References read the host the Supervisor runs on, so they are only accepted in local
+//! configuration layers: `default.toml`, `user.toml` and the environment. Gossiped configuration
+//! may not hold any reference, as anyone who can apply configuration to a ring could otherwise
+//! read files, run commands and read the Supervisor's environment, including its own secrets, on
+//! every member. Census data, such as the configuration exported by other members, is never
+//! resolved either.
+//!
+//! References are kept as they are in every configuration layer, so plaintext secrets never
+//! reach gossip, the HTTP gateway or the logs. They are only resolved by `CfgRenderer::render`
+//! when rendering configuration templates, which are written with restricted permissions, and
+//! their plaintext is redacted from the errors of a failed rendering. Hooks are world readable
+//! and therefore only ever see the reference.
+
+use crate::error::{Error,
+                   Result};
+use std::{env,
+          fmt,
+          fs,
+          path::PathBuf,
+          process::Command,
+          str::FromStr};
+
+/// The key of a table that marks it as a secret reference.
+pub const SECRET_KEY: &str = "secret";
+
+/// Where the value of a secret is read from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SecretRef {
+    File(PathBuf),
+    Env(String),
+    Exec(String),
+}
+
+impl FromStr for SecretRef {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.split_once(':') {
+            Some(("file", path)) if !path.is_empty() => Ok(SecretRef::File(path.into())),
+            Some(("env", name)) if !name.is_empty() => Ok(SecretRef::Env(name.to_string())),
+            Some(("exec", command)) if !command.trim().is_empty() => {
+                Ok(SecretRef::Exec(command.to_string()))
+            }
+            _ => Err(Error::InvalidSecretReference(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretRef::File(path) => write!(f, "file:{}", path.display()),
+            SecretRef::Env(name) => write!(f, "env:{}", name),
+            SecretRef::Exec(command) => write!(f, "exec:{}", command),
+        }
+    }
+}
+
+impl SecretRef {
+    /// Read the plaintext value of the secret.
+    pub fn resolve(&self) -> Result<String> {
+        let failed = |reason: String| Error::SecretResolutionFailed(self.to_string(), reason);
+        let value = match self {
+            SecretRef::File(path) => fs::read_to_string(path).map_err(|e| failed(e.to_string()))?,
+            SecretRef::Env(name) => env::var(name).map_err(|e| failed(e.to_string()))?,
+            SecretRef::Exec(command) => {
+                let mut args = command.split_whitespace();
+                let program = args.next().unwrap_or_default();
+                let output = Command::new(program).args(args)
+                                                  .output()
+                                                  .map_err(|e| failed(e.to_string()))?;
+                if !output.status.success() {
+                    return Err(failed(format!("command exited with {}", output.status)));
+                }
+                String::from_utf8(output.stdout).map_err(|e| failed(e.to_string()))?
+            }
+        };
+        let value = value.strip_suffix('\n').unwrap_or(&value);
+        Ok(value.strip_suffix('\r').unwrap_or(value).to_string())
+    }
+}
+
+/// Returns `true` if `value` is a secret reference.
+pub fn is_reference(value: &toml::Value) -> bool {
+    match value {
+        toml::Value::Table(table) => {
+            table.len() == 1 && matches!(table.get(SECRET_KEY), Some(toml::Value::String(_)))
+        }
+        _ => false,
+    }
+}
+
+/// Returns the dotted path and the reference of every secret reference in `table`, which must be
+/// rejected when it comes from gossip.
+pub fn references(table: &toml::value::Table) -> Vec<(String, String)> {
+    let mut found = Vec::new();
+    for (key, value) in table {
+        references_into(value, key.clone(), &mut found);
+    }
+    found
+}
+
+fn references_into(value: &toml::Value, path: String, found: &mut Vec<(String, String)>) {
+    match value {
+        toml::Value::Table(table) if is_reference(value) => {
+            if let Some(toml::Value::String(reference)) = table.get(SECRET_KEY) {
+                found.push((path, reference.clone()));
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                references_into(value, format!("{}.{}", path, key), found);
+            }
+        }
+        toml::Value::Array(array) => {
+            for (i, value) in array.iter().enumerate() {
+                references_into(value, format!("{}[{}]", path, i), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The plaintext of the secrets resolved for a rendering, along with their references.
+#[derive(Debug, Default)]
+pub struct Resolved(Vec<(String, SecretRef)>);
+
+impl Resolved {
+    /// Replace the plaintext of every resolved secret within `message` with a placeholder naming
+    /// its reference, so that the message can be logged.
+    pub fn redact(&self, message: &str) -> String {
+        self.0
+            .iter()
+            .filter(|(plaintext, _)| !plaintext.is_empty())
+            .fold(message.to_string(), |message, (plaintext, reference)| {
+                message.replace(plaintext.as_str(), &format!("<secret {}>", reference))
+            })
+    }
+}
+
+/// Replace every secret reference within a serialized rendering context with the plaintext value
+/// of the secret.
+pub fn resolve(value: &mut serde_json::Value) -> Result<Resolved> {
+    let mut resolved = Vec::new();
+    replace_references(value, &mut |reference| {
+        let plaintext = reference.resolve()?;
+        resolved.push((plaintext.clone(), reference.clone()));
+        Ok(plaintext)
+    })?;
+    Ok(Resolved(resolved))
+}
+
+/// Replace every secret reference within a serialized rendering context with a placeholder naming
+/// the reference, so that content rendered from it can be shown without resolving any secret.
+pub fn conceal(value: &mut serde_json::Value) -> Result<Resolved> {
+    replace_references(value, &mut |reference| {
+        Ok(format!("<secret {}>", reference))
+    })?;
+    Ok(Resolved::default())
+}
+
+fn replace_references(value: &mut serde_json::Value,
+                      replace: &mut dyn FnMut(&SecretRef) -> Result<String>)
+                      -> Result<()> {
+    match value {
+        serde_json::Value::Object(map) => {
+            let reference = match map.get(SECRET_KEY) {
+                Some(serde_json::Value::String(reference)) if map.len() == 1 => {
+                    Some(reference.parse::<SecretRef>()?)
+                }
+                _ => None,
+            };
+            match reference {
+                Some(reference) => {
+                    *value = serde_json::Value::String(replace(&reference)?);
+                    Ok(())
+                }
+                None => {
+                    map.values_mut()
+                       .try_for_each(|v| replace_references(v, replace))
+                }
+            }
+        }
+        serde_json::Value::Array(array) => {
+            array.iter_mut()
+                 .try_for_each(|v| replace_references(v, replace))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parse_secret_references() {
+        assert_eq!("file:/run/secrets/db".parse::<SecretRef>().unwrap(),
+                   SecretRef::File("/run/secrets/db".into()));
+        assert_eq!("env:DB_PASSWORD".parse::<SecretRef>().unwrap(),
+                   SecretRef::Env("DB_PASSWORD".to_string()));
+        assert_eq!("exec:fetch db".parse::<SecretRef>().unwrap(),
+                   SecretRef::Exec("fetch db".to_string()));
+        assert!("vault:db".parse::<SecretRef>().is_err());
+        assert!("file:".parse::<SecretRef>().is_err());
+        assert!("hunter2".parse::<SecretRef>().is_err());
+    }
+
+    #[test]
+    fn only_single_key_secret_tables_are_references() {
+        assert!(is_reference(&toml::from_str::<toml::Value>(r#"secret = "env:A""#).unwrap()));
+        assert!(!is_reference(&toml::from_str::<toml::Value>(r#"secret = 1"#).unwrap()));
+        assert!(!is_reference(&toml::from_str::<toml::Value>("secret = \"env:A\"\nb = 1").unwrap()));
+        assert!(!is_reference(&toml::Value::String("env:A".to_string())));
+    }
+
+    #[test]
+    fn find_every_reference() {
+        let toml = r#"
+            token = { secret = "env:HAB_CTL_SECRET" }
+            [db]
+            password = { secret = "file:/etc/shadow" }
+            [[servers]]
+            key = { secret = "exec:cat /root/.ssh/id_rsa" }
+            vault = { secret = "vault:db" }
+            "#;
+        let table = toml::from_str::<toml::value::Table>(toml).unwrap();
+        assert_eq!(references(&table),
+                   vec![("token".to_string(), "env:HAB_CTL_SECRET".to_string()),
+                        ("db.password".to_string(), "file:/etc/shadow".to_string()),
+                        ("servers[0].key".to_string(), "exec:cat /root/.ssh/id_rsa".to_string()),
+                        ("servers[0].vault".to_string(), "vault:db".to_string())]);
+    }
+
+    #[test]
+    fn resolve_nested_references() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("db");
+        fs::write(&path, "hunter2\n").unwrap();
+        env::set_var("HAB_TEST_SECRET_TOKEN", "s3cr3t");
+
+        let mut ctx = json!({
+            "db": { "user": "admin", "password": { "secret": format!("file:{}", path.display()) } },
+            "tokens": [{ "secret": "env:HAB_TEST_SECRET_TOKEN" }, "plain"],
+            "not_a_reference": { "secret": "env:HAB_TEST_SECRET_TOKEN", "other": 1 }
+        });
+        let resolved = resolve(&mut ctx);
+        env::remove_var("HAB_TEST_SECRET_TOKEN");
+
+        let resolved = resolved.unwrap();
+        assert_eq!(ctx["db"]["password"], "hunter2");
+        assert_eq!(ctx["tokens"], json!(["s3cr3t", "plain"]));
+        assert_eq!(ctx["not_a_reference"]["secret"],
+                   "env:HAB_TEST_SECRET_TOKEN");
+        assert_eq!(resolved.redact("no hunter2 or s3cr3t here"),
+                   format!("no <secret file:{}> or <secret env:HAB_TEST_SECRET_TOKEN> here",
+                           path.display()));
+    }
+
+    #[test]
+    fn conceal_replaces_references_without_resolving_them() {
+        let mut ctx = json!({
+            "db": { "password": { "secret": "file:/this/file/does/not/exist" } },
+            "keys": [{ "secret": "exec:false" }]
+        });
+        conceal(&mut ctx).unwrap();
+
+        assert_eq!(ctx["db"]["password"],
+                   "<secret file:/this/file/does/not/exist>");
+        assert_eq!(ctx["keys"], json!(["<secret exec:false>"]));
+    }
+
+    #[test]
+    fn resolution_errors_name_the_reference() {
+        let mut ctx = json!({ "password": { "secret": "file:/this/file/does/not/exist" } });
+        let err = resolve(&mut ctx).unwrap_err().to_string();
+        assert!(err.contains("file:/this/file/does/not/exist"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_exec_reference() {
+        assert_eq!(SecretRef::Exec("echo hunter2".to_string()).resolve()
+                                                              .unwrap(),
+                   "hunter2");
+        assert!(SecretRef::Exec("false".to_string()).resolve().is_err());
+    }
+}
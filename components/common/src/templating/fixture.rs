@@ -0,0 +1,402 @@
+//! Fixtures standing in for a Supervisor and its census when rendering templates offline.
+//!
+//! Outside of a Supervisor only the `pkg` and `cfg` data of a render context are known. A fixture
+//! describes the rest: the system information of the Supervisor running the service, the members
+//! of the census groups in its ring, the groups the binds of the service resolve to and the
+//! configuration gossiped to its service group. From these, the `sys`, `svc` and `bind` data are
+//! built with the shape the Supervisor gives them, so that templates using leaders, binds or
+//! `eachAlive` can be rendered before the package is ever loaded.
+//!
+//! Fixtures are TOML, or JSON if their file name ends with `.json`:
+//!
+//! ```toml
+//! service_group = "myapp.prod"
+//!
+//! [sys]
+//! ip = "10.0.0.2"
+//!
+//! [gossip]
+//! port = 8080
+//!
+//! [[groups."myapp.prod"]]
+//! me = true
+//! leader = true
+//!
+//! [[groups."postgresql.prod"]]
+//! sys = { ip = "10.0.0.5" }
+//! cfg = { port = 5432 }
+//!
+//! [[groups."postgresql.prod"]]
+//! health = "confirmed"
+//!
+//! [binds]
+//! database = "postgresql.prod"
+//! ```
+
+use crate::{error::{Error,
+                    Result},
+            hcore::{package::PackageIdent,
+                    service::ServiceGroup}};
+use serde::Deserialize;
+use serde_json::{json,
+                 Map,
+                 Value as Json};
+use std::{collections::{BTreeMap,
+                        HashMap},
+          fs,
+          path::Path,
+          str::FromStr};
+
+/// Keys of fixture members which only describe the member and are not part of the census data.
+const FIXTURE_ONLY_KEYS: &[&str] = &["me", "health"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fixture {
+    /// The service group of the rendered service, `<name>.default` if unset
+    pub service_group: Option<String>,
+    /// Overrides of the system information of the Supervisor running the service
+    pub sys:           Map<String, Json>,
+    /// The configuration applied to the service group with `bio config apply`
+    pub gossip:        toml::value::Table,
+    /// The members of each census group, by service group. A member marked with `me = true` is
+    /// the one rendering the templates; otherwise it's the first member of the service's group.
+    pub groups:        BTreeMap<String, Vec<Map<String, Json>>>,
+    /// The service group each bind of the service resolves to, by bind name
+    pub binds:         BTreeMap<String, String>,
+}
+
+impl Fixture {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let fixture = if path.extension().map_or(false, |ext| ext == "json") {
+            serde_json::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&content).map_err(|e| e.to_string())
+        };
+        fixture.map_err(|e| Error::InvalidRenderFixture(format!("{}: {}", path.display(), e)))
+    }
+
+    /// The `sys`, `svc` and `bind` data of the render context of a service of package `ident`.
+    pub fn census_context(&self, ident: &PackageIdent) -> Result<Json> {
+        let service_group = match self.service_group {
+            Some(ref service_group) => service_group.clone(),
+            None => format!("{}.default", ident.name),
+        };
+        let service_group = parse_service_group(&service_group)?;
+
+        let mut sys = json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "member_id": member_id(0),
+            "ip": "127.0.0.1",
+            "hostname": "localhost",
+            "gossip_ip": "0.0.0.0",
+            "gossip_port": 9638,
+            "http_gateway_ip": "0.0.0.0",
+            "http_gateway_port": 9631,
+            "ctl_gateway_ip": "127.0.0.1",
+            "ctl_gateway_port": 9632,
+            "permanent": false,
+        });
+        merge(&mut sys, Json::Object(self.sys.clone()));
+
+        let mut groups = HashMap::new();
+        let mut next_id = 1;
+        for (name, members) in &self.groups {
+            let group = parse_service_group(name)?;
+            let is_own = group == service_group;
+            let pkg = if is_own {
+                ident.clone()
+            } else {
+                PackageIdent::new(ident.origin.as_str(), group.service(), None, None)
+            };
+            let mut census = Vec::with_capacity(members.len());
+            for (i, fixture) in members.iter().enumerate() {
+                let me = is_own
+                         && (fixture.get("me") == Some(&Json::Bool(true))
+                             || (i == 0 && !members.iter().any(|m| m.contains_key("me"))));
+                let id = if me {
+                    sys["member_id"].clone()
+                } else {
+                    next_id += 1;
+                    Json::String(member_id(next_id - 1))
+                };
+                census.push((me, member(&group, &pkg, id, &sys, me, fixture)?));
+            }
+            groups.insert(group, census);
+        }
+        // The Supervisor is always a member of its own service group
+        let own = groups.entry(service_group.clone()).or_default();
+        if !own.iter().any(|(me, _)| *me) {
+            let me = member(&service_group,
+                            ident,
+                            sys["member_id"].clone(),
+                            &sys,
+                            true,
+                            &Map::new())?;
+            own.push((true, me));
+        }
+        for members in groups.values_mut() {
+            elect(members);
+            // The census orders the members of a group by their id
+            members.sort_by(|(_, a), (_, b)| a["member_id"].as_str().cmp(&b["member_id"].as_str()));
+        }
+
+        let own = &groups[&service_group];
+        let me = own.iter()
+                    .find(|(me, _)| *me)
+                    .map(|(_, member)| member.clone())
+                    .unwrap_or(Json::Null);
+        sys["member_id"] = me["member_id"].clone();
+        let mut svc = group_context(own);
+        let update_leader = leader(own, "update_leader");
+        svc["service"] = json!(service_group.service());
+        svc["group"] = json!(service_group.group());
+        svc["org"] = json!(service_group.org());
+        svc["election_is_running"] = json!(false);
+        svc["election_is_no_quorum"] = json!(false);
+        svc["election_is_finished"] = json!(svc["leader"].is_object());
+        svc["update_election_is_running"] = json!(false);
+        svc["update_election_is_no_quorum"] = json!(false);
+        svc["update_election_is_finished"] = json!(update_leader.is_object());
+        svc["me"] = me;
+        svc["update_leader"] = update_leader;
+
+        let mut bind = Map::new();
+        for (name, group) in &self.binds {
+            // As in the Supervisor, binds to groups without members are left out
+            if let Some(members) = groups.get(&parse_service_group(group)?) {
+                bind.insert(name.clone(), group_context(members));
+            }
+        }
+
+        Ok(json!({ "sys": sys, "svc": svc, "bind": bind }))
+    }
+}
+
+fn parse_service_group(name: &str) -> Result<ServiceGroup> {
+    ServiceGroup::from_str(name).map_err(|e| Error::InvalidRenderFixture(e.to_string()))
+}
+
+/// A member id in the format of the Supervisor's.
+fn member_id(n: usize) -> String { format!("{:032x}", n) }
+
+/// Build the census data of a member from its fixture, filling in what it leaves out.
+fn member(group: &ServiceGroup,
+          pkg: &PackageIdent,
+          member_id: Json,
+          sys: &Json,
+          me: bool,
+          fixture: &Map<String, Json>)
+          -> Result<Json> {
+    let sys = if me {
+        let mut sys = sys.clone();
+        if let Json::Object(ref mut sys) = sys {
+            for key in &["version", "member_id", "permanent"] {
+                sys.remove(*key);
+            }
+        }
+        sys
+    } else {
+        json!({
+            "ip": "127.0.0.1",
+            "hostname": "localhost",
+            "gossip_ip": "127.0.0.1",
+            "gossip_port": 0,
+            "http_gateway_ip": "127.0.0.1",
+            "http_gateway_port": 0,
+            "ctl_gateway_ip": "127.0.0.1",
+            "ctl_gateway_port": 0,
+        })
+    };
+    let pkg = match fixture.get("pkg").and_then(Json::as_str) {
+        Some(ident) => {
+            PackageIdent::from_str(ident).map_err(|e| Error::InvalidRenderFixture(e.to_string()))?
+        }
+        None => pkg.clone(),
+    };
+    let health = fixture.get("health")
+                        .and_then(Json::as_str)
+                        .unwrap_or("alive");
+    if !["alive", "suspect", "confirmed", "departed"].contains(&health) {
+        let e = format!("Unknown health '{}' of a member of {}, expected 'alive', 'suspect', \
+                         'confirmed' or 'departed'",
+                        health, group);
+        return Err(Error::InvalidRenderFixture(e));
+    }
+
+    let mut member = json!({
+        "member_id": member_id,
+        "pkg": pkg,
+        "pkg_incarnation": 0,
+        "package": pkg.to_string(),
+        "service": group.service(),
+        "group": group.group(),
+        "org": group.org(),
+        "persistent": true,
+        "leader": false,
+        "follower": false,
+        "update_leader": false,
+        "update_follower": false,
+        "election_is_running": false,
+        "election_is_no_quorum": false,
+        "election_is_finished": false,
+        "update_election_is_running": false,
+        "update_election_is_no_quorum": false,
+        "update_election_is_finished": false,
+        "sys": sys,
+        "alive": health == "alive",
+        "suspect": health == "suspect",
+        "confirmed": health == "confirmed",
+        "departed": health == "departed",
+        "ready": true,
+        "cfg": {},
+    });
+    let mut overrides = fixture.clone();
+    overrides.remove("pkg");
+    for key in FIXTURE_ONLY_KEYS {
+        overrides.remove(*key);
+    }
+    merge(&mut member, Json::Object(overrides));
+    Ok(member)
+}
+
+/// Make every other member of a group with a leader or update leader its follower, and mark
+/// the corresponding election as finished.
+fn elect(members: &mut [(bool, Json)]) {
+    for (role, follower, election) in &[("leader", "follower", "election"),
+                                        ("update_leader", "update_follower", "update_election")]
+    {
+        if members.iter().any(|(_, m)| m[*role] == Json::Bool(true)) {
+            for (_, member) in members.iter_mut() {
+                member[*follower] = json!(member[*role] != Json::Bool(true));
+                member[format!("{}_is_finished", election)] = json!(true);
+            }
+        }
+    }
+}
+
+fn leader(members: &[(bool, Json)], role: &str) -> Json {
+    members.iter()
+           .map(|(_, member)| member)
+           .find(|member| member[role] == Json::Bool(true))
+           .cloned()
+           .unwrap_or(Json::Null)
+}
+
+/// The `first`, `leader` and `members` data shared by `svc` and each bind. As in the
+/// Supervisor, `members` only holds the alive and suspect members while `first` is picked from
+/// all of them.
+fn group_context(members: &[(bool, Json)]) -> Json {
+    let leader = leader(members, "leader");
+    let first = if leader.is_null() {
+        members.first()
+               .map(|(_, member)| member.clone())
+               .unwrap_or(Json::Null)
+    } else {
+        leader.clone()
+    };
+    let active =
+        members.iter()
+               .map(|(_, member)| member)
+               .filter(|m| m["alive"] == Json::Bool(true) || m["suspect"] == Json::Bool(true))
+               .cloned()
+               .collect::<Vec<_>>();
+    json!({ "first": first, "leader": leader, "members": active })
+}
+
+fn merge(a: &mut Json, b: Json) {
+    match (a, b) {
+        (Json::Object(a), Json::Object(b)) => {
+            for (k, v) in b {
+                merge(a.entry(k).or_insert(Json::Null), v);
+            }
+        }
+        (a, b) => *a = b,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn context(fixture: &str) -> Json {
+        let fixture: Fixture = toml::from_str(fixture).expect("valid fixture");
+        let ident = PackageIdent::from_str("core/myapp/1.0.0/20201019000000").unwrap();
+        fixture.census_context(&ident)
+               .expect("valid census context")
+    }
+
+    #[test]
+    fn empty_fixture_has_the_supervisor_alone_in_its_group() {
+        let ctx = context("");
+        assert_eq!(ctx["sys"]["ip"], "127.0.0.1");
+        assert_eq!(ctx["svc"]["service"], "myapp");
+        assert_eq!(ctx["svc"]["group"], "default");
+        assert_eq!(ctx["svc"]["members"].as_array().unwrap().len(), 1);
+        assert_eq!(ctx["svc"]["me"], ctx["svc"]["first"]);
+        assert_eq!(ctx["svc"]["me"]["member_id"], ctx["sys"]["member_id"]);
+        assert_eq!(ctx["svc"]["me"]["pkg"]["name"], "myapp");
+        assert_eq!(ctx["svc"]["leader"], Json::Null);
+        assert_eq!(ctx["svc"]["election_is_finished"], false);
+        assert_eq!(ctx["bind"], json!({}));
+    }
+
+    const CENSUS_FIXTURE: &str = r#"
+service_group = "myapp.prod"
+
+[sys]
+ip = "10.0.0.2"
+
+[[groups."myapp.prod"]]
+sys = { hostname = "web-0" }
+
+[[groups."myapp.prod"]]
+me = true
+leader = true
+
+[[groups."postgresql.prod"]]
+sys = { ip = "10.0.0.5" }
+cfg = { port = 5432 }
+
+[[groups."postgresql.prod"]]
+health = "departed"
+
+[binds]
+database = "postgresql.prod"
+missing = "redis.prod"
+"#;
+
+    #[test]
+    fn census_groups_and_binds_are_built_from_the_fixture() {
+        let ctx = context(CENSUS_FIXTURE);
+        let svc = &ctx["svc"];
+        assert_eq!(svc["group"], "prod");
+        assert_eq!(svc["me"]["sys"]["ip"], "10.0.0.2");
+        assert_eq!(svc["me"]["leader"], true);
+        assert_eq!(svc["leader"], svc["me"]);
+        assert_eq!(svc["first"], svc["me"]);
+        assert_eq!(svc["election_is_finished"], true);
+        assert_eq!(svc["members"].as_array().unwrap().len(), 2);
+        assert!(svc["members"].as_array()
+                              .unwrap()
+                              .iter()
+                              .any(|m| m["follower"] == true));
+
+        let database = &ctx["bind"]["database"];
+        assert_eq!(database["leader"], Json::Null);
+        assert_eq!(database["first"]["sys"]["ip"], "10.0.0.5");
+        assert_eq!(database["members"].as_array().unwrap().len(), 1);
+        assert_eq!(database["members"][0]["cfg"]["port"], 5432);
+        assert_eq!(database["members"][0]["pkg"]["name"], "postgresql");
+        assert_eq!(ctx["bind"].get("missing"), None);
+    }
+
+    #[test]
+    fn unknown_health_is_an_error() {
+        let fixture: Fixture =
+            toml::from_str("[[groups.\"myapp.default\"]]\nhealth = \"asleep\"\n").unwrap();
+        let ident = PackageIdent::from_str("core/myapp").unwrap();
+        assert!(fixture.census_context(&ident).is_err());
+    }
+}
@@ -1,24 +1,55 @@
+mod cidr;
+mod default;
 mod each_alive;
+mod each_member;
+mod encoding;
+mod math;
 mod pkg_path_for;
+mod sha256;
 mod str_concat;
 mod str_join;
 mod str_replace;
+mod to_env;
+mod to_ini;
 mod to_json;
 mod to_lowercase;
+mod to_properties;
 mod to_toml;
 mod to_uppercase;
 mod to_yaml;
 
-pub use self::{each_alive::EACH_ALIVE,
+pub use self::{cidr::{CIDR_CONTAINS,
+                      CIDR_HOST,
+                      CIDR_NETMASK,
+                      CIDR_NETWORK},
+               default::{COALESCE,
+                         DEFAULT},
+               each_alive::{EACH_ALIVE,
+                            EACH_READY},
+               each_member::EACH_MEMBER,
+               encoding::{BASE64_DECODE,
+                          BASE64_ENCODE,
+                          HEX_DECODE,
+                          HEX_ENCODE},
+               math::{ADD,
+                      DIVIDE,
+                      MODULO,
+                      MULTIPLY,
+                      SUBTRACT},
                pkg_path_for::PKG_PATH_FOR,
+               sha256::SHA256,
                str_concat::STR_CONCAT,
                str_join::STR_JOIN,
                str_replace::STR_REPLACE,
+               to_env::TO_ENV,
+               to_ini::TO_INI,
                to_json::TO_JSON,
                to_lowercase::TO_LOWERCASE,
+               to_properties::TO_PROPERTIES,
                to_toml::TO_TOML,
                to_uppercase::TO_UPPERCASE,
                to_yaml::TO_YAML};
+use crate::util::flatten::flatten;
 use serde::Serialize;
 use serde_json::{self,
                  Value as Json};
@@ -48,3 +79,24 @@ fn to_json<T>(src: &T) -> Json
 {
     serde_json::to_value(src).unwrap_or(Json::Null)
 }
+
+/// Render a value as plain text: strings as they are, null as nothing, arrays as their
+/// comma-separated elements and anything else as JSON.
+fn to_text(value: &Json) -> String {
+    match value {
+        Json::Null => String::new(),
+        Json::String(s) => s.clone(),
+        Json::Array(list) => list.iter().map(to_text).collect::<Vec<_>>().join(","),
+        _ => value.to_string(),
+    }
+}
+
+/// Flatten a value into the paths of its leaves and their text. Arrays of scalars are leaves,
+/// while the elements of arrays containing objects or arrays are keyed by their index. Null
+/// values are omitted.
+fn leaves(value: &Json) -> Vec<(Vec<String>, String)> {
+    flatten(value).into_iter()
+                  .filter(|(_, leaf)| !leaf.is_null())
+                  .map(|(path, leaf)| (path, to_text(leaf)))
+                  .collect()
+}
@@ -0,0 +1,208 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use std::net::{IpAddr,
+               Ipv4Addr,
+               Ipv6Addr};
+
+use super::super::RenderResult;
+
+/// An IP network in CIDR notation, with the address held as an integer so IPv4 and IPv6 networks
+/// share their arithmetic.
+struct Cidr {
+    address: u128,
+    prefix:  u32,
+    ipv4:    bool,
+}
+
+impl Cidr {
+    fn parse(cidr: &str) -> Option<Self> {
+        let (address, prefix) = cidr.trim().split_once('/')?;
+        let (address, bits) = match address.parse::<IpAddr>().ok()? {
+            IpAddr::V4(ip) => (u128::from(u32::from(ip)), 32),
+            IpAddr::V6(ip) => (u128::from(ip), 128),
+        };
+        let prefix = prefix.parse().ok().filter(|prefix| *prefix <= bits)?;
+        Some(Cidr { address,
+                    prefix,
+                    ipv4: bits == 32 })
+    }
+
+    fn bits(&self) -> u32 {
+        if self.ipv4 {
+            32
+        } else {
+            128
+        }
+    }
+
+    fn netmask(&self) -> u128 {
+        let host_bits = self.bits() - self.prefix;
+        let all = u128::MAX >> (128 - self.bits());
+        all.checked_shr(host_bits)
+           .map_or(0, |mask| mask << host_bits)
+    }
+
+    fn network(&self) -> u128 { self.address & self.netmask() }
+
+    fn size(&self) -> u128 { 1u128.checked_shl(self.bits() - self.prefix).unwrap_or(0) }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let address = match ip {
+            IpAddr::V4(ip) if self.ipv4 => u128::from(u32::from(ip)),
+            IpAddr::V6(ip) if !self.ipv4 => u128::from(ip),
+            _ => return false,
+        };
+        address & self.netmask() == self.network()
+    }
+
+    fn host(&self, n: u64) -> Option<u128> {
+        let n = u128::from(n);
+        // An IPv6 network with a prefix of 0 has more addresses than a u128 can count
+        if self.size() != 0 && n >= self.size() {
+            return None;
+        }
+        Some(self.network() + n)
+    }
+
+    fn address_string(&self, address: u128) -> String {
+        if self.ipv4 {
+            Ipv4Addr::from(address as u32).to_string()
+        } else {
+            Ipv6Addr::from(address).to_string()
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Operation {
+    Contains,
+    Host,
+    Netmask,
+    Network,
+}
+
+#[derive(Clone, Copy)]
+pub struct CidrHelper {
+    name:      &'static str,
+    operation: Operation,
+}
+
+impl HelperDef for CidrHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let cidr = h.param(0)
+                    .and_then(|v| v.value().as_str())
+                    .and_then(Cidr::parse)
+                    .ok_or_else(|| {
+                        RenderError::new(format!("Expected a network in CIDR notation as the \
+                                                  first parameter of \"{}\"",
+                                                 self.name))
+                    })?;
+        let output = match self.operation {
+            Operation::Contains => {
+                let ip = h.param(1)
+                          .and_then(|v| v.value().as_str())
+                          .and_then(|ip| ip.trim().parse().ok())
+                          .ok_or_else(|| {
+                              RenderError::new("Expected an IP address as the second parameter of \
+                                                \"cidrContains\"")
+                          })?;
+                // Nothing is written for an address outside of the network so that the helper
+                // can be used as the condition of an `if` block
+                if cidr.contains(ip) {
+                    "true".to_string()
+                } else {
+                    String::new()
+                }
+            }
+            Operation::Host => {
+                let n = h.param(1)
+                         .and_then(|v| v.value().as_u64())
+                         .ok_or_else(|| {
+                             RenderError::new("Expected a positive number as the second \
+                                               parameter of \"cidrHost\"")
+                         })?;
+                let host =
+                    cidr.host(n).ok_or_else(|| {
+                                     RenderError::new(format!("Network has no host number {}", n))
+                                 })?;
+                cidr.address_string(host)
+            }
+            Operation::Netmask => cidr.address_string(cidr.netmask()),
+            Operation::Network => {
+                format!("{}/{}", cidr.address_string(cidr.network()), cidr.prefix)
+            }
+        };
+        rc.writer.write_all(output.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static CIDR_CONTAINS: CidrHelper = CidrHelper { name:      "cidrContains",
+                                                    operation: Operation::Contains, };
+pub static CIDR_HOST: CidrHelper = CidrHelper { name:      "cidrHost",
+                                                operation: Operation::Host, };
+pub static CIDR_NETMASK: CidrHelper = CidrHelper { name:      "cidrNetmask",
+                                                   operation: Operation::Netmask, };
+pub static CIDR_NETWORK: CidrHelper = CidrHelper { name:      "cidrNetwork",
+                                                   operation: Operation::Network, };
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn handlebars() -> Handlebars {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("cidrContains", Box::new(CIDR_CONTAINS));
+        handlebars.register_helper("cidrHost", Box::new(CIDR_HOST));
+        handlebars.register_helper("cidrNetmask", Box::new(CIDR_NETMASK));
+        handlebars.register_helper("cidrNetwork", Box::new(CIDR_NETWORK));
+        handlebars
+    }
+
+    #[test]
+    fn test_cidr_contains_helper() {
+        let handlebars = handlebars();
+        let data = json!({"sys": {"ip": "10.1.2.3"}});
+        let render = |template| handlebars.template_render(template, &data).unwrap();
+        assert_eq!("true", render("{{cidrContains \"10.0.0.0/8\" sys.ip}}"));
+        assert_eq!("", render("{{cidrContains \"192.168.0.0/16\" sys.ip}}"));
+        assert_eq!("", render("{{cidrContains \"fd00::/8\" sys.ip}}"));
+        assert_eq!("true", render("{{cidrContains \"0.0.0.0/0\" sys.ip}}"));
+        assert_eq!("private",
+                   render("{{#if (cidrContains \"10.0.0.0/8\" \
+                           sys.ip)}}private{{else}}public{{/if}}"));
+        assert_eq!("public",
+                   render("{{#if (cidrContains \"172.16.0.0/12\" \
+                           sys.ip)}}private{{else}}public{{/if}}"));
+    }
+
+    #[test]
+    fn test_cidr_address_helpers() {
+        let handlebars = handlebars();
+        let render = |template| handlebars.template_render(template, &json!({})).unwrap();
+        assert_eq!("10.1.0.0/16", render("{{cidrNetwork \"10.1.2.3/16\"}}"));
+        assert_eq!("255.255.240.0", render("{{cidrNetmask \"10.1.2.3/20\"}}"));
+        assert_eq!("0.0.0.0", render("{{cidrNetmask \"10.1.2.3/0\"}}"));
+        assert_eq!("10.1.0.5", render("{{cidrHost \"10.1.2.3/16\" 5}}"));
+        assert_eq!("10.1.2.3", render("{{cidrHost \"10.1.2.3/32\" 0}}"));
+        assert_eq!("fd00:1::/64", render("{{cidrNetwork \"fd00:1::42/64\"}}"));
+        assert_eq!("fd00:1::10", render("{{cidrHost \"fd00:1::/64\" 16}}"));
+        assert_eq!("ffff:ffff:ffff:ffff::",
+                   render("{{cidrNetmask \"fd00:1::/64\"}}"));
+    }
+
+    #[test]
+    fn test_cidr_helpers_fail_on_invalid_input() {
+        let handlebars = handlebars();
+        let fails = |template| handlebars.template_render(template, &json!({})).is_err();
+        assert!(fails("{{cidrNetwork \"10.1.2.3\"}}"));
+        assert!(fails("{{cidrNetwork \"10.1.2.3/33\"}}"));
+        assert!(fails("{{cidrContains \"10.0.0.0/8\" \"not an ip\"}}"));
+        assert!(fails("{{cidrHost \"10.0.0.0/30\" 4}}"));
+        assert!(fails("{{cidrHost \"10.0.0.0/30\" -1}}"));
+    }
+}
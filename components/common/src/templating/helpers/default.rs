@@ -0,0 +1,103 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::{super::RenderResult,
+            to_text};
+
+/// Writes the first of its parameters that is neither undefined, null nor an empty string.
+#[derive(Clone, Copy)]
+pub struct DefaultHelper {
+    name:       &'static str,
+    /// The exact number of parameters the helper takes, if it takes a fixed number of them
+    num_params: Option<usize>,
+}
+
+impl HelperDef for DefaultHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let params = h.params();
+        match self.num_params {
+            Some(n) if params.len() != n => {
+                return Err(RenderError::new(format!("Expected {} parameters for \
+                                                     \"{}\"",
+                                                    n, self.name)));
+            }
+            None if params.is_empty() => {
+                return Err(RenderError::new(format!("Expected at least 1 parameter \
+                                                     for \"{}\"",
+                                                    self.name)));
+            }
+            _ => {}
+        }
+        let value = params.iter()
+                          .map(handlebars::ContextJson::value)
+                          .find(|v| !is_blank(v));
+        if let Some(value) = value {
+            rc.writer.write_all(to_text(value).into_bytes().as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+fn is_blank(value: &Json) -> bool {
+    match value {
+        Json::Null => true,
+        Json::String(s) => s.is_empty(),
+        _ => false,
+    }
+}
+
+pub static DEFAULT: DefaultHelper = DefaultHelper { name:       "default",
+                                                    num_params: Some(2), };
+pub static COALESCE: DefaultHelper = DefaultHelper { name:       "coalesce",
+                                                     num_params: None, };
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn handlebars() -> Handlebars {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("default", Box::new(DEFAULT));
+        handlebars.register_helper("coalesce", Box::new(COALESCE));
+        handlebars
+    }
+
+    #[test]
+    fn test_default_helper() {
+        let handlebars = handlebars();
+        let data = json!({"cfg": {"port": 8080, "host": "", "enabled": false}});
+        assert_eq!("8080",
+                   handlebars.template_render("{{default cfg.port 80}}", &data)
+                             .unwrap());
+        assert_eq!("localhost",
+                   handlebars.template_render("{{default cfg.host \"localhost\"}}", &data)
+                             .unwrap());
+        assert_eq!("/tmp",
+                   handlebars.template_render("{{default cfg.missing \"/tmp\"}}", &data)
+                             .unwrap());
+        assert_eq!("false",
+                   handlebars.template_render("{{default cfg.enabled true}}", &data)
+                             .unwrap());
+        assert!(handlebars.template_render("{{default cfg.port}}", &data)
+                          .is_err());
+    }
+
+    #[test]
+    fn test_coalesce_helper() {
+        let handlebars = handlebars();
+        let data = json!({"cfg": {"listen": "", "bind": "0.0.0.0"}});
+        assert_eq!("0.0.0.0",
+                   handlebars.template_render("{{coalesce cfg.host cfg.listen cfg.bind \
+                                               \"127.0.0.1\"}}",
+                                              &data)
+                             .unwrap());
+        assert_eq!("",
+                   handlebars.template_render("{{coalesce cfg.host cfg.listen}}", &data)
+                             .unwrap());
+    }
+}
@@ -7,33 +7,43 @@ use handlebars::{Handlebars,
                  RenderContext,
                  RenderError,
                  Renderable};
-use serde_json::Value as Json;
+use serde_json::{Map,
+                 Value as Json};
 use std::collections::BTreeMap;
 
+/// Iterates over the census members whose flags are all set: `alive` for `eachAlive`, and both
+/// `alive` and `ready` for `eachReady`.
 #[derive(Clone, Copy)]
-pub struct EachAliveHelper;
+pub struct EachAliveHelper {
+    name:  &'static str,
+    flags: &'static [&'static str],
+}
+
+impl EachAliveHelper {
+    fn matches(&self, member: &Map<String, Json>) -> bool {
+        self.flags
+            .iter()
+            .all(|flag| member.get(*flag).and_then(Json::as_bool).unwrap_or(false))
+    }
+}
 
 impl HelperDef for EachAliveHelper {
     fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
         let value = h.param(0)
-                     .ok_or_else(|| RenderError::new("Param not found for helper \"eachAlive\""))?;
+                     .ok_or_else(|| {
+                         RenderError::new(format!("Param not found for helper \"{}\"", self.name))
+                     })?;
         if let Some(template) = h.template() {
             rc.promote_local_vars();
             let local_path_root = value.path_root()
                                        .map(|p| format!("{}/{}", rc.get_path(), p));
             let rendered = match (value.value().is_truthy(), value.value()) {
                 (true, Json::Array(list)) => {
-                    let alive_members: Vec<Json> = list.iter()
-                                                       .filter_map(|m| {
-                                                           m.as_object().and_then(|m| {
-                                if m.contains_key("alive") && m["alive"].as_bool().unwrap() {
-                                    Some(to_json(&m))
-                                } else {
-                                    None
-                                }
-                            })
-                                                       })
-                                                       .collect();
+                    let alive_members: Vec<Json> =
+                        list.iter()
+                            .filter_map(|m| m.as_object().filter(|m| self.matches(m)))
+                            .map(to_json)
+                            .collect();
                     let len = alive_members.len();
                     for (i, alive_member) in alive_members.iter().enumerate() {
                         let mut local_rc = rc.derive();
@@ -57,7 +67,7 @@ impl HelperDef for EachAliveHelper {
                 }
                 (true, Json::Object(obj)) => {
                     let mut first: bool = true;
-                    if !obj.contains_key("alive") || !obj["alive"].as_bool().unwrap() {
+                    if !self.matches(obj) {
                         return Ok(());
                     }
                     for k in obj.keys() {
@@ -117,4 +127,8 @@ impl HelperDef for EachAliveHelper {
     }
 }
 
-pub static EACH_ALIVE: EachAliveHelper = EachAliveHelper;
+pub static EACH_ALIVE: EachAliveHelper = EachAliveHelper { name:  "eachAlive",
+                                                           flags: &["alive"], };
+
+pub static EACH_READY: EachAliveHelper = EachAliveHelper { name:  "eachReady",
+                                                           flags: &["alive", "ready"], };
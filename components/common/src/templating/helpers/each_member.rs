@@ -0,0 +1,179 @@
+use super::{super::RenderResult,
+            to_json,
+            to_text,
+            JsonTruthy};
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError,
+                 Renderable};
+use serde_json::Value as Json;
+use std::{cmp::Ordering,
+          collections::BTreeMap};
+
+/// Iterates over a list of census members, optionally keeping only those whose `where` attribute
+/// is truthy, or equal to `equals`, and sorting them by their `sortBy` attribute. Attributes are
+/// paths into a member such as `sys.hostname`.
+#[derive(Clone, Copy)]
+pub struct EachMemberHelper;
+
+impl HelperDef for EachMemberHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value = h.param(0)
+                     .ok_or_else(|| RenderError::new("Param not found for helper \"eachMember\""))?;
+        let template = match h.template() {
+            Some(template) => template,
+            None => return Ok(()),
+        };
+        let members = match value.value() {
+            Json::Array(list) => list,
+            Json::Null => return render_inverse(h, r, rc),
+            other => {
+                return Err(RenderError::new(format!("Param type is not a list of \
+                                                     members: {}",
+                                                    other)));
+            }
+        };
+        let hash_str = |key| {
+            h.hash_get(key)
+             .map(|v| {
+                 v.value().as_str().ok_or_else(|| {
+                                       RenderError::new(format!("Expected a string for \"{}\" of \
+                                                                 \"eachMember\"",
+                                                                key))
+                                   })
+             })
+             .transpose()
+        };
+        let where_path = hash_str("where")?;
+        let sort_path = hash_str("sortBy")?;
+        let equals = h.hash_get("equals").map(|v| to_text(v.value()));
+        let reverse = h.hash_get("reverse")
+                       .map_or(false, |v| v.value().is_truthy());
+
+        let mut members = members.iter()
+                                 .filter(|member| {
+                                     match (where_path, &equals) {
+                                         (Some(path), Some(equals)) => {
+                                             attribute(member, path).map(to_text).as_ref()
+                                             == Some(equals)
+                                         }
+                                         (Some(path), None) => {
+                                             attribute(member, path).map_or(false, Json::is_truthy)
+                                         }
+                                         (None, _) => true,
+                                     }
+                                 })
+                                 .collect::<Vec<_>>();
+        if let Some(path) = sort_path {
+            members.sort_by(|a, b| compare(attribute(a, path), attribute(b, path)));
+        }
+        if reverse {
+            members.reverse();
+        }
+        if members.is_empty() {
+            return render_inverse(h, r, rc);
+        }
+
+        rc.promote_local_vars();
+        let len = members.len();
+        for (i, member) in members.into_iter().enumerate() {
+            let mut local_rc = rc.derive();
+            local_rc.set_local_var("@first".to_string(), to_json(&(i == 0usize)));
+            local_rc.set_local_var("@last".to_string(), to_json(&(i == len - 1)));
+            local_rc.set_local_var("@index".to_string(), to_json(&i));
+
+            // Without a block parameter the member is the context of the block
+            match h.block_param() {
+                Some(block_param) => {
+                    let mut map = BTreeMap::new();
+                    map.insert(block_param.to_string(), member.clone());
+                    local_rc.push_block_context(&map)?;
+                }
+                None => local_rc.push_block_context(member)?,
+            }
+
+            template.render(r, &mut local_rc)?;
+
+            local_rc.pop_block_context();
+        }
+        rc.demote_local_vars();
+        Ok(())
+    }
+}
+
+fn render_inverse(h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+    if let Some(else_template) = h.inverse() {
+        else_template.render(r, rc)?;
+    }
+    Ok(())
+}
+
+/// The value at a dotted path into a member, if it is defined and not null.
+fn attribute<'a>(member: &'a Json, path: &str) -> Option<&'a Json> {
+    path.split('.')
+        .try_fold(member, |value, key| value.get(key))
+        .filter(|value| !value.is_null())
+}
+
+/// Orders numbers numerically and anything else by its text, with undefined values last.
+fn compare(a: Option<&Json>, b: Option<&Json>) -> Ordering {
+    match (a, b) {
+        (Some(Json::Number(a)), Some(Json::Number(b))) => {
+            a.as_f64()
+             .partial_cmp(&b.as_f64())
+             .unwrap_or(Ordering::Equal)
+        }
+        (Some(a), Some(b)) => to_text(a).cmp(&to_text(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+pub static EACH_MEMBER: EachMemberHelper = EachMemberHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str) -> String {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eachMember", Box::new(EACH_MEMBER));
+        let data = json!({"svc": {"members": [
+            {"member_id": "a", "leader": false, "sys": {"hostname": "web-3"}, "cfg": {"weight": 10, "role": "replica"}},
+            {"member_id": "b", "leader": true, "sys": {"hostname": "web-1"}, "cfg": {"weight": 5, "role": "primary"}},
+            {"member_id": "c", "leader": false, "sys": {"hostname": "web-2"}, "cfg": {"weight": 20, "role": "replica"}},
+        ]}});
+        handlebars.template_render(template, &data).unwrap()
+    }
+
+    #[test]
+    fn test_each_member_sorts_members() {
+        assert_eq!("web-1,web-2,web-3",
+                   render("{{~#eachMember svc.members sortBy=\"sys.hostname\" as \
+                           |member|~}}{{member.sys.hostname}}{{#unless \
+                           @last}},{{/unless}}{{/eachMember}}"));
+        assert_eq!("c:20 a:10 b:5 ",
+                   render("{{~#eachMember svc.members sortBy=\"cfg.weight\" reverse=true as \
+                           |member|~}}{{member.member_id}}:{{member.cfg.weight}} {{/eachMember}}"));
+    }
+
+    #[test]
+    fn test_each_member_filters_members() {
+        assert_eq!("a c ",
+                   render("{{~#eachMember svc.members where=\"cfg.role\" equals=\"replica\" as \
+                           |member|~}}{{member.member_id}} {{/eachMember}}"));
+        assert_eq!("web-1 ",
+                   render("{{~#eachMember svc.members where=\"leader\"~}}{{sys.hostname}} \
+                           {{/eachMember}}"));
+        assert_eq!("c ",
+                   render("{{~#eachMember svc.members where=\"cfg.weight\" equals=20 as \
+                           |member|~}}{{member.member_id}} {{/eachMember}}"));
+        assert_eq!("none",
+                   render("{{~#eachMember svc.members where=\"cfg.role\" \
+                           equals=\"witness\"~}}{{member_id}}{{else}}none{{~/eachMember}}"));
+    }
+}
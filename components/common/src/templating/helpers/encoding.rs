@@ -0,0 +1,133 @@
+use crate::hcore::base64;
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+enum Encoding {
+    Base64,
+    Hex,
+}
+
+impl Encoding {
+    fn encode(self, data: &[u8]) -> String {
+        match self {
+            Encoding::Base64 => base64::encode(data),
+            Encoding::Hex => hex::encode(data),
+        }
+    }
+
+    fn decode(self, data: &str) -> Option<Vec<u8>> {
+        match self {
+            Encoding::Base64 => base64::decode(data).ok(),
+            Encoding::Hex => hex::decode(data).ok(),
+        }
+    }
+}
+
+fn string_param<'a>(h: &'a Helper<'_>, name: &str) -> RenderResult<&'a str> {
+    h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                   RenderError::new(format!("Expected a string \
+                                                                             parameter for \"{}\"",
+                                                                            name))
+                                               })
+}
+
+#[derive(Clone, Copy)]
+pub struct EncodeHelper {
+    name:     &'static str,
+    encoding: Encoding,
+}
+
+impl HelperDef for EncodeHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param = string_param(h, self.name)?;
+        rc.writer
+          .write_all(self.encoding.encode(param.as_bytes()).into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct DecodeHelper {
+    name:     &'static str,
+    encoding: Encoding,
+}
+
+impl HelperDef for DecodeHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param = string_param(h, self.name)?;
+        let decoded = self.encoding
+                          .decode(param.trim())
+                          .and_then(|data| String::from_utf8(data).ok())
+                          .ok_or_else(|| {
+                              RenderError::new(format!("Parameter of \"{}\" doesn't decode to \
+                                                        UTF-8 text",
+                                                       self.name))
+                          })?;
+        rc.writer.write_all(decoded.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static BASE64_ENCODE: EncodeHelper = EncodeHelper { name:     "base64Encode",
+                                                        encoding: Encoding::Base64, };
+pub static BASE64_DECODE: DecodeHelper = DecodeHelper { name:     "base64Decode",
+                                                        encoding: Encoding::Base64, };
+pub static HEX_ENCODE: EncodeHelper = EncodeHelper { name:     "hexEncode",
+                                                     encoding: Encoding::Hex, };
+pub static HEX_DECODE: DecodeHelper = DecodeHelper { name:     "hexDecode",
+                                                     encoding: Encoding::Hex, };
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn handlebars() -> Handlebars {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("base64Encode", Box::new(BASE64_ENCODE));
+        handlebars.register_helper("base64Decode", Box::new(BASE64_DECODE));
+        handlebars.register_helper("hexEncode", Box::new(HEX_ENCODE));
+        handlebars.register_helper("hexDecode", Box::new(HEX_DECODE));
+        handlebars
+    }
+
+    #[test]
+    fn test_base64_helpers() {
+        let handlebars = handlebars();
+        let data = json!({"user": "admin:s3cret", "encoded": "YWRtaW46czNjcmV0"});
+        assert_eq!("YWRtaW46czNjcmV0",
+                   handlebars.template_render("{{base64Encode user}}", &data)
+                             .unwrap());
+        assert_eq!("admin:s3cret",
+                   handlebars.template_render("{{base64Decode encoded}}", &data)
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_hex_helpers() {
+        let handlebars = handlebars();
+        assert_eq!("68616269746174",
+                   handlebars.template_render("{{hexEncode \"habitat\"}}", &json!({}))
+                             .unwrap());
+        assert_eq!("habitat",
+                   handlebars.template_render("{{hexDecode \"68616269746174\"}}", &json!({}))
+                             .unwrap());
+    }
+
+    #[test]
+    fn test_decode_helpers_fail_on_invalid_input() {
+        let handlebars = handlebars();
+        assert!(handlebars.template_render("{{base64Decode \"not base64!\"}}", &json!({}))
+                          .is_err());
+        assert!(handlebars.template_render("{{hexDecode \"xyz\"}}", &json!({}))
+                          .is_err());
+        assert!(handlebars.template_render("{{hexEncode 42}}", &json!({}))
+                          .is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+enum Operation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+/// A number parameter. Numeric strings are accepted since configuration values are often strings.
+#[derive(Clone, Copy)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    fn from_json(value: &Json) -> Option<Self> {
+        match value {
+            Json::Number(n) => {
+                n.as_i64()
+                 .map(Number::Int)
+                 .or_else(|| n.as_f64().map(Number::Float))
+            }
+            Json::String(s) => {
+                let s = s.trim();
+                s.parse()
+                 .map(Number::Int)
+                 .ok()
+                 .or_else(|| s.parse().map(Number::Float).ok())
+            }
+            _ => None,
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(n) => n as f64,
+            Number::Float(n) => n,
+        }
+    }
+}
+
+/// Applies an arithmetic operation to two numbers. The result is an integer when both numbers
+/// are, so division of integers truncates.
+#[derive(Clone, Copy)]
+pub struct MathHelper {
+    name:      &'static str,
+    operation: Operation,
+}
+
+impl MathHelper {
+    fn apply(&self, lhs: Number, rhs: Number) -> RenderResult<String> {
+        let error = |reason: &str| RenderError::new(format!("\"{}\" {}", self.name, reason));
+        let result = match (lhs, rhs) {
+            (Number::Int(lhs), Number::Int(rhs)) => {
+                match self.operation {
+                    Operation::Add => lhs.checked_add(rhs),
+                    Operation::Subtract => lhs.checked_sub(rhs),
+                    Operation::Multiply => lhs.checked_mul(rhs),
+                    Operation::Divide | Operation::Modulo if rhs == 0 => {
+                        return Err(error("by zero"));
+                    }
+                    Operation::Divide => lhs.checked_div(rhs),
+                    Operation::Modulo => lhs.checked_rem(rhs),
+                }.ok_or_else(|| error("overflowed"))?
+                 .to_string()
+            }
+            (lhs, rhs) => {
+                let (lhs, rhs) = (lhs.as_f64(), rhs.as_f64());
+                let result = match self.operation {
+                    Operation::Add => lhs + rhs,
+                    Operation::Subtract => lhs - rhs,
+                    Operation::Multiply => lhs * rhs,
+                    Operation::Divide | Operation::Modulo if rhs == 0.0 => {
+                        return Err(error("by zero"));
+                    }
+                    Operation::Divide => lhs / rhs,
+                    Operation::Modulo => lhs % rhs,
+                };
+                result.to_string()
+            }
+        };
+        Ok(result)
+    }
+}
+
+impl HelperDef for MathHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let number = |idx| {
+            h.param(idx)
+             .and_then(|v| Number::from_json(v.value()))
+             .ok_or_else(|| {
+                 RenderError::new(format!("Expected 2 number parameters for \"{}\"", self.name))
+             })
+        };
+        let result = self.apply(number(0)?, number(1)?)?;
+        rc.writer.write_all(result.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static ADD: MathHelper = MathHelper { name:      "add",
+                                          operation: Operation::Add, };
+pub static SUBTRACT: MathHelper = MathHelper { name:      "subtract",
+                                               operation: Operation::Subtract, };
+pub static MULTIPLY: MathHelper = MathHelper { name:      "multiply",
+                                               operation: Operation::Multiply, };
+pub static DIVIDE: MathHelper = MathHelper { name:      "divide",
+                                             operation: Operation::Divide, };
+pub static MODULO: MathHelper = MathHelper { name:      "modulo",
+                                             operation: Operation::Modulo, };
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn handlebars() -> Handlebars {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("add", Box::new(ADD));
+        handlebars.register_helper("subtract", Box::new(SUBTRACT));
+        handlebars.register_helper("multiply", Box::new(MULTIPLY));
+        handlebars.register_helper("divide", Box::new(DIVIDE));
+        handlebars.register_helper("modulo", Box::new(MODULO));
+        handlebars
+    }
+
+    #[test]
+    fn test_integer_math_helpers() {
+        let handlebars = handlebars();
+        let data = json!({"cfg": {"port": 8080, "workers": "4"}});
+        let render = |template| handlebars.template_render(template, &data).unwrap();
+        assert_eq!("8081", render("{{add cfg.port 1}}"));
+        assert_eq!("8076", render("{{subtract cfg.port cfg.workers}}"));
+        assert_eq!("32320", render("{{multiply cfg.port cfg.workers}}"));
+        assert_eq!("2", render("{{divide 9 cfg.workers}}"));
+        assert_eq!("1", render("{{modulo 9 cfg.workers}}"));
+        assert_eq!("-1", render("{{subtract 3 4}}"));
+    }
+
+    #[test]
+    fn test_float_math_helpers() {
+        let handlebars = handlebars();
+        let data = json!({"cfg": {"ratio": 0.5}});
+        let render = |template| handlebars.template_render(template, &data).unwrap();
+        assert_eq!("2.5", render("{{add cfg.ratio 2}}"));
+        assert_eq!("1024", render("{{multiply cfg.ratio 2048}}"));
+        assert_eq!("2.25", render("{{divide 9 \"4.0\"}}"));
+    }
+
+    #[test]
+    fn test_math_helpers_fail_on_invalid_input() {
+        let handlebars = handlebars();
+        let data = json!({"cfg": {"name": "web"}});
+        assert!(handlebars.template_render("{{add cfg.name 1}}", &data)
+                          .is_err());
+        assert!(handlebars.template_render("{{add 1}}", &data).is_err());
+        assert!(handlebars.template_render("{{divide 1 0}}", &data).is_err());
+        assert!(handlebars.template_render("{{modulo 1.5 0}}", &data)
+                          .is_err());
+        assert!(handlebars.template_render("{{multiply 9223372036854775807 2}}", &data)
+                          .is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use sodiumoxide::crypto::hash::sha256;
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct Sha256Helper;
+
+impl HelperDef for Sha256Helper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"sha256\"")
+                                                        })?;
+        let digest = sha256::hash(param.as_bytes());
+        rc.writer
+          .write_all(hex::encode(digest.as_ref()).into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static SHA256: Sha256Helper = Sha256Helper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sha256_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("sha256", Box::new(SHA256));
+        assert_eq!("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+                   handlebars.template_render("{{sha256 greeting}}",
+                                              &json!({"greeting": "hello"}))
+                             .unwrap());
+        assert_eq!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                   handlebars.template_render("{{sha256 \"\"}}", &json!({}))
+                             .unwrap());
+    }
+}
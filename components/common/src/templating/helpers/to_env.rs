@@ -0,0 +1,97 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::{super::RenderResult,
+            leaves};
+
+/// Serializes an object to shell environment variable assignments. The name of a variable is the
+/// path of its value, upper-cased and joined by underscores, after an optional prefix given as a
+/// second parameter. Values are single-quoted so the output can be sourced by a shell.
+#[derive(Clone, Copy)]
+pub struct ToEnvHelper;
+
+impl HelperDef for ToEnvHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0)
+             .map(|v| v.value())
+             .filter(|v| v.is_object())
+             .ok_or_else(|| RenderError::new("Expected an object parameter for \"toEnv\""))?;
+        let prefix = match h.param(1) {
+            Some(prefix) => {
+                Some(prefix.value()
+                           .as_str()
+                           .ok_or_else(|| {
+                               RenderError::new("Expected a string prefix for \"toEnv\"")
+                           })?)
+            }
+            None => None,
+        };
+        let mut env = String::new();
+        for (path, value) in leaves(param) {
+            let name = prefix.into_iter()
+                             .chain(path.iter().map(String::as_str))
+                             .collect::<Vec<_>>()
+                             .join("_");
+            env.push_str(&variable_name(&name));
+            env.push_str("='");
+            env.push_str(&value.replace('\'', "'\\''"));
+            env.push_str("'\n");
+        }
+        rc.writer.write_all(env.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+fn variable_name(name: &str) -> String {
+    let name = name.chars()
+                   .map(|c| {
+                       if c.is_ascii_alphanumeric() {
+                           c.to_ascii_uppercase()
+                       } else {
+                           '_'
+                       }
+                   })
+                   .collect::<String>();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+pub static TO_ENV: ToEnvHelper = ToEnvHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_env_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("toEnv", Box::new(TO_ENV));
+        let data = json!({"cfg": {"port": 8080,
+                                  "log-level": "info",
+                                  "motd": "it's up",
+                                  "db": {"hosts": ["a", "b"], "tls": true}}});
+        assert_eq!(r"PORT='8080'
+LOG_LEVEL='info'
+MOTD='it'\''s up'
+DB_HOSTS='a,b'
+DB_TLS='true'
+",
+                   handlebars.template_render("{{toEnv cfg}}", &data).unwrap());
+        assert_eq!("APP_HOSTS='a,b'\nAPP_TLS='true'\n",
+                   handlebars.template_render("{{toEnv cfg.db \"app\"}}", &data)
+                             .unwrap());
+        assert_eq!("_1='x'\n",
+                   handlebars.template_render("{{toEnv data}}", &json!({"data": {"1": "x"}}))
+                             .unwrap());
+        assert!(handlebars.template_render("{{toEnv cfg.port}}", &data)
+                          .is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::{Map,
+                 Value as Json};
+
+use super::{super::RenderResult,
+            to_text};
+
+/// Serializes an object to INI. Values of the object come first, followed by a section for each
+/// nested object, named after its path joined by dots.
+#[derive(Clone, Copy)]
+pub struct ToIniHelper;
+
+impl HelperDef for ToIniHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0)
+             .and_then(|v| v.value().as_object())
+             .ok_or_else(|| RenderError::new("Expected an object parameter for \"toIni\""))?;
+        let mut ini = String::new();
+        write_section(&mut ini, None, param)?;
+        rc.writer.write_all(ini.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+fn write_section(ini: &mut String,
+                 name: Option<&str>,
+                 section: &Map<String, Json>)
+                 -> RenderResult<()> {
+    let mut values = section.iter()
+                            .filter(|(_, value)| !value.is_object() && !value.is_null())
+                            .peekable();
+    if values.peek().is_some() {
+        if let Some(name) = name {
+            if !ini.is_empty() {
+                ini.push('\n');
+            }
+            ini.push_str(&format!("[{}]\n", name));
+        }
+    }
+    for (key, value) in values {
+        if value.as_array().map_or(false, |list| {
+                               list.iter().any(|v| v.is_object() || v.is_array())
+                           })
+        {
+            return Err(RenderError::new(format!("Can't serialize \"{}\" to INI, \
+                                                 arrays can only hold values",
+                                                key)));
+        }
+        ini.push_str(&format!("{} = {}\n", key, to_text(value)));
+    }
+    for (key, value) in section {
+        if let Json::Object(subsection) = value {
+            let name = match name {
+                Some(name) => format!("{}.{}", name, key),
+                None => key.clone(),
+            };
+            write_section(ini, Some(&name), subsection)?;
+        }
+    }
+    Ok(())
+}
+
+pub static TO_INI: ToIniHelper = ToIniHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_ini_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("toIni", Box::new(TO_INI));
+        let data = json!({"cfg": {"debug": false,
+                                  "server": {"port": 8080,
+                                             "hosts": ["a", "b"],
+                                             "tls": {"cert": "/etc/cert.pem"}},
+                                  "plugins": {"auth": {"enabled": true}},
+                                  "name": "web"}});
+        assert_eq!(r"debug = false
+name = web
+
+[server]
+port = 8080
+hosts = a,b
+
+[server.tls]
+cert = /etc/cert.pem
+
+[plugins.auth]
+enabled = true
+",
+                   handlebars.template_render("{{toIni cfg}}", &data).unwrap());
+        assert_eq!("cert = /etc/cert.pem\n",
+                   handlebars.template_render("{{toIni cfg.server.tls}}", &data)
+                             .unwrap());
+        assert!(handlebars.template_render("{{toIni cfg}}",
+                                           &json!({"cfg": {"servers": [{"host": "a"}]}}))
+                          .is_err());
+        assert!(handlebars.template_render("{{toIni cfg.name}}", &data)
+                          .is_err());
+    }
+}
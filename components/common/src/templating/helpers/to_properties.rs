@@ -0,0 +1,87 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::{super::RenderResult,
+            leaves};
+
+/// Serializes an object to Java properties, with the keys of nested objects joined by dots.
+#[derive(Clone, Copy)]
+pub struct ToPropertiesHelper;
+
+impl HelperDef for ToPropertiesHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0)
+             .map(|v| v.value())
+             .filter(|v| v.is_object())
+             .ok_or_else(|| {
+                 RenderError::new("Expected an object parameter for \"toProperties\"")
+             })?;
+        let mut properties = String::new();
+        for (path, value) in leaves(param) {
+            properties.push_str(&escape(&path.join("."), true));
+            properties.push('=');
+            properties.push_str(&escape(&value, false));
+            properties.push('\n');
+        }
+        rc.writer.write_all(properties.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+fn escape(text: &str, key: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (idx, c) in text.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '=' | ':' | '#' | '!' | ' ' if key => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            // Leading whitespace of a value would otherwise be dropped
+            ' ' if idx == 0 => escaped.push_str("\\ "),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub static TO_PROPERTIES: ToPropertiesHelper = ToPropertiesHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_properties_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("toProperties", Box::new(TO_PROPERTIES));
+        let data = json!({"cfg": {"port": 8080,
+                                  "motd": " Hello\nWorld",
+                                  "path": "C:\\data",
+                                  "hosts": ["a", "b"],
+                                  "jdbc": {"url": "jdbc:postgresql://db/app", "pool size": 5},
+                                  "servers": [{"host": "a"}, {"host": "b"}],
+                                  "unset": null}});
+        assert_eq!(r"port=8080
+motd=\ Hello\nWorld
+path=C:\\data
+hosts=a,b
+jdbc.url=jdbc:postgresql://db/app
+jdbc.pool\ size=5
+servers.0.host=a
+servers.1.host=b
+",
+                   handlebars.template_render("{{toProperties cfg}}", &data)
+                             .unwrap());
+        assert!(handlebars.template_render("{{toProperties cfg.port}}", &data)
+                          .is_err());
+    }
+}
@@ -77,6 +77,24 @@ pub trait Hook: fmt::Debug + Sized + Send {
                   -> Option<Self>
         where C: AsRef<Path>,
               T: AsRef<Path>
+    {
+        Self::load_with_strictness(package_name,
+                                   concrete_path,
+                                   template_path,
+                                   feature_flags,
+                                   false)
+    }
+
+    /// Like `load`, but the hook is rendered by a strict renderer when `strict` is set so
+    /// references to undefined variables fail its rendering.
+    fn load_with_strictness<C, T>(package_name: &str,
+                                  concrete_path: C,
+                                  template_path: T,
+                                  feature_flags: FeatureFlag,
+                                  strict: bool)
+                                  -> Option<Self>
+        where C: AsRef<Path>,
+              T: AsRef<Path>
     {
         let file_name = Self::FILE_NAME;
         let deprecated_file_name = if Self::FILE_NAME.contains('-') {
@@ -120,7 +138,12 @@ pub trait Hook: fmt::Debug + Sized + Send {
                    template.display());
             return None;
         };
-        match RenderPair::new(concrete, template_to_use, Self::FILE_NAME) {
+        let renderer = if strict {
+            TemplateRenderer::strict()
+        } else {
+            TemplateRenderer::new()
+        };
+        match RenderPair::with_renderer(concrete, template_to_use, Self::FILE_NAME, renderer) {
             Ok(pair) => Some(Self::new(package_name, pair, feature_flags)),
             Err(err) => {
                 outputln!(preamble package_name, "Failed to load hook: {}", err);
@@ -131,16 +154,32 @@ pub trait Hook: fmt::Debug + Sized + Send {
 
     fn new(package_name: &str, render_pair: RenderPair, feature_flags: FeatureFlag) -> Self;
 
+    /// Render the content of a hook without writing it.
+    fn render<T>(&self, ctx: &T) -> Result<String>
+        where T: Serialize
+    {
+        self.renderer().render(Self::FILE_NAME, ctx)
+    }
+
+    /// The path a hook is compiled to, which never uses a deprecated file name.
+    fn compiled_path(&self) -> PathBuf { self.path().with_file_name(Self::FILE_NAME) }
+
     /// Compile a hook into its destination service directory.
     ///
     /// Returns `true` if the hook has changed.
     fn compile<T>(&self, service_group: &str, ctx: &T) -> Result<bool>
         where T: Serialize
     {
-        let content = self.renderer().render(Self::FILE_NAME, ctx)?;
-        // We make sure we don't use a deprecated file name
-        let path = self.path().with_file_name(Self::FILE_NAME);
-        if write_hook(&content, &path)? {
+        let content = self.render(ctx)?;
+        self.write_compiled(service_group, &content)
+    }
+
+    /// Write the already rendered `content` of a hook into its destination service directory.
+    ///
+    /// Returns `true` if the hook has changed.
+    fn write_compiled(&self, service_group: &str, content: &str) -> Result<bool> {
+        let path = self.compiled_path();
+        if write_hook(content, &path)? {
             outputln!(preamble service_group,
                       "Modified hook content in {}",
                       &path.display());
@@ -513,7 +552,18 @@ impl RenderPair {
         where C: Into<PathBuf>,
               T: AsRef<Path>
     {
-        let mut renderer = TemplateRenderer::new();
+        Self::with_renderer(concrete_path, template_path, name, TemplateRenderer::new())
+    }
+
+    /// Register the template with the given renderer, such as a strict one.
+    pub fn with_renderer<C, T>(concrete_path: C,
+                               template_path: T,
+                               name: &'static str,
+                               mut renderer: TemplateRenderer)
+                               -> Result<Self>
+        where C: Into<PathBuf>,
+              T: AsRef<Path>
+    {
         renderer.register_template_file(name, template_path.as_ref())?;
         Ok(RenderPair { path: concrete_path.into(),
                         renderer })
@@ -717,6 +767,31 @@ echo "The message is Hello World"
         assert_eq!(hash_content(hook.path()).unwrap(), None);
     }
 
+    #[test]
+    fn strict_hooks_fail_to_render_undefined_variables() {
+        let service_group = service_group();
+        let concrete_path = rendered_hooks_path();
+        let template_path = hook_templates_path();
+        let ctx = serde_json::json!({ "cfg": {} });
+
+        let hook = InstallHook::load(&service_group,
+                                     &concrete_path,
+                                     &template_path,
+                                     FeatureFlag::empty()).expect("Could not create testing \
+                                                                   install hook");
+        assert!(hook.render(&ctx).unwrap().contains(r#"The message is ""#));
+
+        let hook = InstallHook::load_with_strictness(&service_group,
+                                                     &concrete_path,
+                                                     &template_path,
+                                                     FeatureFlag::empty(),
+                                                     true).expect("Could not create testing \
+                                                                   install hook");
+        let err = hook.render(&ctx).unwrap_err().to_string();
+        assert!(err.contains("cfg.message"), "{}", err);
+        assert!(err.contains("line 3"), "{}", err);
+    }
+
     #[test]
     fn updating_a_hook_with_the_same_content_is_a_noop() {
         let service_group = service_group();
@@ -124,6 +124,36 @@ impl Pkg {
                         ident };
         Ok(pkg)
     }
+
+    /// The `Pkg` of a package built from a plan that hasn't been built or installed yet, for
+    /// rendering its templates offline. Only its identifier and paths are known, so it has no
+    /// dependencies, environment, exposed ports or exports.
+    pub fn from_plan(ident: FullyQualifiedPackageIdent) -> Self {
+        let name = ident.as_ref().name.clone();
+        Pkg { svc_path: fs::svc_path(&name),
+              svc_config_path: fs::svc_config_path(&name),
+              svc_config_install_path: fs::svc_config_install_path(&name),
+              svc_data_path: fs::svc_data_path(&name),
+              svc_files_path: fs::svc_files_path(&name),
+              svc_run: fs::svc_path(&name).join("run"),
+              svc_static_path: fs::svc_static_path(&name),
+              svc_var_path: fs::svc_var_path(&name),
+              svc_pid_file: fs::svc_pid_file(&name),
+              svc_user: DEFAULT_USER.to_string(),
+              svc_group: DEFAULT_GROUP.to_string(),
+              env: Env(BTreeMap::new()),
+              deps: Vec::new(),
+              exposes: Vec::new(),
+              exports: BTreeMap::new(),
+              path: fs::pkg_install_path(ident.as_ref(), None::<&PathBuf>),
+              origin: ident.as_ref().origin.clone(),
+              name,
+              version: String::from(ident.version()),
+              release: String::from(ident.release()),
+              shutdown_signal: ShutdownSignal::default(),
+              shutdown_timeout: ShutdownTimeout::default(),
+              ident }
+    }
 }
 
 /// Queryable representation of service package
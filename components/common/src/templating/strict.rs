@@ -0,0 +1,230 @@
+//! Strict rendering of templates.
+//!
+//! Handlebars renders a reference to an undefined variable as an empty string, which silently
+//! produces broken files. In strict mode the source of every template is rewritten before it is
+//! registered so that each expression is preceded by a check of the variables it references. The
+//! check renders nothing, but fails the render with the line, column and path of the first
+//! undefined variable.
+//!
+//! The condition of an `if` or `unless` block and the parameters of `default` and `coalesce` are
+//! not checked, since handling undefined variables is their purpose. Literals and subexpressions
+//! are never checked.
+
+use super::RenderResult;
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+/// The name of the helper checking that the variables of an expression are defined.
+pub const STRICT_CHECK: &str = "_strictCheck";
+
+/// Helpers whose parameters may reference undefined variables, as conditions or fallbacks.
+const UNCHECKED_HELPERS: &[&str] = &["if", "unless", "default", "coalesce"];
+
+#[derive(Clone, Copy)]
+pub struct StrictCheckHelper;
+
+impl HelperDef for StrictCheckHelper {
+    /// Expects the line and column of the checked expression followed by the variables it
+    /// references.
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, _: &mut RenderContext<'_>) -> RenderResult<()> {
+        let position = |idx| {
+            h.param(idx)
+             .and_then(|p| p.value().as_u64())
+             .map(|n| n as usize)
+        };
+        for param in h.params().iter().skip(2) {
+            if param.value().is_null() {
+                let mut err = RenderError::new(format!("Undefined variable \"{}\"",
+                                                       param.path().map_or("", String::as_str)));
+                err.line_no = position(0);
+                err.column_no = position(1);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+pub static STRICT_CHECK_HELPER: StrictCheckHelper = StrictCheckHelper;
+
+/// Rewrite the source of a template so that rendering it fails on undefined variables.
+pub fn strict_source(source: &str) -> String {
+    let mut strict = String::with_capacity(source.len());
+    let mut rest = source;
+    let (mut line, mut col) = (1, 1);
+    while let Some(start) = rest.find("{{") {
+        let (text, tag) = rest.split_at(start);
+        strict.push_str(text);
+        advance(&mut line, &mut col, text);
+        let len = if text.ends_with('\\') {
+            // An escaped expression is rendered as text
+            2
+        } else {
+            let len = tag_len(tag);
+            if let Some(check) = check(&tag[..len], line, col) {
+                strict.push_str(&check);
+            }
+            len
+        };
+        strict.push_str(&tag[..len]);
+        advance(&mut line, &mut col, &tag[..len]);
+        rest = &tag[len..];
+    }
+    strict.push_str(rest);
+    strict
+}
+
+/// The length of the tag at the start of `tag`, including the content of raw blocks.
+fn tag_len(tag: &str) -> usize {
+    let end = if tag.starts_with("{{{{") {
+        tag.find("{{{{/")
+           .and_then(|close| tag[close..].find("}}}}").map(|end| close + end + 4))
+    } else if tag.starts_with("{{{") {
+        tag.find("}}}").map(|end| end + 3)
+    } else {
+        tag.find("}}").map(|end| end + 2)
+    };
+    // An unterminated tag is left for Handlebars to report
+    end.unwrap_or(tag.len())
+}
+
+/// The check to insert before a tag, if it references any variable.
+fn check(tag: &str, line: usize, col: usize) -> Option<String> {
+    let (open, close) = if tag.starts_with("{{{") && !tag.starts_with("{{{{") {
+        (3, 3)
+    } else {
+        (2, 2)
+    };
+    if tag.len() < open + close {
+        return None;
+    }
+    let body = &tag[open..tag.len() - close];
+    let omit_pre_ws = body.starts_with('~');
+    let body = body.trim_start_matches('~').trim_end_matches('~').trim();
+    let (block, body) = match body.strip_prefix('#') {
+        Some(body) => (true, body),
+        None => (false, body),
+    };
+    if body.is_empty()
+       || body.starts_with(&['{', '!', '/', '>', '^', '*', '('][..])
+       || body == "else"
+    {
+        return None;
+    }
+    let tokens = tokens(body);
+    let references = if tokens.len() == 1 && !block {
+        // A variable, unless it is a literal
+        tokens
+    } else if UNCHECKED_HELPERS.contains(&tokens[0]) {
+        return None;
+    } else {
+        tokens[1..].iter()
+                   .take_while(|token| **token != "as")
+                   .map(|token| token.split_once('=').map_or(*token, |(_, value)| value))
+                   .collect()
+    };
+    let references = references.into_iter()
+                               .filter(|token| is_reference(token))
+                               .collect::<Vec<_>>();
+    if references.is_empty() {
+        None
+    } else {
+        let pre_ws = if omit_pre_ws { "~" } else { "" };
+        Some(format!("{{{{{}{} {} {} {}}}}}",
+                     pre_ws,
+                     STRICT_CHECK,
+                     line,
+                     col,
+                     references.join(" ")))
+    }
+}
+
+/// Split the body of an expression into its name and parameters, keeping string literals,
+/// subexpressions and bracketed path segments whole.
+fn tokens(body: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = None;
+    for (i, c) in body.char_indices() {
+        match c {
+            '"' if !body[..i].ends_with('\\') => quoted = !quoted,
+            '(' | '[' if !quoted => depth += 1,
+            ')' | ']' if !quoted => depth -= 1,
+            c if c.is_whitespace() && !quoted && depth == 0 => {
+                if let Some(s) = start.take() {
+                    tokens.push(&body[s..i]);
+                }
+                continue;
+            }
+            _ => (),
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        tokens.push(&body[s..]);
+    }
+    tokens
+}
+
+fn is_reference(token: &str) -> bool {
+    !(token.is_empty()
+      || token.starts_with(|c: char| {
+                  c.is_ascii_digit() || matches!(c, '"' | '(' | '[' | '{' | '-')
+              })
+      || matches!(token, "true" | "false" | "null"))
+}
+
+fn advance(line: &mut usize, col: &mut usize, text: &str) {
+    for c in text.chars() {
+        if c == '\n' {
+            *line += 1;
+            *col = 1;
+        } else {
+            *col += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::templating::helpers;
+    use serde_json::json;
+
+    fn render(template: &str, ctx: &serde_json::Value) -> Result<String, String> {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper(STRICT_CHECK, Box::new(STRICT_CHECK_HELPER));
+        handlebars.register_helper("default", Box::new(helpers::DEFAULT));
+        handlebars.register_template_string("t", strict_source(template))
+                  .expect("valid template");
+        handlebars.render("t", ctx).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn defined_variables_render_as_usual() {
+        let ctx = json!({ "cfg": { "port": 80, "hosts": ["a", "b"], "on": false } });
+        assert_eq!(render("port {{cfg.port}}\n{{#each cfg.hosts}}{{@index}}={{this}} \
+                           {{/each}}{{#if cfg.missing}}{{cfg.missing}}{{else}}none{{/if}}",
+                          &ctx).unwrap(),
+                   "port 80\n0=a 1=b none");
+        assert_eq!(render("{{{cfg.on}}} \\{{cfg.missing}} {{! cfg.missing }}", &ctx).unwrap(),
+                   "false \\{{cfg.missing}} ");
+        assert_eq!(render("{{default cfg.missing cfg.port}}", &ctx).unwrap(), "80");
+    }
+
+    #[test]
+    fn undefined_variables_are_errors_with_their_position() {
+        let ctx = json!({ "cfg": { "port": 80, "hosts": [{ "name": "a" }] } });
+        assert_eq!(render("port {{cfg.port}}\n  host {{cfg.host}}", &ctx).unwrap_err(),
+                   "Error rendering \"t\" line 2, col 8: Undefined variable \"cfg.host\"");
+        assert_eq!(render("{{cfg.port}} {{#each cfg.hosts}}\n{{this.ip}}{{/each}}",
+                          &ctx).unwrap_err(),
+                   "Error rendering \"t\" line 2, col 1: Undefined variable \"this.ip\"");
+        assert_eq!(render("{{#each cfg.nodes}}{{/each}}", &ctx).unwrap_err(),
+                   "Error rendering \"t\" line 1, col 1: Undefined variable \"cfg.nodes\"");
+    }
+}
@@ -1,3 +1,4 @@
+pub mod flatten;
 pub mod path;
 
 use crate::error::Error;
@@ -0,0 +1,122 @@
+//! Flattening of nested configuration values into the paths of their leaves.
+
+use std::collections::BTreeMap;
+
+/// A value which may nest other values under keys.
+pub trait Nested {
+    /// The values nested in this one along with their keys, or `None` if this value is a leaf.
+    fn children(&self) -> Option<Vec<(String, &Self)>>;
+}
+
+/// Tables nest their values by key. Empty tables are leaves, so they are kept as values.
+impl Nested for toml::Value {
+    fn children(&self) -> Option<Vec<(String, &Self)>> {
+        match self {
+            toml::Value::Table(table) if !table.is_empty() => {
+                Some(table.iter()
+                          .map(|(key, value)| (key.clone(), value))
+                          .collect())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Objects nest their values by key, and arrays that contain objects or arrays nest their
+/// elements by index. Arrays of scalars are leaves.
+impl Nested for serde_json::Value {
+    fn children(&self) -> Option<Vec<(String, &Self)>> {
+        match self {
+            serde_json::Value::Object(map) => {
+                Some(map.iter()
+                        .map(|(key, value)| (key.clone(), value))
+                        .collect())
+            }
+            serde_json::Value::Array(list)
+                if list.iter().any(|v| v.is_object() || v.is_array()) =>
+            {
+                Some(list.iter()
+                         .enumerate()
+                         .map(|(idx, value)| (idx.to_string(), value))
+                         .collect())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns every leaf of `value` along with the path of keys leading to it, depth first. A
+/// `value` which is a leaf itself has an empty path.
+pub fn flatten<V: Nested>(value: &V) -> Vec<(Vec<String>, &V)> {
+    fn walk<'a, V: Nested>(value: &'a V,
+                           path: &mut Vec<String>,
+                           leaves: &mut Vec<(Vec<String>, &'a V)>) {
+        match value.children() {
+            Some(children) => {
+                for (key, child) in children {
+                    path.push(key);
+                    walk(child, path, leaves);
+                    path.pop();
+                }
+            }
+            None => leaves.push((path.clone(), value)),
+        }
+    }
+    let mut leaves = Vec::new();
+    walk(value, &mut Vec::new(), &mut leaves);
+    leaves
+}
+
+/// Flattens the nested tables of a TOML table into dotted keys, e.g. `tls.cert`.
+pub fn dotted_keys(table: &toml::value::Table) -> BTreeMap<String, &toml::Value> {
+    let mut keys = BTreeMap::new();
+    for (key, value) in table {
+        for (path, leaf) in flatten(value) {
+            let dotted = Some(key).into_iter()
+                                  .chain(&path)
+                                  .map(String::as_str)
+                                  .collect::<Vec<_>>()
+                                  .join(".");
+            keys.insert(dotted, leaf);
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn toml_tables_flatten_into_dotted_keys() {
+        let toml = r#"
+            port = 80
+            empty = {}
+            list = [1, 2]
+            [tls]
+            cert = "a"
+            [tls.ca]
+            path = "b"
+            "#;
+        let table = toml::from_str::<toml::value::Table>(toml).unwrap();
+        let keys = dotted_keys(&table);
+        assert_eq!(keys.keys().map(String::as_str).collect::<Vec<_>>(),
+                   vec!["empty", "list", "port", "tls.ca.path", "tls.cert"]);
+        assert_eq!(keys["tls.ca.path"], &toml::Value::String("b".to_string()));
+        assert_eq!(keys["empty"],
+                   &toml::Value::Table(toml::value::Table::new()));
+    }
+
+    #[test]
+    fn json_arrays_are_only_nested_when_they_hold_structures() {
+        let value = json!({ "ports": [80, 443], "servers": [{ "host": "a" }], "none": null });
+        let leaves = flatten(&value).into_iter()
+                                    .map(|(path, leaf)| (path.join("."), leaf.clone()))
+                                    .collect::<Vec<_>>();
+        assert_eq!(leaves,
+                   vec![("ports".to_string(), json!([80, 443])),
+                        ("servers.0.host".to_string(), json!("a")),
+                        ("none".to_string(), json!(null))]);
+    }
+}
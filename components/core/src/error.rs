@@ -82,6 +82,10 @@ pub enum Error {
     FullyQualifiedPackageIdentRequired(String),
     /// Occurs when a service binding cannot be successfully parsed.
     InvalidBinding(String),
+    /// Occurs when a job concurrency policy is not recognized.
+    InvalidJobConcurrency(String),
+    /// Occurs when a job schedule is neither `once` nor a cron-like schedule.
+    InvalidJobSchedule(String),
     /// Occurs when an origin is in an invalid format
     InvalidOrigin(String),
     /// Occurs when a package identifier string cannot be successfully parsed.
@@ -94,10 +98,16 @@ pub enum Error {
     InvalidPort(ParseIntError),
     /// Occurs when an OsString path cannot be converted to a String
     InvalidPathString(ffi::OsString),
+    /// Occurs when a cron-like schedule string cannot be successfully parsed.
+    InvalidSchedule(String),
     /// Occurs when a service group string cannot be successfully parsed.
     InvalidServiceGroup(String),
+    /// Occurs when an update batch size is neither a positive count nor a percentage.
+    InvalidUpdateBatchSize(String),
     /// Occurs when a Url is in an invalid format.
     InvalidUrl(String),
+    /// Occurs when a version constraint can't be parsed.
+    InvalidVersionConstraint(String),
     /// Occurs when making lower level IO calls.
     IO(io::Error),
     /// Errors when joining paths :)
@@ -122,6 +132,8 @@ pub enum Error {
     NoOutboundIpAddr(io::Error),
     /// Occurs when a call to OpenDesktopW fails
     OpenDesktopFailed(String),
+    /// Occurs when a package bundle is malformed or fails verification
+    PackageBundleInvalid(String),
     /// Occurs when a suitable installed package cannot be found.
     PackageNotFound(Box<package::PackageIdent>),
     /// Occurs where trying to unpack a package
@@ -275,6 +287,16 @@ impl fmt::Display for Error {
                          <NAME> is a service name, and <SERVICE_GROUP> is a valid service group",
                         binding)
             }
+            Error::InvalidJobConcurrency(ref e) => {
+                format!("Invalid job concurrency policy: {}. A valid policy is forbid or replace",
+                        e)
+            }
+            Error::InvalidJobSchedule(ref e) => {
+                format!("Invalid job schedule: {}. A valid job schedule is once or the five \
+                         fields of a crontab entry optionally followed by a time zone (example: \
+                         \"0 3 * * * local\")",
+                        e)
+            }
             Error::InvalidOrigin(ref origin) => {
                 format!("Invalid origin: {}. Origins must begin with a lowercase letter or \
                          number. Allowed characters include lowercase letters, numbers, -, and _. \
@@ -296,12 +318,29 @@ impl fmt::Display for Error {
                 format!("Could not generate String from path: {:?}", s)
             }
             Error::InvalidPort(ref e) => format!("Invalid port: {}.", e),
+            Error::InvalidSchedule(ref e) => {
+                format!("Invalid schedule: {}. A valid schedule is the five fields of a crontab \
+                         entry optionally followed by a time zone of UTC, local, an offset from \
+                         UTC or a tz database name (example: \"* 2-4 * * SAT,SUN Europe/Paris\")",
+                        e)
+            }
             Error::InvalidServiceGroup(ref e) => {
                 format!("Invalid service group: {}. A valid service group string is in the form \
                          service.group (example: redis.production)",
                         e)
             }
+            Error::InvalidUpdateBatchSize(ref e) => {
+                format!("Invalid update batch size: {}. A valid batch size is a number of members \
+                         or a percentage of the service group (example: 3 or 25%)",
+                        e)
+            }
             Error::InvalidUrl(ref url) => format!("Invalid url: {}", url),
+            Error::InvalidVersionConstraint(ref e) => {
+                format!("Invalid version constraint: {}. A valid version constraint is a comma \
+                         separated list of versions, each optionally prefixed with one of =, >, \
+                         >=, < or <= (example: >=11.0,<14)",
+                        e)
+            }
             Error::IO(ref err) => format!("{}", err),
             Error::JoinPathsError(ref err) => format!("{}", err),
             Error::LogonTypeNotGranted => {
@@ -325,6 +364,7 @@ impl fmt::Display for Error {
                 format!("Failed to discover this host's outbound IP address: {}", e)
             }
             Error::OpenDesktopFailed(ref e) => e.to_string(),
+            Error::PackageBundleInvalid(ref e) => format!("Package bundle is invalid: {}", e),
             Error::PackageNotFound(ref pkg) => {
                 if pkg.fully_qualified() {
                     format!("Cannot find package: {}", pkg)
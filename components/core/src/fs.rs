@@ -802,6 +802,18 @@ impl AtomicWriter {
         Ok(r)
     }
 
+    /// Writes to the temporary file without moving it into place, so that several files can be
+    /// written before any of them is completed. The file is discarded if the writer is dropped
+    /// without calling `complete`.
+    pub fn stage<F, T, E>(&mut self, op: F) -> std::result::Result<T, E>
+        where F: FnOnce(&mut std::fs::File) -> std::result::Result<T, E>
+    {
+        op(self.tempfile.as_file_mut())
+    }
+
+    /// Moves a file written with `stage` into place.
+    pub fn complete(self) -> io::Result<()> { self.finish() }
+
     /// Completes the atomic write by calling sync on the temporary
     /// file to ensure all data is flushed to disk and then renaming
     /// the file into place.
@@ -1197,7 +1209,8 @@ mod test_find_command {
 mod test_atomic_writer {
     use super::{atomic_write,
                 AtomicWriter};
-    use std::{fs::File,
+    use std::{fs::{self,
+                   File},
               io::{Read,
                    Seek,
                    Write}};
@@ -1243,4 +1256,28 @@ mod test_atomic_writer {
          .expect("failed to read file");
         assert_eq!(EXPECTED_CONTENT, actual_content);
     }
+
+    #[test]
+    fn staged_atomic_writes_are_only_moved_into_place_when_completed() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        let completed = dir.path().join("completed");
+        let discarded = dir.path().join("discarded");
+
+        let mut w = AtomicWriter::new(&completed).expect("could not create AtomicWriter");
+        w.stage(|writer| writer.write_all(EXPECTED_CONTENT.as_bytes()))
+         .expect("failed to stage file");
+        assert!(!completed.exists());
+        w.complete().expect("failed to complete file");
+        assert_eq!(fs::read_to_string(&completed).expect("file not found"),
+                   EXPECTED_CONTENT);
+
+        let mut w = AtomicWriter::new(&discarded).expect("could not create AtomicWriter");
+        w.stage(|writer| writer.write_all(EXPECTED_CONTENT.as_bytes()))
+         .expect("failed to stage file");
+        drop(w);
+        assert!(!discarded.exists());
+        assert_eq!(fs::read_dir(dir.path()).expect("could not read dir")
+                                           .count(),
+                   1);
+    }
 }
@@ -13,6 +13,7 @@ pub mod locked_env_var;
 pub mod origin;
 pub mod os;
 pub mod package;
+pub mod schedule;
 pub mod service;
 pub mod tls;
 pub mod url;
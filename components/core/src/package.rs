@@ -1,4 +1,6 @@
 pub mod archive;
+pub mod audit;
+pub mod bundle;
 pub mod ident;
 pub mod install;
 pub mod list;
@@ -8,7 +10,11 @@ pub mod target;
 
 pub use self::{archive::{FromArchive,
                          PackageArchive,
+                         PackageArchiveFile,
                          PackageArchiveInfo},
+               audit::PackageAudit,
+               bundle::{BundleManifest,
+                        PackageBundle},
                ident::{FullyQualifiedPackageIdent,
                        Identifiable,
                        PackageIdent},
@@ -157,10 +157,23 @@ lazy_static::lazy_static! {
         );
         map
     };
+    static ref PAYLOAD_PATH_REGX: Regex =
+        Regex::new(r"^/?hab/pkgs/[^/]+/[^/]+/[^/]+/[^/]+/(.+)$").unwrap();
 }
 
 type Metadata = HashMap<MetaFile, String>;
 
+/// A regular file contained in the payload of a package archive.
+#[derive(Clone, Debug)]
+pub struct PackageArchiveFile {
+    /// The path of the file, relative to the package's installed path.
+    pub path:     PathBuf,
+    pub size:     u64,
+    pub checksum: Blake2bHash,
+    /// The raw contents of the file, if they were requested.
+    pub contents: Option<Vec<u8>>,
+}
+
 #[derive(Debug)]
 pub struct PackageArchive {
     pub path: PathBuf,
@@ -314,6 +327,50 @@ impl PackageArchive {
         Ok(())
     }
 
+    /// Returns the size and Blake2b checksum of every regular file in the archive's payload. The
+    /// contents of the files for which `keep_contents` returns `true` are returned as well, which
+    /// avoids decompressing the archive a second time to read them.
+    ///
+    /// # Failures
+    ///
+    /// * If the archive cannot be read
+    pub fn files<F>(&self, keep_contents: F) -> Result<Vec<PackageArchiveFile>>
+        where F: Fn(&Path) -> bool
+    {
+        let hart_payload_tar_xz = artifact::get_archive_reader(&self.path)?;
+        let decoder = XzDecoder::new(hart_payload_tar_xz);
+        let mut tar = Archive::new(decoder);
+
+        let mut files = Vec::new();
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = {
+                let path_bytes = entry.path_bytes();
+                let path_str = String::from_utf8_lossy(&path_bytes);
+                match PAYLOAD_PATH_REGX.captures(&path_str) {
+                    Some(captures) => PathBuf::from(&captures[1]),
+                    None => continue,
+                }
+            };
+            let size = entry.header().size()?;
+            let (checksum, contents) = if keep_contents(&path) {
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents)?;
+                (Blake2bHash::from_bytes(&contents), Some(contents))
+            } else {
+                (Blake2bHash::from_reader(&mut entry)?, None)
+            };
+            files.push(PackageArchiveFile { path,
+                                            size,
+                                            checksum,
+                                            contents });
+        }
+        Ok(files)
+    }
+
     fn read_deps(&mut self, file: MetaFile) -> Result<Vec<PackageIdent>> {
         let mut deps = vec![];
 
@@ -549,6 +606,27 @@ mod test {
         let _ = hart.tdeps().unwrap();
     }
 
+    #[test]
+    fn reading_artifact_files() {
+        let hart =
+            PackageArchive::new(fixtures().join("happyhumans-possums-8.1.\
+                                                 4-20160427165340-x86_64-linux.hart")).unwrap();
+        let files = hart.files(|path| path == Path::new("IDENT")).unwrap();
+        let paths = files.iter()
+                         .map(|f| f.path.to_string_lossy().into_owned())
+                         .collect::<Vec<_>>();
+        assert!(paths.contains(&"IDENT".to_string()));
+        assert!(paths.contains(&"share/signme.dat".to_string()));
+
+        let ident = files.iter().find(|f| f.path == Path::new("IDENT")).unwrap();
+        let contents = ident.contents.as_ref().expect("IDENT contents to be kept");
+        assert_eq!(ident.size, contents.len() as u64);
+        assert_eq!(ident.checksum, Blake2bHash::from_bytes(contents));
+        assert!(files.iter()
+                     .filter(|f| f.path != Path::new("IDENT"))
+                     .all(|f| f.contents.is_none()));
+    }
+
     #[test]
     fn reading_artifact_large_tdeps() {
         let mut hart =
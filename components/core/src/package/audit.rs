@@ -0,0 +1,247 @@
+//! Verify the files of an installed package against the checksums recorded when it was built.
+//!
+//! Every package ships a signed `FILES` metadata file listing the BLAKE2b hash and build path of
+//! each file in the package. An audit checks the signature of `FILES` against the origin's public
+//! key and then compares every file below the package's installed path with its recorded hash.
+
+use super::{metadata::MetaFile,
+            PackageIdent,
+            PackageInstall};
+use crate::{crypto::{artifact,
+                     keys::KeyCache,
+                     Blake2bHash},
+            error::{Error,
+                    Result}};
+use serde::{Deserialize,
+            Serialize};
+use std::{collections::BTreeMap,
+          ffi::OsStr,
+          fs,
+          io::BufRead,
+          path::{Component,
+                 Path,
+                 PathBuf}};
+
+/// Files that are written to a package directory after `FILES` was generated, either at build or
+/// at install time, and which are therefore never listed in it.
+const UNLISTED_FILES: &[&str] = &["FILES",
+                                  "MANIFEST",
+                                  "INSTALL_HOOK_STATUS",
+                                  "UNINSTALL_HOOK_STATUS"];
+
+/// The result of auditing an installed package. Paths are relative to the package's installed
+/// path.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PackageAudit {
+    pub ident:    PackageIdent,
+    /// The key `FILES` was signed with
+    pub signer:   String,
+    /// Files whose content no longer matches the recorded checksum
+    pub modified: Vec<PathBuf>,
+    /// Files that are recorded but no longer exist
+    pub missing:  Vec<PathBuf>,
+    /// Files that exist but were never recorded
+    pub extra:    Vec<PathBuf>,
+}
+
+impl PackageAudit {
+    /// Audit `install`, verifying its `FILES` metadata file with a key from `key_cache`.
+    pub fn run(install: &PackageInstall, key_cache: &KeyCache) -> Result<Self> {
+        let installed_path = install.installed_path();
+        let files_path = installed_path.join(MetaFile::Files.to_string());
+        if !files_path.is_file() {
+            return Err(Error::MetaFileNotFound(MetaFile::Files));
+        }
+        let (signer, _) = artifact::verify(&files_path, key_cache)?;
+        let mut recorded = recorded_checksums(&files_path, &install.ident)?;
+
+        let mut modified = Vec::new();
+        let mut extra = Vec::new();
+        for path in installed_files(installed_path)? {
+            let relative = path.strip_prefix(installed_path)
+                               .expect("installed files are below the installed path")
+                               .to_path_buf();
+            match recorded.remove(&relative) {
+                Some(checksum) => {
+                    if Blake2bHash::from_file(&path)? != checksum {
+                        modified.push(relative);
+                    }
+                }
+                None => {
+                    if !is_unlisted(&relative) {
+                        extra.push(relative);
+                    }
+                }
+            }
+        }
+
+        Ok(PackageAudit { ident: install.ident.clone(),
+                          signer: signer.to_string(),
+                          modified,
+                          missing: recorded.into_keys().collect(),
+                          extra })
+    }
+
+    /// Returns `true` if the installed files match the recorded checksums exactly.
+    pub fn is_clean(&self) -> bool {
+        self.modified.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// Parse the body of a `FILES` metadata file into checksums keyed by paths relative to the
+/// package's installed path.
+///
+/// Each line is `<checksum>  <path>`, where `path` is the absolute path of the file at build
+/// time. The recorded paths are made relative by dropping everything up to the package's
+/// `origin/name/version/release` components so that packages installed under a different
+/// filesystem root can be audited too.
+fn recorded_checksums(files_path: &Path,
+                      ident: &PackageIdent)
+                      -> Result<BTreeMap<PathBuf, Blake2bHash>> {
+    let ident_parts = [Some(ident.origin.as_str()),
+                       Some(ident.name.as_str()),
+                       ident.version.as_deref(),
+                       ident.release.as_deref()];
+    let mut recorded = BTreeMap::new();
+    for line in artifact::get_archive_reader(files_path)?.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (checksum, path) = line.split_once(char::is_whitespace)
+                                   .ok_or(Error::MetaFileMalformed(MetaFile::Files))?;
+        let checksum = checksum.parse()?;
+        let components = Path::new(path.trim_start()).components()
+                                                     .filter_map(|c| {
+                                                         match c {
+                                                             Component::Normal(c) => Some(c),
+                                                             _ => None,
+                                                         }
+                                                     })
+                                                     .collect::<Vec<_>>();
+        let start = components.windows(ident_parts.len())
+                              .position(|window| {
+                                  window.iter()
+                                        .zip(ident_parts.iter())
+                                        .all(|(c, p)| p.map(OsStr::new) == Some(*c))
+                              })
+                              .ok_or(Error::MetaFileMalformed(MetaFile::Files))?;
+        let relative = components[start + ident_parts.len()..].iter()
+                                                              .collect::<PathBuf>();
+        recorded.insert(relative, checksum);
+    }
+    Ok(recorded)
+}
+
+/// Returns all regular files below `dir`. Symbolic links are not followed, matching how the
+/// checksums were recorded at build time.
+fn installed_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                dirs.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn is_unlisted(relative: &Path) -> bool {
+    let mut components = relative.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None) => UNLISTED_FILES.iter().any(|f| name == *f),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{crypto::{keys::{PublicOriginSigningKey,
+                                SecretOriginSigningKey},
+                         test_support::*},
+                fs as hfs,
+                package::test_support::testing_package_install};
+    use std::fmt::Write;
+    use tempfile::TempDir;
+
+    /// Install a package with a couple of files and a signed `FILES` listing them, as
+    /// `bio pkg build` would.
+    fn audited_package_install(fs_root: &Path) -> PackageInstall {
+        let install = testing_package_install("happyhumans/possums", fs_root);
+        let installed_path = install.installed_path();
+        fs::create_dir_all(installed_path.join("bin")).unwrap();
+        fs::write(installed_path.join("bin/possum"),
+                  "#!/bin/sh\necho possum\n").unwrap();
+        fs::write(installed_path.join("README"), "Possums are great\n").unwrap();
+
+        // Checksums are recorded with the package's build path, not the installed path
+        let build_path = hfs::pkg_install_path(&install.ident, None::<&Path>);
+        let mut sums = String::new();
+        for path in installed_files(installed_path).unwrap() {
+            let relative = path.strip_prefix(installed_path).unwrap();
+            writeln!(sums,
+                     "{}  {}",
+                     Blake2bHash::from_file(&path).unwrap(),
+                     build_path.join(relative).display()).unwrap();
+        }
+        let sums_path = fs_root.join("possums_blake2bsums");
+        fs::write(&sums_path, sums).unwrap();
+        let key: SecretOriginSigningKey = fixture_key("happyhumans-20160424223347.sig.key");
+        artifact::sign(&sums_path, &installed_path.join("FILES"), &key).unwrap();
+        install
+    }
+
+    fn cache_with_public_key() -> (KeyCache, TempDir) {
+        let (cache, dir) = new_cache();
+        let key: PublicOriginSigningKey = fixture_key("happyhumans-20160424223347.pub");
+        cache.write_key(&key).unwrap();
+        (cache, dir)
+    }
+
+    #[test]
+    fn audit_of_untouched_package_is_clean() {
+        let fs_root = TempDir::new().unwrap();
+        let install = audited_package_install(fs_root.path());
+        let (cache, _dir) = cache_with_public_key();
+
+        let audit = PackageAudit::run(&install, &cache).unwrap();
+        assert!(audit.is_clean(), "{:?}", audit);
+        assert_eq!(audit.signer, "happyhumans-20160424223347");
+    }
+
+    #[test]
+    fn audit_reports_modified_missing_and_extra_files() {
+        let fs_root = TempDir::new().unwrap();
+        let install = audited_package_install(fs_root.path());
+        let installed_path = install.installed_path();
+        fs::write(installed_path.join("bin/possum"),
+                  "#!/bin/sh\necho opossum\n").unwrap();
+        fs::remove_file(installed_path.join("README")).unwrap();
+        fs::write(installed_path.join("bin/intruder"), "").unwrap();
+        fs::write(installed_path.join("INSTALL_HOOK_STATUS"), "0").unwrap();
+        let (cache, _dir) = cache_with_public_key();
+
+        let audit = PackageAudit::run(&install, &cache).unwrap();
+        assert!(!audit.is_clean());
+        assert_eq!(audit.modified, vec![PathBuf::from("bin/possum")]);
+        assert_eq!(audit.missing, vec![PathBuf::from("README")]);
+        assert_eq!(audit.extra, vec![PathBuf::from("bin/intruder")]);
+    }
+
+    #[test]
+    fn audit_requires_signer_key() {
+        let fs_root = TempDir::new().unwrap();
+        let install = audited_package_install(fs_root.path());
+        let (cache, _dir) = new_cache();
+
+        assert!(PackageAudit::run(&install, &cache).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+//! Signed bundles of Biome Artifacts for installing packages without access to Builder.
+//!
+//! A bundle uses the same signed container format as a `.hart` file. Its payload is an
+//! xz-compressed tarball holding a `MANIFEST.toml`, the bundled artifacts under `artifacts/` and
+//! the public origin keys needed to verify them under `keys/`. The manifest records the Blake2b
+//! checksum of every other file in the bundle, so a bundle is only unpacked when its signature
+//! and every checksum check out.
+
+use super::{Identifiable,
+            PackageArchive,
+            PackageIdent,
+            PackageTarget};
+use crate::{crypto::{artifact,
+                     keys::{KeyCache,
+                            NamedRevision,
+                            PublicOriginSigningKey,
+                            SecretOriginSigningKey},
+                     Blake2bHash},
+            error::{Error,
+                    Result},
+            fs::{AtomicWriter,
+                 DEFAULT_CACHED_ARTIFACT_PERMISSIONS}};
+use serde::{Deserialize,
+            Serialize};
+use std::{collections::BTreeMap,
+          fs,
+          io::{self,
+               BufReader,
+               Read,
+               Seek,
+               SeekFrom,
+               Write},
+          path::{Path,
+                 PathBuf}};
+use tar::{Archive,
+          Builder,
+          Header};
+use xz2::{read::XzDecoder,
+          write::XzEncoder};
+
+/// The version of the bundle format written by this crate.
+pub const BUNDLE_FORMAT_VERSION: u8 = 1;
+
+const MANIFEST_FILE: &str = "MANIFEST.toml";
+const ARTIFACTS_DIR: &str = "artifacts";
+const KEYS_DIR: &str = "keys";
+
+/// Describes the contents of a bundle.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BundleManifest {
+    pub format_version: u8,
+    /// The packages the bundle was created for. The transitive dependencies of each of these
+    /// are also part of the bundle.
+    pub seeds:          Vec<String>,
+    pub packages:       Vec<BundlePackage>,
+    pub keys:           Vec<BundleFile>,
+}
+
+/// An artifact contained in a bundle.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BundlePackage {
+    pub ident:    String,
+    pub target:   PackageTarget,
+    pub file:     String,
+    pub checksum: String,
+}
+
+/// A public origin key contained in a bundle.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BundleFile {
+    pub file:     String,
+    pub checksum: String,
+}
+
+impl BundleManifest {
+    /// Returns the identifiers of the packages the bundle was created for.
+    pub fn seeds(&self) -> Result<Vec<PackageIdent>> {
+        self.seeds.iter().map(|s| s.parse()).collect()
+    }
+
+    /// Returns the newest package in the bundle for `target` that satisfies `ident`.
+    pub fn latest_satisfying(&self,
+                             ident: &PackageIdent,
+                             target: PackageTarget)
+                             -> Result<Option<PackageIdent>> {
+        let mut latest: Option<PackageIdent> = None;
+        for package in self.packages.iter().filter(|p| p.target == target) {
+            let candidate = package.ident.parse::<PackageIdent>()?;
+            if candidate.satisfies(ident) && latest.as_ref().map_or(true, |l| candidate > *l) {
+                latest = Some(candidate);
+            }
+        }
+        Ok(latest)
+    }
+
+    /// The checksums of every file in the bundle other than the manifest, keyed by their path
+    /// within the bundle.
+    fn checksums(&self) -> BTreeMap<String, &str> {
+        self.packages
+            .iter()
+            .map(|p| (format!("{}/{}", ARTIFACTS_DIR, p.file), p.checksum.as_str()))
+            .chain(self.keys
+                       .iter()
+                       .map(|k| (format!("{}/{}", KEYS_DIR, k.file), k.checksum.as_str())))
+            .collect()
+    }
+}
+
+/// A signed bundle of Biome Artifacts on disk.
+pub struct PackageBundle {
+    path:     PathBuf,
+    signer:   NamedRevision,
+    manifest: BundleManifest,
+}
+
+impl PackageBundle {
+    /// Write a new bundle to `dst` containing `artifacts` and the public `keys` needed to verify
+    /// them, signed with `signing_key`.
+    pub fn create(dst: &Path,
+                  seeds: &[PackageIdent],
+                  artifacts: &[PathBuf],
+                  keys: &[PathBuf],
+                  signing_key: &SecretOriginSigningKey)
+                  -> Result<BundleManifest> {
+        let mut packages = Vec::with_capacity(artifacts.len());
+        let mut files = Vec::with_capacity(artifacts.len() + keys.len());
+        for path in artifacts {
+            let mut archive = PackageArchive::new(path)?;
+            let file = file_name(path)?;
+            packages.push(BundlePackage { ident:    archive.ident()?.to_string(),
+                                          target:   archive.target()?,
+                                          file:     file.clone(),
+                                          checksum: Blake2bHash::from_file(path)?.to_string(), });
+            files.push((format!("{}/{}", ARTIFACTS_DIR, file), path));
+        }
+        let mut bundle_keys = Vec::with_capacity(keys.len());
+        for path in keys {
+            let file = file_name(path)?;
+            bundle_keys.push(BundleFile { file:     file.clone(),
+                                          checksum: Blake2bHash::from_file(path)?.to_string(), });
+            files.push((format!("{}/{}", KEYS_DIR, file), path));
+        }
+        let manifest = BundleManifest { format_version: BUNDLE_FORMAT_VERSION,
+                                        seeds: seeds.iter().map(ToString::to_string).collect(),
+                                        packages,
+                                        keys: bundle_keys };
+        let manifest_toml =
+            toml::to_string(&manifest).map_err(|e| Error::PackageBundleInvalid(e.to_string()))?;
+
+        // The payload is staged next to the destination and signed into its final location.
+        let payload = tempfile::Builder::new().prefix(".bundle-payload")
+                                              .tempfile_in(dst.parent()
+                                                              .filter(|p| !p.as_os_str().is_empty())
+                                                              .unwrap_or_else(|| Path::new(".")))?;
+        {
+            let mut builder = Builder::new(XzEncoder::new(payload.as_file(), 6));
+            let mut header = Header::new_gnu();
+            header.set_size(manifest_toml.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, MANIFEST_FILE, manifest_toml.as_bytes())?;
+            for (name, path) in files {
+                builder.append_path_with_name(path, name)?;
+            }
+            builder.into_inner()?.finish()?.flush()?;
+        }
+        artifact::sign(payload.path(), dst, signing_key)?;
+        Ok(manifest)
+    }
+
+    /// Open the bundle at `path`, verifying its signature against the public keys in
+    /// `key_cache`.
+    pub fn open<P>(path: P, key_cache: &KeyCache) -> Result<Self>
+        where P: AsRef<Path>
+    {
+        let path = path.as_ref();
+        let (signer, _) = artifact::verify(path, key_cache)?;
+        let mut archive = payload(path)?;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if entry.path()? == Path::new(MANIFEST_FILE) {
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                let manifest: BundleManifest = toml::from_str(&content).map_err(|e| {
+                                                   Error::PackageBundleInvalid(format!("Malformed \
+                                                                                     {}: {}",
+                                                                                    MANIFEST_FILE,
+                                                                                    e))
+                                               })?;
+                if manifest.format_version != BUNDLE_FORMAT_VERSION {
+                    return Err(Error::PackageBundleInvalid(format!("Unsupported format \
+                                                                    version: {}",
+                                                                   manifest.format_version)));
+                }
+                return Ok(PackageBundle { path: path.to_path_buf(),
+                                          signer,
+                                          manifest });
+            }
+        }
+        Err(Error::PackageBundleInvalid(format!("{} not found", MANIFEST_FILE)))
+    }
+
+    pub fn path(&self) -> &Path { &self.path }
+
+    /// The key that the bundle was signed with.
+    pub fn signer(&self) -> &NamedRevision { &self.signer }
+
+    pub fn manifest(&self) -> &BundleManifest { &self.manifest }
+
+    /// Copy the bundled artifacts into `artifact_cache_path` and the bundled public keys into
+    /// `key_cache`. Every file is checked against the manifest's checksum before anything is
+    /// moved into place, and unpacking fails if a file is missing, unexpected or altered.
+    /// Artifacts are streamed to temporary files next to their destination while they are
+    /// checked, so a bundle is never held in memory.
+    pub fn unpack(&self, artifact_cache_path: &Path, key_cache: &KeyCache) -> Result<()> {
+        fs::create_dir_all(artifact_cache_path)?;
+        let mut checksums = self.manifest.checksums();
+        let mut artifacts = Vec::new();
+        let mut keys = Vec::new();
+        let mut archive = payload(&self.path)?;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().into_owned();
+            if name == MANIFEST_FILE {
+                continue;
+            }
+            let expected = checksums.remove(name.as_str()).ok_or_else(|| {
+                               Error::PackageBundleInvalid(format!("Unexpected file {}", name))
+                           })?;
+            let checksum_mismatch =
+                || Error::PackageBundleInvalid(format!("Checksum mismatch for {}", name));
+            if let Some(file) = name.strip_prefix(&format!("{}/", ARTIFACTS_DIR)) {
+                let dst = artifact_cache_path.join(file);
+                let mut writer =
+                    AtomicWriter::new_with_permissions(&dst, DEFAULT_CACHED_ARTIFACT_PERMISSIONS)?;
+                let checksum = writer.stage(|f| -> Result<Blake2bHash> {
+                                         io::copy(&mut entry, f)?;
+                                         f.seek(SeekFrom::Start(0))?;
+                                         Blake2bHash::from_reader(&mut BufReader::new(f))
+                                     })?;
+                if checksum.to_string() != expected {
+                    return Err(checksum_mismatch());
+                }
+                artifacts.push((dst, checksum, writer));
+            } else {
+                // Public keys are small enough to be checked in memory.
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes)?;
+                if Blake2bHash::from_bytes(&bytes).to_string() != expected {
+                    return Err(checksum_mismatch());
+                }
+                keys.push(bytes);
+            }
+        }
+        if let Some(name) = checksums.keys().next() {
+            return Err(Error::PackageBundleInvalid(format!("Missing file {}", name)));
+        }
+
+        for (dst, checksum, writer) in artifacts {
+            // An identical artifact is left in place and the staged copy is discarded.
+            if dst.is_file() && Blake2bHash::from_file(&dst)? == checksum {
+                continue;
+            }
+            writer.complete()?;
+        }
+        for bytes in keys {
+            let key = String::from_utf8_lossy(&bytes).parse::<PublicOriginSigningKey>()?;
+            key_cache.write_key(&key)?;
+        }
+        Ok(())
+    }
+}
+
+fn payload(path: &Path) -> Result<Archive<XzDecoder<impl Read + '_>>> {
+    Ok(Archive::new(XzDecoder::new(artifact::get_archive_reader(path)?)))
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .ok_or_else(|| Error::FileNotFound(path.display().to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::test_support::*;
+    use tempfile::TempDir;
+
+    const HART: &str = "happyhumans-possums-8.1.4-20160427165340-x86_64-linux.hart";
+
+    fn create_bundle(dir: &Path) -> PathBuf {
+        let signing_key: SecretOriginSigningKey = fixture_key("happyhumans-20160424223347.sig.key");
+        let dst = dir.join("possums.bundle");
+        PackageBundle::create(&dst,
+                              &["happyhumans/possums".parse().unwrap()],
+                              &[fixture(HART)],
+                              &[fixture("happyhumans-20160424223347.pub")],
+                              &signing_key).unwrap();
+        dst
+    }
+
+    #[test]
+    fn bundle_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = create_bundle(dir.path());
+
+        let (cache, _cache_dir) = new_cache();
+        let key: PublicOriginSigningKey = fixture_key("happyhumans-20160424223347.pub");
+        cache.write_key(&key).unwrap();
+        let bundle = PackageBundle::open(path, &cache).unwrap();
+        assert_eq!(bundle.signer().to_string(), "happyhumans-20160424223347");
+        assert_eq!(bundle.manifest().seeds().unwrap(),
+                   vec!["happyhumans/possums".parse::<PackageIdent>().unwrap()]);
+        let target = "x86_64-linux".parse().unwrap();
+        assert_eq!(bundle.manifest()
+                         .latest_satisfying(&"happyhumans/possums".parse().unwrap(), target)
+                         .unwrap(),
+                   Some("happyhumans/possums/8.1.4/20160427165340".parse().unwrap()));
+
+        let artifact_cache = dir.path().join("artifacts");
+        let (target_cache, _target_cache_dir) = new_cache();
+        bundle.unpack(&artifact_cache, &target_cache).unwrap();
+        assert_eq!(Blake2bHash::from_file(artifact_cache.join(HART)).unwrap(),
+                   Blake2bHash::from_file(fixture(HART)).unwrap());
+        assert!(target_cache.public_signing_key(bundle.signer()).is_ok());
+    }
+
+    #[test]
+    fn bundle_requires_signer_key() {
+        let dir = TempDir::new().unwrap();
+        let path = create_bundle(dir.path());
+
+        let (cache, _cache_dir) = new_cache();
+        assert!(PackageBundle::open(path, &cache).is_err());
+    }
+}
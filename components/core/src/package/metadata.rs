@@ -1,8 +1,12 @@
-use crate::error::{Error,
-                   Result};
+use crate::{error::{Error,
+                    Result},
+            package::{ident::version_sort,
+                      PackageIdent}};
 use serde::{Deserialize,
-            Serialize};
+            Serialize,
+            Serializer};
 use std::{self,
+          cmp::Ordering,
           collections::BTreeMap,
           env,
           fmt,
@@ -11,6 +15,7 @@ use std::{self,
           iter::IntoIterator,
           path::{Path,
                  PathBuf},
+          result,
           str::FromStr,
           string::ToString,
           vec::IntoIter};
@@ -28,33 +33,228 @@ pub fn parse_key_value(s: &str) -> Result<BTreeMap<String, String>> {
         .collect())
 }
 
+/// A bind contract, as declared by a package's `pkg_binds` or `pkg_binds_optional`.
+///
+/// Each contract is a line of the form `service=TOKEN...`, where every whitespace separated token
+/// is one of:
+///
+/// * `key`: an export the producer must provide
+/// * `key?` or `key?=default`: an export the consumer can do without, rendered as `default` (if
+///   given) when the producer doesn't provide it
+/// * `pkg:origin/name`: the package the producer must be running
+/// * `version:CONSTRAINT`: the versions of the producer's package that are accepted (see
+///   `VersionConstraint`)
 #[derive(Clone, Debug, Serialize)]
 pub struct Bind {
-    pub service: String,
-    pub exports: Vec<String>,
+    pub service:          String,
+    pub exports:          Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub optional_exports: Vec<OptionalExport>,
+    #[serde(serialize_with = "serialize_producer",
+            skip_serializing_if = "Option::is_none")]
+    pub producer:         Option<PackageIdent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version:          Option<VersionConstraint>,
+}
+
+impl Bind {
+    /// Is a producer running the given package acceptable to this bind?
+    pub fn accepts(&self, ident: &PackageIdent) -> bool {
+        let producer_matches = self.producer.as_ref().map_or(true, |producer| {
+                                                         producer.origin == ident.origin
+                                                         && producer.name == ident.name
+                                                     });
+        let version_matches = match (&self.version, &ident.version) {
+            (None, _) => true,
+            (Some(constraint), Some(version)) => constraint.matches(version),
+            (Some(_), None) => false,
+        };
+        producer_matches && version_matches
+    }
+
+    /// The optional exports that have a default value
+    pub fn export_defaults(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.optional_exports
+            .iter()
+            .filter_map(|e| e.default.as_ref().map(|d| (e.name.as_str(), d.as_str())))
+    }
 }
 
 impl FromStr for Bind {
     type Err = Error;
 
     fn from_str(line: &str) -> Result<Self> {
-        let mut parts = line.split('=');
+        let mut parts = line.splitn(2, '=');
         let service = match parts.next() {
             None => return Err(Error::MetaFileBadBind),
             Some(service) => service.to_string(),
         };
-        let exports = match parts.next() {
+        let tokens = match parts.next() {
             None => return Err(Error::MetaFileBadBind),
-            Some(exports) => exports.split_whitespace().map(str::to_string).collect(),
+            Some(tokens) => tokens,
         };
-        Ok(Bind { service, exports })
+        let mut bind = Bind { service,
+                              exports: Vec::new(),
+                              optional_exports: Vec::new(),
+                              producer: None,
+                              version: None };
+        for token in tokens.split_whitespace() {
+            if let Some(ident) = token.strip_prefix("pkg:") {
+                let ident = PackageIdent::from_str(ident).map_err(|_| Error::MetaFileBadBind)?;
+                if ident.version.is_some() {
+                    return Err(Error::MetaFileBadBind);
+                }
+                bind.producer = Some(ident);
+            } else if let Some(constraint) = token.strip_prefix("version:") {
+                bind.version = Some(constraint.parse().map_err(|_| Error::MetaFileBadBind)?);
+            } else if let Some(idx) = token.find('?') {
+                let default = match &token[idx + 1..] {
+                    "" => None,
+                    rest => Some(rest.strip_prefix('=').ok_or(Error::MetaFileBadBind)?),
+                };
+                bind.optional_exports
+                    .push(OptionalExport { name:    token[..idx].to_string(),
+                                           default: default.map(str::to_string), });
+            } else {
+                bind.exports.push(token.to_string());
+            }
+        }
+        Ok(bind)
     }
 }
 
 impl fmt::Display for Bind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let formatted_exports = self.exports.join(" ");
-        write!(f, "[{}]={}", self.service, formatted_exports)
+        let mut tokens = self.exports.clone();
+        tokens.extend(self.optional_exports.iter().map(ToString::to_string));
+        if let Some(ref producer) = self.producer {
+            tokens.push(format!("pkg:{}", producer));
+        }
+        if let Some(ref version) = self.version {
+            tokens.push(format!("version:{}", version));
+        }
+        write!(f, "[{}]={}", self.service, tokens.join(" "))
+    }
+}
+
+fn serialize_producer<S>(producer: &Option<PackageIdent>,
+                         serializer: S)
+                         -> result::Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    match producer {
+        Some(ident) => serializer.collect_str(ident),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// An export a bind can do without.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OptionalExport {
+    pub name:    String,
+    /// The value to use when the producer doesn't provide the export
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+impl fmt::Display for OptionalExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.default {
+            Some(ref default) => write!(f, "{}?={}", self.name, default),
+            None => write!(f, "{}?", self.name),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VersionOp {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl VersionOp {
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            VersionOp::Eq => ordering == Ordering::Equal,
+            VersionOp::Gt => ordering == Ordering::Greater,
+            VersionOp::Ge => ordering != Ordering::Less,
+            VersionOp::Lt => ordering == Ordering::Less,
+            VersionOp::Le => ordering != Ordering::Greater,
+        }
+    }
+}
+
+impl fmt::Display for VersionOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match *self {
+            VersionOp::Eq => "=",
+            VersionOp::Gt => ">",
+            VersionOp::Ge => ">=",
+            VersionOp::Lt => "<",
+            VersionOp::Le => "<=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// A comma separated list of version comparisons, all of which a version must meet, for example
+/// `>=11.0,<14`. A version without an operator must match exactly. Versions are compared the way
+/// `version_sort` orders them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionConstraint(Vec<(VersionOp, String)>);
+
+impl VersionConstraint {
+    /// Does the version meet every comparison? Versions that can't be compared meet none.
+    pub fn matches(&self, version: &str) -> bool {
+        self.0.iter().all(|(op, bound)| {
+                         version_sort(version, bound).map_or(false, |ordering| op.accepts(ordering))
+                     })
+    }
+}
+
+impl FromStr for VersionConstraint {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let mut comparisons = Vec::new();
+        for comparison in value.split(',').map(str::trim) {
+            let (op, bound) = [(">=", VersionOp::Ge),
+                               ("<=", VersionOp::Le),
+                               (">", VersionOp::Gt),
+                               ("<", VersionOp::Lt),
+                               ("=", VersionOp::Eq)].iter()
+                                                    .find_map(|(prefix, op)| {
+                                                        comparison.strip_prefix(*prefix)
+                                                                  .map(|bound| (*op, bound.trim()))
+                                                    })
+                                                    .unwrap_or((VersionOp::Eq, comparison));
+            if bound.is_empty() || version_sort(bound, bound).is_err() {
+                return Err(Error::InvalidVersionConstraint(value.to_string()));
+            }
+            comparisons.push((op, bound.to_string()));
+        }
+        Ok(VersionConstraint(comparisons))
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comparisons = self.0
+                              .iter()
+                              .map(|(op, bound)| format!("{}{}", op, bound))
+                              .collect::<Vec<_>>();
+        write!(f, "{}", comparisons.join(","))
+    }
+}
+
+impl Serialize for VersionConstraint {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.collect_str(self)
     }
 }
 
@@ -118,6 +318,7 @@ pub enum MetaFile {
     EnvironmentSep,
     Exports,
     Exposes,
+    Files,
     Ident,
     LdFlags,
     LdRunPath,
@@ -149,6 +350,7 @@ impl fmt::Display for MetaFile {
             MetaFile::EnvironmentSep => "ENVIRONMENT_SEP",
             MetaFile::Exports => "EXPORTS",
             MetaFile::Exposes => "EXPOSES",
+            MetaFile::Files => "FILES",
             MetaFile::Ident => "IDENT",
             MetaFile::LdFlags => "LDFLAGS",
             MetaFile::LdRunPath => "LD_RUN_PATH",
@@ -334,6 +536,80 @@ port=front-end.port
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn can_parse_plain_bind() {
+        let bind = Bind::from_str("database=port host").unwrap();
+
+        assert_eq!("database", bind.service);
+        assert_eq!(vec!["port".to_string(), "host".to_string()], bind.exports);
+        assert!(bind.optional_exports.is_empty());
+        assert!(bind.producer.is_none());
+        assert!(bind.version.is_none());
+        assert_eq!("[database]=port host", bind.to_string());
+    }
+
+    #[test]
+    fn can_parse_bind_contract() {
+        let bind = Bind::from_str("database=port user?=admin sslmode? pkg:core/postgresql \
+                                   version:>=11.0,<14").unwrap();
+
+        assert_eq!(vec!["port".to_string()], bind.exports);
+        assert_eq!(vec![OptionalExport { name:    "user".to_string(),
+                                         default: Some("admin".to_string()), },
+                        OptionalExport { name:    "sslmode".to_string(),
+                                         default: None, },],
+                   bind.optional_exports);
+        assert_eq!(vec![("user", "admin")],
+                   bind.export_defaults().collect::<Vec<_>>());
+        assert_eq!(Some(PackageIdent::from_str("core/postgresql").unwrap()),
+                   bind.producer);
+        assert_eq!("[database]=port user?=admin sslmode? pkg:core/postgresql version:>=11.0,<14",
+                   bind.to_string());
+    }
+
+    #[test]
+    fn bad_bind_contracts_are_errors() {
+        assert!(Bind::from_str("database").is_err());
+        assert!(Bind::from_str("database=port user?admin").is_err());
+        assert!(Bind::from_str("database=port pkg:core/postgresql/11.2").is_err());
+        assert!(Bind::from_str("database=port pkg:postgresql").is_err());
+        assert!(Bind::from_str("database=port version:>=eleven").is_err());
+        assert!(Bind::from_str("database=port version:>=11,").is_err());
+    }
+
+    #[test]
+    fn bind_accepts_matching_producers() {
+        let bind = Bind::from_str("database=port pkg:core/postgresql version:>=11.0,<14").unwrap();
+        let accepts = |ident| bind.accepts(&PackageIdent::from_str(ident).unwrap());
+
+        assert!(accepts("core/postgresql/11.2/20190101000000"));
+        assert!(accepts("core/postgresql/13.9.1/20230101000000"));
+        assert!(!accepts("core/postgresql/14.0/20230101000000"));
+        assert!(!accepts("core/postgresql/10.4/20180101000000"));
+        assert!(!accepts("acme/postgresql/12.0/20200101000000"));
+        assert!(!accepts("core/mysql/12.0/20200101000000"));
+        assert!(!accepts("core/postgresql"));
+
+        let bind = Bind::from_str("database=port").unwrap();
+        assert!(bind.accepts(&PackageIdent::from_str("core/mysql").unwrap()));
+    }
+
+    #[test]
+    fn version_constraint_matches() {
+        let exact = VersionConstraint::from_str("1.2.3").unwrap();
+        assert!(exact.matches("1.2.3"));
+        assert!(!exact.matches("1.2.4"));
+        assert_eq!("=1.2.3", exact.to_string());
+
+        let range = VersionConstraint::from_str("> 1.0, <= 2.0").unwrap();
+        assert!(range.matches("2.0"));
+        assert!(range.matches("1.0.1"));
+        assert!(!range.matches("1.0"));
+        assert!(!range.matches("2.0.1"));
+        assert!(!range.matches("not-a-version"));
+        assert_eq!(">1.0,<=2.0", range.to_string());
+    }
+
     #[test]
     fn can_read_metafile() {
         let pkg_root = Builder::new().prefix("pkg-root").tempdir().unwrap();
@@ -0,0 +1,348 @@
+//! Cron-like schedules.
+//!
+//! A schedule is written as the five fields of a crontab entry, `minute hour day-of-month month
+//! day-of-week`, optionally followed by the time zone the fields are evaluated in. The time zone is
+//! either `UTC` (the default), `local`, a fixed offset from UTC such as `+02:00`, or the name of a
+//! time zone of the tz database such as `Europe/Paris`, which follows its daylight saving time.
+//!
+//! Each field is a comma separated list of values, `a-b` ranges or `*`, each optionally followed
+//! by a `/step`. Months and days of the week may also be given by their three letter English names
+//! (ex: `JAN`, `SAT`), and Sunday is either `0` or `7`. As in cron, when both the day of the month
+//! and the day of the week are restricted, a day matches if either of them does.
+//!
+//! Examples:
+//!
+//! * `0 3 * * *`: at 03:00 UTC every day
+//! * `* 2-4 * * SAT,SUN local`: every minute from 02:00 to 04:59 local time on weekends
+//! * `*/15 * * * MON-FRI -05:00`: every fifteen minutes on weekdays at UTC-5
+//! * `0 3 * * * America/New_York`: at 03:00 every day in New York, whether on EST or EDT
+
+use crate::error::Error;
+use chrono::{DateTime,
+             Datelike,
+             FixedOffset,
+             Local,
+             NaiveDateTime,
+             Timelike,
+             Utc};
+use chrono_tz::Tz;
+use std::{fmt,
+          result,
+          str::FromStr};
+
+const MONTHS: &[&str] =
+    &["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+const DAYS_OF_WEEK: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// The time zone the fields of a `Schedule` are evaluated in.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Timezone {
+    #[default]
+    Utc,
+    Local,
+    /// A fixed offset from UTC in seconds
+    Offset(i32),
+    /// A time zone of the tz database, whose offset changes with daylight saving time
+    Named(Tz),
+}
+
+impl Timezone {
+    fn localize(self, time: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Timezone::Utc => time.naive_utc(),
+            Timezone::Local => time.with_timezone(&Local).naive_local(),
+            Timezone::Offset(seconds) => {
+                // Offsets are validated to be less than a day when parsed
+                let offset = FixedOffset::east_opt(seconds).expect("valid UTC offset");
+                time.with_timezone(&offset).naive_local()
+            }
+            Timezone::Named(tz) => time.with_timezone(&tz).naive_local(),
+        }
+    }
+}
+
+impl fmt::Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Timezone::Utc => write!(f, "UTC"),
+            Timezone::Local => write!(f, "local"),
+            Timezone::Offset(seconds) => {
+                let sign = if seconds < 0 { '-' } else { '+' };
+                let minutes = seconds.abs() / 60;
+                write!(f, "{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+            }
+            Timezone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let err = || Error::InvalidSchedule(value.to_string());
+        if value.eq_ignore_ascii_case("utc") || value.eq_ignore_ascii_case("z") {
+            return Ok(Timezone::Utc);
+        }
+        if value.eq_ignore_ascii_case("local") {
+            return Ok(Timezone::Local);
+        }
+        let sign = match value.chars().next() {
+            Some('+') => 1,
+            Some('-') => -1,
+            _ => return value.parse().map(Timezone::Named).map_err(|_| err()),
+        };
+        let offset = value[1..].replace(':', "");
+        if offset.len() != 4 || !offset.chars().all(|c| c.is_ascii_digit()) {
+            return Err(err());
+        }
+        let hours = offset[..2].parse::<i32>().map_err(|_| err())?;
+        let minutes = offset[2..].parse::<i32>().map_err(|_| err())?;
+        if hours > 23 || minutes > 59 {
+            return Err(err());
+        }
+        Ok(Timezone::Offset(sign * (hours * 3600 + minutes * 60)))
+    }
+}
+
+/// The set of values one field of a `Schedule` matches.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Field {
+    values:     u64,
+    /// Whether the field was anything other than `*`
+    restricted: bool,
+}
+
+impl Field {
+    fn parse(value: &str, min: u32, max: u32, names: &[&str], name_offset: u32) -> Option<Self> {
+        let parse_value = |v: &str| -> Option<u32> {
+            match names.iter().position(|n| n.eq_ignore_ascii_case(v)) {
+                Some(i) => Some(i as u32 + name_offset),
+                None => v.parse().ok().filter(|v| *v >= min && *v <= max),
+            }
+        };
+        let mut values = 0u64;
+        for item in value.split(',') {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, Some(step.parse::<u32>().ok().filter(|s| *s > 0)?)),
+                None => (item, None),
+            };
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((start, end)) = range.split_once('-') {
+                (parse_value(start)?, parse_value(end)?)
+            } else {
+                let start = parse_value(range)?;
+                (start, if step.is_some() { max } else { start })
+            };
+            if start > end {
+                return None;
+            }
+            for v in (start..=end).step_by(step.unwrap_or(1) as usize) {
+                values |= 1 << v;
+            }
+        }
+        Some(Field { values,
+                     restricted: !value.starts_with('*') })
+    }
+
+    fn matches(self, value: u32) -> bool { self.values & (1 << value) != 0 }
+}
+
+/// A cron-like schedule evaluated in a time zone. See the module documentation for the format.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Schedule {
+    minutes:       Field,
+    hours:         Field,
+    days_of_month: Field,
+    months:        Field,
+    days_of_week:  Field,
+    timezone:      Timezone,
+    /// The fields as they were written, to display the schedule
+    fields:        String,
+}
+
+impl Schedule {
+    pub fn timezone(&self) -> Timezone { self.timezone }
+
+    /// Whether the minute that `time` falls in matches the schedule.
+    pub fn matches(&self, time: DateTime<Utc>) -> bool {
+        let time = self.timezone.localize(time);
+        let day_of_month = self.days_of_month.matches(time.day());
+        let day_of_week = self.days_of_week
+                              .matches(time.weekday().num_days_from_sunday());
+        let day = if self.days_of_month.restricted && self.days_of_week.restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        };
+        day
+        && self.minutes.matches(time.minute())
+        && self.hours.matches(time.hour())
+        && self.months.matches(time.month())
+    }
+
+    /// Whether the current minute matches the schedule.
+    pub fn matches_now(&self) -> bool { self.matches(Utc::now()) }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.fields, self.timezone)
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let err = || Error::InvalidSchedule(value.to_string());
+        let parts = value.split_whitespace().collect::<Vec<_>>();
+        let (fields, timezone) = match parts.len() {
+            5 => (&parts[..], Timezone::default()),
+            6 => (&parts[..5], parts[5].parse().map_err(|_| err())?),
+            _ => return Err(err()),
+        };
+        let mut days_of_week = Field::parse(fields[4], 0, 7, DAYS_OF_WEEK, 0).ok_or_else(err)?;
+        // Sunday is both 0 and 7
+        if days_of_week.matches(7) {
+            days_of_week.values = (days_of_week.values | 1) & !(1 << 7);
+        }
+        Ok(Schedule { minutes: Field::parse(fields[0], 0, 59, &[], 0).ok_or_else(err)?,
+                      hours: Field::parse(fields[1], 0, 23, &[], 0).ok_or_else(err)?,
+                      days_of_month: Field::parse(fields[2], 1, 31, &[], 0).ok_or_else(err)?,
+                      months: Field::parse(fields[3], 1, 12, MONTHS, 1).ok_or_else(err)?,
+                      days_of_week,
+                      timezone,
+                      fields: fields.join(" ") })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Schedule {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        struct ScheduleVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for ScheduleVisitor {
+            type Value = Schedule;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter,
+                       "five cron fields optionally followed by a time zone (example \"* 2-4 * * \
+                        SAT,SUN UTC\")")
+            }
+
+            fn visit_str<E>(self, s: &str) -> std::result::Result<Self::Value, E>
+                where E: serde::de::Error
+            {
+                Schedule::from_str(s).map_err(|_| {
+                    serde::de::Error::invalid_value(serde::de::Unexpected::Str(s), &self)
+                })
+            }
+        }
+
+        deserializer.deserialize_str(ScheduleVisitor)
+    }
+}
+
+impl serde::Serialize for Schedule {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+           .unwrap()
+    }
+
+    #[test]
+    fn schedule_display_round_trips() {
+        let schedule = "*/15  2-4 * jan-mar SAT,SUN".parse::<Schedule>().unwrap();
+        assert_eq!(schedule.to_string(), "*/15 2-4 * jan-mar SAT,SUN UTC");
+        assert_eq!(schedule.to_string().parse::<Schedule>().unwrap(), schedule);
+
+        let schedule = "0 3 * * * -05:30".parse::<Schedule>().unwrap();
+        assert_eq!(schedule.timezone(), Timezone::Offset(-(5 * 3600 + 30 * 60)));
+        assert_eq!(schedule.to_string(), "0 3 * * * -05:30");
+
+        let schedule = "0 3 * * * Europe/Paris".parse::<Schedule>().unwrap();
+        assert_eq!(schedule.timezone(), Timezone::Named(Tz::Europe__Paris));
+        assert_eq!(schedule.to_string(), "0 3 * * * Europe/Paris");
+    }
+
+    #[test]
+    fn schedule_rejects_invalid_fields() {
+        for value in &["",
+                       "* * * *",
+                       "* * * * * * *",
+                       "60 * * * *",
+                       "* 24 * * *",
+                       "* * 0 * *",
+                       "* * * 13 *",
+                       "* * * * 8",
+                       "5-1 * * * *",
+                       "*/0 * * * *",
+                       "* * * * FOO",
+                       "* * * * * +25:00",
+                       "* * * * * Mars/Olympus_Mons"]
+        {
+            assert!(value.parse::<Schedule>().is_err(),
+                    "{:?} should not parse",
+                    value);
+        }
+    }
+
+    #[test]
+    fn schedule_matches_a_weekend_window() {
+        let schedule = "* 2-4 * * SAT,SUN".parse::<Schedule>().unwrap();
+        // 2021-01-02 is a Saturday
+        assert!(schedule.matches(at(2021, 1, 2, 2, 0)));
+        assert!(schedule.matches(at(2021, 1, 3, 4, 59)));
+        assert!(!schedule.matches(at(2021, 1, 2, 5, 0)));
+        assert!(!schedule.matches(at(2021, 1, 4, 3, 0)));
+    }
+
+    #[test]
+    fn schedule_treats_7_as_sunday() {
+        let schedule = "0 0 * * 7".parse::<Schedule>().unwrap();
+        assert!(schedule.matches(at(2021, 1, 3, 0, 0)));
+    }
+
+    #[test]
+    fn schedule_matches_either_restricted_day() {
+        let schedule = "0 0 1 * MON".parse::<Schedule>().unwrap();
+        // 2021-01-01 is a Friday and 2021-01-04 a Monday
+        assert!(schedule.matches(at(2021, 1, 1, 0, 0)));
+        assert!(schedule.matches(at(2021, 1, 4, 0, 0)));
+        assert!(!schedule.matches(at(2021, 1, 5, 0, 0)));
+
+        let schedule = "0 0 1 * *".parse::<Schedule>().unwrap();
+        assert!(!schedule.matches(at(2021, 1, 4, 0, 0)));
+    }
+
+    #[test]
+    fn schedule_is_evaluated_in_its_time_zone() {
+        let schedule = "* 2 * * * +02:00".parse::<Schedule>().unwrap();
+        assert!(schedule.matches(at(2021, 1, 1, 0, 30)));
+        assert!(!schedule.matches(at(2021, 1, 1, 2, 30)));
+    }
+
+    #[test]
+    fn schedule_follows_daylight_saving_time() {
+        let schedule = "* 2 * * * Europe/Paris".parse::<Schedule>().unwrap();
+        // Paris is at UTC+1 in the winter and UTC+2 in the summer
+        assert!(schedule.matches(at(2021, 1, 1, 1, 30)));
+        assert!(!schedule.matches(at(2021, 1, 1, 0, 30)));
+        assert!(schedule.matches(at(2021, 7, 1, 0, 30)));
+        assert!(!schedule.matches(at(2021, 7, 1, 1, 30)));
+    }
+}
@@ -1,5 +1,6 @@
-use crate::error::{Error,
-                   Result};
+use crate::{error::{Error,
+                    Result},
+            schedule::Schedule};
 use regex::Regex;
 use serde::{Deserialize,
             Serialize};
@@ -300,6 +301,219 @@ impl From<Duration> for HealthCheckInterval {
     fn from(d: Duration) -> Self { Self(d) }
 }
 
+/// The number of members of a service group that update together in each wave of a batched
+/// rolling update. It is either a count of members (ex: `3`) or a percentage of the service group
+/// (ex: `25%`).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum UpdateBatchSize {
+    Count(u32),
+    Percent(u32),
+}
+
+impl UpdateBatchSize {
+    /// The number of members in a single wave for a service group of `group_size` members. A
+    /// percentage is rounded up and a wave always contains at least one member.
+    pub fn members(self, group_size: usize) -> usize {
+        let members = match self {
+            UpdateBatchSize::Count(count) => count as usize,
+            UpdateBatchSize::Percent(percent) => (group_size * percent as usize + 99) / 100,
+        };
+        members.max(1)
+    }
+}
+
+impl Default for UpdateBatchSize {
+    fn default() -> Self { UpdateBatchSize::Count(1) }
+}
+
+impl fmt::Display for UpdateBatchSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            UpdateBatchSize::Count(count) => write!(f, "{}", count),
+            UpdateBatchSize::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+impl FromStr for UpdateBatchSize {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let value = value.trim();
+        let err = || Error::InvalidUpdateBatchSize(value.to_string());
+        match value.strip_suffix('%') {
+            Some(percent) => {
+                match percent.trim().parse::<u32>() {
+                    Ok(p) if p > 0 && p <= 100 => Ok(UpdateBatchSize::Percent(p)),
+                    _ => Err(err()),
+                }
+            }
+            None => {
+                match value.parse::<u32>() {
+                    Ok(c) if c > 0 => Ok(UpdateBatchSize::Count(c)),
+                    _ => Err(err()),
+                }
+            }
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for UpdateBatchSize {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        struct UpdateBatchSizeVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for UpdateBatchSizeVisitor {
+            type Value = UpdateBatchSize;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter,
+                       "a number of members or a percentage of the service group (example 3 or \
+                        25%)")
+            }
+
+            fn visit_str<E>(self, s: &str) -> std::result::Result<Self::Value, E>
+                where E: serde::de::Error
+            {
+                UpdateBatchSize::from_str(s).map_err(|_| {
+                    serde::de::Error::invalid_value(serde::de::Unexpected::Str(s), &self)
+                })
+            }
+
+            fn visit_i64<E>(self, v: i64) -> std::result::Result<Self::Value, E>
+                where E: serde::de::Error
+            {
+                self.visit_str(&v.to_string())
+            }
+
+            fn visit_u64<E>(self, v: u64) -> std::result::Result<Self::Value, E>
+                where E: serde::de::Error
+            {
+                self.visit_str(&v.to_string())
+            }
+        }
+
+        deserializer.deserialize_any(UpdateBatchSizeVisitor)
+    }
+}
+
+impl serde::Serialize for UpdateBatchSize {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// When a job service runs. A job runs its `run` hook to completion instead of keeping it running,
+/// either once after the service starts (`once`) or at every minute matching a cron-like schedule.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum JobSchedule {
+    Once,
+    Cron(Schedule),
+}
+
+impl fmt::Display for JobSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobSchedule::Once => write!(f, "once"),
+            JobSchedule::Cron(schedule) => write!(f, "{}", schedule),
+        }
+    }
+}
+
+impl FromStr for JobSchedule {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("once") {
+            return Ok(JobSchedule::Once);
+        }
+        value.parse()
+             .map(JobSchedule::Cron)
+             .map_err(|_| Error::InvalidJobSchedule(value.to_string()))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for JobSchedule {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        struct JobScheduleVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for JobScheduleVisitor {
+            type Value = JobSchedule;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter,
+                       "once or five cron fields optionally followed by a time zone (example \"0 \
+                        3 * * * UTC\")")
+            }
+
+            fn visit_str<E>(self, s: &str) -> std::result::Result<Self::Value, E>
+                where E: serde::de::Error
+            {
+                JobSchedule::from_str(s).map_err(|_| {
+                    serde::de::Error::invalid_value(serde::de::Unexpected::Str(s), &self)
+                })
+            }
+        }
+
+        deserializer.deserialize_str(JobScheduleVisitor)
+    }
+}
+
+impl serde::Serialize for JobSchedule {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// What a job service does when a run becomes due while its previous run is still going.
+#[derive(Clone,
+         Copy,
+         Debug,
+         Default,
+         Deserialize,
+         Eq,
+         Hash,
+         PartialEq,
+         Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobConcurrency {
+    /// Skip the new run and let the previous run finish
+    #[default]
+    Forbid,
+    /// Stop the previous run and start the new one
+    Replace,
+}
+
+impl fmt::Display for JobConcurrency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match *self {
+            JobConcurrency::Forbid => "forbid",
+            JobConcurrency::Replace => "replace",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for JobConcurrency {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_ref() {
+            "forbid" => Ok(JobConcurrency::Forbid),
+            "replace" => Ok(JobConcurrency::Replace),
+            _ => Err(Error::InvalidJobConcurrency(value.to_string())),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
@@ -545,6 +759,80 @@ mod test {
                    format!("{}", HealthCheckInterval::from_str("5").unwrap()));
     }
 
+    #[test]
+    fn update_batch_size_from_str() {
+        assert_eq!(UpdateBatchSize::from_str("3").unwrap(),
+                   UpdateBatchSize::Count(3));
+        assert_eq!(UpdateBatchSize::from_str("25%").unwrap(),
+                   UpdateBatchSize::Percent(25));
+        assert!(UpdateBatchSize::from_str("0").is_err());
+        assert!(UpdateBatchSize::from_str("0%").is_err());
+        assert!(UpdateBatchSize::from_str("101%").is_err());
+        assert!(UpdateBatchSize::from_str("-1").is_err());
+        assert!(UpdateBatchSize::from_str("some").is_err());
+    }
+
+    #[test]
+    fn update_batch_size_display_round_trips() {
+        for value in &["3", "25%"] {
+            assert_eq!(UpdateBatchSize::from_str(value).unwrap().to_string(),
+                       *value);
+        }
+    }
+
+    #[test]
+    fn update_batch_size_members() {
+        assert_eq!(UpdateBatchSize::Count(3).members(10), 3);
+        assert_eq!(UpdateBatchSize::Percent(25).members(10), 3);
+        assert_eq!(UpdateBatchSize::Percent(50).members(4), 2);
+        assert_eq!(UpdateBatchSize::Percent(10).members(3), 1);
+        assert_eq!(UpdateBatchSize::Percent(100).members(0), 1);
+    }
+
+    #[test]
+    fn update_batch_size_toml() {
+        #[derive(Deserialize, Serialize)]
+        struct Data {
+            size: UpdateBatchSize,
+        }
+        let data: Data = toml::from_str("size = \"25%\"").unwrap();
+        assert_eq!(data.size, UpdateBatchSize::Percent(25));
+        let data: Data = toml::from_str("size = 4").unwrap();
+        assert_eq!(data.size, UpdateBatchSize::Count(4));
+        assert_eq!(toml::to_string(&data).unwrap(), "size = \"4\"\n");
+    }
+
+    #[test]
+    fn job_schedule_from_str() {
+        assert_eq!(JobSchedule::from_str("once").unwrap(), JobSchedule::Once);
+        assert_eq!(JobSchedule::from_str("0 3 * * *").unwrap(),
+                   JobSchedule::Cron("0 3 * * * UTC".parse().unwrap()));
+        assert!(JobSchedule::from_str("twice").is_err());
+        assert!(JobSchedule::from_str("0 3 * *").is_err());
+    }
+
+    #[test]
+    fn job_schedule_toml() {
+        #[derive(Deserialize, Serialize)]
+        struct Data {
+            job: JobSchedule,
+        }
+        let data: Data = toml::from_str("job = \"*/5 * * * *\"").unwrap();
+        assert_eq!(toml::to_string(&data).unwrap(),
+                   "job = \"*/5 * * * * UTC\"\n");
+        let data: Data = toml::from_str("job = \"once\"").unwrap();
+        assert_eq!(data.job, JobSchedule::Once);
+    }
+
+    #[test]
+    fn job_concurrency_from_str() {
+        assert_eq!(JobConcurrency::from_str("forbid").unwrap(),
+                   JobConcurrency::Forbid);
+        assert_eq!(JobConcurrency::from_str("Replace").unwrap(),
+                   JobConcurrency::Replace);
+        assert!(JobConcurrency::from_str("allow").is_err());
+    }
+
     /// This ensures that we can safely transition from the old
     /// application/environment formulation of service group
     /// names. Once this has been in the wild for a while, we can
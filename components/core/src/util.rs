@@ -3,6 +3,7 @@ pub mod docker;
 pub mod posix_perm;
 pub mod serde;
 pub mod sys;
+pub mod text_diff;
 pub mod text_render;
 #[cfg(windows)]
 pub mod win_perm;
@@ -26,6 +26,30 @@ pub mod string {
     }
 }
 
+/// `Serialize` and `Deserialize` an optional type using the `ToString` and `FromStr` traits.
+pub mod option_string {
+    use super::*;
+
+    pub fn serialize<T, S>(t: &Option<T>, s: S) -> Result<S::Ok, S::Error>
+        where T: ToString,
+              S: Serializer
+    {
+        match t {
+            Some(t) => s.serialize_some(&t.to_string()),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(d: D) -> Result<Option<T>, D::Error>
+        where T: FromStr,
+              T::Err: Error,
+              D: Deserializer<'de>
+    {
+        Option::<String>::deserialize(d)?.map(|s| s.parse().map_err(de::Error::custom))
+                                         .transpose()
+    }
+}
+
 /// `Serialize` and `Deserialize` a type using a proxy type that implements `Serialize` and
 /// `Deserialize`.
 pub mod proxy {
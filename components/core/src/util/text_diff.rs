@@ -0,0 +1,174 @@
+//! Line-oriented comparison of text, rendered in the unified diff format.
+
+use std::fmt::Write;
+
+/// The number of unchanged lines shown around each change in a unified diff.
+const CONTEXT_LINES: usize = 3;
+
+/// Inputs whose changed regions would need a comparison table larger than this are not compared
+/// line by line; the whole region is reported as removed and re-added instead.
+const MAX_TABLE_SIZE: usize = 1 << 22;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineChange<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+impl LineChange<'_> {
+    fn in_old(&self) -> bool { !matches!(self, LineChange::Added(_)) }
+
+    fn in_new(&self) -> bool { !matches!(self, LineChange::Removed(_)) }
+}
+
+/// Compute the sequence of line changes required to turn `old` into `new`.
+pub fn line_changes<'a>(old: &'a str, new: &'a str) -> Vec<LineChange<'a>> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // Common leading and trailing lines are trimmed before building the comparison table to keep
+    // it small in the common case of a few localized edits.
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter()
+                              .rev()
+                              .zip(new[prefix..].iter().rev())
+                              .take_while(|(a, b)| a == b)
+                              .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut changes = old[..prefix].iter()
+                                   .map(|l| LineChange::Same(l))
+                                   .collect::<Vec<_>>();
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_TABLE_SIZE {
+        changes.extend(old_mid.iter().map(|l| LineChange::Removed(l)));
+        changes.extend(new_mid.iter().map(|l| LineChange::Added(l)));
+    } else {
+        // `lcs[i * width + j]` is the length of the longest common subsequence of
+        // `old_mid[i..]` and `new_mid[j..]`.
+        let width = new_mid.len() + 1;
+        let mut lcs = vec![0usize; (old_mid.len() + 1) * width];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() && j < new_mid.len() {
+            if old_mid[i] == new_mid[j] {
+                changes.push(LineChange::Same(old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+                changes.push(LineChange::Removed(old_mid[i]));
+                i += 1;
+            } else {
+                changes.push(LineChange::Added(new_mid[j]));
+                j += 1;
+            }
+        }
+        changes.extend(old_mid[i..].iter().map(|l| LineChange::Removed(l)));
+        changes.extend(new_mid[j..].iter().map(|l| LineChange::Added(l)));
+    }
+    changes.extend(old[old.len() - suffix..].iter()
+                                            .map(|l| LineChange::Same(l)));
+    changes
+}
+
+/// Render the differences between `old` and `new` as a unified diff, labelling the two sides
+/// with `old_label` and `new_label`. Returns `None` if the inputs have the same lines.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> Option<String> {
+    let changes = line_changes(old, new);
+    let changed = changes.iter()
+                         .enumerate()
+                         .filter(|(_, c)| !matches!(c, LineChange::Same(_)))
+                         .map(|(i, _)| i)
+                         .collect::<Vec<_>>();
+    if changed.is_empty() {
+        return None;
+    }
+
+    // Group the changed lines into hunks, merging changes whose context would overlap.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for i in changed {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + CONTEXT_LINES + 1).min(changes.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks {
+        let old_start = changes[..start].iter().filter(|c| c.in_old()).count();
+        let new_start = changes[..start].iter().filter(|c| c.in_new()).count();
+        let hunk = &changes[start..end];
+        let old_len = hunk.iter().filter(|c| c.in_old()).count();
+        let new_len = hunk.iter().filter(|c| c.in_new()).count();
+        // Writing to a `String` cannot fail
+        let _ = writeln!(out,
+                         "@@ -{},{} +{},{} @@",
+                         if old_len == 0 {
+                             old_start
+                         } else {
+                             old_start + 1
+                         },
+                         old_len,
+                         if new_len == 0 {
+                             new_start
+                         } else {
+                             new_start + 1
+                         },
+                         new_len);
+        for change in hunk {
+            let _ = match change {
+                LineChange::Same(l) => writeln!(out, " {}", l),
+                LineChange::Removed(l) => writeln!(out, "-{}", l),
+                LineChange::Added(l) => writeln!(out, "+{}", l),
+            };
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_text_has_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), None);
+    }
+
+    #[test]
+    fn line_changes_are_minimal() {
+        let changes = line_changes("a\nb\nc", "a\nx\nc\nd");
+        assert_eq!(changes,
+                   vec![LineChange::Same("a"),
+                        LineChange::Removed("b"),
+                        LineChange::Added("x"),
+                        LineChange::Same("c"),
+                        LineChange::Added("d"),]);
+    }
+
+    #[test]
+    fn unified_diff_renders_hunks_with_context() {
+        let old = (1..=16).map(|i| i.to_string() + "\n").collect::<String>();
+        let new = old.replacen("\n6\n", "\nsix\n", 1) + "17\n";
+        let expected = "--- old\n+++ new\n@@ -3,7 +3,7 @@\n 3\n 4\n 5\n-6\n+six\n 7\n 8\n 9\n@@ \
+                        -14,3 +14,4 @@\n 14\n 15\n 16\n+17\n";
+        assert_eq!(unified_diff(&old, &new, "old", "new").unwrap(), expected);
+    }
+
+    #[test]
+    fn unified_diff_of_new_content() {
+        assert_eq!(unified_diff("", "a\n", "old", "new").unwrap(),
+                   "--- old\n+++ new\n@@ -0,0 +1,1 @@\n+a\n");
+    }
+}
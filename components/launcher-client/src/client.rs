@@ -6,7 +6,8 @@ use crate::error::{ConnectError,
                    TryIPCCommandError,
                    TryReceiveError};
 use biome_common::types::UserInfo;
-use biome_core::os::process::Pid;
+use biome_core::{os::process::Pid,
+                   package::PackageIdent};
 use biome_launcher_protocol as protocol;
 use ipc_channel::ipc::{IpcError,
                        IpcOneShotServer,
@@ -255,6 +256,17 @@ impl LauncherCli {
         }
     }
 
+    /// Query the launcher for the exit code of the last process of the named service. You'll
+    /// get `Ok(Some(i32))` only once per process, and only if it exited on its own.
+    pub fn exit_status_of(&self, service_name: &str) -> Result<Option<i32>, TryIPCCommandError> {
+        let msg = protocol::ExitStatusOf { service_name: service_name.to_string(), };
+        Self::send(&self.tx, &msg).map_err(|err| TryIPCCommandError::Send("exit_status_of", err))?;
+        // We only expect to not receive a response when dealing with
+        // older Launchers that didn't keep track of exit codes.
+        let reply = Self::recv_timeout::<protocol::ExitStatusIs>(&self.rx, self.timeout).map_err(|err| TryIPCCommandError::TryReceive("exit_status_of", err))?;
+        Ok(reply.exit_code)
+    }
+
     /// Query the launcher for its version. If the
     /// Launcher is aware of it, you'll get `Ok(u32)`
     pub fn version(&self) -> Result<u32, TryIPCCommandError> {
@@ -267,6 +279,36 @@ impl LauncherCli {
         Ok(reply.version)
     }
 
+    /// Tell the Launcher that the Supervisor is shutting down to switch from the `previous` to
+    /// the `updated` release of itself. If the updated Supervisor does not report that it is
+    /// ready within `ready_timeout`, the Launcher falls back to the `previous` release.
+    pub fn self_update(&self,
+                       previous: &PackageIdent,
+                       updated: &PackageIdent,
+                       ready_timeout: Duration)
+                       -> Result<(), TryIPCCommandError> {
+        let msg = protocol::SupUpdate { previous:           previous.to_string(),
+                                        updated:            updated.to_string(),
+                                        ready_timeout_secs: ready_timeout.as_secs() as u32, };
+        Self::send(&self.tx, &msg).map_err(|err| TryIPCCommandError::Send("self_update", err))?;
+        // Older Launchers do not know about self-updates, and may not reply at all.
+        Self::recv_timeout::<protocol::NetOk>(&self.rx, self.timeout).map_err(|err| {
+            TryIPCCommandError::TryReceive("self_update", err)
+        })?;
+        Ok(())
+    }
+
+    /// Tell the Launcher that the Supervisor is ready.
+    pub fn ready(&self) -> Result<(), TryIPCCommandError> {
+        let msg = protocol::SupReady {};
+        Self::send(&self.tx, &msg).map_err(|err| TryIPCCommandError::Send("ready", err))?;
+        // Older Launchers do not know about self-updates, and may not reply at all.
+        Self::recv_timeout::<protocol::NetOk>(&self.rx, self.timeout).map_err(|err| {
+            TryIPCCommandError::TryReceive("ready", err)
+        })?;
+        Ok(())
+    }
+
     pub fn terminate(&self, pid: Pid) -> Result<i32, IPCCommandError> {
         let msg = protocol::Terminate { pid: pid.into() };
         Self::send(&self.tx, &msg).map_err(|err| IPCCommandError::Send("terminate", err))?;
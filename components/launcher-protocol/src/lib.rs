@@ -8,6 +8,19 @@ pub use crate::{error::Error,
 
 pub const LAUNCHER_PIPE_ENV: &str = "HAB_LAUNCHER_PIPE";
 pub const LAUNCHER_PID_ENV: &str = "HAB_LAUNCHER_PID";
+/// Set by the Launcher on a Supervisor it starts for a self-update, to the fully-qualified ident
+/// of the Supervisor release that was updated from.
+pub const SUP_UPDATE_PREVIOUS_ENV: &str = "HAB_SUP_UPDATE_PREVIOUS";
+/// Set alongside `SUP_UPDATE_PREVIOUS_ENV`, to the fully-qualified ident of the Supervisor release
+/// that was updated to.
+pub const SUP_UPDATE_UPDATED_ENV: &str = "HAB_SUP_UPDATE_UPDATED";
+/// Set alongside `SUP_UPDATE_PREVIOUS_ENV` when the updated Supervisor did not become ready and
+/// the Launcher fell back to the previous release.
+pub const SUP_UPDATE_ROLLED_BACK_ENV: &str = "HAB_SUP_UPDATE_ROLLED_BACK";
+/// The file in the Launcher's root directory, next to its PID file, in which the Launcher records
+/// the fully-qualified ident of the last Supervisor release it rolled back from. Neither the
+/// Launcher nor the Supervisor starts or updates to that release again, even after a restart.
+pub const SUP_REJECTED_RELEASE_FILE: &str = "REJECTED";
 /// Process exit code from Supervisor which indicates to Launcher that the Supervisor
 /// ran to completion with a successful result. The Launcher should not attempt to restart
 /// the Supervisor and should exit immediately with a successful exit code.
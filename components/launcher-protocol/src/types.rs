@@ -321,6 +321,47 @@ impl From<PidIs> for generated::PidIs {
     fn from(value: PidIs) -> Self { generated::PidIs { pid: value.pid } }
 }
 
+#[derive(Clone, Debug)]
+pub struct ExitStatusOf {
+    pub service_name: String,
+}
+
+impl LauncherMessage for ExitStatusOf {
+    type Generated = generated::ExitStatusOf;
+
+    const MESSAGE_ID: &'static str = "ExitStatusOf";
+
+    fn from_proto(proto: generated::ExitStatusOf) -> Result<Self> {
+        Ok(ExitStatusOf { service_name: proto.service_name
+                                             .ok_or(Error::ProtocolMismatch("service_name"))?, })
+    }
+}
+
+impl From<ExitStatusOf> for generated::ExitStatusOf {
+    fn from(value: ExitStatusOf) -> Self {
+        generated::ExitStatusOf { service_name: Some(value.service_name), }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExitStatusIs {
+    pub exit_code: Option<i32>,
+}
+
+impl LauncherMessage for ExitStatusIs {
+    type Generated = generated::ExitStatusIs;
+
+    const MESSAGE_ID: &'static str = "ExitStatusIs";
+
+    fn from_proto(proto: generated::ExitStatusIs) -> Result<Self> {
+        Ok(ExitStatusIs { exit_code: proto.exit_code, })
+    }
+}
+
+impl From<ExitStatusIs> for generated::ExitStatusIs {
+    fn from(value: ExitStatusIs) -> Self { generated::ExitStatusIs { exit_code: value.exit_code, } }
+}
+
 #[derive(Clone, Debug)]
 pub struct VersionNumber {
     pub version: u32,
@@ -356,3 +397,49 @@ impl LauncherMessage for Version {
 impl From<Version> for generated::Version {
     fn from(_value: Version) -> Self { generated::Version {} }
 }
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SupUpdate {
+    pub previous:           String,
+    pub updated:            String,
+    pub ready_timeout_secs: u32,
+}
+
+impl LauncherMessage for SupUpdate {
+    type Generated = generated::SupUpdate;
+
+    const MESSAGE_ID: &'static str = "SupUpdate";
+
+    fn from_proto(proto: generated::SupUpdate) -> Result<Self> {
+        Ok(SupUpdate { previous:           proto.previous
+                                                .ok_or(Error::ProtocolMismatch("previous"))?,
+                       updated:            proto.updated
+                                                .ok_or(Error::ProtocolMismatch("updated"))?,
+                       ready_timeout_secs:
+                           proto.ready_timeout_secs
+                                .ok_or(Error::ProtocolMismatch("ready_timeout_secs"))?, })
+    }
+}
+
+impl From<SupUpdate> for generated::SupUpdate {
+    fn from(value: SupUpdate) -> Self {
+        generated::SupUpdate { previous:           Some(value.previous),
+                               updated:            Some(value.updated),
+                               ready_timeout_secs: Some(value.ready_timeout_secs), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SupReady {}
+
+impl LauncherMessage for SupReady {
+    type Generated = generated::SupReady;
+
+    const MESSAGE_ID: &'static str = "SupReady";
+
+    fn from_proto(_proto: generated::SupReady) -> Result<Self> { Ok(SupReady {}) }
+}
+
+impl From<SupReady> for generated::SupReady {
+    fn from(_value: SupReady) -> Self { generated::SupReady {} }
+}
@@ -1,4 +1,5 @@
 mod handlers;
+mod self_update;
 
 use crate::{core::{self,
                    fs::{launcher_root_path,
@@ -10,7 +11,8 @@ use crate::{core::{self,
             protocol::{self,
                        ERR_NO_RETRY_EXCODE,
                        OK_NO_RETRY_EXCODE},
-            server::handlers::Handler,
+            server::{handlers::Handler,
+                     self_update::SelfUpdate},
             service::Service,
             SUP_CMD,
             SUP_PACKAGE_IDENT};
@@ -75,6 +77,7 @@ pub struct Server {
     rx:            Receiver,
     supervisor:    Child,
     args:          Vec<String>,
+    self_update:   SelfUpdate,
 }
 
 impl Drop for Server {
@@ -101,13 +104,16 @@ impl Server {
                                                                        pid_file_path.display())
                                                            })?;
 
-        let ((rx, tx), supervisor) = Self::init(&args).context("Failed to initialize launcher")?;
+        let self_update = SelfUpdate::default();
+        let ((rx, tx), supervisor) =
+            Self::init(&args, &self_update).context("Failed to initialize launcher")?;
         Ok(Server { pid_file_path,
                     services: ServiceTable::default(),
                     tx,
                     rx,
                     supervisor,
-                    args })
+                    args,
+                    self_update })
     }
 
     /// Spawn a Supervisor and setup a bi-directional IPC connection to it.
@@ -115,10 +121,11 @@ impl Server {
     /// Passing a value of true to the `clean` argument will force the Supervisor to clean the
     /// Launcher's process LOCK before starting. This is useful when restarting a Supervisor
     /// that terminated gracefully.
-    fn init(args: &[String]) -> Result<((Receiver, Sender), Child)> {
+    fn init(args: &[String], self_update: &SelfUpdate) -> Result<((Receiver, Sender), Child)> {
         let (server, pipe) =
             IpcOneShotServer::new().context("Failed to create incoming IPC channel for launcher")?;
-        let supervisor = spawn_supervisor(&pipe, args).context("Failed to spawn supervisor")?;
+        let supervisor =
+            spawn_supervisor(&pipe, args, self_update).context("Failed to spawn supervisor")?;
         let ipc_channel = setup_connection(server).context("Failed to setup launcher IPC \
                                                             connection with supervisor")?;
         Ok((ipc_channel, supervisor))
@@ -128,7 +135,12 @@ impl Server {
     fn reload(&mut self) -> Result<()> {
         self.supervisor.kill();
         self.supervisor.wait();
-        let ((rx, tx), supervisor) = Self::init(&self.args).context("Failed to reload launcher")?;
+        if let Some(rejected) = self.self_update.restarting() {
+            uninstall_supervisor(&rejected);
+            record_rejected_supervisor(&rejected);
+        }
+        let ((rx, tx), supervisor) =
+            Self::init(&self.args, &self.self_update).context("Failed to reload launcher")?;
         self.tx = tx;
         self.rx = rx;
         self.supervisor = supervisor;
@@ -148,7 +160,7 @@ impl Server {
     fn handle_message(&mut self) -> Result<TickState> {
         match self.rx.try_recv() {
             Ok(bytes) => {
-                dispatch(&self.tx, &bytes, &mut self.services);
+                dispatch(&self.tx, &bytes, &mut self.services, &mut self.self_update);
                 Ok(TickState::Continue)
             }
             Err(_) => {
@@ -171,6 +183,11 @@ impl Server {
     /// exit code, figure out whether we need to restart it or not.
     fn handle_supervisor_exit(&mut self, status: ExitStatus) -> Result<TickState> {
         let code = status.code();
+        if self.self_update.is_starting() {
+            // Whatever the exit code, an updated Supervisor that exits before it is ready is
+            // reloaded so that the update is rolled back.
+            return Err(anyhow!("Updated supervisor process exited before it was ready"));
+        }
         match code {
             Some(ERR_NO_RETRY_EXCODE) => {
                 self.services.kill_all();
@@ -305,6 +322,10 @@ impl Server {
                 self.forward_signal(Signal::HUP);
             }
         }
+
+        if self.self_update.timed_out() {
+            return Err(anyhow!("Updated supervisor did not become ready in time"));
+        }
         self.handle_message()
     }
 
@@ -369,16 +390,24 @@ impl Server {
 }
 
 #[derive(Debug, Default)]
-pub struct ServiceTable(HashMap<u32, Service>);
+pub struct ServiceTable {
+    services:   HashMap<u32, Service>,
+    /// Exit codes of services that exited on their own, keyed by service group name, kept until
+    /// the Supervisor asks for them.
+    exit_codes: HashMap<String, i32>,
+}
 
 impl ServiceTable {
-    pub fn get(&self, pid: u32) -> Option<&Service> { self.0.get(&pid) }
+    pub fn get(&self, pid: u32) -> Option<&Service> { self.services.get(&pid) }
 
-    pub fn get_mut(&mut self, pid: u32) -> Option<&mut Service> { self.0.get_mut(&pid) }
+    pub fn get_mut(&mut self, pid: u32) -> Option<&mut Service> { self.services.get_mut(&pid) }
 
-    pub fn insert(&mut self, service: Service) { self.0.insert(service.id(), service); }
+    pub fn insert(&mut self, service: Service) {
+        self.exit_codes.remove(service.name());
+        self.services.insert(service.id(), service);
+    }
 
-    pub fn remove(&mut self, pid: u32) -> Option<Service> { self.0.remove(&pid) }
+    pub fn remove(&mut self, pid: u32) -> Option<Service> { self.services.remove(&pid) }
 
     // Obviously this is not the most elegant implementation. However,
     // in practice we don't have a whole lot of processes per
@@ -402,17 +431,26 @@ impl ServiceTable {
     /// figure out if there are currently-running services to which it
     /// needs to re-attach itself.
     pub fn pid_of(&self, service_name: &str) -> Option<u32> {
-        self.0.iter().find_map(|(pid, service)| {
-                         if service_name == service.args().id {
-                             Some(*pid)
-                         } else {
-                             None
-                         }
-                     })
+        self.services.iter().find_map(|(pid, service)| {
+                                if service_name == service.args().id {
+                                    Some(*pid)
+                                } else {
+                                    None
+                                }
+                            })
+    }
+
+    /// Given the name of a service group, return the exit code of its last process if it exited
+    /// on its own since it was last spawned. The exit code is only returned once.
+    ///
+    /// This allows the Supervisor to learn how a service that runs to completion, like a job,
+    /// finished.
+    pub fn take_exit_code(&mut self, service_name: &str) -> Option<i32> {
+        self.exit_codes.remove(service_name)
     }
 
     fn kill_all(&mut self) {
-        for service in self.0.values_mut() {
+        for service in self.services.values_mut() {
             outputln!(preamble service.name(), "Stopping...");
             let shutdown_method = service.kill();
             outputln!(preamble service.name(), "Shutdown OK: {}", shutdown_method);
@@ -421,7 +459,7 @@ impl ServiceTable {
 
     fn reap_services(&mut self) {
         let mut dead: Vec<u32> = vec![];
-        for service in self.0.values_mut() {
+        for service in self.services.values_mut() {
             match service.try_wait() {
                 Ok(None) => (),
                 Ok(Some(code)) => {
@@ -429,6 +467,10 @@ impl ServiceTable {
                               service.name(),
                               service.id(),
                               code);
+                    if let Some(exit_code) = code.code() {
+                        self.exit_codes
+                            .insert(service.name().to_string(), exit_code);
+                    }
                     dead.push(service.id());
                 }
                 Err(err) => {
@@ -438,7 +480,7 @@ impl ServiceTable {
             }
         }
         for pid in dead {
-            self.0.remove(&pid);
+            self.services.remove(&pid);
         }
     }
 }
@@ -495,7 +537,7 @@ pub fn send<T>(tx: &Sender, msg: &T) -> Result<()>
 // Private Func
 //
 
-fn dispatch(tx: &Sender, bytes: &[u8], services: &mut ServiceTable) {
+fn dispatch(tx: &Sender, bytes: &[u8], services: &mut ServiceTable, self_update: &mut SelfUpdate) {
     let msg = match protocol::NetTxn::from_bytes(bytes) {
         Ok(msg) => msg,
         Err(err) => {
@@ -508,7 +550,12 @@ fn dispatch(tx: &Sender, bytes: &[u8], services: &mut ServiceTable) {
         "Spawn" => handlers::SpawnHandler::run,
         "Terminate" => handlers::TerminateHandler::run,
         "PidOf" => handlers::PidHandler::run,
+        "ExitStatusOf" => handlers::ExitStatusHandler::run,
         "Version" => handlers::VersionHandler::run,
+        "SupUpdate" | "SupReady" => {
+            dispatch_self_update(tx, &msg, self_update);
+            return;
+        }
         unknown => {
             // This sucks a bit because it replicates some code from the
             // Handler trait, but manipulating an unknown message
@@ -526,6 +573,28 @@ fn dispatch(tx: &Sender, bytes: &[u8], services: &mut ServiceTable) {
     func(tx, msg, services);
 }
 
+/// Handle the messages of a Supervisor switching to a newer release of itself. These need the
+/// Launcher's self-update state rather than its service table.
+fn dispatch_self_update(tx: &Sender, msg: &protocol::NetTxn, self_update: &mut SelfUpdate) {
+    let decoded = match msg.message_id() {
+        "SupUpdate" => {
+            msg.decode::<protocol::SupUpdate>()
+               .map(|update| self_update.requested(update))
+        }
+        _ => {
+            msg.decode::<protocol::SupReady>()
+               .map(|_| self_update.ready())
+        }
+    };
+    if let Err(err) = decoded {
+        error!("{}: decoding, {}", msg.message_id(), err);
+        return;
+    }
+    if let Err(err) = send(tx, &protocol::NetOk::default()) {
+        error!("{}: replying, {}", msg.message_id(), err);
+    }
+}
+
 #[allow(clippy::mutex_atomic)] // A Mutex is required for Condvar::wait_timeout
 fn setup_connection(server: IpcOneShotServer<Vec<u8>>) -> Result<(Receiver, Sender)> {
     let pair = Arc::new((Mutex::new(false), Condvar::new()));
@@ -624,8 +693,10 @@ fn is_supported_supervisor_version(version_output: &str) -> bool {
 /// Passing a value of true to the `clean` argument will force the Supervisor to clean the
 /// Launcher's process LOCK before starting. This is useful when restarting a Supervisor
 /// that terminated gracefully.
-fn spawn_supervisor(pipe: &str, args: &[String]) -> Result<Child> {
-    let binary = supervisor_cmd().context("Failed to find supervisor binary")?;
+fn spawn_supervisor(pipe: &str, args: &[String], self_update: &SelfUpdate) -> Result<Child> {
+    let rejected = recorded_rejected_supervisor();
+    let binary = supervisor_cmd(self_update.pinned_release(), rejected.as_deref())
+        .context("Failed to find supervisor binary")?;
 
     if core::env::var(SUP_VERSION_CHECK_DISABLE).is_ok() {
         warn!("Launching Supervisor {:?} without version checking", binary);
@@ -662,23 +733,90 @@ fn spawn_supervisor(pipe: &str, args: &[String]) -> Result<Child> {
                        .env(protocol::LAUNCHER_PIPE_ENV, pipe)
                        .env(protocol::LAUNCHER_PID_ENV,
                             process::current_pid().to_string())
+                       .envs(self_update.env())
                        .args(args)
                        .spawn()
                        .context("Failed to spawn supervisor")?;
     Ok(child)
 }
 
+/// Remove a Supervisor release that was rolled back, so that it is not started again as the
+/// latest installed release, even by a restarted Launcher.
+fn uninstall_supervisor(release: &str) {
+    let ident = match PackageIdent::from_str(release) {
+        Ok(ident) if ident.fully_qualified() => ident,
+        _ => {
+            warn!("Not uninstalling invalid supervisor release '{}'", release);
+            return;
+        }
+    };
+    let path = core::fs::pkg_install_path(&ident, Some(FS_ROOT_PATH.as_path()));
+    match fs::remove_dir_all(&path) {
+        Ok(()) => outputln!("Uninstalled supervisor {}, which was rolled back", ident),
+        Err(err) => {
+            warn!("Failed to uninstall supervisor {} from {}: {}",
+                  ident,
+                  path.display(),
+                  err)
+        }
+    }
+}
+
+/// The file in which the last Supervisor release that was rolled back is recorded.
+fn rejected_supervisor_path() -> PathBuf {
+    launcher_root_path(Some(&*FS_ROOT_PATH)).join(protocol::SUP_REJECTED_RELEASE_FILE)
+}
+
+/// Record a Supervisor release that was rolled back, so that neither a restarted Launcher nor a
+/// restarted Supervisor starts or updates to it again.
+fn record_rejected_supervisor(release: &str) {
+    let path = rejected_supervisor_path();
+    if let Err(err) = fs::write(&path, release) {
+        warn!("Failed to record rolled back supervisor {} in {}: {}",
+              release,
+              path.display(),
+              err);
+    }
+}
+
+/// The last Supervisor release that was rolled back, if one was recorded.
+fn recorded_rejected_supervisor() -> Option<String> {
+    fs::read_to_string(rejected_supervisor_path()).ok()
+                                                  .map(|release| release.trim().to_string())
+                                                  .filter(|release| !release.is_empty())
+}
+
 /// Determines the most viable Supervisor binary to run and returns a `PathBuf` to it.
 ///
 /// Setting a filepath value to the `HAB_SUP_BINARY` env variable will force that binary to be used
-/// instead.
-fn supervisor_cmd() -> Result<PathBuf> {
+/// instead. A `pinned` release, such as the release a failed self-update was rolled back to, is
+/// used instead of the latest installed release. A `rejected` release that was installed again
+/// after it was rolled back is uninstalled rather than started.
+fn supervisor_cmd(pinned: Option<&str>, rejected: Option<&str>) -> Result<PathBuf> {
     if let Ok(command) = core::env::var(SUP_CMD_ENVVAR) {
         return Ok(PathBuf::from(command));
     }
-    let ident = PackageIdent::from_str(SUP_PACKAGE_IDENT).unwrap();
     let fs_root_path = FS_ROOT_PATH.as_path();
-    match PackageInstall::load_at_least(&ident, Some(fs_root_path)) {
+    let install = match pinned {
+        Some(pinned) => {
+            let ident = PackageIdent::from_str(pinned).with_context(|| {
+                                                          format!("Invalid supervisor release '{}'",
+                                                                  pinned)
+                                                      })?;
+            PackageInstall::load(&ident, Some(fs_root_path))
+        }
+        None => {
+            let ident = PackageIdent::from_str(SUP_PACKAGE_IDENT).unwrap();
+            match PackageInstall::load_at_least(&ident, Some(fs_root_path)) {
+                Ok(install) if rejected == Some(install.ident().to_string().as_str()) => {
+                    uninstall_supervisor(&install.ident().to_string());
+                    PackageInstall::load_at_least(&ident, Some(fs_root_path))
+                }
+                install => install,
+            }
+        }
+    };
+    match install {
         Ok(install) => {
             match core::fs::find_command_in_pkg(SUP_CMD, &install, fs_root_path) {
                 Ok(Some(cmd)) => Ok(cmd),
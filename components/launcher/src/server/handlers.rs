@@ -1,3 +1,4 @@
+mod exit_status;
 mod pid;
 mod restart;
 mod spawn;
@@ -7,7 +8,8 @@ mod version;
 use log::{error,
           trace};
 
-pub use self::{pid::*,
+pub use self::{exit_status::*,
+               pid::*,
                restart::*,
                spawn::*,
                terminate::*,
@@ -0,0 +1,17 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct ExitStatusHandler;
+
+impl Handler for ExitStatusHandler {
+    type Message = protocol::ExitStatusOf;
+    type Reply = protocol::ExitStatusIs;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        let exit_code = services.take_exit_code(&msg.service_name);
+        let reply = protocol::ExitStatusIs { exit_code };
+        Ok(reply)
+    }
+}
@@ -0,0 +1,192 @@
+use crate::protocol;
+use biome_common::outputln;
+use std::{mem,
+          time::{Duration,
+                 Instant}};
+
+static LOGKEY: &str = "SU";
+
+enum State {
+    /// No self-update is in progress.
+    Idle,
+    /// The Supervisor is shutting down to switch to the updated release.
+    Requested(protocol::SupUpdate),
+    /// The updated Supervisor was started and has until the deadline to report that it is ready.
+    Starting(protocol::SupUpdate, Instant),
+    /// The updated Supervisor failed, so the previous release is run in its place.
+    RolledBack(protocol::SupUpdate),
+}
+
+/// Tracks a Supervisor switching to a newer release of itself. The updated Supervisor has to
+/// report that it is ready before its timeout; otherwise the Launcher falls back to the release
+/// that was updated from.
+pub struct SelfUpdate(State);
+
+impl Default for SelfUpdate {
+    fn default() -> Self { SelfUpdate(State::Idle) }
+}
+
+impl SelfUpdate {
+    /// The running Supervisor is shutting down to switch to a newer release of itself.
+    pub fn requested(&mut self, update: protocol::SupUpdate) {
+        outputln!("Supervisor restarting to update from {} to {}",
+                  update.previous,
+                  update.updated);
+        self.0 = State::Requested(update);
+    }
+
+    /// The running Supervisor reported that it is ready. A rolled back update has been reported
+    /// to the Supervisor that is now ready, so it is not reported again.
+    pub fn ready(&mut self) {
+        match &self.0 {
+            State::Starting(update, _) => {
+                outputln!("Supervisor {} is ready; keeping the update from {}",
+                          update.updated,
+                          update.previous);
+                self.0 = State::Idle;
+            }
+            State::RolledBack(_) => self.0 = State::Idle,
+            State::Idle | State::Requested(_) => {}
+        }
+    }
+
+    /// Whether the running Supervisor is an updated release that has yet to report that it is
+    /// ready.
+    pub fn is_starting(&self) -> bool { matches!(self.0, State::Starting(..)) }
+
+    /// Whether the running Supervisor is an updated release that did not report that it is ready
+    /// before its timeout.
+    pub fn timed_out(&self) -> bool {
+        matches!(self.0, State::Starting(_, deadline) if Instant::now() > deadline)
+    }
+
+    /// Advance the self-update as the Supervisor is (re)started. A requested update starts its
+    /// ready timeout; an updated Supervisor that is started again before it was ready is rolled
+    /// back, and the release it was rolled back from is returned. A rollback is only reported to
+    /// the first Supervisor started after it.
+    pub fn restarting(&mut self) -> Option<String> {
+        let (state, rejected) = match mem::replace(&mut self.0, State::Idle) {
+            State::Requested(update) => {
+                let timeout = Duration::from_secs(update.ready_timeout_secs.into());
+                (State::Starting(update, Instant::now() + timeout), None)
+            }
+            State::Starting(update, _) => {
+                outputln!("Supervisor {} did not become ready; rolling back to {}",
+                          update.updated,
+                          update.previous);
+                let rejected = update.updated.clone();
+                (State::RolledBack(update), Some(rejected))
+            }
+            State::Idle | State::RolledBack(_) => (State::Idle, None),
+        };
+        self.0 = state;
+        rejected
+    }
+
+    /// The fully-qualified ident of the Supervisor release to start instead of the latest
+    /// installed one.
+    pub fn pinned_release(&self) -> Option<&str> {
+        match &self.0 {
+            State::RolledBack(update) => Some(&update.previous),
+            _ => None,
+        }
+    }
+
+    /// The environment that tells a starting Supervisor about the self-update it is started for.
+    pub fn env(&self) -> Vec<(&'static str, &str)> {
+        match &self.0 {
+            State::Starting(update, _) => {
+                vec![(protocol::SUP_UPDATE_PREVIOUS_ENV, &update.previous),
+                     (protocol::SUP_UPDATE_UPDATED_ENV, &update.updated)]
+            }
+            State::RolledBack(update) => {
+                vec![(protocol::SUP_UPDATE_PREVIOUS_ENV, &update.previous),
+                     (protocol::SUP_UPDATE_UPDATED_ENV, &update.updated),
+                     (protocol::SUP_UPDATE_ROLLED_BACK_ENV, "true")]
+            }
+            State::Idle | State::Requested(_) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn update(ready_timeout_secs: u32) -> protocol::SupUpdate {
+        protocol::SupUpdate { previous: "biome/bio-sup/1.6.0/20200101000000".to_string(),
+                              updated: "biome/bio-sup/1.6.1/20200201000000".to_string(),
+                              ready_timeout_secs }
+    }
+
+    #[test]
+    fn an_update_that_becomes_ready_is_kept() {
+        let mut self_update = SelfUpdate::default();
+        self_update.requested(update(60));
+        assert!(!self_update.is_starting());
+        assert!(self_update.env().is_empty());
+
+        assert_eq!(self_update.restarting(), None);
+        assert!(self_update.is_starting());
+        assert!(!self_update.timed_out());
+        assert_eq!(self_update.pinned_release(), None);
+        assert_eq!(self_update.env(),
+                   vec![(protocol::SUP_UPDATE_PREVIOUS_ENV, "biome/bio-sup/1.6.0/20200101000000"),
+                        (protocol::SUP_UPDATE_UPDATED_ENV, "biome/bio-sup/1.6.1/20200201000000"),]);
+
+        self_update.ready();
+        assert!(!self_update.is_starting());
+        assert!(self_update.env().is_empty());
+        assert_eq!(self_update.restarting(), None);
+        assert_eq!(self_update.pinned_release(), None);
+    }
+
+    #[test]
+    fn an_update_that_restarts_before_it_is_ready_is_rolled_back() {
+        let mut self_update = SelfUpdate::default();
+        self_update.requested(update(60));
+        self_update.restarting();
+
+        assert_eq!(self_update.restarting().as_deref(),
+                   Some("biome/bio-sup/1.6.1/20200201000000"));
+        assert!(!self_update.is_starting());
+        assert!(!self_update.timed_out());
+        assert_eq!(self_update.pinned_release(),
+                   Some("biome/bio-sup/1.6.0/20200101000000"));
+        assert_eq!(self_update.env(),
+                   vec![(protocol::SUP_UPDATE_PREVIOUS_ENV, "biome/bio-sup/1.6.0/20200101000000"),
+                        (protocol::SUP_UPDATE_UPDATED_ENV, "biome/bio-sup/1.6.1/20200201000000"),
+                        (protocol::SUP_UPDATE_ROLLED_BACK_ENV, "true"),]);
+    }
+
+    #[test]
+    fn a_rollback_is_only_reported_once() {
+        let mut self_update = SelfUpdate::default();
+        self_update.requested(update(60));
+        self_update.restarting();
+        self_update.restarting();
+
+        assert_eq!(self_update.restarting(), None);
+        assert_eq!(self_update.pinned_release(), None);
+        assert!(self_update.env().is_empty());
+
+        self_update.requested(update(60));
+        self_update.restarting();
+        self_update.restarting();
+        self_update.ready();
+        assert_eq!(self_update.pinned_release(), None);
+        assert!(self_update.env().is_empty());
+    }
+
+    #[test]
+    fn an_update_times_out_once_its_deadline_passes() {
+        let mut self_update = SelfUpdate::default();
+        self_update.requested(update(0));
+        assert!(!self_update.timed_out());
+
+        self_update.restarting();
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(self_update.is_starting());
+        assert!(self_update.timed_out());
+    }
+}
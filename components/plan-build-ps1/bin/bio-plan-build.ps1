@@ -1231,7 +1231,7 @@ function Invoke-DefaultInstall {
 }
 
 # Copy the `./config` directory, relative to the Plan, to `$pkg_prefix\config`.
-# Do the same with `default.toml`. Delegates most of the implementation to the
+# Do the same with `default.toml` and `schema.toml`. Delegates most of the implementation to the
 # `Invoke-DefaultBuildConfig` function.
 function Invoke-BuildConfig {
     Invoke-DefaultBuildConfig
@@ -1266,6 +1266,10 @@ function Invoke-DefaultBuildConfig {
         Write-BuildLine "Writing default.toml"
         Copy-Item "$PLAN_CONTEXT/default.toml" $pkg_prefix
     }
+    if (Test-Path "$PLAN_CONTEXT/schema.toml") {
+        Write-BuildLine "Writing schema.toml"
+        Copy-Item "$PLAN_CONTEXT/schema.toml" $pkg_prefix
+    }
 }
 
 # Write out the `$pkg_prefix\run` file. If a file named `hooks\run`
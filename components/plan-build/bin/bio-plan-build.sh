@@ -198,7 +198,9 @@
 # you expect the service to export (by their `pkg_exports`). These binds *must* be set for the
 # Supervisor to load the service. The loaded service will wait to run until its bind becomes
 # available. If the bind does not contain the expected keys, the service will not start
-# successfully.
+# successfully. A key suffixed with `?` is optional and may be given a default (`user?=admin`),
+# while `pkg:<origin>/<name>` and `version:<constraint>` restrict which packages the bound service
+# may run.
 # ```
 # pkg_binds=(
 #   [database]="port host"
@@ -1989,7 +1991,7 @@ _build_metadata() {
 }
 
 # Copy the `./config` directory, relative to the Plan, to `$pkg_prefix/config`.
-# Do the same with `default.toml`. Delegates most of the implementation to the
+# Do the same with `default.toml` and `schema.toml`. Delegates most of the implementation to the
 # `do_default_build_config()` function.
 do_build_config() {
   do_default_build_config
@@ -2022,6 +2024,9 @@ do_default_build_config() {
   if [[ -f "$PLAN_CONTEXT/default.toml" ]]; then
     cp "$PLAN_CONTEXT/default.toml" "$pkg_prefix"
   fi
+  if [[ -f "$PLAN_CONTEXT/schema.toml" ]]; then
+    cp "$PLAN_CONTEXT/schema.toml" "$pkg_prefix"
+  fi
   return 0
 }
 
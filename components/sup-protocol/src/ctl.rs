@@ -28,6 +28,10 @@ impl message::MessageStatic for SupRestart {
     const MESSAGE_ID: &'static str = "SupRestart";
 }
 
+impl message::MessageStatic for SupUpdateNow {
+    const MESSAGE_ID: &'static str = "SupUpdateNow";
+}
+
 impl message::MessageStatic for SvcFilePut {
     const MESSAGE_ID: &'static str = "SvcFilePut";
 }
@@ -40,6 +44,14 @@ impl message::MessageStatic for SvcValidateCfg {
     const MESSAGE_ID: &'static str = "SvcValidateCfg";
 }
 
+impl message::MessageStatic for SvcCfgDryRun {
+    const MESSAGE_ID: &'static str = "SvcCfgDryRun";
+}
+
+impl message::MessageStatic for SvcGetCfgHistory {
+    const MESSAGE_ID: &'static str = "SvcGetCfgHistory";
+}
+
 impl message::MessageStatic for SvcSetCfg {
     const MESSAGE_ID: &'static str = "SvcSetCfg";
 }
@@ -64,6 +76,18 @@ impl message::MessageStatic for SvcStop {
     const MESSAGE_ID: &'static str = "SvcStop";
 }
 
+impl message::MessageStatic for SvcRollout {
+    const MESSAGE_ID: &'static str = "SvcRollout";
+}
+
+impl message::MessageStatic for SvcUpdateNow {
+    const MESSAGE_ID: &'static str = "SvcUpdateNow";
+}
+
+impl message::MessageStatic for SvcRunJob {
+    const MESSAGE_ID: &'static str = "SvcRunJob";
+}
+
 impl message::MessageStatic for SvcStatus {
     const MESSAGE_ID: &'static str = "SvcStatus";
 }
@@ -12,7 +12,8 @@ use crate::{core::{self,
             net::{self,
                   ErrCode,
                   NetErr}};
-use std::{fmt::{self,
+use std::{convert::TryFrom,
+          fmt::{self,
                 Write},
           str::FromStr};
 
@@ -30,12 +31,24 @@ impl message::MessageStatic for ServiceBind {
 impl message::MessageStatic for ServiceCfg {
     const MESSAGE_ID: &'static str = "ServiceCfg";
 }
+impl message::MessageStatic for ServiceCfgKey {
+    const MESSAGE_ID: &'static str = "ServiceCfgKey";
+}
+impl message::MessageStatic for ServiceCfgHistoryEntry {
+    const MESSAGE_ID: &'static str = "ServiceCfgHistoryEntry";
+}
+impl message::MessageStatic for ServiceCfgDryRun {
+    const MESSAGE_ID: &'static str = "ServiceCfgDryRun";
+}
 impl message::MessageStatic for ServiceGroup {
     const MESSAGE_ID: &'static str = "ServiceGroup";
 }
 impl message::MessageStatic for ServiceStatus {
     const MESSAGE_ID: &'static str = "ServiceStatus";
 }
+impl message::MessageStatic for UpdateRollout {
+    const MESSAGE_ID: &'static str = "UpdateRollout";
+}
 impl message::MessageStatic for HealthCheckInterval {
     const MESSAGE_ID: &'static str = "HealthCheckInterval";
 }
@@ -306,6 +319,9 @@ impl UpdateStrategy {
             UpdateStrategy::None => "none",
             UpdateStrategy::AtOnce => "at-once",
             UpdateStrategy::Rolling => "rolling",
+            UpdateStrategy::Canary => "canary",
+            UpdateStrategy::Batched => "batched",
+            UpdateStrategy::Notify => "notify",
         }
     }
 }
@@ -318,6 +334,9 @@ impl FromStr for UpdateStrategy {
             "none" => Ok(UpdateStrategy::None),
             "at-once" => Ok(UpdateStrategy::AtOnce),
             "rolling" => Ok(UpdateStrategy::Rolling),
+            "canary" => Ok(UpdateStrategy::Canary),
+            "batched" => Ok(UpdateStrategy::Batched),
+            "notify" => Ok(UpdateStrategy::Notify),
             _ => Err(net::err(ErrCode::InvalidPayload, "Invalid update strategy.")),
         }
     }
@@ -327,6 +346,55 @@ impl fmt::Display for UpdateStrategy {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}", self.as_str()) }
 }
 
+impl UpdateStrategy {
+    /// Whether the strategy updates the members of a service group in turn, coordinated by an
+    /// update leader.
+    pub fn is_rolling(self) -> bool {
+        matches!(self,
+                 UpdateStrategy::Rolling | UpdateStrategy::Canary | UpdateStrategy::Batched)
+    }
+}
+
+impl RolloutAction {
+    pub const VARIANTS: &'static [&'static str] = &["pause", "resume", "approve", "abort"];
+
+    pub fn as_str(&self) -> &str {
+        match *self {
+            RolloutAction::Pause => "pause",
+            RolloutAction::Resume => "resume",
+            RolloutAction::Approve => "approve",
+            RolloutAction::Abort => "abort",
+        }
+    }
+}
+
+impl FromStr for RolloutAction {
+    type Err = NetErr;
+
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        match action {
+            "pause" => Ok(RolloutAction::Pause),
+            "resume" => Ok(RolloutAction::Resume),
+            "approve" => Ok(RolloutAction::Approve),
+            "abort" => Ok(RolloutAction::Abort),
+            _ => Err(net::err(ErrCode::InvalidPayload, "Invalid rollout action.")),
+        }
+    }
+}
+
+impl fmt::Display for RolloutAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}", self.as_str()) }
+}
+
+impl fmt::Display for UpdateRollout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strategy = UpdateStrategy::try_from(self.strategy).unwrap_or_default();
+        write!(f,
+               "{} update to {}: wave {}/{}, {}/{} members updated ({})",
+               strategy, self.ident, self.wave, self.waves, self.updated, self.members, self.state)
+    }
+}
+
 impl UpdateCondition {
     pub const VARIANTS: &'static [&'static str] = &["latest", "track-channel"];
 
@@ -444,6 +512,35 @@ mod test {
         assert_eq!("at-once", strategy.to_string())
     }
 
+    #[test]
+    fn update_strategy_canary_and_batched_round_trip() {
+        for strategy_str in &["canary", "batched"] {
+            let strategy = UpdateStrategy::from_str(strategy_str).unwrap();
+
+            assert!(strategy.is_rolling());
+            assert_eq!(*strategy_str, strategy.to_string());
+        }
+        assert!(!UpdateStrategy::AtOnce.is_rolling());
+    }
+
+    #[test]
+    fn update_strategy_notify_round_trip() {
+        let strategy = UpdateStrategy::from_str("notify").unwrap();
+
+        assert_eq!(UpdateStrategy::Notify, strategy);
+        assert_eq!("notify", strategy.to_string());
+        assert!(!strategy.is_rolling());
+    }
+
+    #[test]
+    fn rollout_action_from_str() {
+        for action_str in RolloutAction::VARIANTS {
+            assert_eq!(*action_str,
+                       RolloutAction::from_str(action_str).unwrap().to_string());
+        }
+        assert!(RolloutAction::from_str("dope").is_err());
+    }
+
     #[test]
     fn update_strategy_toml_deserialize() {
         #[derive(Deserialize)]
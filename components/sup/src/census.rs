@@ -6,7 +6,8 @@ use biome_butterfly::{member::{Health,
                         rumor::{election::{Election as ElectionRumor,
                                            ElectionStatus as ElectionStatusRumor,
                                            ElectionUpdate as ElectionUpdateRumor},
-                                service::{Service as ServiceRumor,
+                                service::{RolloutControl,
+                                          Service as ServiceRumor,
                                           SysInfo},
                                 service_config::ServiceConfig as ServiceConfigRumor,
                                 service_file::ServiceFile as ServiceFileRumor,
@@ -305,6 +306,8 @@ pub struct ServiceFile {
 #[derive(Debug, Serialize)]
 pub struct ServiceConfig {
     pub incarnation: u64,
+    /// The member id of the Supervisor that gossiped the configuration
+    pub from_id:     MemberId,
     pub value:       toml::value::Table,
 }
 
@@ -356,6 +359,16 @@ impl CensusGroup {
         }
     }
 
+    /// Returns the member that leads this group for leader-only update hooks and jobs: the
+    /// elected leader, then the update leader, and otherwise the alive member with the lowest
+    /// member ID, so that exactly one member leads a group that holds no elections.
+    pub fn group_leader(&self) -> Option<&CensusMember> {
+        let lowest_alive_member = || self.population.values().find(|cm| cm.alive());
+        self.leader()
+            .or_else(|| self.update_leader())
+            .or_else(lowest_alive_member)
+    }
+
     /// Returns a list of all members in the census ring.
     pub fn members(&self) -> impl Iterator<Item = &CensusMember> { self.population.values() }
 
@@ -369,6 +382,24 @@ impl CensusGroup {
             .filter(|cm| cm.alive() || cm.suspect())
     }
 
+    /// Same as `members`, but only returns members that are alive and
+    /// report that they are ready to serve.
+    pub fn ready_members(&self) -> impl Iterator<Item = &CensusMember> {
+        self.population
+            .values()
+            .filter(|cm| cm.alive() && cm.ready())
+    }
+
+    /// Returns the latest rollout control issued on any active member for the rollout that the
+    /// update leader is currently driving. Controls issued for an earlier rollout are ignored.
+    pub fn rollout_control(&self) -> Option<RolloutControl> {
+        let rollout = self.update_leader()?.pkg_incarnation;
+        self.active_members()
+            .filter_map(|cm| cm.rollout_control)
+            .filter(|control| control.rollout == rollout)
+            .max_by_key(|control| control.sequence)
+    }
+
     /// Return references to all a `CensusGroup`'s `ServiceFiles`.
     pub fn service_files(&self) -> impl IntoIterator<Item = &ServiceFile> {
         self.service_files.values()
@@ -481,9 +512,10 @@ impl CensusGroup {
                 if self.service_config.is_none()
                    || service_config.incarnation > self.service_config.as_ref().unwrap().incarnation
                 {
-                    self.service_config = Some(ServiceConfig { incarnation:
-                                                                   service_config.incarnation,
-                                                               value:       config, });
+                    self.service_config =
+                        Some(ServiceConfig { incarnation: service_config.incarnation,
+                                             from_id:     service_config.from_id.clone(),
+                                             value:       config, });
                 }
             }
             Err(err) => warn!("{}", err),
@@ -595,7 +627,9 @@ pub struct CensusMember {
     pub suspect: bool,
     pub confirmed: bool,
     pub departed: bool,
+    pub ready: bool,
     pub cfg: toml::value::Table,
+    pub rollout_control: Option<RolloutControl>,
 }
 
 impl CensusMember {
@@ -614,6 +648,8 @@ impl CensusMember {
         self.sys = rumor.sys.clone();
         self.cfg =
             toml::from_str(str::from_utf8(&rumor.cfg).unwrap_or_default()).unwrap_or_default();
+        self.rollout_control = rumor.rollout_control;
+        self.ready = rumor.ready;
     }
 
     fn update_from_election_rumor(&mut self, election: &ElectionRumor) -> bool {
@@ -675,6 +711,9 @@ impl CensusMember {
     pub fn confirmed(&self) -> bool { self.confirmed }
 
     pub fn departed(&self) -> bool { self.departed }
+
+    /// Does this member report that its service is ready to serve?
+    pub fn ready(&self) -> bool { self.ready }
 }
 
 /// This data structure just wraps the CensusMember and allows us to tweak the serialization logic.
@@ -684,6 +723,29 @@ pub struct CensusMemberProxy<'a>(Cow<'a, CensusMember>);
 impl<'a> CensusMemberProxy<'a> {
     pub fn new(c: &'a CensusMember) -> Self { CensusMemberProxy(Cow::Borrowed(c)) }
 
+    /// Wraps the member, filling in the given default values for any
+    /// keys it doesn't export. Defaults are read as TOML values,
+    /// falling back to plain strings.
+    pub fn with_defaults<'b, I>(c: &'a CensusMember, defaults: I) -> Self
+        where I: IntoIterator<Item = (&'b str, &'b str)>
+    {
+        let missing = defaults.into_iter()
+                              .filter(|(key, _)| !c.cfg.contains_key(*key))
+                              .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Self::new(c);
+        }
+        let mut member = c.clone();
+        for (key, value) in missing {
+            let value = toml::from_str::<toml::value::Table>(&format!("value = {}", value))
+                            .ok()
+                            .and_then(|mut t| t.remove("value"))
+                            .unwrap_or_else(|| toml::Value::String(value.to_string()));
+            member.cfg.insert(key.to_string(), value);
+        }
+        CensusMemberProxy(Cow::Owned(member))
+    }
+
     #[cfg(test)]
     pub fn new_owned(c: CensusMember) -> Self { CensusMemberProxy(Cow::Owned(c)) }
 
@@ -701,7 +763,7 @@ impl<'a> Serialize for CensusMemberProxy<'a> {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
         where S: Serializer
     {
-        let mut strukt = serializer.serialize_struct("census_member", 24)?;
+        let mut strukt = serializer.serialize_struct("census_member", 25)?;
         strukt.serialize_field("member_id", &self.member_id)?;
         strukt.serialize_field("pkg", &self.pkg)?;
         strukt.serialize_field("pkg_incarnation", &self.pkg_incarnation)?;
@@ -729,6 +791,7 @@ impl<'a> Serialize for CensusMemberProxy<'a> {
         strukt.serialize_field("suspect", &self.suspect)?;
         strukt.serialize_field("confirmed", &self.confirmed)?;
         strukt.serialize_field("departed", &self.departed)?;
+        strukt.serialize_field("ready", &self.ready)?;
         strukt.serialize_field("cfg", &self.cfg)?;
         strukt.end()
     }
@@ -887,7 +950,9 @@ mod tests {
                        suspect: health == Health::Suspect,
                        confirmed: health == Health::Confirmed,
                        departed: health == Health::Departed,
-                       cfg: toml::value::Table::new() }
+                       ready: true,
+                       cfg: toml::value::Table::new(),
+                       rollout_control: None }
     }
 
     #[test]
@@ -913,6 +978,87 @@ mod tests {
         assert!(active_members.next().is_none());
     }
 
+    #[test]
+    fn ready_members_leaves_only_alive_members_that_are_ready() {
+        let mut starting = test_census_member("live-starting", Health::Alive);
+        starting.ready = false;
+        let population = vec![test_census_member("live-ready", Health::Alive),
+                              starting,
+                              test_census_member("suspect-ready", Health::Suspect),];
+
+        let sg: ServiceGroup =
+            "test-service.default".parse()
+                                  .expect("This should be a valid service group");
+
+        let mut census_group = CensusGroup::new(sg, "live-ready");
+        for member in population {
+            census_group.population
+                        .insert(member.member_id.clone(), member);
+        }
+
+        let ready: Vec<_> = census_group.ready_members()
+                                        .map(|cm| cm.member_id.as_str())
+                                        .collect();
+        assert_eq!(ready, vec!["live-ready"]);
+    }
+
+    #[test]
+    fn group_leader_falls_back_to_the_lowest_alive_member() {
+        let population = vec![test_census_member("member-c", Health::Alive),
+                              test_census_member("member-a", Health::Departed),
+                              test_census_member("member-b", Health::Alive),];
+
+        let sg: ServiceGroup =
+            "test-service.default".parse()
+                                  .expect("This should be a valid service group");
+
+        let mut census_group = CensusGroup::new(sg, "member-c");
+        for member in population {
+            census_group.population
+                        .insert(member.member_id.clone(), member);
+        }
+        assert_eq_member_ids(census_group.group_leader(), Some("member-b"));
+
+        census_group.update_leader_id = Some("member-c".to_string());
+        assert_eq_member_ids(census_group.group_leader(), Some("member-c"));
+
+        census_group.leader_id = Some("member-a".to_string());
+        assert_eq_member_ids(census_group.group_leader(), Some("member-a"));
+    }
+
+    #[test]
+    fn rollout_control_is_the_latest_for_the_current_rollout() {
+        let control = |rollout, sequence| {
+            Some(RolloutControl { rollout,
+                                  sequence,
+                                  paused: sequence % 2 == 1,
+                                  ..Default::default() })
+        };
+        let mut leader = test_census_member("leader", Health::Alive);
+        leader.pkg_incarnation = 2;
+        let mut stale = test_census_member("stale", Health::Alive);
+        stale.rollout_control = control(1, 9);
+        let mut latest = test_census_member("latest", Health::Alive);
+        latest.rollout_control = control(2, 3);
+        let mut earlier = test_census_member("earlier", Health::Alive);
+        earlier.rollout_control = control(2, 2);
+        let mut departed = test_census_member("departed", Health::Departed);
+        departed.rollout_control = control(2, 5);
+
+        let sg: ServiceGroup =
+            "test-service.default".parse()
+                                  .expect("This should be a valid service group");
+        let mut census_group = CensusGroup::new(sg, "leader");
+        assert_eq!(census_group.rollout_control(), None);
+        for member in vec![leader, stale, latest, earlier, departed] {
+            census_group.population
+                        .insert(member.member_id.clone(), member);
+        }
+        census_group.update_leader_id = Some("leader".to_string());
+
+        assert_eq!(census_group.rollout_control(), control(2, 3));
+    }
+
     fn assert_eq_member_ids(cm: Option<&CensusMember>, id: Option<&str>) {
         assert_eq!(cm.map(|cm| cm.member_id.as_str()), id);
     }
@@ -988,4 +1134,25 @@ mod tests {
         assert_eq_member_ids(CensusGroup::previous_peer_impl(members.iter(), &me),
                              Some("left_of_me_with_wrapping"));
     }
+
+    #[test]
+    fn census_member_proxy_fills_in_missing_exports() {
+        let mut member = test_census_member("live-one", Health::Alive);
+        member.cfg
+              .insert("port".to_string(), toml::Value::Integer(5432));
+        let defaults = vec![("port", "6543"),
+                            ("user", "admin"),
+                            ("pool", "10"),
+                            ("sslmode", "\"require\"")];
+
+        let proxy = CensusMemberProxy::with_defaults(&member, defaults);
+
+        assert_eq!(Some(&toml::Value::Integer(5432)), proxy.cfg.get("port"));
+        assert_eq!(Some(&toml::Value::String("admin".to_string())),
+                   proxy.cfg.get("user"));
+        assert_eq!(Some(&toml::Value::Integer(10)), proxy.cfg.get("pool"));
+        assert_eq!(Some(&toml::Value::String("require".to_string())),
+                   proxy.cfg.get("sslmode"));
+        assert!(!member.cfg.contains_key("user"));
+    }
 }
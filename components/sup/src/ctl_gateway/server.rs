@@ -311,9 +311,15 @@ impl SrvHandler {
                                           -> std::result::Result<CtlCommand, HandlerError> {
         match msg.message_id() {
             "SvcGetDefaultCfg" => util::to_command(msg, ctl_sender, commands::service_cfg_msr),
+            "SvcGetCfgHistory" => {
+                util::to_command(msg, ctl_sender, commands::service_cfg_history_msr)
+            }
             "SvcFilePut" => util::to_command(msg, ctl_sender, commands::service_file_put),
             "SvcSetCfg" => util::to_command(msg, ctl_sender, commands::service_cfg_set),
-            "SvcValidateCfg" => util::to_command(msg, ctl_sender, commands::service_cfg_validate),
+            "SvcValidateCfg" => {
+                util::to_command(msg, ctl_sender, commands::service_cfg_validate_msr)
+            }
+            "SvcCfgDryRun" => util::to_command(msg, ctl_sender, commands::service_cfg_dry_run_msr),
             "SvcLoad" => {
                 // This arm doesn't use a `util` module helper because
                 // it's currently the only thing that behaves like
@@ -335,13 +341,23 @@ impl SrvHandler {
                                        })
                                    }))
             }
-            "SvcUpdate" => util::to_supervisor_command(msg, ctl_sender, commands::service_update),
+            "SvcUpdate" => {
+                util::to_supervisor_command(msg, ctl_sender, commands::service_update_msr)
+            }
             "SvcUnload" => util::to_supervisor_command(msg, ctl_sender, commands::service_unload),
             "SvcStart" => util::to_command(msg, ctl_sender, commands::service_start),
             "SvcStop" => util::to_supervisor_command(msg, ctl_sender, commands::service_stop),
+            "SvcRollout" => util::to_supervisor_command(msg, ctl_sender, commands::service_rollout),
+            "SvcUpdateNow" => {
+                util::to_supervisor_command(msg, ctl_sender, commands::service_update_now)
+            }
+            "SvcRunJob" => util::to_supervisor_command(msg, ctl_sender, commands::service_run_job),
             "SvcStatus" => util::to_command(msg, ctl_sender, commands::service_status_gsr),
             "SupDepart" => util::to_command(msg, ctl_sender, commands::supervisor_depart),
             "SupRestart" => util::to_command(msg, ctl_sender, commands::supervisor_restart),
+            "SupUpdateNow" => {
+                util::to_supervisor_command(msg, ctl_sender, commands::supervisor_update_now)
+            }
             _ => {
                 warn!("Unhandled message, {}", msg.message_id());
                 Err(HandlerError::from(io::Error::from(io::ErrorKind::InvalidData)))
@@ -48,6 +48,7 @@ pub enum Error {
     Bio(bio::error::Error),
     BiomeCommon(biome_common::Error),
     BiomeCore(biome_core::Error),
+    IncompatibleBinds(Vec<String>),
     InvalidBinds(Vec<String>),
     InvalidCertFile(PathBuf),
     InvalidHealthCheckResult(i32),
@@ -173,6 +174,9 @@ impl fmt::Display for Error {
             Error::FileNotFound(ref e) => format!("File not found at: {}", e),
             Error::FileWatcherFileIsRoot => "Watched file is root".to_string(),
             Error::GroupNotFound(ref e) => format!("No GID for group '{}' could be found", e),
+            Error::IncompatibleBinds(ref e) => {
+                format!("Bind(s) to incompatible service groups, {}", e.join(", "))
+            }
             Error::InvalidBinds(ref e) => format!("Invalid bind(s), {}", e.join(", ")),
             Error::InvalidCertFile(ref path) => format!("Invalid cert file: {}", path.display()),
             Error::InvalidHealthCheckResult(code) => {
@@ -306,7 +310,9 @@ impl From<biome_api_client::Error> for Error {
 impl From<Error> for biome_sup_protocol::net::NetErr {
     fn from(err: Error) -> biome_sup_protocol::net::NetErr {
         match err {
-            Error::MissingRequiredBind(_) | Error::InvalidBinds(_) => {
+            Error::MissingRequiredBind(_)
+            | Error::InvalidBinds(_)
+            | Error::IncompatibleBinds(_) => {
                 biome_sup_protocol::net::err(biome_sup_protocol::net::ErrCode::InvalidPayload,
                                                err)
             }
@@ -21,7 +21,11 @@ use self::types::{EventMessage,
                   HealthCheckEvent,
                   ServiceStartedEvent,
                   ServiceStoppedEvent,
-                  ServiceUpdateStartedEvent};
+                  ServiceUpdateRolledBackEvent,
+                  ServiceUpdateStartedEvent,
+                  SupervisorUpdateRolledBackEvent,
+                  SupervisorUpdatedEvent,
+                  TemplateRenderFailedEvent};
 use crate::manager::{service::{HealthCheckHookStatus,
                                HealthCheckResult,
                                ProcessOutput,
@@ -59,8 +63,16 @@ lazy_static! {
         "habitat.event.service_stopped".parse().expect("valid NATS subject");
     static ref SERVICE_UPDATE_STARTED_SUBJECT: Subject =
         "habitat.event.service_update_started".parse().expect("valid NATS subject");
+    static ref SERVICE_UPDATE_ROLLED_BACK_SUBJECT: Subject =
+        "habitat.event.service_update_rolled_back".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_UPDATED_SUBJECT: Subject =
+        "habitat.event.supervisor_updated".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_UPDATE_ROLLED_BACK_SUBJECT: Subject =
+        "habitat.event.supervisor_update_rolled_back".parse().expect("valid NATS subject");
     static ref HEALTHCHECK_SUBJECT: Subject =
         "habitat.event.healthcheck".parse().expect("valid NATS subject");
+    static ref TEMPLATE_RENDER_FAILED_SUBJECT: Subject =
+        "habitat.event.template_render_failed".parse().expect("valid NATS subject");
 
     /// Reference to the event stream.
     static ref NATS_MESSAGE_STREAM: InitCell<NatsMessageStream> = InitCell::new();
@@ -130,6 +142,55 @@ pub fn service_update_started(service: &Service, update: &PackageIdent) {
     }
 }
 
+/// Send an event when a Service update is rolled back because the Service failed within its
+/// rollback window.
+pub fn service_update_rolled_back(service: &Service,
+                                  failed: &PackageIdent,
+                                  rollback: &PackageIdent,
+                                  reason: &str) {
+    if initialized() {
+        publish(&SERVICE_UPDATE_ROLLED_BACK_SUBJECT,
+                ServiceUpdateRolledBackEvent { event_metadata:         None,
+                                               service_metadata:
+                                                   Some(service.to_service_metadata()),
+                                               failed_package_ident:   failed.to_string(),
+                                               rollback_package_ident: rollback.to_string(),
+                                               reason:                 reason.to_string(), });
+    }
+}
+
+/// Send an event when the Supervisor was started for an update and became ready.
+pub fn supervisor_updated(previous: &str, updated: &str) {
+    if initialized() {
+        publish(&SUPERVISOR_UPDATED_SUBJECT,
+                SupervisorUpdatedEvent { event_metadata:         None,
+                                         previous_package_ident: previous.to_string(),
+                                         updated_package_ident:  updated.to_string(), });
+    }
+}
+
+/// Send an event when the Launcher rolled back an update of the Supervisor because the updated
+/// Supervisor did not become ready.
+pub fn supervisor_update_rolled_back(failed: &str, rollback: &str) {
+    if initialized() {
+        publish(&SUPERVISOR_UPDATE_ROLLED_BACK_SUBJECT,
+                SupervisorUpdateRolledBackEvent { event_metadata:         None,
+                                                  failed_package_ident:   failed.to_string(),
+                                                  rollback_package_ident: rollback.to_string(), });
+    }
+}
+
+/// Send an event when the templates of a Service fail to render, blocking its reconfiguration
+/// or restart.
+pub fn template_render_failed(service: &Service, error: &str) {
+    if initialized() {
+        publish(&TEMPLATE_RENDER_FAILED_SUBJECT,
+                TemplateRenderFailedEvent { service_metadata: Some(service.to_service_metadata()),
+                                            event_metadata:   None,
+                                            error:            error.to_string(), });
+    }
+}
+
 // Takes metadata directly, rather than a `&Service` like other event
 // functions, because of how the asynchronous health checking
 // currently works. Revisit when async/await + Pin is all stabilized.
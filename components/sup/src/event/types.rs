@@ -51,6 +51,9 @@ impl Service {
             }
             DomainUpdateStrategy::AtOnce => UpdateStrategy::AtOnce,
             DomainUpdateStrategy::Rolling => UpdateStrategy::Rolling,
+            DomainUpdateStrategy::Canary => UpdateStrategy::Canary,
+            DomainUpdateStrategy::Batched => UpdateStrategy::Batched,
+            DomainUpdateStrategy::Notify => UpdateStrategy::Notify,
         };
 
         Some(UpdateConfig { strategy: strategy.into(),
@@ -104,4 +107,8 @@ macro_rules! event_msg_impl {
 event_msg_impl!(ServiceStartedEvent);
 event_msg_impl!(ServiceStoppedEvent);
 event_msg_impl!(ServiceUpdateStartedEvent);
+event_msg_impl!(ServiceUpdateRolledBackEvent);
+event_msg_impl!(SupervisorUpdatedEvent);
+event_msg_impl!(SupervisorUpdateRolledBackEvent);
 event_msg_impl!(HealthCheckEvent);
+event_msg_impl!(TemplateRenderFailedEvent);
@@ -1,6 +1,8 @@
 use crate::manager::{self,
                      service::{HealthCheckHook,
-                               HealthCheckResult}};
+                               HealthCheckResult,
+                               ServiceQueryModel,
+                               UnixTimestamp}};
 use actix_rt::System;
 use actix_web::{body::BoxBody,
                 dev::{Service,
@@ -89,6 +91,19 @@ struct HealthCheckBody {
     stderr: String,
 }
 
+/// The update status of a service, as reported by `/services/updates`.
+#[derive(Serialize)]
+pub(crate) struct ServiceUpdateBody {
+    service_group:    String,
+    update_strategy:  String,
+    current:          String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latest:           Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_checked:     Option<UnixTimestamp>,
+    update_available: bool,
+}
+
 #[allow(clippy::from_over_into)]
 impl Into<StatusCode> for HealthCheckResult {
     fn into(self) -> StatusCode {
@@ -227,6 +242,7 @@ impl Services {
     // Route registration
     pub fn register(cfg: &mut ServiceConfig) {
         cfg.route("/services", web::get().to(services_gsr))
+           .route("/services/updates", web::get().to(service_updates_gsr))
            .route("/services/{svc}/{group}",
                   web::get().to(service_without_org_gsr))
            .route("/services/{svc}/{group}/config",
@@ -262,6 +278,15 @@ impl Census {
     }
 }
 
+struct Packages {}
+
+impl Packages {
+    // Route registration
+    pub fn register(cfg: &mut ServiceConfig) {
+        cfg.route("/packages/audit", web::get().to(package_audit_gsr));
+    }
+}
+
 pub struct Server;
 
 impl Server {
@@ -295,6 +320,7 @@ impl Server {
                               .configure(Services::register)
                               .configure(Butterfly::register)
                               .configure(Census::register)
+                              .configure(Packages::register)
                               .service(web::resource("/metrics").route(web::get().to(metrics)))
                              }).workers(thread_count);
 
@@ -351,6 +377,13 @@ async fn census_gsr(state: Data<AppState>) -> HttpResponse {
     json_response(data)
 }
 
+/// # Locking (see locking.md)
+/// * `GatewayState::inner` (read)
+#[allow(clippy::needless_pass_by_value)]
+async fn package_audit_gsr(state: Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok().json(state.gateway_state.lock_gsr().package_audit_data())
+}
+
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
 #[allow(clippy::needless_pass_by_value)]
@@ -358,6 +391,29 @@ async fn services_gsr(state: Data<AppState>) -> HttpResponse {
     HttpResponse::Ok().json(state.gateway_state.lock_gsr().services_data())
 }
 
+/// # Locking (see locking.md)
+/// * `GatewayState::inner` (read)
+#[allow(clippy::needless_pass_by_value)]
+async fn service_updates_gsr(state: Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok().json(service_updates(state.gateway_state.lock_gsr().services_data()))
+}
+
+/// The update status of each of `services`.
+pub(crate) fn service_updates(services: &[ServiceQueryModel]) -> Vec<ServiceUpdateBody> {
+    services.iter()
+            .map(|service| {
+                ServiceUpdateBody { service_group:    service.service_group.to_string(),
+                                    update_strategy:  service.update_strategy.to_string(),
+                                    current:          service.pkg.ident.to_string(),
+                                    latest:           service.latest_in_channel
+                                                             .as_ref()
+                                                             .map(ToString::to_string),
+                                    last_checked:     service.last_update_check.clone(),
+                                    update_available: service.available_update.is_some(), }
+            })
+            .collect()
+}
+
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
 // Honestly, this doesn't feel great, but it's the pattern builder-api uses, and at the
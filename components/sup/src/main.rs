@@ -285,6 +285,7 @@ async fn split_apart_sup_run(sup_run: SupRun,
     let cfg =
         ManagerConfig { auto_update: sup_run.auto_update,
                         auto_update_period: sup_run.auto_update_period.into(),
+                        auto_update_window: sup_run.auto_update_window,
                         service_update_period: sup_run.service_update_period.into(),
                         service_restart_config:
                             ServiceRestartConfig::new(sup_run.service_min_backoff_period.into(),
@@ -296,6 +297,7 @@ async fn split_apart_sup_run(sup_run: SupRun,
                         update_url: bldr_url.clone(),
                         update_channel: shared_load.channel.clone(),
                         http_disable: sup_run.http_disable,
+                        dns_listen: sup_run.listen_dns,
                         organization: sup_run.organization,
                         gossip_permanent: sup_run.permanent_peer,
                         ring_key,
@@ -319,6 +321,7 @@ async fn split_apart_sup_run(sup_run: SupRun,
                         feature_flags,
                         event_stream_config,
                         keep_latest_packages: sup_run.keep_latest_packages,
+                        package_audit_period: sup_run.package_audit_period.map(Into::into),
                         sys_ip: sup_run.sys_ip_address
                                        .or_else(|| {
                                            let result_ip = biome_core::util::sys::ip();
@@ -332,7 +335,22 @@ async fn split_apart_sup_run(sup_run: SupRun,
     info!("Using sys IP address {}", cfg.sys_ip);
 
     // Do we have an initial service to start?
-    let maybe_svc_load_msg = if let Some(install_source) = sup_run.pkg_ident_or_artifact {
+    let maybe_svc_load_msg = if let Some(bundle_path) = sup_run.bundle {
+        // Install everything from the bundle up front so the service can be loaded without
+        // contacting Builder. As with an archive, this locks the service to the bundled release.
+        let idents = sup_run.pkg_ident_or_artifact
+                            .iter()
+                            .map(|source| source.as_ref().clone())
+                            .collect::<Vec<_>>();
+        let mut installs =
+            util::pkg::install_from_bundle(&mut ui::ui(), &bundle_path, &idents).await?;
+        if installs.len() == 1 {
+            let ident = installs.remove(0).ident;
+            Some(svc::shared_load_cli_to_ctl(ident, shared_load, true)?)
+        } else {
+            None
+        }
+    } else if let Some(install_source) = sup_run.pkg_ident_or_artifact {
         let ident = match install_source {
             source @ InstallSource::Archive(_) => {
                 // Install the archive manually then explicitly set the pkg ident to the version
@@ -728,6 +746,7 @@ gpoVMSncu2jMIDZX63IkQII=
             let config = config_from_cmd_str("bio-sup run");
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_window:         None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -742,6 +761,7 @@ gpoVMSncu2jMIDZX63IkQII=
                                        ctl_client_ca_certificates: None,
                                        http_listen:                HttpListenAddr::default(),
                                        http_disable:               false,
+                                       dns_listen:                 None,
                                        gossip_peers:               vec![],
                                        gossip_permanent:           false,
                                        ring_key:                   None,
@@ -751,6 +771,7 @@ gpoVMSncu2jMIDZX63IkQII=
                                        feature_flags:              FeatureFlag::empty(),
                                        event_stream_config:        None,
                                        keep_latest_packages:       None,
+                                       package_audit_period:       None,
                                        sys_ip:
                                            biome_core::util::sys::ip().unwrap(), },
                        config);
@@ -787,9 +808,9 @@ gpoVMSncu2jMIDZX63IkQII=
 
             let args = format!("bio-sup run --listen-gossip=1.2.3.4:4321 \
                                 --listen-http=5.5.5.5:11111 --http-disable \
-                                --listen-ctl=7.8.9.1:12 --org=MY_ORG --peer 1.1.1.1:1111 \
-                                2.2.2.2:2222 3.3.3.3 --permanent-peer --ring tester \
-                                --cache-key-path={} --auto-update --auto-update-period 90 \
+                                --listen-dns=127.0.0.1:5353 --listen-ctl=7.8.9.1:12 --org=MY_ORG \
+                                --peer 1.1.1.1:1111 2.2.2.2:2222 3.3.3.3 --permanent-peer --ring \
+                                tester --cache-key-path={} --auto-update --auto-update-period 90 \
                                 --service-update-period 30 --key={} --certs={} --ca-certs {} \
                                 --keep-latest-packages=5 --sys-ip-address 7.8.9.0",
                                temp_dir_str, key_path_str, cert_path_str, ca_cert_path_str);
@@ -802,6 +823,7 @@ gpoVMSncu2jMIDZX63IkQII=
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update: true,
                                        auto_update_period: Duration::from_secs(90),
+                                       auto_update_window: None,
                                        service_update_period: Duration::from_secs(30),
                                        service_restart_config: ServiceRestartConfig::default(),
                                        custom_state_path: None,
@@ -818,6 +840,7 @@ gpoVMSncu2jMIDZX63IkQII=
                                        http_listen:
                                            HttpListenAddr::from_str("5.5.5.5:11111").unwrap(),
                                        http_disable: true,
+                                       dns_listen: Some("127.0.0.1:5353".parse().unwrap()),
                                        gossip_peers,
                                        gossip_permanent: true,
                                        ring_key: Some(ring_key),
@@ -830,6 +853,7 @@ gpoVMSncu2jMIDZX63IkQII=
                                        feature_flags: FeatureFlag::empty(),
                                        event_stream_config: None,
                                        keep_latest_packages: Some(5),
+                                       package_audit_period: None,
                                        sys_ip: "7.8.9.0".parse().unwrap() },
                        config);
         }
@@ -844,6 +868,7 @@ gpoVMSncu2jMIDZX63IkQII=
             let config = config_from_cmd_str(args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_window:         None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -859,6 +884,7 @@ gpoVMSncu2jMIDZX63IkQII=
                                        ctl_client_ca_certificates: None,
                                        http_listen:                HttpListenAddr::default(),
                                        http_disable:               false,
+                                       dns_listen:                 None,
                                        gossip_peers:               vec![],
                                        gossip_permanent:           false,
                                        ring_key:                   None,
@@ -868,6 +894,7 @@ gpoVMSncu2jMIDZX63IkQII=
                                        feature_flags:              FeatureFlag::empty(),
                                        event_stream_config:        None,
                                        keep_latest_packages:       None,
+                                       package_audit_period:       None,
                                        sys_ip:
                                            biome_core::util::sys::ip().unwrap(), },
                        config);
@@ -883,6 +910,7 @@ gpoVMSncu2jMIDZX63IkQII=
             let config = config_from_cmd_str(args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_window:         None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -897,6 +925,7 @@ gpoVMSncu2jMIDZX63IkQII=
                                        ctl_client_ca_certificates: None,
                                        http_listen:                HttpListenAddr::default(),
                                        http_disable:               false,
+                                       dns_listen:                 None,
                                        gossip_peers:               vec![],
                                        gossip_permanent:           false,
                                        ring_key:                   None,
@@ -906,6 +935,7 @@ gpoVMSncu2jMIDZX63IkQII=
                                        feature_flags:              FeatureFlag::empty(),
                                        event_stream_config:        None,
                                        keep_latest_packages:       None,
+                                       package_audit_period:       None,
                                        sys_ip:
                                            biome_core::util::sys::ip().unwrap(), },
                        config);
@@ -956,6 +986,7 @@ gpoVMSncu2jMIDZX63IkQII=
                 ManagerConfig {
                     auto_update: false,
                     auto_update_period: Duration::from_secs(60),
+                    auto_update_window: None,
                     service_update_period: Duration::from_secs(60),
                     service_restart_config: ServiceRestartConfig::default(),
                     custom_state_path: None,
@@ -969,6 +1000,7 @@ gpoVMSncu2jMIDZX63IkQII=
                     ctl_client_ca_certificates: None,
                     http_listen: HttpListenAddr::default(),
                     http_disable: false,
+                    dns_listen: None,
                     gossip_peers: vec![],
                     gossip_permanent: false,
                     ring_key: None,
@@ -987,6 +1019,7 @@ gpoVMSncu2jMIDZX63IkQII=
                         server_certificate: Some(certificate_path_str.parse().unwrap()),
                     }),
                     keep_latest_packages: None,
+                    package_audit_period: None,
                     sys_ip: biome_core::util::sys::ip().unwrap(),
                 },
                 config,
@@ -1002,7 +1035,10 @@ gpoVMSncu2jMIDZX63IkQII=
                                 two:service2.default --binding-mode relaxed --url http://my_url.com \
                                 --config-from={} --group MyGroup --topology leader \
                                 --strategy rolling --update-condition track-channel --health-check-interval 17 \
-                                --shutdown-timeout=12 core/redis",
+                                --shutdown-timeout=12 --drain-timeout=20 --strict-templates --rollback-window=300 \
+                                --canary-count=2 --update-batch-size=25% --max-unavailable=1 --post-update-on-leader \
+                                --job=once --job-timeout=600 --job-concurrency=replace --job-on-leader \
+                                core/redis",
                                temp_dir_str);
 
             let mut binds = ServiceBindList::default();
@@ -1037,7 +1073,20 @@ gpoVMSncu2jMIDZX63IkQII=
                                                      Some(health_check_interval),
                                                  shutdown_timeout:       Some(12),
                                                  update_condition:
-                                                     Some(UpdateCondition::TrackChannel.into()), },
+                                                     Some(UpdateCondition::TrackChannel.into()),
+                                                 strict_templates:       Some(true),
+                                                 rollback_window:        Some(300),
+                                                 canary_count:           Some(2),
+                                                 update_batch_size:      Some("25%".to_string()),
+                                                 max_unavailable:        Some(1),
+                                                 update_window:          None,
+                                                 post_update_on_leader:  Some(true),
+                                                 drain_timeout:          Some(20),
+                                                 job:                    Some(String::from("once")),
+                                                 job_timeout:            Some(600),
+                                                 job_concurrency:
+                                                     Some(String::from("replace")),
+                                                 job_on_leader:          Some(true), },
                        service_load);
         }
 
@@ -1102,6 +1151,7 @@ gpoVMSncu2jMIDZX63IkQII=
 listen_gossip = "1.2.3.4:4321"
 listen_http = "5.5.5.5:11111"
 http_disable = true
+listen_dns = "127.0.0.1:5353"
 listen_ctl = "7.8.9.1:12"
 organization = "MY_ORG"
 peer = ["1.1.1.1:1111", "2.2.2.2:2222", "3.3.3.3:9638"]
@@ -1110,6 +1160,7 @@ ring = "tester"
 cache_key_path = "{}"
 auto_update = true
 auto_update_period = 3600
+auto_update_window = "0 3 * * SUN"
 service_update_period = 1_000
 key_file = "{}"
 cert_file = "{}"
@@ -1137,6 +1188,7 @@ sys_ip_address = "7.8.9.0"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update: true,
                                        auto_update_period: Duration::from_secs(3600),
+                                       auto_update_window: Some("0 3 * * SUN".parse().unwrap()),
                                        service_update_period: Duration::from_secs(1_000),
                                        service_restart_config: ServiceRestartConfig::default(),
                                        custom_state_path: None,
@@ -1153,6 +1205,7 @@ sys_ip_address = "7.8.9.0"
                                        http_listen:
                                            HttpListenAddr::from_str("5.5.5.5:11111").unwrap(),
                                        http_disable: true,
+                                       dns_listen: Some("127.0.0.1:5353".parse().unwrap()),
                                        gossip_peers,
                                        gossip_permanent: true,
                                        ring_key: Some(ring_key),
@@ -1165,6 +1218,7 @@ sys_ip_address = "7.8.9.0"
                                        feature_flags: FeatureFlag::empty(),
                                        event_stream_config: None,
                                        keep_latest_packages: Some(5),
+                                       package_audit_period: None,
                                        sys_ip: "7.8.9.0".parse().unwrap() },
                        config);
         }
@@ -1188,6 +1242,7 @@ sys_ip_address = "7.8.9.0"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_window:         None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -1203,6 +1258,7 @@ sys_ip_address = "7.8.9.0"
                                        ctl_client_ca_certificates: None,
                                        http_listen:                HttpListenAddr::default(),
                                        http_disable:               false,
+                                       dns_listen:                 None,
                                        gossip_peers:               vec![],
                                        gossip_permanent:           false,
                                        ring_key:                   None,
@@ -1212,6 +1268,7 @@ sys_ip_address = "7.8.9.0"
                                        feature_flags:              FeatureFlag::empty(),
                                        event_stream_config:        None,
                                        keep_latest_packages:       None,
+                                       package_audit_period:       None,
                                        sys_ip:
                                            biome_core::util::sys::ip().unwrap(), },
                        config);
@@ -1236,6 +1293,7 @@ sys_ip_address = "7.8.9.0"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_window:         None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -1250,6 +1308,7 @@ sys_ip_address = "7.8.9.0"
                                        ctl_client_ca_certificates: None,
                                        http_listen:                HttpListenAddr::default(),
                                        http_disable:               false,
+                                       dns_listen:                 None,
                                        gossip_peers:               vec![],
                                        gossip_permanent:           false,
                                        ring_key:                   None,
@@ -1259,6 +1318,7 @@ sys_ip_address = "7.8.9.0"
                                        feature_flags:              FeatureFlag::empty(),
                                        event_stream_config:        None,
                                        keep_latest_packages:       None,
+                                       package_audit_period:       None,
                                        sys_ip:
                                            biome_core::util::sys::ip().unwrap(), },
                        config);
@@ -1289,6 +1349,7 @@ sys_ip_address = "7.8.9.0"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update: false,
                                        auto_update_period: Duration::from_secs(60),
+                                       auto_update_window: None,
                                        service_update_period: Duration::from_secs(60),
                                        service_restart_config: ServiceRestartConfig::default(),
                                        custom_state_path: None,
@@ -1303,6 +1364,7 @@ sys_ip_address = "7.8.9.0"
                                        ctl_client_ca_certificates: None,
                                        http_listen: HttpListenAddr::default(),
                                        http_disable: false,
+                                       dns_listen: None,
                                        gossip_peers,
                                        gossip_permanent: false,
                                        ring_key: None,
@@ -1312,6 +1374,7 @@ sys_ip_address = "7.8.9.0"
                                        feature_flags: FeatureFlag::empty(),
                                        event_stream_config: None,
                                        keep_latest_packages: None,
+                                       package_audit_period: None,
                                        sys_ip: biome_core::util::sys::ip().unwrap() },
                        config);
         }
@@ -1398,6 +1461,7 @@ event_stream_server_certificate = "{}"
                 ManagerConfig {
                     auto_update: false,
                     auto_update_period: Duration::from_secs(60),
+                    auto_update_window: None,
                     service_update_period: Duration::from_secs(60),
                     service_restart_config: ServiceRestartConfig::default(),
                     custom_state_path: None,
@@ -1411,6 +1475,7 @@ event_stream_server_certificate = "{}"
                     ctl_client_ca_certificates: None,
                     http_listen: HttpListenAddr::default(),
                     http_disable: false,
+                    dns_listen: None,
                     gossip_peers: vec![],
                     gossip_permanent: false,
                     ring_key: None,
@@ -1429,6 +1494,7 @@ event_stream_server_certificate = "{}"
                         server_certificate: Some(certificate_path_str.parse().unwrap()),
                     }),
                     keep_latest_packages: None,
+                    package_audit_period: None,
                     sys_ip: biome_core::util::sys::ip().unwrap(),
                 },
                 config,
@@ -1454,6 +1520,18 @@ strategy = "at-once"
 update_condition = "track-channel"
 health_check_interval = 17
 shutdown_timeout = 12
+drain_timeout = 20
+strict_templates = true
+rollback_window = 300
+canary_count = 2
+update_batch_size = "25%"
+max_unavailable = 1
+update_window = "0 3 * * *"
+post_update_on_leader = true
+job = "*/15 * * * *"
+job_timeout = 600
+job_concurrency = "replace"
+job_on_leader = true
 pkg_ident_or_artifact = "core/redis"
 "#,
                                           temp_dir_str.replace('\\', "/")
@@ -1497,7 +1575,22 @@ pkg_ident_or_artifact = "core/redis"
                                                      Some(health_check_interval),
                                                  shutdown_timeout:       Some(12),
                                                  update_condition:
-                                                     Some(UpdateCondition::TrackChannel.into()), },
+                                                     Some(UpdateCondition::TrackChannel.into()),
+                                                 strict_templates:       Some(true),
+                                                 rollback_window:        Some(300),
+                                                 canary_count:           Some(2),
+                                                 update_batch_size:      Some("25%".to_string()),
+                                                 max_unavailable:        Some(1),
+                                                 update_window:
+                                                     Some("0 3 * * * UTC".to_string()),
+                                                 post_update_on_leader:  Some(true),
+                                                 drain_timeout:          Some(20),
+                                                 job:
+                                                     Some("*/15 * * * * UTC".to_string()),
+                                                 job_timeout:            Some(600),
+                                                 job_concurrency:
+                                                     Some(String::from("replace")),
+                                                 job_on_leader:          Some(true), },
                        service_load);
         }
 
@@ -1587,6 +1680,7 @@ organization = "MY_ORG_FROM_SECOND_CONFG"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_window:         None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -1604,6 +1698,7 @@ organization = "MY_ORG_FROM_SECOND_CONFG"
                                        http_listen:
                                            HttpListenAddr::from_str("3.3.3.3:3333").unwrap(),
                                        http_disable:               false,
+                                       dns_listen:                 None,
                                        gossip_peers:               vec![],
                                        gossip_permanent:           false,
                                        ring_key:                   None,
@@ -1614,6 +1709,7 @@ organization = "MY_ORG_FROM_SECOND_CONFG"
                                        feature_flags:              FeatureFlag::empty(),
                                        event_stream_config:        None,
                                        keep_latest_packages:       None,
+                                       package_audit_period:       None,
                                        sys_ip:
                                            biome_core::util::sys::ip().unwrap(), },
                        config);
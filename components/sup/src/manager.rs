@@ -1,6 +1,8 @@
 pub(crate) mod action;
 pub mod commands;
+mod dns_responder;
 mod file_watcher;
+mod package_auditor;
 mod peer_watcher;
 mod self_updater;
 pub mod service;
@@ -14,7 +16,9 @@ mod user_config_watcher;
 use self::{action::{ShutdownInput,
                     SupervisorAction},
            peer_watcher::PeerWatcher,
-           self_updater::{SelfUpdater,
+           self_updater::{self,
+                          SelfUpdateOutcome,
+                          SelfUpdater,
                           SUP_PKG_IDENT},
            service::{spec::{RefreshOperation,
                             ServiceOperation},
@@ -25,8 +29,11 @@ use self::{action::{ShutdownInput,
                      ServiceQueryModel,
                      ServiceRunState,
                      ServiceSpec,
-                     Topology},
-           service_updater::ServiceUpdater,
+                     Topology,
+                     UnixTimestamp},
+           service_updater::{rollout::RolloutProgress,
+                             ServiceUpdater,
+                             UpdateVerdict},
            spec_dir::SpecDir,
            spec_watcher::SpecWatcher,
            sys::Sys,
@@ -53,6 +60,7 @@ use futures::{channel::{mpsc as fut_mpsc,
               prelude::*,
               stream::FuturesUnordered};
 use biome_butterfly::{member::Member,
+                        rumor::service::RolloutControl,
                         server::{timing::Timing,
                                  ServerProxy,
                                  Suitability}};
@@ -74,14 +82,17 @@ use biome_core::{crypto::keys::{KeyCache,
                    os::process::{self,
                                  ShutdownTimeout},
                    package::{Identifiable,
+                             PackageAudit,
                              PackageIdent,
                              PackageInstall},
+                   schedule::Schedule,
                    service::ServiceGroup,
                    util::ToI64,
                    ChannelIdent};
 use biome_launcher_client::{LauncherCli,
                               LauncherStatus};
-use biome_sup_protocol::{self};
+use biome_sup_protocol::{self,
+                           types::RolloutAction};
 use lazy_static::lazy_static;
 use log::{debug,
           error,
@@ -104,6 +115,7 @@ use serde::{Deserialize,
             Serialize};
 use std::{collections::{HashMap,
                         HashSet},
+          convert::TryFrom,
           ffi::OsStr,
           fs::{self,
                File},
@@ -233,6 +245,16 @@ impl ShutdownConfig {
     }
 }
 
+/// The butterfly data served by the HTTP gateway, along with the outcome of the self-update this
+/// Supervisor was started for.
+#[derive(Serialize)]
+struct ButterflyProxy<'a> {
+    #[serde(flatten)]
+    server:            ServerProxy<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supervisor_update: Option<&'a SelfUpdateOutcome>,
+}
+
 /// FileSystem paths that the Manager uses to persist data to disk.
 ///
 /// This is shared with the `http_gateway` and `service` modules for reading and writing
@@ -297,6 +319,7 @@ impl Default for ServiceRestartConfig {
 pub struct ManagerConfig {
     pub auto_update:                bool,
     pub auto_update_period:         Duration,
+    pub auto_update_window:         Option<Schedule>,
     pub service_update_period:      Duration,
     pub service_restart_config:     ServiceRestartConfig,
     pub custom_state_path:          Option<PathBuf>,
@@ -311,6 +334,9 @@ pub struct ManagerConfig {
     pub ctl_client_ca_certificates: Option<RootCertStore>,
     pub http_listen:                HttpListenAddr,
     pub http_disable:               bool,
+    /// If this field is `Some`, answer DNS queries for census data on the indicated address. If
+    /// this field is `None`, the DNS responder is disabled.
+    pub dns_listen:                 Option<SocketAddr>,
     pub gossip_peers:               Vec<SocketAddr>,
     pub gossip_permanent:           bool,
     pub ring_key:                   Option<RingKey>,
@@ -323,6 +349,10 @@ pub struct ManagerConfig {
     /// others during service start. If this field is `None`, automatic package cleanup is
     /// disabled.
     pub keep_latest_packages:       Option<usize>,
+    /// If this field is `Some`, audit the packages of all running services against their
+    /// recorded checksums at the indicated interval. If this field is `None`, periodic package
+    /// audits are disabled.
+    pub package_audit_period:       Option<Duration>,
     pub sys_ip:                     IpAddr,
 }
 
@@ -431,6 +461,8 @@ pub struct ManagerState {
     cfg:            ManagerConfig,
     services:       Arc<sync::ManagerServices>,
     gateway_state:  Arc<sync::GatewayState>,
+    /// The census, so that the CtlGateway can render templates as the services would
+    census_ring:    Arc<RwLock<CensusRing>>,
     should_restart: AtomicBool,
 }
 
@@ -450,6 +482,10 @@ pub(crate) mod sync {
         pub fn census_data(&self) -> &str { &self.0.census_data }
 
         pub fn services_data(&self) -> &[ServiceQueryModel] { self.0.services_data.as_slice() }
+
+        pub fn package_audit_data(&self) -> &[PackageAudit] {
+            self.0.package_audit_data.as_slice()
+        }
     }
 
     pub struct GatewayStateWriteGuard<'a>(WriteGuard<'a, GatewayStateInner>);
@@ -468,6 +504,10 @@ pub(crate) mod sync {
         pub fn get_services_data_mut(&mut self) -> &mut Vec<ServiceQueryModel> {
             self.0.services_data.as_mut()
         }
+
+        pub fn set_package_audit_data(&mut self, new_data: Vec<PackageAudit>) {
+            self.0.package_audit_data = new_data
+        }
     }
 
     /// All the data that is ultimately served from the Supervisor's HTTP
@@ -490,11 +530,13 @@ pub(crate) mod sync {
     #[derive(Debug, Default)]
     struct GatewayStateInner {
         /// JSON returned by the /census endpoint
-        census_data:    String,
+        census_data:        String,
         /// JSON returned by the /butterfly endpoint
-        butterfly_data: String,
+        butterfly_data:     String,
         /// JSON returned by the /services endpoint
-        services_data:  Vec<ServiceQueryModel>,
+        services_data:      Vec<ServiceQueryModel>,
+        /// Results of the latest package audit returned by the /packages/audit endpoint
+        package_audit_data: Vec<PackageAudit>,
     }
 
     type ManagerServicesInner = HashMap<PackageIdent, PersistentServiceWrapper>;
@@ -619,6 +661,8 @@ pub struct Manager {
     spec_dir:            SpecDir,
     organization:        Option<String>,
     self_updater:        Option<SelfUpdater>,
+    /// The outcome of the self-update this Supervisor was started for, if it was
+    self_update_outcome: Option<SelfUpdateOutcome>,
     sys:                 Arc<Sys>,
     http_disable:        bool,
     /// Though it is a `HashMap`, `service_states` not really used as
@@ -646,6 +690,9 @@ pub struct Manager {
     // the different operations.
     busy_services: Arc<Mutex<HashSet<PackageIdent>>>,
     updated_service_pkg_incarnations: Arc<Mutex<HashMap<ServiceGroup, u64>>>,
+    /// The latest rollout control issued on this Supervisor for each service group. It is
+    /// gossiped with the service rumor of the group.
+    rollout_controls:                 Mutex<HashMap<ServiceGroup, RolloutControl>>,
     services_need_reconciliation:     ReconciliationFlag,
 
     feature_flags: FeatureFlag,
@@ -701,12 +748,18 @@ impl Manager {
         debug!("new(cfg: {:?}, fs_cfg: {:?}", cfg, fs_cfg);
         outputln!("{} ({})", SUP_PKG_IDENT, *THIS_SUPERVISOR_IDENT);
         let cfg_static = cfg.clone();
+        let self_update_outcome = SelfUpdateOutcome::from_env();
         let self_updater = if cfg.auto_update {
             if THIS_SUPERVISOR_IDENT.fully_qualified() {
+                let rejected = self_update_outcome.as_ref()
+                                                  .and_then(SelfUpdateOutcome::rejected)
+                                                  .or_else(self_updater::recorded_rejected_release);
                 Some(SelfUpdater::new(&THIS_SUPERVISOR_IDENT,
                                       cfg.update_url,
                                       cfg.update_channel,
-                                      cfg.auto_update_period))
+                                      cfg.auto_update_period,
+                                      cfg.auto_update_window.clone(),
+                                      rejected))
             } else {
                 warn!("Supervisor version not fully qualified, unable to start self-updater");
                 None
@@ -765,8 +818,10 @@ impl Manager {
         Ok(Manager { state: Arc::new(ManagerState { cfg: cfg_static,
                                                     services,
                                                     gateway_state: Arc::default(),
+                                                    census_ring: Arc::clone(&census_ring),
                                                     should_restart: AtomicBool::default() }),
                      self_updater,
+                     self_update_outcome,
                      service_updater:
                          Arc::new(Mutex::new(ServiceUpdater::new(server.clone(),
                                                                  Arc::clone(&census_ring),
@@ -785,6 +840,7 @@ impl Manager {
                      http_disable: cfg.http_disable,
                      busy_services: Arc::default(),
                      updated_service_pkg_incarnations: Arc::default(),
+                     rollout_controls: Mutex::default(),
                      services_need_reconciliation: ReconciliationFlag::new(false),
                      feature_flags: cfg.feature_flags,
                      pid_source,
@@ -870,15 +926,19 @@ impl Manager {
 
     async fn maybe_uninstall_old_packages(&self, ident: &PackageIdent) {
         if let Some(number_latest_to_keep) = self.state.cfg.keep_latest_packages {
-            match pkg::uninstall_all_but_latest(ident, number_latest_to_keep).await {
-                Ok(uninstalled) => {
-                    info!("Uninstalled '{}' '{}' packages keeping the '{}' latest",
-                          uninstalled, ident, number_latest_to_keep)
-                }
-                Err(e) => {
-                    error!("Failed to uninstall '{}' packages keeping the '{}' latest, err: {}",
-                           ident, number_latest_to_keep, e)
-                }
+            Self::uninstall_all_but_latest(ident, number_latest_to_keep).await;
+        }
+    }
+
+    async fn uninstall_all_but_latest(ident: &PackageIdent, number_latest_to_keep: usize) {
+        match pkg::uninstall_all_but_latest(ident, number_latest_to_keep).await {
+            Ok(uninstalled) => {
+                info!("Uninstalled '{}' '{}' packages keeping the '{}' latest",
+                      uninstalled, ident, number_latest_to_keep)
+            }
+            Err(e) => {
+                error!("Failed to uninstall '{}' packages keeping the '{}' latest, err: {}",
+                       ident, number_latest_to_keep, e)
             }
         }
     }
@@ -960,7 +1020,15 @@ impl Manager {
             return;
         }
 
-        self.maybe_uninstall_old_packages(&ident).await;
+        // An update within its rollback window keeps the package it replaced installed until
+        // the window closes. A held or pinned service may run a release older than the latest
+        // installed ones, so its packages are left alone.
+        let update_watched = self.service_updater
+                                 .lock()
+                                 .is_watching(&service.service_group);
+        if !update_watched && service.hold().is_none() && service.pinned().is_none() {
+            self.maybe_uninstall_old_packages(&ident).await;
+        }
 
         event::service_started(&service);
 
@@ -1145,6 +1213,19 @@ impl Manager {
             debug!("http-gateway started");
         }
 
+        if let Some(dns_listen) = self.state.cfg.dns_listen {
+            if let Err(e) = dns_responder::spawn(dns_listen, Arc::clone(&self.census_ring)) {
+                error!("Unable to start the DNS responder on {}: {}", dns_listen, e);
+                return Err(Error::BadAddress(dns_listen.to_string()));
+            }
+        }
+
+        if let Some(period) = self.state.cfg.package_audit_period {
+            package_auditor::spawn(Arc::clone(&self.state), period);
+        }
+
+        self.supervisor_ready();
+
         // Enter the main Supervisor loop. When we break out, it'll be
         // because we've been instructed to shutdown. The value we
         // break out with governs exactly how we shut down.
@@ -1213,6 +1294,7 @@ impl Manager {
             if let Some(package) = self.check_for_updated_supervisor().await {
                 outputln!("Supervisor shutting down for automatic update to {}",
                           package);
+                self.request_self_update_check(package.ident());
                 break ShutdownMode::Restarting;
             }
 
@@ -1227,12 +1309,12 @@ impl Manager {
                             warn!("Tried to stop '{}', but couldn't update the spec: {:?}",
                                   service_spec.ident, err);
                         }
-                        self.stop_service_gsw_msw(&service_spec.ident, &shutdown_input);
+                        self.stop_service_rsw_mlw_gsw_rhw_msw(&service_spec.ident, &shutdown_input);
                     }
                     UnloadService { service_spec,
                                     shutdown_input, } => {
                         self.remove_spec_file(&service_spec.ident).ok();
-                        self.stop_service_gsw_msw(&service_spec.ident, &shutdown_input);
+                        self.stop_service_rsw_mlw_gsw_rhw_msw(&service_spec.ident, &shutdown_input);
                     }
                     UpdateService { service_spec } => {
                         trace!("Received UpdateService action for {}", service_spec.ident);
@@ -1241,6 +1323,32 @@ impl Manager {
                                   service_spec.ident, err);
                         }
                     }
+                    ControlRollout { service_spec,
+                                     action, } => {
+                        self.control_rollout_rsw_mlw_rhw_msr(&service_spec.ident, action);
+                    }
+                    ForceServiceUpdate { service_spec } => {
+                        self.force_service_update_msr(&service_spec.ident);
+                    }
+                    RunJob { service_spec } => {
+                        self.run_job_msw(&service_spec.ident);
+                    }
+                    ForceSupervisorUpdate => {
+                        if let Some(self_updater) = self.self_updater.as_mut() {
+                            match self_updater.force_update() {
+                                Some(ident) => {
+                                    outputln!("Forcing the deferred Supervisor update to {}", ident)
+                                }
+                                None => {
+                                    outputln!("Forcing the next Supervisor update regardless of \
+                                               its update window")
+                                }
+                            }
+                        } else {
+                            warn!("Tried to force a Supervisor update, but the self updater is \
+                                   not running");
+                        }
+                    }
                 }
             }
 
@@ -1362,16 +1470,14 @@ impl Manager {
                 outputln!("Gracefully departing from butterfly network.");
                 self.butterfly.set_departed_mlw_smw_rhw();
 
+                let stop_service =
+                    |service| self.stop_service_future_rsw_mlw_gsw_rhw(service, None, None);
                 #[allow(clippy::from_iter_instead_of_collect)]
-                let service_stop_futures =
-                    FuturesUnordered::from_iter(self.state
-                                                    .services
-                                                    .lock_msw()
-                                                    .drain_services()
-                                                    .map(|service| {
-                                                        self.stop_service_future_gsw(service, None,
-                                                                                     None)
-                                                    }));
+                let service_stop_futures = FuturesUnordered::from_iter(self.state
+                                                                           .services
+                                                                           .lock_msw()
+                                                                           .drain_services()
+                                                                           .map(stop_service));
                 // Wait while all services are stopped
                 service_stop_futures.collect::<Vec<_>>().await;
             }
@@ -1385,6 +1491,42 @@ impl Manager {
         }
     }
 
+    /// Tell the Launcher that this Supervisor is ready, and report the outcome of the self-update
+    /// this Supervisor was started for, if any.
+    fn supervisor_ready(&self) {
+        if let Err(err) = self.launcher.ready() {
+            debug!("Launcher did not acknowledge that the Supervisor is ready: {}",
+                   err);
+        }
+        if let Some(outcome) = &self.self_update_outcome {
+            if outcome.rolled_back {
+                outputln!("Supervisor update from {} to {} was rolled back because {} did not \
+                           become ready",
+                          outcome.previous,
+                          outcome.updated,
+                          outcome.updated);
+                event::supervisor_update_rolled_back(&outcome.updated, &outcome.previous);
+            } else {
+                outputln!("Supervisor updated from {} to {}",
+                          outcome.previous,
+                          outcome.updated);
+                event::supervisor_updated(&outcome.previous, &outcome.updated);
+            }
+        }
+    }
+
+    /// Ask the Launcher to check that the `updated` Supervisor becomes ready once it restarts,
+    /// and to fall back to this release if it does not.
+    fn request_self_update_check(&self, updated: &PackageIdent) {
+        if let Err(err) = self.launcher.self_update(&THIS_SUPERVISOR_IDENT,
+                                                    updated,
+                                                    self_updater::ready_timeout())
+        {
+            warn!("Launcher cannot roll back the Supervisor update to {} if it fails: {}",
+                  updated, err);
+        }
+    }
+
     async fn check_for_updated_supervisor(&mut self) -> Option<PackageInstall> {
         if let Some(ref mut self_updater) = self.self_updater {
             return self_updater.updated().await;
@@ -1394,6 +1536,9 @@ impl Manager {
 
     /// Restart the Services that have an update or have set their `needs_restart` flag set.
     ///
+    /// Services that fail within the rollback window of an update are restarted with the package
+    /// they were updated from.
+    ///
     /// # Locking (see locking.md)
     /// * `RumorStore::list` (write)
     /// * `MemberList::entries` (read)
@@ -1407,8 +1552,41 @@ impl Manager {
         for (ident, service_state) in state_services.iter_mut() {
             // We need to use this has_update flag due to the borrow checker rules
             let mut has_update = false;
+            let mut post_update = None;
             if let Some(service) = service_state.service() {
-                if let Some(new_ident) = service_updater.has_update(&service.service_group) {
+                // Changes the Supervisor makes to the spec of the service are saved at once
+                let mut spec = service.spec();
+                let mut update_kept = false;
+                let verdict = service_updater.check_update(service,
+                                                           service_state.service_run_state()
+                                                                        .restart_count);
+                if let Some(UpdateVerdict::Confirmed) = verdict {
+                    outputln!("Keeping update of {} to {}", ident, service.pkg.ident);
+                    spec.rejected_release = None;
+                    update_kept = true;
+                }
+                if let Some(UpdateVerdict::RollBack { previous,
+                                                      failed,
+                                                      reason, }) = verdict
+                {
+                    outputln!("Rolling back {} from {} to {} because {}",
+                              ident,
+                              failed,
+                              previous,
+                              reason);
+                    // The service is started again from its spec once it stops, so the
+                    // rejected release is known to its next update worker.
+                    spec.rejected_release = Some(failed.clone());
+                    spec.pinned = None;
+                    has_update = true;
+                    service_updater.remove(&service.service_group);
+                    event::service_update_rolled_back(service,
+                                                      &failed,
+                                                      &previous,
+                                                      &reason.to_string());
+                    idents_to_restart_and_latest_desired_on_restart.push((ident.clone(),
+                                                                          Some(previous)));
+                } else if let Some(new_ident) = service_updater.due_update(service) {
                     if service.pkg.ident.as_ref() == &new_ident.ident {
                         // Here a rolling follower got asked to update to the same version it
                         // already had This is because the leader had a
@@ -1432,24 +1610,57 @@ impl Manager {
                                 .lock()
                                 .insert(service.service_group.clone(), incarnation);
                         }
+                        spec.pinned = None;
                         event::service_update_started(service, &new_ident.ident);
+                        service_updater.watch_update(service, &new_ident.ident);
+                        post_update =
+                            Some((service.pkg.ident.as_ref().clone(), new_ident.ident.clone()));
                         // The supervisor always runs the latest package on disk. When we have an
                         // update ensure that the lastest package on disk is
                         // the package we updated to.
                         idents_to_restart_and_latest_desired_on_restart.push((ident.clone(),
                                                                             Some(new_ident.ident)));
                     }
-                } else if service_state.should_shutdown_for_restart() {
-                    idents_to_restart_and_latest_desired_on_restart.push((ident.clone(), None));
                 } else {
-                    trace!("No restart required for {}", ident);
+                    // An update that is already installed but waits for the update window or for
+                    // the `pre-update` hook must not be started early by a restart of the service
+                    // or of the Supervisor, so the service is pinned to the release it runs.
+                    if service.hold().is_none()
+                       && service_updater.has_update(&service.service_group)
+                                         .map_or(false, |update| {
+                                             &update.ident != service.pkg.ident.as_ref()
+                                         })
+                    {
+                        spec.pinned = Some(service.pkg.ident.as_ref().clone());
+                    }
+                    if service_state.should_shutdown_for_restart() {
+                        idents_to_restart_and_latest_desired_on_restart.push((ident.clone(), None));
+                    } else {
+                        trace!("No restart required for {}", ident);
+                    }
                 };
+                // The release a held or pinned service runs must survive the cleanup of the
+                // packages the kept update replaced.
+                if update_kept && spec.hold.is_none() && spec.pinned.is_none() {
+                    if let Some(number_latest_to_keep) = self.state.cfg.keep_latest_packages {
+                        let ident = ident.clone();
+                        tokio::spawn(async move {
+                            Self::uninstall_all_but_latest(&ident, number_latest_to_keep).await
+                        });
+                    }
+                }
+                if spec != service.spec() {
+                    self.save_supervisor_spec_changes(&spec);
+                }
             } else {
                 trace!("Restart in progress for {}", ident);
             }
             if has_update {
                 service_state.mark_for_restart_due_to_update(SystemTime::now());
             }
+            if let Some((previous, updated)) = post_update {
+                service_state.expect_post_update(previous, updated);
+            }
         }
 
         for (ident, latest_desired_on_restart) in idents_to_restart_and_latest_desired_on_restart {
@@ -1467,7 +1678,18 @@ impl Manager {
             // our specfile reconciliation logic to catch the fact that
             // the service needs to be restarted. At that point, this function
             // can be renamed; right now, it says exactly what it's doing.
-            tokio::spawn(self.stop_service_future_gsw(service, latest_desired_on_restart, None));
+            tokio::spawn(self.stop_service_future_rsw_mlw_gsw_rhw(service,
+                                                                  latest_desired_on_restart,
+                                                                  None));
+        }
+    }
+
+    /// Save the fields of a service spec that are set by the Supervisor, such as the release the
+    /// service was rolled back from or the release it is pinned to. The spec file is picked up by
+    /// the spec reconciliation like any other change to it.
+    fn save_supervisor_spec_changes(&self, spec: &ServiceSpec) {
+        if let Err(err) = self.state.cfg.save_spec_for(spec) {
+            outputln!("Unable to save the spec of {}: {}", spec.ident, err);
         }
     }
 
@@ -1493,8 +1715,115 @@ impl Manager {
         // from the rumour store.
         let pkg_incarnation = updated_pkg_incarnation.unwrap_or(0)
                                                      .max(last_pkg_incarnation);
-        self.butterfly
-            .insert_service_rsw_mlw_rhw(service.to_rumor(incarnation, pkg_incarnation));
+        let mut rumor = service.to_rumor(incarnation, pkg_incarnation);
+        rumor.rollout_control = self.rollout_controls
+                                    .lock()
+                                    .get(&service.service_group)
+                                    .copied();
+        self.butterfly.insert_service_rsw_mlw_rhw(rumor);
+    }
+
+    /// Apply the pending or next update of a service regardless of its update window.
+    ///
+    /// # Locking (see locking.md)
+    /// * `ManagerServices::inner` (read)
+    fn force_service_update_msr(&self, ident: &PackageIdent) {
+        let service_group = match self.state
+                                      .services
+                                      .lock_msr()
+                                      .get(ident)
+                                      .and_then(PersistentServiceWrapper::service)
+        {
+            Some(service) => service.service_group.clone(),
+            None => {
+                warn!("Tried to force the update of '{}', but it is not running",
+                      ident);
+                return;
+            }
+        };
+        // The services lock is released before taking the service updater lock, which is taken
+        // first when restarting updated services.
+        match self.service_updater.lock().force_update(&service_group) {
+            Some(update) => {
+                outputln!("Forcing the deferred update of {} to {}",
+                          service_group,
+                          update)
+            }
+            None => {
+                outputln!("Forcing the next update of {} regardless of its update window",
+                          service_group)
+            }
+        }
+    }
+
+    /// Run a job service as soon as possible, in addition to its schedule.
+    ///
+    /// # Locking (see locking.md)
+    /// * `ManagerServices::inner` (write)
+    fn run_job_msw(&self, ident: &PackageIdent) {
+        match self.state.services.lock_msw().get_mut(ident) {
+            Some(service) => {
+                outputln!("Running the job {} on demand", ident);
+                service.request_job_run();
+            }
+            None => warn!("Tried to run the job '{}', but it is not running", ident),
+        }
+    }
+
+    /// Issue a control for the canary or batched update of a service and gossip it with the
+    /// service rumor, so that every member of the service group follows it.
+    ///
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
+    /// * `RumorHeat::inner` (write)
+    /// * `ManagerServices::inner` (read)
+    fn control_rollout_rsw_mlw_rhw_msr(&self, ident: &PackageIdent, action: RolloutAction) {
+        let services = self.state.services.lock_msr();
+        let service = match services.get(ident)
+                                    .and_then(PersistentServiceWrapper::service)
+        {
+            Some(service) => service,
+            None => {
+                warn!("Tried to {} the update of '{}', but it is not running",
+                      action, ident);
+                return;
+            }
+        };
+        let rollout = self.census_ring
+                          .read()
+                          .census_group_for(&service.service_group)
+                          .and_then(|census_group| {
+                              census_group.update_leader().map(|leader| {
+                                                              (leader.pkg_incarnation,
+                                                               census_group.rollout_control())
+                                                          })
+                          });
+        let mut control = match rollout {
+            Some((rollout, current)) => {
+                current.unwrap_or(RolloutControl { rollout,
+                                                   ..Default::default() })
+            }
+            None => {
+                warn!("Tried to {} the update of '{}', but its update leader is not yet known",
+                      action, service.service_group);
+                return;
+            }
+        };
+        control.sequence += 1;
+        match action {
+            RolloutAction::Pause => control.paused = true,
+            RolloutAction::Resume => control.paused = false,
+            RolloutAction::Approve => control.approved = true,
+            RolloutAction::Abort => control.aborted = true,
+        }
+        outputln!("Sending {} to the update of {}",
+                  action,
+                  service.service_group);
+        self.rollout_controls
+            .lock()
+            .insert(service.service_group.clone(), control);
+        self.gossip_latest_service_rumor_rsw_mlw_rhw(service, None);
     }
 
     fn check_for_departure(&self) -> bool { self.butterfly.is_departed() }
@@ -1571,7 +1900,8 @@ impl Manager {
     /// * `MemberList::entries` (read)
     /// * `GatewayState::inner` (write)
     fn persist_butterfly_state_rsr_mlr_gsw(&self) {
-        let bs = ServerProxy::new(&self.butterfly);
+        let bs = ButterflyProxy { server:            ServerProxy::new(&self.butterfly),
+                                  supervisor_update: self.self_update_outcome.as_ref(), };
         let json = serde_json::to_string(&bs).expect("ServerProxy::serialize failure");
         self.state.gateway_state.lock_gsw().set_butterfly_data(json);
     }
@@ -1644,17 +1974,34 @@ impl Manager {
                                 ServiceQueryModel::new(service, service_run_state, config_rendering)
                             })
                             .collect();
+        let census_ring = self.census_ring.read();
+        let service_updater = self.service_updater.lock();
         let mut services_data: Vec<ServiceQueryModel> =
             service_map.iter()
                        .filter_map(|(_, svc_state)| {
                            if let Some(service) = svc_state.service() {
-                               return Some(ServiceQueryModel::new(service,
-                                                                  svc_state.service_run_state(),
-                                                                  config_rendering));
+                               let mut service_data =
+                                   ServiceQueryModel::new(service,
+                                                          svc_state.service_run_state(),
+                                                          config_rendering);
+                               service_data.update_rollout =
+                                   RolloutProgress::for_service(service, &census_ring);
+                               service_data.available_update =
+                                   service_updater.available_update(service);
+                               if let Some(check) =
+                                   service_updater.update_check(&service.service_group)
+                               {
+                                   service_data.last_update_check =
+                                       UnixTimestamp::try_from(check.checked_at).ok();
+                                   service_data.latest_in_channel = Some(check.latest);
+                               }
+                               return Some(service_data);
                            }
                            None
                        })
                        .collect();
+        drop(service_updater);
+        drop(census_ring);
 
         services_data.extend(watched_service_proxies);
 
@@ -1677,12 +2024,18 @@ impl Manager {
     }
 
     /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
     /// * `GatewayState::inner` (write)
+    /// * `RumorHeat::inner` (write)
     /// * `ManagerServices::inner` (write)
-    fn stop_service_gsw_msw(&mut self, ident: &PackageIdent, shutdown_input: &ShutdownInput) {
+    fn stop_service_rsw_mlw_gsw_rhw_msw(&mut self,
+                                        ident: &PackageIdent,
+                                        shutdown_input: &ShutdownInput) {
         if let Some(mut service_state) = self.remove_service_from_state_msw(ident) {
             if let Some(service) = service_state.shutdown(false) {
-                let future = self.stop_service_future_gsw(service, None, Some(shutdown_input));
+                let future =
+                    self.stop_service_future_rsw_mlw_gsw_rhw(service, None, Some(shutdown_input));
                 tokio::spawn(future);
             }
         } else {
@@ -1694,18 +2047,28 @@ impl Manager {
     /// Create a future for stopping a Service removing it from the manager. The Service is assumed
     /// to have been removed from the internal list of active services already (see, e.g.,
     /// restart_services_rsw_mlr_rhw_msw and remove_service_from_state).
+    ///
+    /// A service that drains is gossiped as not ready before the returned future drains and
+    /// stops it, so that its peers stop relying on it first.
+    /// # Locking (see locking.md)
+    /// * `RumorStore::list` (write)
+    /// * `MemberList::entries` (write)
+    /// * `RumorHeat::inner` (write)
     /// # Locking for the returned Future (see locking.md)
     /// * `GatewayState::inner` (write)
-    fn stop_service_future_gsw(&self,
-                               mut service: Service,
-                               latest_desired_on_restart: Option<PackageIdent>,
-                               shutdown_input: Option<&ShutdownInput>)
-                               -> impl Future<Output = ()> {
+    fn stop_service_future_rsw_mlw_gsw_rhw(&self,
+                                           mut service: Service,
+                                           latest_desired_on_restart: Option<PackageIdent>,
+                                           shutdown_input: Option<&ShutdownInput>)
+                                           -> impl Future<Output = ()> {
         let mut user_config_watcher = self.user_config_watcher.clone();
         let service_updater = Arc::clone(&self.service_updater);
         let busy_services = Arc::clone(&self.busy_services);
         let services_need_reconciliation = self.services_need_reconciliation.clone();
         let shutdown_config = ShutdownConfig::new(shutdown_input, &service);
+        if service.start_draining() {
+            self.gossip_latest_service_rumor_rsw_mlw_rhw(&service, None);
+        }
 
         // JW TODO: Update service rumor to remove service from
         // cluster
@@ -1860,7 +2223,7 @@ impl Manager {
                         self.remove_service_from_state_msw(&spec.ident)
                             .and_then(|mut service_state| service_state.shutdown(false))
                     {
-                        tokio::spawn(self.stop_service_future_gsw(service, None, None));
+                        tokio::spawn(self.stop_service_future_rsw_mlw_gsw_rhw(service, None, None));
                     } else {
                         // We really don't expect this to happen....
                         outputln!("Tried to remove service for {} but could not find it running, \
@@ -2125,6 +2488,7 @@ mod test {
         fn default() -> Self {
             ManagerConfig { auto_update:                false,
                             auto_update_period:         Duration::from_secs(60),
+                            auto_update_window:         None,
                             service_update_period:      Duration::from_secs(60),
                             service_restart_config:     ServiceRestartConfig::default(),
                             custom_state_path:          None,
@@ -2138,6 +2502,7 @@ mod test {
                             ctl_client_ca_certificates: None,
                             http_listen:                HttpListenAddr::default(),
                             http_disable:               false,
+                            dns_listen:                 None,
                             gossip_peers:               vec![],
                             gossip_permanent:           false,
                             ring_key:                   None,
@@ -2147,6 +2512,7 @@ mod test {
                             feature_flags:              FeatureFlag::empty(),
                             event_stream_config:        None,
                             keep_latest_packages:       None,
+                            package_audit_period:       None,
                             sys_ip:                     IpAddr::V4(Ipv4Addr::LOCALHOST), }
         }
     }
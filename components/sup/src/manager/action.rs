@@ -3,6 +3,7 @@
 
 use super::service::ServiceSpec;
 use biome_core::os::process::ShutdownTimeout;
+use biome_sup_protocol::types::RolloutAction;
 use std::sync::mpsc;
 
 /// Defines the parameters by which a service process is to be shut
@@ -32,6 +33,17 @@ pub enum SupervisorAction {
     UpdateService {
         service_spec: ServiceSpec,
     },
+    ControlRollout {
+        service_spec: ServiceSpec,
+        action:       RolloutAction,
+    },
+    ForceServiceUpdate {
+        service_spec: ServiceSpec,
+    },
+    RunJob {
+        service_spec: ServiceSpec,
+    },
+    ForceSupervisorUpdate,
 }
 
 pub type ActionSender = mpsc::Sender<SupervisorAction>;
@@ -11,6 +11,7 @@ use crate::{ctl_gateway::CtlRequest,
 use biome_butterfly as butterfly;
 use biome_common::{command::package::install::InstallSource,
                      outputln,
+                     templating::config::CfgKeySource,
                      ui::UIWriter};
 use biome_core::{package::{Identifiable,
                              PackageIdent,
@@ -33,15 +34,26 @@ pub fn service_cfg_msr(mgr: &ManagerState,
                        opts: protocol::ctl::SvcGetDefaultCfg)
                        -> NetResult<()> {
     let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    let effective = opts.effective.unwrap_or(false);
     let mut msg = protocol::types::ServiceCfg { format:
                                                     Some(protocol::types::service_cfg::Format::Toml
                                                          as i32),
-                                                default: None, };
+                                                default: None,
+                                                keys:    Vec::new(), };
     for service in mgr.services.lock_msr().running_services() {
         if service.pkg.ident.satisfies(&ident) {
             if let Some(ref cfg) = service.cfg.default {
                 msg.default =
                     Some(toml::to_string_pretty(&toml::value::Value::Table(cfg.clone())).unwrap());
+            }
+            if effective {
+                msg.keys = service.cfg
+                                  .provenance()
+                                  .into_iter()
+                                  .map(service_cfg_key)
+                                  .collect();
+            }
+            if msg.default.is_some() || effective {
                 req.reply_complete(msg);
             }
             return Ok(());
@@ -50,64 +62,91 @@ pub fn service_cfg_msr(mgr: &ManagerState,
     Err(net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident)))
 }
 
-pub fn service_cfg_validate(_mgr: &ManagerState,
-                            req: &mut CtlRequest,
-                            opts: protocol::ctl::SvcValidateCfg)
-                            -> NetResult<()> {
-    let cfg = opts.cfg.ok_or_else(err_update_client)?;
-    let format = opts.format
-                     .and_then(|f| protocol::types::service_cfg::Format::try_from(f).ok())
-                     .unwrap_or_default();
-    if cfg.len() > protocol::butterfly::MAX_SVC_CFG_SIZE {
-        return Err(net::err(ErrCode::EntityTooLarge, "Configuration too large."));
-    }
-    if format != protocol::types::service_cfg::Format::Toml {
-        return Err(net::err(ErrCode::NotSupported,
-                            format!("Configuration format {} not available.",
-                                    format)));
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+pub fn service_cfg_validate_msr(mgr: &ManagerState,
+                                req: &mut CtlRequest,
+                                opts: protocol::ctl::SvcValidateCfg)
+                                -> NetResult<()> {
+    let new_cfg = candidate_cfg(opts.cfg, opts.format)?;
+    // Services which aren't loaded on this Supervisor are only known through rumor propagation
+    // and can't be validated, so the configuration is accepted for them.
+    let service_group: Option<ServiceGroup> = opts.service_group.map(Into::into);
+    for service in mgr.services.lock_msr().running_services() {
+        if Some(&service.service_group) != service_group.as_ref() {
+            continue;
+        }
+        for violation in service.cfg.validate_gossip(&new_cfg) {
+            req.reply_partial(net::err(ErrCode::InvalidPayload, violation.to_string()));
+        }
+        break;
     }
-    let cfg_str =
-        str::from_utf8(&cfg).map_err(|e| {
-                                net::err(ErrCode::BadPayload,
-                                         format!("Unable to decode configuration to string, {}", e))
-                            })?;
-    let _new_cfg: toml::value::Table = toml::from_str(cfg_str).map_err(|e| {
-                                                                  net::err(ErrCode::BadPayload,
-                                            format!("Unable to decode configuration as {}, {}",
-                                                    format, e))
-                                                              })?;
     req.reply_complete(net::ok());
     Ok(())
-    // JW TODO: Hold off on validation until we can validate services which aren't currently
-    // loaded in the Supervisor but are known through rumor propagation.
-    // let service_group: ServiceGroup = opts.service_group.into();
-    // for service in mgr.services.read().unwrap().iter() {
-    //     if service.service_group != service_group {
-    //         continue;
-    //     }
-    //     if let Some(interface) = service.cfg.interface() {
-    //         match Cfg::validate(interface, &new_cfg) {
-    //             None => req.reply_complete(net::ok()),
-    //             Some(errors) => {
-    //                 for error in errors {
-    //                     req.reply_partial(net::err(ErrCode::InvalidPayload, error));
-    //                 }
-    //                 req.reply_complete(net::ok());
-    //             }
-    //         }
-    //         return Ok(());
-    //     } else {
-    //         // No interface, this service can't be configured.
-    //         return Err(net::err(
-    //             ErrCode::NotFound,
-    //             "Service has no configurable attributes.",
-    //         ));
-    //     }
-    // }
-    // Err(net::err(
-    //     ErrCode::NotFound,
-    //     format!("Service not loaded, {}", service_group),
-    // ))
+}
+
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+pub fn service_cfg_dry_run_msr(mgr: &ManagerState,
+                               req: &mut CtlRequest,
+                               opts: protocol::ctl::SvcCfgDryRun)
+                               -> NetResult<()> {
+    let new_cfg = candidate_cfg(opts.cfg, opts.format)?;
+    let service_group: ServiceGroup = opts.service_group.ok_or_else(err_update_client)?.into();
+    let services = mgr.services.lock_msr();
+    let service = services.running_services()
+                          .find(|service| service.service_group == service_group)
+                          .ok_or_else(|| {
+                              net::err(ErrCode::NotFound,
+                                       format!("Service not loaded, {}", service_group))
+                          })?;
+    let dry_run = service.dry_run_cfg(&mgr.census_ring.read(), new_cfg)
+                         .map_err(|e| net::err(ErrCode::InvalidPayload, e.to_string()))?;
+    let changed_hooks = dry_run.changed_hooks
+                               .into_iter()
+                               .map(String::from)
+                               .collect();
+    let reconfigure = Some(dry_run.reconfigure);
+    let restart_reason = dry_run.restart_reason.map(|reason| reason.to_string());
+    req.reply_complete(protocol::types::ServiceCfgDryRun { diffs: dry_run.diffs,
+                                                           changed_hooks,
+                                                           reconfigure,
+                                                           restart_reason });
+    Ok(())
+}
+
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+pub fn service_cfg_history_msr(mgr: &ManagerState,
+                               req: &mut CtlRequest,
+                               opts: protocol::ctl::SvcGetCfgHistory)
+                               -> NetResult<()> {
+    let service_group: ServiceGroup = opts.service_group.ok_or_else(err_update_client)?.into();
+    let entries: Vec<protocol::types::ServiceCfgHistoryEntry> =
+        match mgr.services
+                 .lock_msr()
+                 .running_services()
+                 .find(|service| service.service_group == service_group)
+        {
+            Some(service) => service.cfg_history.entries().map(Into::into).collect(),
+            None => {
+                return Err(net::err(ErrCode::NotFound,
+                                    format!("Service not loaded, {}", service_group)))
+            }
+        };
+    if entries.is_empty() {
+        req.reply_complete(net::ok());
+    } else {
+        let mut list = entries.into_iter().peekable();
+        while let Some(entry) = list.next() {
+            if list.peek().is_some() {
+                req.reply_partial(entry);
+            } else {
+                req.reply_complete(entry);
+            }
+        }
+    }
+    Ok(())
 }
 
 pub fn service_cfg_set(mgr: &ManagerState,
@@ -205,13 +244,36 @@ pub async fn service_load(mgr: &ManagerState,
     Ok(())
 }
 
-pub fn service_update(mgr: &ManagerState,
-                      req: &mut CtlRequest,
-                      opts: protocol::ctl::SvcUpdate,
-                      action_sender: &ActionSender)
-                      -> NetResult<()> {
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+pub fn service_update_msr(mgr: &ManagerState,
+                          req: &mut CtlRequest,
+                          opts: protocol::ctl::SvcUpdate,
+                          action_sender: &ActionSender)
+                          -> NetResult<()> {
     let ident: PackageIdent = opts.ident.clone().ok_or_else(err_update_client)?.into();
     if let Some(mut service_spec) = mgr.cfg.spec_for_ident(&ident) {
+        match opts.hold {
+            Some(true) => {
+                // Hold the service at the release it is running right now
+                let running = mgr.services
+                                 .lock_msr()
+                                 .running_services()
+                                 .find(|service| service.spec_ident() == service_spec.ident)
+                                 .map(|service| service.pkg.ident.as_ref().clone());
+                match running {
+                    Some(running) => service_spec.hold = Some(running),
+                    None => {
+                        return Err(net::err(ErrCode::NotFound,
+                                            format!("{} is not running; only a running \
+                                                     service can be held",
+                                                    ident)));
+                    }
+                }
+            }
+            Some(false) => service_spec.hold = None,
+            None => {}
+        }
         service_spec.merge_svc_update(opts);
         let action = SupervisorAction::UpdateService { service_spec };
         send_action(action, action_sender)?;
@@ -224,6 +286,102 @@ pub fn service_update(mgr: &ManagerState,
     }
 }
 
+pub fn service_rollout(mgr: &ManagerState,
+                       req: &mut CtlRequest,
+                       opts: protocol::ctl::SvcRollout,
+                       action_sender: &ActionSender)
+                       -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    let action = opts.action
+                     .and_then(|a| protocol::types::RolloutAction::try_from(a).ok())
+                     .ok_or_else(err_update_client)?;
+    if let Some(service_spec) = mgr.cfg.spec_for_ident(&ident) {
+        match service_spec.update_strategy {
+            protocol::types::UpdateStrategy::Canary | protocol::types::UpdateStrategy::Batched => {}
+            strategy => {
+                return Err(net::err(ErrCode::InvalidPayload,
+                                    format!("{} uses the {} update strategy; only \
+                                             canary and batched updates can be \
+                                             controlled",
+                                            ident, strategy)));
+            }
+        }
+        send_action(SupervisorAction::ControlRollout { service_spec,
+                                                       action },
+                    action_sender)?;
+
+        req.info(format!("Sending {} to the update of {}. See the Supervisor output for more \
+                          details.",
+                         action, ident))?;
+        req.reply_complete(net::ok());
+        Ok(())
+    } else {
+        Err(net::err(ErrCode::Internal, Error::ServiceNotLoaded(ident)))
+    }
+}
+
+pub fn service_update_now(mgr: &ManagerState,
+                          req: &mut CtlRequest,
+                          opts: protocol::ctl::SvcUpdateNow,
+                          action_sender: &ActionSender)
+                          -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    if let Some(service_spec) = mgr.cfg.spec_for_ident(&ident) {
+        match service_spec.update_strategy {
+            protocol::types::UpdateStrategy::None => {
+                return Err(net::err(ErrCode::InvalidPayload,
+                                    format!("{} does not have an update strategy",
+                                            ident)));
+            }
+            protocol::types::UpdateStrategy::Notify => {
+                return Err(net::err(ErrCode::InvalidPayload,
+                                    format!("{} uses the notify update strategy, \
+                                             which never applies updates",
+                                            ident)));
+            }
+            _ => {}
+        }
+        if let Some(held_at) = service_spec.hold {
+            return Err(net::err(ErrCode::InvalidPayload,
+                                format!("{} is held at {}; release it before \
+                                         updating it",
+                                        ident, held_at)));
+        }
+        send_action(SupervisorAction::ForceServiceUpdate { service_spec },
+                    action_sender)?;
+
+        req.info(format!("Applying the next update of {} without waiting for its update \
+                          window. See the Supervisor output for more details.",
+                         ident))?;
+        req.reply_complete(net::ok());
+        Ok(())
+    } else {
+        Err(net::err(ErrCode::Internal, Error::ServiceNotLoaded(ident)))
+    }
+}
+
+pub fn service_run_job(mgr: &ManagerState,
+                       req: &mut CtlRequest,
+                       opts: protocol::ctl::SvcRunJob,
+                       action_sender: &ActionSender)
+                       -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    if let Some(service_spec) = mgr.cfg.spec_for_ident(&ident) {
+        if service_spec.job.is_none() {
+            return Err(net::err(ErrCode::InvalidPayload,
+                                format!("{} is not a job service", ident)));
+        }
+        send_action(SupervisorAction::RunJob { service_spec }, action_sender)?;
+
+        req.info(format!("Running the job {} now. See the Supervisor output for more details.",
+                         ident))?;
+        req.reply_complete(net::ok());
+        Ok(())
+    } else {
+        Err(net::err(ErrCode::Internal, Error::ServiceNotLoaded(ident)))
+    }
+}
+
 pub fn service_unload(mgr: &ManagerState,
                       req: &mut CtlRequest,
                       opts: protocol::ctl::SvcUnload,
@@ -336,6 +494,24 @@ pub fn supervisor_restart(mgr: &ManagerState,
     Ok(())
 }
 
+pub fn supervisor_update_now(mgr: &ManagerState,
+                             req: &mut CtlRequest,
+                             _opts: protocol::ctl::SupUpdateNow,
+                             action_sender: &ActionSender)
+                             -> NetResult<()> {
+    if !mgr.cfg.auto_update {
+        return Err(net::err(ErrCode::InvalidPayload,
+                            "The Supervisor is not running with automatic \
+                             updates enabled"));
+    }
+    send_action(SupervisorAction::ForceSupervisorUpdate, action_sender)?;
+
+    req.info("Switching to the next Supervisor update without waiting for the update window. \
+              See the Supervisor output for more details.")?;
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
 pub fn service_status_gsr(mgr: &ManagerState,
@@ -389,6 +565,39 @@ pub fn service_status_gsr(mgr: &ManagerState,
 
 ////////////////////////////////////////////////////////////////////////
 // Private helper functions
+fn service_cfg_key(key: CfgKeySource) -> protocol::types::ServiceCfgKey {
+    protocol::types::ServiceCfgKey { key:           Some(key.key),
+                                     value:         Some(key.value.to_string()),
+                                     layer:         Some(key.layer.to_string()),
+                                     source:        Some(key.source),
+                                     default_value: key.default.map(|d| d.to_string()), }
+}
+
+/// Decode an unencrypted candidate configuration sent by a client.
+fn candidate_cfg(cfg: Option<Vec<u8>>, format: Option<i32>) -> NetResult<toml::value::Table> {
+    let cfg = cfg.ok_or_else(err_update_client)?;
+    let format = format.and_then(|f| protocol::types::service_cfg::Format::try_from(f).ok())
+                       .unwrap_or_default();
+    if cfg.len() > protocol::butterfly::MAX_SVC_CFG_SIZE {
+        return Err(net::err(ErrCode::EntityTooLarge, "Configuration too large."));
+    }
+    if format != protocol::types::service_cfg::Format::Toml {
+        return Err(net::err(ErrCode::NotSupported,
+                            format!("Configuration format {} not available.",
+                                    format)));
+    }
+    let cfg_str =
+        str::from_utf8(&cfg).map_err(|e| {
+                                net::err(ErrCode::BadPayload,
+                                         format!("Unable to decode configuration to string, {}", e))
+                            })?;
+    toml::from_str(cfg_str).map_err(|e| {
+                               net::err(ErrCode::BadPayload,
+                                        format!("Unable to decode configuration as {}, {}",
+                                                format, e))
+                           })
+}
+
 fn err_update_client() -> net::NetErr { net::err(ErrCode::UpdateClient, "client out of date") }
 
 /// Helper function to ensure that all errors in sending are handled identically.
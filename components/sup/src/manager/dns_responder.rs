@@ -0,0 +1,716 @@
+//! Answers DNS queries about the services in the census, so applications on the host that don't
+//! run under a Supervisor can discover the services of the ring.
+//!
+//! Names are resolved under the `svc.local` domain:
+//!
+//! * `<service>.<group>.svc.local` resolves to every alive member of the service group
+//! * `leader.<service>.<group>.svc.local` resolves to the group's leader only
+//! * `<member-id>.<service>.<group>.svc.local` resolves to a single member
+//!
+//! The service groups of an organization, such as `redis.default@acme`, are resolved under
+//! `<org>.org.svc.local` instead, e.g. `leader.redis.default.acme.org.svc.local`.
+//!
+//! `A` and `AAAA` queries are answered with the members' IP addresses. `SRV` queries are answered
+//! with the port each member exports as `port`, or as `<export>` when the name is prefixed with
+//! `_<export>._tcp` or `_<export>._udp`. The target of each `SRV` record is the member's own
+//! name, whose address is included with the response.
+//!
+//! Responses are limited to 512 bytes, or to the UDP payload size a query advertises with EDNS.
+//! Records that don't fit are left out, addresses of `SRV` targets first, and the response is
+//! only marked as truncated when answers are missing.
+
+use crate::census::{CensusMember,
+                    CensusRing};
+use biome_common::outputln;
+use log::{debug,
+          warn};
+use parking_lot::RwLock;
+use std::{convert::TryFrom,
+          io,
+          net::{IpAddr,
+                SocketAddr},
+          sync::Arc};
+use tokio::net::UdpSocket;
+
+static LOGKEY: &str = "DN";
+
+/// The domain all names are resolved under
+pub const DNS_DOMAIN: &str = "svc.local";
+
+/// Census data changes quickly, so answers are only cached briefly.
+const TTL: u32 = 5;
+/// The largest response sent without EDNS; larger ones are truncated.
+const MAX_UDP_RESPONSE: usize = 512;
+/// The largest response sent to queries advertising a larger UDP payload size with EDNS, which
+/// avoids fragmentation on any common network.
+const MAX_EDNS_RESPONSE: usize = 1232;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const TYPE_SRV: u16 = 33;
+const TYPE_OPT: u16 = 41;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+
+const FLAG_RESPONSE: u16 = 0x8000;
+const FLAG_AUTHORITATIVE: u16 = 0x0400;
+const FLAG_TRUNCATED: u16 = 0x0200;
+const FLAG_RECURSION_DESIRED: u16 = 0x0100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rcode {
+    NoError  = 0,
+    FormErr  = 1,
+    NxDomain = 3,
+    NotImp   = 4,
+    Refused  = 5,
+}
+
+/// Bind the responder to `listen` and answer queries from the census in a background task.
+pub fn spawn(listen: SocketAddr, census_ring: Arc<RwLock<CensusRing>>) -> io::Result<()> {
+    let socket = std::net::UdpSocket::bind(listen)?;
+    socket.set_nonblocking(true)?;
+    let socket = UdpSocket::from_std(socket)?;
+    outputln!("Starting DNS responder on {}", listen);
+    tokio::spawn(async move {
+        let mut buf = [0; MAX_EDNS_RESPONSE];
+        loop {
+            let (len, peer) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    warn!("DNS responder failed to receive a query: {}", e);
+                    continue;
+                }
+            };
+            let response = respond(&buf[..len], &census_ring.read());
+            if let Some(response) = response {
+                if let Err(e) = socket.send_to(&response, peer).await {
+                    debug!("DNS responder failed to answer {}: {}", peer, e);
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Returns the response to a query, or `None` if the message should be ignored.
+fn respond(msg: &[u8], census_ring: &CensusRing) -> Option<Vec<u8>> {
+    respond_with(msg, |service, group, org| {
+        let groups = census_ring.groups()
+                                .into_iter()
+                                .filter(|g| {
+                                    g.service_group.service() == service
+                                    && g.service_group.group() == group
+                                    && g.service_group.org() == org
+                                })
+                                .collect::<Vec<_>>();
+        if groups.is_empty() {
+            None
+        } else {
+            Some(groups.into_iter().flat_map(|g| g.members()).collect())
+        }
+    })
+}
+
+/// Returns the response to a query, finding the members of a service group with `members_of`,
+/// which returns `None` when there is no such group.
+fn respond_with<'a, F>(msg: &[u8], members_of: F) -> Option<Vec<u8>>
+    where F: Fn(&str, &str, Option<&str>) -> Option<Vec<&'a CensusMember>>
+{
+    let header = Header::parse(msg)?;
+    if header.flags & FLAG_RESPONSE != 0 {
+        return None;
+    }
+    let mut response = Response::new(&header);
+    if header.flags & 0x7800 != 0 {
+        response.rcode = Rcode::NotImp;
+        return Some(response.finish());
+    }
+    let question = match Question::parse(msg, &header) {
+        Some(question) => question,
+        None => {
+            response.rcode = Rcode::FormErr;
+            return Some(response.finish());
+        }
+    };
+    response.question = Some(&msg[Header::LEN..question.end]);
+    if let Some(edns) = Edns::parse(msg, &header, question.end) {
+        response.edns = true;
+        response.limit = usize::from(edns.udp_size).clamp(MAX_UDP_RESPONSE, MAX_EDNS_RESPONSE);
+        if edns.version != 0 {
+            response.extended_rcode = Edns::BADVERS;
+            return Some(response.finish());
+        }
+    }
+    if question.class != CLASS_IN {
+        response.rcode = Rcode::Refused;
+        return Some(response.finish());
+    }
+
+    let lookup = match Lookup::parse(&question.labels) {
+        Ok(lookup) => lookup,
+        Err(rcode) => {
+            response.rcode = rcode;
+            return Some(response.finish());
+        }
+    };
+    let members = match members_of(lookup.service, lookup.group, lookup.org) {
+        Some(members) => members,
+        None => {
+            response.rcode = Rcode::NxDomain;
+            return Some(response.finish());
+        }
+    };
+    let members = members.into_iter()
+                         .filter(|m| m.alive())
+                         .filter(|m| {
+                             match lookup.selection {
+                                 Selection::All => true,
+                                 Selection::Leader => m.leader,
+                                 Selection::Member(id) => m.member_id == id,
+                             }
+                         })
+                         .collect::<Vec<_>>();
+    if let Selection::Member(_) = lookup.selection {
+        if members.is_empty() {
+            response.rcode = Rcode::NxDomain;
+            return Some(response.finish());
+        }
+    }
+
+    match (question.qtype, lookup.export) {
+        (TYPE_SRV, export) => {
+            let export = export.unwrap_or("port");
+            for member in members {
+                if let Some(port) = export_port(member, export) {
+                    let target = lookup.member_name(&member.member_id);
+                    response.add_srv(port, &target);
+                    if let Some(ip) = member_ip(member) {
+                        response.add_address(&target, ip);
+                    }
+                }
+            }
+        }
+        (TYPE_A, None) | (TYPE_AAAA, None) | (TYPE_ANY, None) => {
+            for ip in members.into_iter().filter_map(member_ip) {
+                if question.qtype == TYPE_ANY || (question.qtype == TYPE_A) == ip.is_ipv4() {
+                    response.add_answer_address(ip);
+                }
+            }
+        }
+        _ => {}
+    }
+    Some(response.finish())
+}
+
+fn member_ip(member: &CensusMember) -> Option<IpAddr> { member.sys.ip.parse().ok() }
+
+/// The port a member exports under the given key, if it exports one
+fn export_port(member: &CensusMember, export: &str) -> Option<u16> {
+    match member.cfg.get(export)? {
+        toml::Value::Integer(port) => u16::try_from(*port).ok(),
+        toml::Value::String(port) => port.parse().ok(),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Selection<'a> {
+    All,
+    Leader,
+    Member(&'a str),
+}
+
+/// A name in the `svc.local` domain
+#[derive(Debug, PartialEq, Eq)]
+struct Lookup<'a> {
+    export:    Option<&'a str>,
+    selection: Selection<'a>,
+    service:   &'a str,
+    group:     &'a str,
+    org:       Option<&'a str>,
+}
+
+impl<'a> Lookup<'a> {
+    fn parse(labels: &'a [String]) -> Result<Self, Rcode> {
+        let domain = DNS_DOMAIN.split('.').collect::<Vec<_>>();
+        if labels.len() < domain.len()
+           || labels[labels.len() - domain.len()..].iter()
+                                                   .zip(&domain)
+                                                   .any(|(l, d)| l != d)
+        {
+            return Err(Rcode::Refused);
+        }
+        let mut labels = labels[..labels.len() - domain.len()].iter()
+                                                              .map(String::as_str)
+                                                              .collect::<Vec<_>>();
+        let mut export = None;
+        if labels.len() > 2 && labels[0].starts_with('_') && matches!(labels[1], "_tcp" | "_udp") {
+            export = Some(&labels[0][1..]);
+            labels.drain(..2);
+        }
+        // Names without an organization have at most three labels, so the longer names of an
+        // organization's service groups can't be mistaken for them
+        let mut org = None;
+        if labels.len() > 3 && labels[labels.len() - 1] == "org" {
+            org = Some(labels[labels.len() - 2]);
+            labels.truncate(labels.len() - 2);
+        }
+        let (selection, service, group) = match labels[..] {
+            [service, group] => (Selection::All, service, group),
+            ["leader", service, group] => (Selection::Leader, service, group),
+            [member_id, service, group] => (Selection::Member(member_id), service, group),
+            _ => return Err(Rcode::NxDomain),
+        };
+        Ok(Lookup { export,
+                    selection,
+                    service,
+                    group,
+                    org })
+    }
+
+    /// The name of a single member of the looked up service group
+    fn member_name(&self, member_id: &str) -> String {
+        match self.org {
+            Some(org) => {
+                format!("{}.{}.{}.{}.org.{}",
+                        member_id, self.service, self.group, org, DNS_DOMAIN)
+            }
+            None => {
+                format!("{}.{}.{}.{}",
+                        member_id, self.service, self.group, DNS_DOMAIN)
+            }
+        }
+    }
+}
+
+struct Header {
+    id:               u16,
+    flags:            u16,
+    question_count:   u16,
+    answer_count:     u16,
+    authority_count:  u16,
+    additional_count: u16,
+}
+
+impl Header {
+    const LEN: usize = 12;
+
+    fn parse(msg: &[u8]) -> Option<Self> {
+        if msg.len() < Self::LEN {
+            return None;
+        }
+        Some(Header { id:               u16::from_be_bytes([msg[0], msg[1]]),
+                      flags:            u16::from_be_bytes([msg[2], msg[3]]),
+                      question_count:   u16::from_be_bytes([msg[4], msg[5]]),
+                      answer_count:     u16::from_be_bytes([msg[6], msg[7]]),
+                      authority_count:  u16::from_be_bytes([msg[8], msg[9]]),
+                      additional_count: u16::from_be_bytes([msg[10], msg[11]]), })
+    }
+}
+
+struct Question {
+    /// The lowercased labels of the queried name
+    labels: Vec<String>,
+    qtype:  u16,
+    class:  u16,
+    /// The offset in the message just past the question
+    end:    usize,
+}
+
+impl Question {
+    fn parse(msg: &[u8], header: &Header) -> Option<Self> {
+        if header.question_count != 1 {
+            return None;
+        }
+        let mut labels = Vec::new();
+        let mut pos = Header::LEN;
+        loop {
+            let len = usize::from(*msg.get(pos)?);
+            pos += 1;
+            if len == 0 {
+                break;
+            }
+            // Compression pointers and extended label types never appear in a sole question.
+            if len > 63 {
+                return None;
+            }
+            let label = std::str::from_utf8(msg.get(pos..pos + len)?).ok()?;
+            labels.push(label.to_ascii_lowercase());
+            pos += len;
+        }
+        let fields = msg.get(pos..pos + 4)?;
+        Some(Question { labels,
+                        qtype: u16::from_be_bytes([fields[0], fields[1]]),
+                        class: u16::from_be_bytes([fields[2], fields[3]]),
+                        end: pos + 4 })
+    }
+}
+
+/// The EDNS pseudo-record of a query
+struct Edns {
+    udp_size: u16,
+    version:  u8,
+}
+
+impl Edns {
+    /// The upper bits of the extended rcode of a response to an unsupported EDNS version
+    const BADVERS: u8 = 1;
+
+    /// Returns the EDNS record of a query, which is the sole additional record following its
+    /// question
+    fn parse(msg: &[u8], header: &Header, question_end: usize) -> Option<Self> {
+        if header.answer_count != 0 || header.authority_count != 0 || header.additional_count != 1 {
+            return None;
+        }
+        // The name of the record is always the root
+        let record = msg.get(question_end..question_end + 11)?;
+        if record[0] != 0 || u16::from_be_bytes([record[1], record[2]]) != TYPE_OPT {
+            return None;
+        }
+        Some(Edns { udp_size: u16::from_be_bytes([record[3], record[4]]),
+                    version:  record[6], })
+    }
+
+    /// The EDNS record of a response
+    fn record(extended_rcode: u8) -> Vec<u8> {
+        let mut record = vec![0];
+        record.extend_from_slice(&TYPE_OPT.to_be_bytes());
+        record.extend_from_slice(&(MAX_EDNS_RESPONSE as u16).to_be_bytes());
+        record.extend_from_slice(&[extended_rcode, 0, 0, 0, 0, 0]);
+        record
+    }
+}
+
+struct Response<'a> {
+    id:             u16,
+    flags:          u16,
+    rcode:          Rcode,
+    extended_rcode: u8,
+    question:       Option<&'a [u8]>,
+    answers:        Vec<Vec<u8>>,
+    additional:     Vec<Vec<u8>>,
+    /// Whether the query used EDNS, which is then used in the response too
+    edns:           bool,
+    /// The size the response must fit in
+    limit:          usize,
+}
+
+impl<'a> Response<'a> {
+    /// A pointer to the name of the question, which directly follows the header
+    const QUESTION_NAME: [u8; 2] = [0xc0, Header::LEN as u8];
+
+    fn new(query: &Header) -> Self {
+        Response { id:             query.id,
+                   flags:          FLAG_RESPONSE
+                                   | FLAG_AUTHORITATIVE
+                                   | (query.flags & FLAG_RECURSION_DESIRED),
+                   rcode:          Rcode::NoError,
+                   extended_rcode: 0,
+                   question:       None,
+                   answers:        Vec::new(),
+                   additional:     Vec::new(),
+                   edns:           false,
+                   limit:          MAX_UDP_RESPONSE, }
+    }
+
+    fn add_answer_address(&mut self, ip: IpAddr) {
+        self.answers
+            .push(record(&Self::QUESTION_NAME, address_type(ip), &address_data(ip)));
+    }
+
+    fn add_srv(&mut self, port: u16, target: &str) {
+        // Priority and weight are equal for all members
+        let mut data = vec![0, 0, 0, 0];
+        data.extend_from_slice(&port.to_be_bytes());
+        data.extend(encode_name(target));
+        self.answers
+            .push(record(&Self::QUESTION_NAME, TYPE_SRV, &data));
+    }
+
+    fn add_address(&mut self, name: &str, ip: IpAddr) {
+        self.additional
+            .push(record(&encode_name(name), address_type(ip), &address_data(ip)));
+    }
+
+    /// The response as sent, leaving out the records that don't fit. Answers are kept over
+    /// additional records, and the response is only marked as truncated when answers are left out.
+    fn finish(self) -> Vec<u8> {
+        let question = self.question.unwrap_or_default();
+        let mut additional = Vec::new();
+        if self.edns {
+            additional.push(Edns::record(self.extended_rcode));
+        }
+        let mut len = Header::LEN + question.len() + additional.iter().map(Vec::len).sum::<usize>();
+        let mut flags = self.flags | self.rcode as u16;
+        let mut answers = Vec::new();
+        for record in self.answers {
+            if len + record.len() > self.limit {
+                flags |= FLAG_TRUNCATED;
+                break;
+            }
+            len += record.len();
+            answers.push(record);
+        }
+        if flags & FLAG_TRUNCATED == 0 {
+            for record in self.additional {
+                if len + record.len() <= self.limit {
+                    len += record.len();
+                    additional.push(record);
+                }
+            }
+        }
+
+        let mut msg = Vec::with_capacity(len);
+        msg.extend_from_slice(&self.id.to_be_bytes());
+        msg.extend_from_slice(&flags.to_be_bytes());
+        msg.extend_from_slice(&u16::from(self.question.is_some()).to_be_bytes());
+        msg.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+        msg.extend_from_slice(&0u16.to_be_bytes());
+        msg.extend_from_slice(&(additional.len() as u16).to_be_bytes());
+        msg.extend_from_slice(question);
+        for record in answers.iter().chain(additional.iter()) {
+            msg.extend_from_slice(record);
+        }
+        msg
+    }
+}
+
+fn record(name: &[u8], rtype: u16, data: &[u8]) -> Vec<u8> {
+    let mut record = name.to_vec();
+    record.extend_from_slice(&rtype.to_be_bytes());
+    record.extend_from_slice(&CLASS_IN.to_be_bytes());
+    record.extend_from_slice(&TTL.to_be_bytes());
+    record.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    record.extend_from_slice(data);
+    record
+}
+
+fn encode_name(name: &str) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(name.len() + 2);
+    for label in name.split('.') {
+        encoded.push(label.len() as u8);
+        encoded.extend_from_slice(label.as_bytes());
+    }
+    encoded.push(0);
+    encoded
+}
+
+fn address_type(ip: IpAddr) -> u16 {
+    match ip {
+        IpAddr::V4(_) => TYPE_A,
+        IpAddr::V6(_) => TYPE_AAAA,
+    }
+}
+
+fn address_data(ip: IpAddr) -> Vec<u8> {
+    match ip {
+        IpAddr::V4(ip) => ip.octets().to_vec(),
+        IpAddr::V6(ip) => ip.octets().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn member(id: &str, ip: &str, leader: bool, port: i64) -> CensusMember {
+        let mut member = CensusMember { member_id: id.to_string(),
+                                        service: "redis".to_string(),
+                                        group: "default".to_string(),
+                                        alive: true,
+                                        leader,
+                                        ..Default::default() };
+        member.sys.ip = ip.to_string();
+        member.cfg
+              .insert("port".to_string(), toml::Value::Integer(port));
+        member.cfg.insert("admin-port".to_string(),
+                          toml::Value::String("9000".to_string()));
+        member
+    }
+
+    fn query(name: &str, qtype: u16) -> Vec<u8> {
+        let mut msg = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        msg.extend(encode_name(name));
+        msg.extend_from_slice(&qtype.to_be_bytes());
+        msg.extend_from_slice(&CLASS_IN.to_be_bytes());
+        msg
+    }
+
+    /// Returns the rcode and the data of each answer record of a response
+    fn answers(members: &[CensusMember], name: &str, qtype: u16) -> (u16, Vec<Vec<u8>>) {
+        let response = respond_with(&query(name, qtype), |service, group, org| {
+                           if service == "redis" && group == "default" && org.is_none() {
+                               Some(members.iter().collect())
+                           } else {
+                               None
+                           }
+                       }).expect("query should be answered");
+        assert_eq!([0x12, 0x34], response[..2]);
+        let rcode = u16::from_be_bytes([response[2], response[3]]) & 0x000f;
+        let count = u16::from_be_bytes([response[6], response[7]]);
+        let mut pos = query(name, qtype).len();
+        let mut records = Vec::new();
+        for _ in 0..count {
+            // Answers always point at the question's name
+            assert_eq!(Response::QUESTION_NAME, response[pos..pos + 2]);
+            let len = usize::from(u16::from_be_bytes([response[pos + 10], response[pos + 11]]));
+            records.push(response[pos + 12..pos + 12 + len].to_vec());
+            pos += 12 + len;
+        }
+        (rcode, records)
+    }
+
+    fn census() -> Vec<CensusMember> {
+        let mut departed = member("departed", "10.0.0.4", false, 6379);
+        departed.alive = false;
+        departed.departed = true;
+        vec![member("one", "10.0.0.1", true, 6379),
+             member("two", "10.0.0.2", false, 6380),
+             member("three", "fd00::3", false, 6381),
+             departed]
+    }
+
+    #[test]
+    fn answers_addresses_of_alive_members() {
+        let census = census();
+        assert_eq!((0, vec![vec![10, 0, 0, 1], vec![10, 0, 0, 2]]),
+                   answers(&census, "redis.default.svc.local", TYPE_A));
+        assert_eq!((0, vec![vec![10, 0, 0, 1]]),
+                   answers(&census, "leader.redis.default.svc.local", TYPE_A));
+        assert_eq!((0, vec![vec![10, 0, 0, 2]]),
+                   answers(&census, "TWO.Redis.Default.svc.local", TYPE_A));
+        let (rcode, records) = answers(&census, "redis.default.svc.local", TYPE_AAAA);
+        assert_eq!(0, rcode);
+        assert_eq!(vec!["fd00::3".parse::<std::net::Ipv6Addr>()
+                                 .unwrap()
+                                 .octets()
+                                 .to_vec()],
+                   records);
+    }
+
+    #[test]
+    fn answers_srv_records_from_exports() {
+        let census = census();
+        let mut expected = vec![0, 0, 0, 0, 0x18, 0xeb];
+        expected.extend(encode_name("one.redis.default.svc.local"));
+        let (rcode, records) = answers(&census, "leader.redis.default.svc.local", TYPE_SRV);
+        assert_eq!((0, vec![expected]), (rcode, records));
+
+        let (_, records) = answers(&census,
+                                   "_admin-port._tcp.redis.default.svc.local",
+                                   TYPE_SRV);
+        assert_eq!(3, records.len());
+        assert!(records.iter().all(|r| r[4..6] == [0x23, 0x28]));
+
+        let (_, records) = answers(&census, "_missing._tcp.redis.default.svc.local", TYPE_SRV);
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        let census = census();
+        assert_eq!((Rcode::NxDomain as u16, vec![]),
+                   answers(&census, "memcached.default.svc.local", TYPE_A));
+        assert_eq!((Rcode::NxDomain as u16, vec![]),
+                   answers(&census, "departed.redis.default.svc.local", TYPE_A));
+        assert_eq!((Rcode::NxDomain as u16, vec![]),
+                   answers(&census, "svc.local", TYPE_A));
+        assert_eq!((Rcode::Refused as u16, vec![]),
+                   answers(&census, "redis.default.example.com", TYPE_A));
+    }
+
+    #[test]
+    fn resolves_service_groups_of_organizations() {
+        let members = census();
+        let response =
+            respond_with(&query("leader.redis.default.acme.org.svc.local", TYPE_SRV),
+                         |service, group, org| {
+                             if (service, group, org) == ("redis", "default", Some("acme")) {
+                                 Some(members.iter().collect())
+                             } else {
+                                 None
+                             }
+                         }).expect("query should be answered");
+        let target = encode_name("one.redis.default.acme.org.svc.local");
+        assert!(response.windows(target.len()).any(|w| w == &target[..]));
+
+        assert_eq!((Rcode::NxDomain as u16, vec![]),
+                   answers(&members, "redis.default.acme.org.svc.local", TYPE_A));
+    }
+
+    /// Returns the flags, answer count and additional record count of a response to a query
+    /// for the `SRV` records of a service group with `size` members
+    fn srv_response(size: usize, edns: Option<u16>) -> (u16, u16, u16) {
+        let members = (0..size).map(|i| {
+                                   member(&format!("member{:02}", i),
+                                          &format!("10.0.0.{}", i),
+                                          false,
+                                          6379)
+                               })
+                               .collect::<Vec<_>>();
+        let mut msg = query("redis.default.svc.local", TYPE_SRV);
+        if let Some(udp_size) = edns {
+            msg[11] = 1;
+            msg.extend_from_slice(&[0, 0, 41]);
+            msg.extend_from_slice(&udp_size.to_be_bytes());
+            msg.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        }
+        let response = respond_with(&msg, |_, _, _| Some(members.iter().collect())).unwrap();
+        assert!(response.len() <= usize::from(edns.unwrap_or(512)).clamp(512, 1232));
+        (u16::from_be_bytes([response[2], response[3]]),
+         u16::from_be_bytes([response[6], response[7]]),
+         u16::from_be_bytes([response[10], response[11]]))
+    }
+
+    #[test]
+    fn leaves_out_the_records_that_dont_fit() {
+        // Every record fits
+        let (flags, answers, additional) = srv_response(3, None);
+        assert_eq!((0, 3, 3), (flags & FLAG_TRUNCATED, answers, additional));
+        // Addresses of the targets are left out before any answer
+        let (flags, answers, additional) = srv_response(8, None);
+        assert_eq!(0, flags & FLAG_TRUNCATED);
+        assert_eq!(8, answers);
+        assert!(additional < 8);
+        // Answers are only left out when they don't fit on their own
+        let (flags, answers, additional) = srv_response(16, None);
+        assert_ne!(0, flags & FLAG_TRUNCATED);
+        assert!(answers > 0 && answers < 16);
+        assert_eq!(0, additional);
+    }
+
+    #[test]
+    fn honors_the_edns_payload_size() {
+        // The EDNS record of the response is counted as an additional record
+        let (flags, answers, additional) = srv_response(8, Some(4096));
+        assert_eq!((0, 8, 9), (flags & FLAG_TRUNCATED, answers, additional));
+        // Sizes below the minimum are raised to it
+        let (flags, answers, additional) = srv_response(16, Some(256));
+        assert_ne!(0, flags & FLAG_TRUNCATED);
+        assert_eq!((8, 1), (answers, additional));
+
+        let mut msg = query("redis.default.svc.local", TYPE_A);
+        msg[11] = 1;
+        msg.extend_from_slice(&[0, 0, 41, 0x10, 0, 0, 1, 0, 0, 0, 0]);
+        let response = respond_with(&msg, |_, _, _| Some(Vec::new())).unwrap();
+        assert_eq!([0, 0, 0, 0, 0, 1], response[6..12]);
+        let opt = &response[msg.len() - 11..];
+        assert_eq!(TYPE_OPT, u16::from_be_bytes([opt[1], opt[2]]));
+        assert_eq!(Edns::BADVERS, opt[5]);
+    }
+
+    #[test]
+    fn ignores_malformed_queries() {
+        let members_of = |_: &str, _: &str, _: Option<&str>| None;
+        assert!(respond_with(&[0x12, 0x34, 0x01], members_of).is_none());
+
+        let mut response = query("redis.default.svc.local", TYPE_A);
+        response[2] |= 0x80;
+        assert!(respond_with(&response, members_of).is_none());
+
+        let truncated = query("redis.default.svc.local", TYPE_A);
+        let response = respond_with(&truncated[..truncated.len() - 2], members_of).unwrap();
+        assert_eq!(Rcode::FormErr as u8, response[3] & 0x0f);
+    }
+}
@@ -0,0 +1,85 @@
+//! Periodically audits the packages of running services against the checksums recorded when they
+//! were built.
+
+use super::ManagerState;
+use biome_common::outputln;
+use biome_core::{crypto::keys::KeyCache,
+                   fs::FS_ROOT_PATH,
+                   package::{PackageAudit,
+                             PackageIdent,
+                             PackageInstall}};
+use log::{debug,
+          error,
+          warn};
+use std::{collections::HashSet,
+          sync::Arc,
+          time::Duration};
+use tokio::{task,
+            time as tokiotime};
+
+static LOGKEY: &str = "PA";
+
+/// Spawn a task that audits the packages of all running services, including their transitive
+/// dependencies, every `period`. The results are published on the HTTP gateway.
+pub fn spawn(state: Arc<ManagerState>, period: Duration) {
+    tokio::spawn(async move {
+        loop {
+            tokiotime::sleep(period).await;
+            let idents = running_packages(&state);
+            let key_cache = state.cfg.key_cache.clone();
+            match task::spawn_blocking(move || audit(&idents, &key_cache)).await {
+                Ok(audits) => {
+                    state.gateway_state
+                         .lock_gsw()
+                         .set_package_audit_data(audits)
+                }
+                Err(e) => error!("Package audit task failed: {}", e),
+            }
+        }
+    });
+}
+
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+fn running_packages(state: &ManagerState) -> HashSet<PackageIdent> {
+    let mut idents = HashSet::new();
+    for service in state.services.lock_msr().running_services() {
+        let ident = service.pkg.ident.as_ref();
+        match PackageInstall::load(ident, Some(&*FS_ROOT_PATH)).and_then(|i| i.tdeps()) {
+            Ok(tdeps) => idents.extend(tdeps),
+            Err(e) => warn!("Unable to determine dependencies of {}: {}", ident, e),
+        }
+        idents.insert(ident.clone());
+    }
+    idents
+}
+
+fn audit(idents: &HashSet<PackageIdent>, key_cache: &KeyCache) -> Vec<PackageAudit> {
+    let mut idents = idents.iter().collect::<Vec<_>>();
+    idents.sort_by(|a, b| a.by_parts_cmp(b));
+    let mut audits = Vec::new();
+    for ident in idents {
+        let result =
+            PackageInstall::load(ident, Some(&*FS_ROOT_PATH)).and_then(|install| {
+                                                                 PackageAudit::run(&install,
+                                                                                   key_cache)
+                                                             });
+        match result {
+            Ok(audit) if audit.is_clean() => {
+                debug!("Package {} passed the integrity audit", ident);
+                audits.push(audit);
+            }
+            Ok(audit) => {
+                outputln!("Package {} failed the integrity audit: {} modified, {} missing, {} \
+                           extra file(s)",
+                          ident,
+                          audit.modified.len(),
+                          audit.missing.len(),
+                          audit.extra.len());
+                audits.push(audit);
+            }
+            Err(e) => warn!("Unable to audit package {}: {}", ident, e),
+        }
+    }
+    audits
+}
@@ -2,15 +2,27 @@
 //! itself.
 
 use crate::util;
-use biome_common::command::package::install::InstallSource;
-use biome_core::{package::{PackageIdent,
+use biome_common::{command::package::install::InstallSource,
+                     outputln};
+use biome_core::{env,
+                   fs::{launcher_root_path,
+                        FS_ROOT_PATH},
+                   package::{Identifiable,
+                             PackageIdent,
                              PackageInstall},
+                   schedule::Schedule,
                    ChannelIdent};
+use biome_launcher_protocol::{SUP_REJECTED_RELEASE_FILE,
+                                SUP_UPDATE_PREVIOUS_ENV,
+                                SUP_UPDATE_ROLLED_BACK_ENV,
+                                SUP_UPDATE_UPDATED_ENV};
 use log::{debug,
           trace,
           warn};
 use rand::Rng;
+use serde::Serialize;
 use std::{borrow::Borrow,
+          fs,
           time::Duration};
 use tokio::{self,
             sync::oneshot::{self,
@@ -21,6 +33,8 @@ use tokio::{self,
 
 pub const SUP_PKG_IDENT: &str = "biome/bio-sup";
 
+static LOGKEY: &str = "SLU";
+
 // TODO (DM): Remove this deprecated env var
 const DEFAULT_PERIOD: Duration = Duration::from_secs(60);
 biome_core::env_config_duration!(
@@ -29,6 +43,61 @@ biome_core::env_config_duration!(
     HAB_SUP_UPDATE_MS => from_millis,
     DEFAULT_PERIOD);
 
+biome_core::env_config_duration!(
+    /// How long an updated Supervisor has to become ready before the Launcher rolls it back.
+    SelfUpdateReadyTimeout,
+    HAB_SUP_UPDATE_READY_TIMEOUT_SECS => from_secs,
+    Duration::from_secs(120));
+
+/// How long an updated Supervisor has to become ready before the Launcher falls back to the
+/// release it was updated from.
+pub fn ready_timeout() -> Duration { SelfUpdateReadyTimeout::configured_value().into() }
+
+/// The outcome of the self-update this Supervisor was started for, as reported by the Launcher.
+#[derive(Clone, Debug, Serialize)]
+pub struct SelfUpdateOutcome {
+    /// The Supervisor release that was updated from
+    pub previous:    String,
+    /// The Supervisor release that was updated to
+    pub updated:     String,
+    /// Whether the updated Supervisor did not become ready, so that the Launcher fell back to
+    /// the previous release
+    pub rolled_back: bool,
+}
+
+impl SelfUpdateOutcome {
+    /// The outcome the Launcher set in this Supervisor's environment, if it was started for a
+    /// self-update.
+    pub fn from_env() -> Option<Self> {
+        let previous = env::var(SUP_UPDATE_PREVIOUS_ENV).ok()?;
+        let updated = env::var(SUP_UPDATE_UPDATED_ENV).ok()?;
+        Some(SelfUpdateOutcome { previous,
+                                 updated,
+                                 rolled_back: env::var(SUP_UPDATE_ROLLED_BACK_ENV).is_ok() })
+    }
+
+    /// The Supervisor release that failed to become ready, if the update was rolled back.
+    pub fn rejected(&self) -> Option<PackageIdent> {
+        if self.rolled_back {
+            self.updated.parse().ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// The last Supervisor release the Launcher rolled back from, as recorded next to its PID file.
+/// Unlike the outcome in the environment, the record survives restarts of the Launcher and of the
+/// Supervisor.
+pub fn recorded_rejected_release() -> Option<PackageIdent> {
+    let path = launcher_root_path(Some(&*FS_ROOT_PATH)).join(SUP_REJECTED_RELEASE_FILE);
+    fs::read_to_string(path).ok()?
+                            .trim()
+                            .parse()
+                            .ok()
+                            .filter(|ident: &PackageIdent| ident.fully_qualified())
+}
+
 impl SelfUpdatePeriod {
     fn get() -> Option<Duration> {
         #[allow(clippy::question_mark)]
@@ -42,11 +111,19 @@ impl SelfUpdatePeriod {
 }
 
 pub struct SelfUpdater {
-    rx:             Receiver<PackageInstall>,
+    rx:             Receiver<PackageIdent>,
     current:        PackageIdent,
     update_url:     String,
     update_channel: ChannelIdent,
     period:         Duration,
+    /// The minutes in which a newer Supervisor is switched to
+    window:         Option<Schedule>,
+    /// A newer Supervisor waiting for the update window to open before it is installed
+    pending:        Option<PackageIdent>,
+    /// Whether the pending or next update is applied regardless of the update window
+    forced:         bool,
+    /// A Supervisor release that was rolled back and must not be updated to again
+    rejected:       Option<PackageIdent>,
 }
 
 /// The subset of data from `SelfUpdater` needed to spawn the updater task.
@@ -55,6 +132,7 @@ struct Runner {
     update_url:     String,
     update_channel: ChannelIdent,
     period:         Duration,
+    rejected:       Option<PackageIdent>,
 }
 
 impl<T: Borrow<SelfUpdater>> From<T> for Runner {
@@ -63,7 +141,8 @@ impl<T: Borrow<SelfUpdater>> From<T> for Runner {
         Self { current:        other.current.clone(),
                update_url:     other.update_url.clone(),
                update_channel: other.update_channel.clone(),
-               period:         other.period, }
+               period:         other.period,
+               rejected:       other.rejected.clone(), }
     }
 }
 
@@ -71,35 +150,44 @@ impl SelfUpdater {
     pub fn new(current: &PackageIdent,
                update_url: String,
                update_channel: ChannelIdent,
-               period: Duration)
+               period: Duration,
+               window: Option<Schedule>,
+               rejected: Option<PackageIdent>)
                -> Self {
         let runner = Runner { current: current.clone(),
                               update_url: update_url.clone(),
                               update_channel: update_channel.clone(),
-                              period };
+                              period,
+                              rejected: rejected.clone() };
         let rx = Self::init(runner);
         SelfUpdater { rx,
                       current: current.clone(),
                       update_url,
                       update_channel,
-                      period }
+                      period,
+                      window,
+                      pending: None,
+                      forced: false,
+                      rejected }
     }
 
     /// Spawn a new Supervisor updater task.
-    fn init(runner: Runner) -> Receiver<PackageInstall> {
+    fn init(runner: Runner) -> Receiver<PackageIdent> {
         let (tx, rx) = oneshot::channel();
         tokio::spawn(Self::run(tx, runner));
         rx
     }
 
-    async fn run(tx: Sender<PackageInstall>, runner: Runner) {
-        // SUP_PKG_IDENT will always parse as a valid PackageIdent,
-        // and thus a valid InstallSource
-        let install_source: InstallSource = SUP_PKG_IDENT.parse().unwrap();
+    /// Check the channel for a newer Supervisor without installing it, so that a release is only
+    /// installed once it is switched to and a rejected release never reaches the disk again.
+    async fn run(tx: Sender<PackageIdent>, runner: Runner) {
+        // SUP_PKG_IDENT will always parse as a valid PackageIdent
+        let sup_ident: PackageIdent = SUP_PKG_IDENT.parse().unwrap();
         let Runner { current,
                      update_url,
                      update_channel,
-                     period, } = runner;
+                     period,
+                     rejected, } = runner;
         let period = SelfUpdatePeriod::get().unwrap_or(period);
         let splay = Duration::from_secs(rand::thread_rng().gen_range(0..period.as_secs()));
         debug!("Starting self updater with current package {} in {}s",
@@ -107,12 +195,16 @@ impl SelfUpdater {
                splay.as_secs());
         tokiotime::sleep(splay).await;
         loop {
-            match util::pkg::install_no_ui(&update_url, &install_source, &update_channel).await {
-                Ok(package) => {
-                    if &current < package.ident() {
-                        debug!("Self updater installing newer Supervisor, {}",
-                               package.ident());
-                        tx.send(package).expect("Main thread has gone away!");
+            match util::pkg::channel_head(&update_url, &sup_ident, &update_channel).await {
+                Ok(head) => {
+                    if rejected.as_ref()
+                               .map_or(false, |rejected| &head <= rejected)
+                    {
+                        debug!("Self updater skipping Supervisor {}, which was rolled back",
+                               head);
+                    } else if current < head {
+                        debug!("Self updater found newer Supervisor, {}", head);
+                        tx.send(head).expect("Main thread has gone away!");
                         break;
                     } else {
                         debug!("Supervisor package found is not newer than ours");
@@ -127,15 +219,55 @@ impl SelfUpdater {
         }
     }
 
+    /// Returns a newer Supervisor package to switch to. A newer Supervisor found outside of the
+    /// update window is neither installed nor switched to until the window opens, unless the
+    /// update was forced.
     pub async fn updated(&mut self) -> Option<PackageInstall> {
-        match self.rx.try_recv() {
-            Ok(package) => Some(package),
-            Err(TryRecvError::Empty) => None,
-            Err(TryRecvError::Closed) => {
-                debug!("Self updater has died, restarting...");
+        if self.pending.is_none() {
+            match self.rx.try_recv() {
+                Ok(ident) => {
+                    if let Some(window) = self.window.as_ref().filter(|_| !self.window_open()) {
+                        outputln!("Deferring Supervisor update to {} until its update window \
+                                   '{}' opens",
+                                  ident,
+                                  window);
+                    }
+                    self.pending = Some(ident);
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Closed) => {
+                    debug!("Self updater has died, restarting...");
+                    self.rx = Self::init(self.into());
+                }
+            }
+        }
+        if !self.window_open() {
+            return None;
+        }
+        let ident = self.pending.take()?;
+        let install_source: InstallSource = ident.clone().into();
+        match util::pkg::install_no_ui(&self.update_url, &install_source, &self.update_channel).await
+        {
+            Ok(package) => {
+                self.forced = false;
+                Some(package)
+            }
+            Err(err) => {
+                warn!("Self updater failed to install Supervisor {}, {}", ident, err);
                 self.rx = Self::init(self.into());
                 None
             }
         }
     }
+
+    /// Switch to the pending or next newer Supervisor as soon as it is found, even outside of the
+    /// update window. Returns the pending Supervisor package, if there is one.
+    pub fn force_update(&mut self) -> Option<&PackageIdent> {
+        self.forced = true;
+        self.pending.as_ref()
+    }
+
+    fn window_open(&self) -> bool {
+        self.forced || self.window.as_ref().map_or(true, Schedule::matches_now)
+    }
 }
@@ -8,10 +8,12 @@
 // here. Ideally, those would exist only at the periphery of the
 // system, and we'd use separate internal types for our core logic.
 
+mod cfg_history;
 mod context;
 mod health;
 mod hook_runner;
 mod hooks;
+mod job;
 #[cfg(windows)]
 mod pipe_hook_client;
 pub mod spec;
@@ -22,11 +24,16 @@ use self::{context::RenderContext,
            hook_runner::HookRunner,
            hooks::{HookCompileTable,
                    HookTable,
-                   HookTableQueryModel},
+                   HookTableQueryModel,
+                   RenderedHooks},
+           job::{JobRun,
+                 JobState},
            supervisor::{PidUpdate,
                         SupervisedProcessQueryModel,
                         Supervisor}};
-pub use self::{health::{HealthCheckBundle,
+pub use self::{cfg_history::{CfgHistory,
+                             CfgHistoryEntry},
+               health::{HealthCheckBundle,
                         HealthCheckHookStatus,
                         HealthCheckResult},
                hooks::{HealthCheckHook,
@@ -41,11 +48,13 @@ use crate::{census::{CensusGroup,
             error::{Error,
                     Result},
             manager::{event,
+                      service_updater::rollout::RolloutProgress,
                       sync::GatewayState,
                       FsCfg,
                       ServicePidSource,
                       ShutdownConfig,
                       Sys}};
+use chrono::Utc;
 use futures::future::{self,
                       AbortHandle};
 use biome_butterfly::rumor::service::Service as ServiceRumor;
@@ -56,9 +65,11 @@ pub use biome_common::templating::{config::{Cfg,
                                      package::{Env,
                                                Pkg}};
 use biome_common::{outputln,
-                     templating::{config::CfgRenderer,
+                     templating::{config::{CfgRenderer,
+                                           RenderedCfg},
                                   hooks::Hook,
-                                  package::PkgQueryModel},
+                                  package::PkgQueryModel,
+                                  TemplateRenderer},
                      FeatureFlag};
 #[cfg(windows)]
 use biome_core::os::users;
@@ -73,9 +84,14 @@ use biome_core::{crypto::Blake2bHash,
                    package::{metadata::Bind,
                              PackageIdent,
                              PackageInstall},
+                   schedule::Schedule,
                    service::{HealthCheckInterval,
+                             JobConcurrency,
+                             JobSchedule,
                              ServiceBind,
-                             ServiceGroup},
+                             ServiceGroup,
+                             UpdateBatchSize},
+                   util::text_diff,
                    ChannelIdent};
 use biome_launcher_client::LauncherCli;
 use biome_sup_protocol::types::BindingMode;
@@ -99,14 +115,18 @@ use std::{self,
           convert::TryFrom,
           fmt,
           fs,
+          future::Future,
           ops::Deref,
           path::{Path,
                  PathBuf},
           result,
-          sync::{Arc,
+          sync::{atomic::{AtomicBool,
+                          Ordering},
+                 Arc,
                  Mutex},
           time::{Duration,
                  SystemTime}};
+use tokio::time;
 
 use super::ServiceRestartConfig;
 use lazy_static::lazy_static;
@@ -134,10 +154,18 @@ enum BindStatus<'a> {
     /// The bound group is present in the census, but has no active
     /// members.
     Empty,
+    /// The bound group is present in the census, but none of its
+    /// alive members are ready to serve. Only services with a
+    /// "strict" binding mode wait for ready members.
+    NotReady,
     /// The bound group is present in the census, has active members,
     /// but does not satisfy the contract of the bind; the set of
     /// unsatisfied exports is returned.
     Unsatisfied(HashSet<&'a String>),
+    /// The bound group is present in the census, has active members,
+    /// but some of them run a package the bind doesn't accept; those
+    /// packages are returned.
+    Incompatible(Vec<String>),
     /// The bound group is present, has active members, and fully
     /// satisfies the contract of the bind.
     Satisfied,
@@ -188,6 +216,18 @@ impl TemplateUpdate {
     }
 }
 
+/// The files and hooks that a candidate configuration would change, and how the service would
+/// react to it.
+#[derive(Debug, Default)]
+pub struct CfgDryRun {
+    /// Unified diffs of the configuration files and hooks that would change
+    pub diffs:          Vec<String>,
+    /// File names of the hooks that would change
+    pub changed_hooks:  Vec<&'static str>,
+    pub reconfigure:    bool,
+    pub restart_reason: Option<ProcessTerminationReason>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum InitializationState {
     Uninitialized,
@@ -226,6 +266,23 @@ pub enum ProcessTerminationReason {
     PostRunHookUpdated,
 }
 
+impl fmt::Display for ProcessTerminationReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            ProcessTerminationReason::PackageUpdated => "the package was updated",
+            ProcessTerminationReason::InitHookFailed => "the init hook failed",
+            ProcessTerminationReason::RunHookFailed => "the run hook failed",
+            ProcessTerminationReason::AppConfigUpdated => {
+                "the configuration changed and there is no reconfigure hook"
+            }
+            ProcessTerminationReason::InitHookUpdated => "the init hook changed",
+            ProcessTerminationReason::RunHookUpdated => "the run hook changed",
+            ProcessTerminationReason::PostRunHookUpdated => "the post-run hook changed",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LastProcessState {
     pub pid:                Option<Pid>,
@@ -264,6 +321,11 @@ pub struct ServiceRunState {
     restart_state:          RestartState,
     restart_backoff:        Backoff,
     last_updated_at:        SystemTime,
+    /// The releases an update restarted the service from and to, until the `post-update` hook
+    /// runs for it
+    post_update:            Option<(PackageIdent, PackageIdent)>,
+    /// When a job service runs next and how its last runs went
+    job:                    JobState,
 }
 
 impl ServiceRunState {
@@ -276,7 +338,9 @@ impl ServiceRunState {
                           restart_backoff:    Backoff::new(restart_config.min_backoff_period,
                                                            restart_config.max_backoff_period,
                                                            3f64),
-                          last_updated_at:    SystemTime::now(), }
+                          last_updated_at:    SystemTime::now(),
+                          post_update:        None,
+                          job:                JobState::default(), }
     }
 
     pub fn mark_for_restart(&mut self,
@@ -342,6 +406,14 @@ impl PersistentServiceWrapper {
                                         timestamp);
     }
 
+    /// Run the `post-update` hook once the service has restarted with the `updated` release.
+    pub fn expect_post_update(&mut self, previous: PackageIdent, updated: PackageIdent) {
+        self.run_state.post_update = Some((previous, updated));
+    }
+
+    /// Run this job service as soon as possible, in addition to its schedule.
+    pub fn request_job_run(&mut self) { self.run_state.job.request(); }
+
     pub fn service(&self) -> Option<&Service> { self.inner.as_ref() }
 
     pub fn service_mut(&mut self) -> Option<&mut Service> { self.inner.as_mut() }
@@ -459,6 +531,8 @@ pub struct Service {
     // given service.
     spec_file:               PathBuf,
     pub cfg:                 Cfg,
+    /// The configurations most recently applied to this service through gossip
+    pub cfg_history:         CfgHistory,
     pub pkg:                 Pkg,
     pub sys:                 Arc<Sys>,
     pub user_config_updated: bool,
@@ -479,6 +553,15 @@ pub struct Service {
     // hook, we need to wrap some Arc<Mutex<_>> protection around it
     // :(
     health_check_result:  Arc<Mutex<HealthCheckResult>>,
+    /// Whether the `ready` hook last reported the service as ready to
+    /// serve. Only consulted when the service has a `ready` hook.
+    ready_hook_result:    Arc<AtomicBool>,
+    /// The readiness last computed by `tick`, so that a change can
+    /// be gossiped.
+    last_ready:           bool,
+    /// Whether the service is draining ahead of being stopped. A
+    /// draining service is not ready.
+    draining:             bool,
     last_election_status: ElectionStatus,
     /// The binds that the current service package declares, both
     /// required and optional. We don't differentiate because this is
@@ -527,6 +610,38 @@ impl Service {
 
     pub(crate) fn shutdown_timeout(&self) -> Option<ShutdownTimeout> { self.spec.shutdown_timeout }
 
+    pub(crate) fn rollback_window(&self) -> Option<Duration> {
+        self.spec
+            .rollback_window
+            .map(|window| Duration::from_secs(window.into()))
+    }
+
+    /// How many members a canary update updates before waiting for approval.
+    pub(crate) fn canary_count(&self) -> u32 { self.spec.canary_count.unwrap_or(1) }
+
+    pub(crate) fn update_batch_size(&self) -> UpdateBatchSize {
+        self.spec.update_batch_size.unwrap_or_default()
+    }
+
+    pub(crate) fn max_unavailable(&self) -> Option<u32> { self.spec.max_unavailable }
+
+    pub(crate) fn update_window(&self) -> Option<&Schedule> { self.spec.update_window.as_ref() }
+
+    pub(crate) fn hold(&self) -> Option<&PackageIdent> { self.spec.hold.as_ref() }
+
+    pub(crate) fn pinned(&self) -> Option<&PackageIdent> { self.spec.pinned.as_ref() }
+
+    pub(crate) fn rejected_release(&self) -> Option<&PackageIdent> {
+        self.spec.rejected_release.as_ref()
+    }
+
+    /// The result of the last health check run for this service.
+    pub(crate) fn health_check_result(&self) -> HealthCheckResult {
+        *self.health_check_result
+             .lock()
+             .expect("Could not unlock service_health_result")
+    }
+
     pub(crate) fn spec(&self) -> ServiceSpec { self.spec.clone() }
 
     pub(crate) fn set_spec(&mut self, spec: ServiceSpec) {
@@ -556,16 +671,27 @@ impl Service {
         let config_root = Self::config_root(&pkg, spec.config_from.as_ref());
         let hooks_root = Self::hooks_root(&pkg, spec.config_from.as_ref());
         let cfg = Cfg::new(&pkg, spec.config_from.as_ref())?;
+        let strict_templates = spec.strict_templates;
+        let config_renderer = if strict_templates {
+            CfgRenderer::with_renderer(config_root, TemplateRenderer::strict())?
+        } else {
+            CfgRenderer::new(config_root)?
+        };
         let mut service =
             Service { spec,
                       sys,
                       cfg,
-                      config_renderer: CfgRenderer::new(config_root)?,
+                      cfg_history: CfgHistory::default(),
+                      config_renderer,
                       health_check_result: Arc::new(Mutex::new(HealthCheckResult::Unknown)),
+                      ready_hook_result: Arc::new(AtomicBool::new(false)),
+                      last_ready: false,
+                      draining: false,
                       hooks: HookTable::load(&pkg.name,
                                              hooks_root,
                                              svc_hooks_path(service_group.service()),
-                                             feature_flags),
+                                             feature_flags,
+                                             strict_templates),
                       last_election_status: ElectionStatus::None,
                       user_config_updated: false,
                       initialization_state:
@@ -645,9 +771,19 @@ impl Service {
                      pid_source: ServicePidSource,
                      feature_flags: FeatureFlag)
                      -> Result<Service> {
-        // The package for a spec should already be installed.
+        // The package for a spec should already be installed. A held service keeps running the
+        // release it is held at even if newer releases are installed, and a pinned service keeps
+        // running the release it ran before an update was installed for as long as it is there.
         let fs_root_path = Path::new(&*FS_ROOT_PATH);
-        let package = PackageInstall::load(&spec.ident, Some(fs_root_path))?;
+        let pinned = spec.pinned
+                         .as_ref()
+                         .and_then(|ident| PackageInstall::load(ident, Some(fs_root_path)).ok());
+        let package = match (&spec.hold, pinned) {
+            (None, Some(package)) => package,
+            (hold, _) => {
+                PackageInstall::load(hold.as_ref().unwrap_or(&spec.ident), Some(fs_root_path))?
+            }
+        };
         Self::with_package(sys,
                            &package,
                            spec,
@@ -689,12 +825,41 @@ impl Service {
         *self.initialization_state.read() == InitializationState::Initialized
     }
 
+    /// Whether the service is ready to serve: it is not draining, it
+    /// is initialized, its process is up, and its `ready` hook, if it
+    /// has one, last succeeded. A job service is ready once it is
+    /// initialized, since its process is only up while a run is going.
+    fn ready(&self) -> bool {
+        let process_state = self.supervisor
+                                .lock()
+                                .expect("Couldn't lock supervisor")
+                                .status();
+        self.ready_with(process_state)
+    }
+
+    /// Same as `ready`, for callers that already hold the supervisor
+    /// lock and read the `process_state` from it.
+    fn ready_with(&self, process_state: ProcessState) -> bool {
+        if self.draining {
+            return false;
+        }
+        if self.spec.job.is_some() {
+            return self.initialized();
+        }
+        self.initialized()
+        && process_state == ProcessState::Up
+        && (self.hooks.ready.is_none() || self.ready_hook_result.load(Ordering::Relaxed))
+    }
+
     /// Initiate an endless task that performs periodic health checks for the service and takes
     /// appropriate actions upon receiving the results of a health check. The actions taken are:
     ///
     /// * Cache the health check result for this service
     /// * Set the health check result for this service in the gateway state
     /// * Send a `HealthCheckEvent` over the event stream
+    ///
+    /// If the service has a `ready` hook, it is run on the same
+    /// interval to track whether the service is ready to serve.
     fn start_health_checks(&mut self) {
         debug!("Starting health checks for {}", self.pkg.ident);
         let mut rx = health::check_repeatedly(Arc::clone(&self.supervisor),
@@ -730,7 +895,15 @@ impl Service {
                 event::health_check(service_event_metadata.clone(), result, status, interval);
             }
         };
-        let (f, handle) = future::abortable(f);
+        self.ready_hook_result.store(false, Ordering::Relaxed);
+        let readiness =
+            health::check_readiness_repeatedly(self.hooks.ready.clone(),
+                                               self.spec.health_check_interval,
+                                               self.service_group.clone(),
+                                               self.pkg.clone(),
+                                               self.spec.svc_encrypted_password.clone(),
+                                               Arc::clone(&self.ready_hook_result));
+        let (f, handle) = future::abortable(future::join(f, readiness));
         self.health_check_handle = Some(handle);
         tokio::spawn(f);
     }
@@ -763,7 +936,9 @@ impl Service {
     fn reattach(&mut self) {
         outputln!("Reattaching to {}", self.service_group);
         *self.initialization_state.write() = InitializationState::Initialized;
-        self.restart_health_checks();
+        if self.spec.job.is_none() {
+            self.restart_health_checks();
+        }
         // We intentionally do not restart the `post_run` retry future. Currently, there is not
         // a way to track if `post_run` ran successfully following a Supervisor restart.
         // See https://github.com/habitat-sh/habitat/issues/6739
@@ -787,12 +962,49 @@ impl Service {
         self.stop_health_checks();
     }
 
+    /// Mark the service as draining ahead of it being stopped, so that
+    /// it is reported as not ready. Returns whether the service drains
+    /// at all, which it does when it has a `pre-stop` hook or a drain
+    /// timeout.
+    pub fn start_draining(&mut self) -> bool {
+        self.draining = self.hooks.pre_stop.is_some() || self.spec.drain_timeout.is_some();
+        self.draining
+    }
+
+    /// Drain the service by running its `pre-stop` hook for at most
+    /// the drain timeout, or the `shutdown_timeout` when there is none.
+    /// Without a `pre-stop` hook, the service drains for the whole
+    /// drain timeout.
+    async fn drain(&self, shutdown_timeout: ShutdownTimeout) {
+        let drain_timeout = self.spec
+                                .drain_timeout
+                                .map(|timeout| Duration::from_secs(timeout.into()));
+        outputln!(preamble self.service_group, "Draining before stopping");
+        let pre_stop = self.pre_stop().map(HookRunner::into_future);
+        match drain_for(pre_stop, drain_timeout, shutdown_timeout.into()).await {
+            Drained::HookFinished(Err(e)) => {
+                outputln!(preamble self.service_group, "Service drain failed: {}", e);
+            }
+            Drained::HookTimedOut(timeout) => {
+                outputln!(preamble self.service_group,
+                          "Stopping while the '{}' hook is still running after {}s",
+                          hooks::PreStopHook::FILE_NAME,
+                          timeout.as_secs());
+            }
+            Drained::HookFinished(Ok(_)) | Drained::Waited => {}
+        }
+    }
+
     /// Return a future that will shut down a service, performing any
-    /// necessary cleanup, and run its post-stop hook, if any.
+    /// necessary cleanup, and run its post-stop hook, if any. A service
+    /// that was marked as draining is drained first.
     /// # Locking for the returned Future (see locking.md)
     /// * `GatewayState::inner` (write)
     pub async fn stop_gsw(&mut self, shutdown_config: ShutdownConfig) {
         debug!("Stopping service {}", self.pkg.ident);
+        if self.draining {
+            self.drain(shutdown_config.timeout).await;
+        }
         self.detach();
 
         let service_group = self.service_group.clone();
@@ -857,7 +1069,9 @@ impl Service {
         }
 
         match self.spec.topology {
-            Topology::Standalone => self.execute_hooks(run_state, launcher, &template_update),
+            Topology::Standalone => {
+                self.execute_hooks(run_state, census_ring, launcher, &template_update)
+            }
             Topology::Leader => {
                 let census_group =
                     census_ring.census_group_for(&self.service_group)
@@ -896,12 +1110,25 @@ impl Service {
                                       leader_id);
                             self.last_election_status = census_group.election_status;
                         }
-                        self.execute_hooks(run_state, launcher, &template_update)
+                        self.execute_hooks(run_state, census_ring, launcher, &template_update)
                     }
                 }
             }
         };
-        template_data_changed
+
+        // Gossip the service again whenever its readiness changes
+        let ready = self.ready();
+        let ready_changed = ready != self.last_ready;
+        self.last_ready = ready;
+
+        if self.initialized() {
+            if let Some((previous, updated)) = run_state.post_update.take() {
+                if self.pkg.ident.as_ref() == &updated {
+                    self.post_update(&previous, census_ring);
+                }
+            }
+        }
+        template_data_changed || ready_changed
     }
 
     /// Iterate through all the service binds, marking any that are
@@ -932,6 +1159,13 @@ impl Service {
                                   bind.service_group(),
                                   bind.name());
                 }
+                BindStatus::NotReady => {
+                    outputln!(preamble self.service_group,
+                                  "The specified service group '{}' for binding '{}' is present in the \
+                                   census, but none of its members are ready yet.",
+                                  bind.service_group(),
+                                  bind.name());
+                }
                 BindStatus::Unsatisfied(ref unsatisfied) => {
                     outputln!(preamble self.service_group,
                                   "The group '{}' cannot satisfy the `{}` bind because it does not export \
@@ -940,6 +1174,14 @@ impl Service {
                                   bind.name(),
                                   unsatisfied);
                 }
+                BindStatus::Incompatible(ref packages) => {
+                    outputln!(preamble self.service_group,
+                                  "The group '{}' cannot satisfy the `{}` bind because its members \
+                                   run packages the bind doesn't accept: {}",
+                                  bind.service_group(),
+                                  bind.name(),
+                                  packages.join(", "));
+                }
                 BindStatus::Satisfied => {
                     // Since this function is currently called any
                     // time the census changes, and this is the
@@ -986,7 +1228,19 @@ impl Service {
             Some(group) => {
                 if group.active_members().count() == 0 {
                     BindStatus::Empty
+                } else if self.spec.binding_mode == BindingMode::Strict
+                          && group.ready_members().count() == 0
+                {
+                    BindStatus::NotReady
                 } else {
+                    let incompatible =
+                        match self.incompatible_bind_producers(group, service_bind.name()) {
+                            Ok(incompatible) => incompatible,
+                            Err(e) => return BindStatus::Unknown(e),
+                        };
+                    if !incompatible.is_empty() {
+                        return BindStatus::Incompatible(incompatible);
+                    }
                     match self.unsatisfied_bind_exports(group, service_bind.name()) {
                         Ok(unsatisfied) => {
                             if unsatisfied.is_empty() {
@@ -1027,18 +1281,47 @@ impl Service {
         Ok(diff)
     }
 
+    /// Does the service we've bound to run the package the bind's
+    /// contract asks for?
+    ///
+    /// Returns the sorted packages of active members that the bind
+    /// doesn't accept. If every member is acceptable, though, you get
+    /// an empty list.
+    ///
+    /// Can return `Error::NoSuchBind` if there's not a bind with the
+    /// given name.
+    fn incompatible_bind_producers(&self,
+                                   group: &CensusGroup,
+                                   bind_name: &str)
+                                   -> Result<Vec<String>> {
+        let pkg_bind = self.pkg_bind(bind_name)?;
+        let mut incompatible = group.active_members()
+                                    .map(|m| &m.pkg)
+                                    .filter(|pkg| !pkg_bind.accepts(pkg))
+                                    .map(ToString::to_string)
+                                    .collect::<Vec<_>>();
+        incompatible.sort();
+        incompatible.dedup();
+        Ok(incompatible)
+    }
+
     /// Returns the list of exported values a given bind requires
     ///
     /// Returns Err if there is no bind by the given name... by the
     /// time we get to this code, though, that shouldn't happen.
     fn exports_required_for_bind<'a>(&'a self, binding_name: &str) -> Result<HashSet<&'a String>> {
+        self.pkg_bind(binding_name)
+            .map(|b| b.exports.iter().collect())
+    }
+
+    /// Returns the package's contract for the bind of the given name
+    fn pkg_bind(&self, binding_name: &str) -> Result<&Bind> {
         // TODO (CM): Really, we want a HashMap of name => HashSet instead of a
         // Vec<Bind>... this finding is for the birds
         self.all_pkg_binds
             .iter()
             .find(|b| b.service == binding_name)
             .ok_or_else(|| Error::NoSuchBind(binding_name.to_string()))
-            .map(|b| b.exports.iter().collect())
     }
 
     /// Updates the process state of the service's supervisor
@@ -1059,8 +1342,18 @@ impl Service {
                 if config.incarnation <= self.cfg.gossip_incarnation {
                     return false;
                 }
-                self.cfg
-                    .set_gossip(config.incarnation, config.value.clone());
+                let previous = self.cfg.gossip.clone();
+                if let Err(e) = self.cfg
+                                    .set_gossip(config.incarnation, config.value.clone())
+                {
+                    outputln!(preamble self.service_group,
+                              "Rejected gossiped configuration: {}", e);
+                    return false;
+                }
+                self.cfg_history.record(config.incarnation,
+                                        &config.from_id,
+                                        config.value.clone(),
+                                        previous.as_ref());
                 true
             }
             None => false,
@@ -1086,15 +1379,81 @@ impl Service {
 
         let template_update = if template_data_changed || census_ring.changed() {
             let ctx = self.render_context(census_ring);
-            TemplateUpdate::new(self.compile_hooks(&ctx),
-                                self.compile_configuration(&ctx),
-                                self.hooks.reconfigure.is_some() || self.hooks.reload.is_some())
+            if self.spec.strict_templates {
+                self.compile_strict_templates(&ctx)
+            } else {
+                TemplateUpdate::new(self.compile_hooks(&ctx),
+                                    self.compile_configuration(&ctx),
+                                    self.hooks.reconfigure.is_some() || self.hooks.reload.is_some())
+            }
         } else {
             TemplateUpdate::default()
         };
         (template_data_changed, template_update)
     }
 
+    /// Renders every configuration template and hook of a service with strict templates before
+    /// writing any of them, so that none of them is written when any references an undefined
+    /// variable.
+    fn compile_strict_templates(&self, ctx: &RenderContext) -> TemplateUpdate {
+        let rendered = self.config_renderer
+                           .render(ctx)
+                           .and_then(|config| Ok((config, self.hooks.render(ctx)?)));
+        match rendered {
+            Ok((config, hooks)) => {
+                TemplateUpdate::new(self.write_hooks(&hooks),
+                                    self.write_configuration(&config),
+                                    self.hooks.reconfigure.is_some() || self.hooks.reload.is_some())
+            }
+            Err(e) => {
+                outputln!(preamble self.service_group,
+                          "Templates failed to render, not reconfiguring or restarting: {}", e);
+                event::template_render_failed(self, &e.to_string());
+                TemplateUpdate::default()
+            }
+        }
+    }
+
+    /// Renders the configuration templates and hooks of the service as if `gossip` had been
+    /// applied to its service group, without writing anything, and reports what would change.
+    ///
+    /// Secrets are never resolved for a dry run. The configuration templates are rendered both
+    /// for the current and for the new configuration with placeholders in place of their secret
+    /// references, so neither side of a diff holds the plaintext of a secret.
+    pub fn dry_run_cfg(&self,
+                       census_ring: &CensusRing,
+                       gossip: toml::value::Table)
+                       -> Result<CfgDryRun> {
+        let mut cfg = self.cfg.clone();
+        cfg.set_gossip(self.cfg.gossip_incarnation + 1, gossip)?;
+        let ctx = self.render_context_for(&cfg, census_ring);
+
+        let current = self.config_renderer
+                          .render_concealed(&self.render_context(census_ring))?;
+        let rendered = self.config_renderer.render_concealed(&ctx)?;
+        let mut diffs = Vec::new();
+        for (file, content) in &rendered.files {
+            let path = self.pkg.svc_config_path.join(file).display().to_string();
+            let current = current.files.get(file).map_or("", String::as_str);
+            diffs.extend(text_diff::unified_diff(current, content, &path, &path));
+        }
+        let config_changed = !diffs.is_empty();
+        let (hooks, hook_diffs) = self.hooks.dry_run(&ctx)?;
+        diffs.extend(hook_diffs);
+
+        let changed_hooks = hooks.changed_hooks();
+        let template_update = TemplateUpdate::new(hooks,
+                                                  config_changed,
+                                                  self.hooks.reconfigure.is_some()
+                                                  || self.hooks.reload.is_some());
+        let restart_reason = template_update.needs_restart();
+        Ok(CfgDryRun { diffs,
+                       changed_hooks,
+                       reconfigure: restart_reason.is_none()
+                                    && template_update.needs_reconfigure(),
+                       restart_reason })
+    }
+
     pub fn to_rumor(&self, incarnation: u64, pkg_incarnation: u64) -> ServiceRumor {
         let exported = match self.cfg.to_exported(&self.pkg) {
             Ok(exported) => Some(exported),
@@ -1112,6 +1471,7 @@ impl Service {
                                           exported);
         rumor.incarnation = incarnation;
         rumor.pkg_incarnation = pkg_incarnation;
+        rumor.ready = self.ready();
         rumor
     }
 
@@ -1202,6 +1562,15 @@ impl Service {
         }
     }
 
+    fn pre_stop(&self) -> Option<HookRunner<hooks::PreStopHook>> {
+        self.hooks.pre_stop.as_ref().map(|hook| {
+                                        HookRunner::new(Arc::clone(hook),
+                                                        self.service_group.clone(),
+                                                        self.pkg.clone(),
+                                                        self.spec.svc_encrypted_password.clone())
+                                    })
+    }
+
     fn post_stop(&self) -> Option<HookRunner<hooks::PostStopHook>> {
         self.hooks.post_stop.as_ref().map(|hook| {
                                          HookRunner::new(Arc::clone(hook),
@@ -1211,6 +1580,75 @@ impl Service {
                                      })
     }
 
+    /// Run the `pre-update` hook, if present, before this service is updated to `update`. The
+    /// returned future resolves to whether the hook allows the update.
+    pub fn pre_update(&self,
+                      update: &PackageIdent)
+                      -> Option<impl Future<Output = bool> + Send + 'static> {
+        let hook = self.hooks.pre_update.as_ref()?;
+        let service_group = self.service_group.clone();
+        let hook_runner = HookRunner::new(Arc::clone(hook),
+                                          self.service_group.clone(),
+                                          self.update_hook_pkg(self.pkg.ident.as_ref(), update),
+                                          self.spec.svc_encrypted_password.clone());
+        Some(async move {
+            match hook_runner.into_future().await {
+                Ok((allowed, _)) => allowed,
+                Err(e) => {
+                    outputln!(preamble service_group, "Pre-update hook failed: {}", e);
+                    false
+                }
+            }
+        })
+    }
+
+    /// Run the `post-update` hook, if present, after this service was updated from `previous`.
+    /// Services that only run the hook on their leader skip it on every other member.
+    fn post_update(&self, previous: &PackageIdent, census_ring: &CensusRing) {
+        let hook = match self.hooks.post_update {
+            Some(ref hook) => hook,
+            None => return,
+        };
+        if self.spec.post_update_on_leader && !self.is_group_leader(census_ring) {
+            debug!("Skipping the post-update hook of {}; it only runs on the leader",
+                   self.service_group);
+            return;
+        }
+        let service_group = self.service_group.clone();
+        let hook_runner = HookRunner::new(Arc::clone(hook),
+                                          self.service_group.clone(),
+                                          self.update_hook_pkg(previous, self.pkg.ident.as_ref()),
+                                          self.spec.svc_encrypted_password.clone());
+        tokio::spawn(async move {
+            if let Err(e) = hook_runner.into_future().await {
+                outputln!(preamble service_group, "Post-update hook failed: {}", e);
+            }
+        });
+    }
+
+    /// Whether this member leads its service group for the purpose of leader-only update hooks
+    /// and jobs. See `CensusGroup::group_leader` for how a group without a leader is led.
+    fn is_group_leader(&self, census_ring: &CensusRing) -> bool {
+        census_ring.census_group_for(&self.service_group)
+                   .and_then(|census_group| {
+                       census_group.me()
+                                   .zip(census_group.group_leader())
+                                   .map(|(me, leader)| me.member_id == leader.member_id)
+                   })
+                   .unwrap_or(false)
+    }
+
+    /// A copy of this service's package whose environment tells an update hook which releases
+    /// the service is updated from and to.
+    fn update_hook_pkg(&self, from: &PackageIdent, to: &PackageIdent) -> Pkg {
+        let mut pkg = self.pkg.clone();
+        let mut env = pkg.env.deref().clone();
+        env.insert(String::from("HAB_UPDATE_FROM"), from.to_string());
+        env.insert(String::from("HAB_UPDATE_TO"), to.to_string());
+        pkg.env = env.into();
+        pkg
+    }
+
     pub fn suitability(&self) -> Option<u64> {
         let _timer = hook_timer("suitability");
 
@@ -1234,13 +1672,25 @@ impl Service {
     ///
     /// Returns `true` if the configuration has changed.
     fn compile_configuration(&self, ctx: &RenderContext) -> bool {
-        match self.config_renderer.compile(&ctx.service_group_name(),
-                                           &self.pkg,
-                                           &self.pkg.svc_config_path,
-                                           ctx)
+        match self.config_renderer.render(ctx) {
+            Ok(rendered) => self.write_configuration(&rendered),
+            Err(e) => {
+                outputln!(preamble self.service_group,
+                          "Failed to compile configuration: {}",
+                          e);
+                false
+            }
+        }
+    }
+
+    /// Helper for writing configuration files which were already rendered.
+    fn write_configuration(&self, rendered: &RenderedCfg) -> bool {
+        match CfgRenderer::write(&self.service_group.to_string(),
+                                 &self.pkg,
+                                 &self.pkg.svc_config_path,
+                                 rendered)
         {
-            Ok(true) => true,
-            Ok(false) => false,
+            Ok(changed) => changed,
             Err(e) => {
                 outputln!(preamble self.service_group,
                           "Failed to compile configuration: {}",
@@ -1254,7 +1704,16 @@ impl Service {
     ///
     /// This function will also perform any necessary post-compilation tasks.
     fn compile_hooks(&self, ctx: &RenderContext<'_>) -> HookCompileTable {
-        let hook_update_table = self.hooks.compile(&self.service_group, ctx);
+        self.hooks_compiled(self.hooks.compile(&self.service_group, ctx))
+    }
+
+    /// Helper for writing hooks which were already rendered, performing the same
+    /// post-compilation tasks as `compile_hooks`.
+    fn write_hooks(&self, rendered: &RenderedHooks) -> HookCompileTable {
+        self.hooks_compiled(self.hooks.write(&self.service_group, rendered))
+    }
+
+    fn hooks_compiled(&self, hook_update_table: HookCompileTable) -> HookCompileTable {
         if let Some(err) = self.copy_run().err() {
             outputln!(preamble self.service_group, "Failed to copy run hook: {}", err);
         }
@@ -1306,6 +1765,7 @@ impl Service {
     /// Returns `true` if the service was marked to be restarted or reconfigured.
     fn execute_hooks(&mut self,
                      run_state: &mut ServiceRunState,
+                     census_ring: &CensusRing,
                      launcher: &LauncherCli,
                      template_update: &TemplateUpdate) {
         let pid_update = self.update_process_state(launcher);
@@ -1336,10 +1796,20 @@ impl Service {
                 // Wait until the initializer finishes running
             }
             InitializationState::InitializerFinished => {
-                self.start(launcher);
-                self.post_run();
+                match self.spec.job {
+                    // A job does not start when it is initialized, but when a run is due
+                    Some(JobSchedule::Once) => run_state.job.request(),
+                    Some(JobSchedule::Cron(_)) => (),
+                    None => {
+                        self.start(launcher);
+                        self.post_run();
+                    }
+                }
                 *self.initialization_state.write() = InitializationState::Initialized;
             }
+            InitializationState::Initialized if self.spec.job.is_some() => {
+                self.tick_job(run_state, census_ring, launcher, &pid_update);
+            }
             InitializationState::Initialized => {
                 let restart_cooldown_period_expired =
                     run_state.restart_backoff
@@ -1374,6 +1844,116 @@ impl Service {
         };
     }
 
+    /// Start the runs of a job service when they are due and record how they end.
+    ///
+    /// A run that exits is recorded with the exit code reaped by the Launcher instead of being
+    /// restarted, and a run that exceeds the job timeout is stopped. When a run is due while the
+    /// previous run is still going, the job's concurrency policy decides whether the new run is
+    /// skipped or replaces the previous one.
+    fn tick_job(&mut self,
+                run_state: &mut ServiceRunState,
+                census_ring: &CensusRing,
+                launcher: &LauncherCli,
+                pid_update: &PidUpdate) {
+        let job = match self.spec.job {
+            Some(ref job) => job.clone(),
+            None => return,
+        };
+        let now = SystemTime::now();
+        if !pid_update.is_running() {
+            if run_state.job.running() {
+                let exit_code =
+                    launcher.exit_status_of(&self.service_group)
+                            .unwrap_or_else(|err| {
+                                debug!("Unable to get the exit code of {} from the Launcher: {}",
+                                       self.service_group, err);
+                                None
+                            });
+                if let Some(run) = run_state.job.finish(exit_code, now) {
+                    match run.exit_code {
+                        Some(exit_code) => {
+                            outputln!(preamble self.service_group,
+                                      "Job run {} with exit code {}", run.outcome, exit_code)
+                        }
+                        None => outputln!(preamble self.service_group, "Job run {}", run.outcome),
+                    }
+                }
+                run_state.last_updated_at = now;
+            }
+        } else if let (Some(timeout), Some(running_for)) =
+            (self.spec.job_timeout, run_state.job.running_for(now))
+        {
+            if running_for >= Duration::from_secs(timeout.into()) {
+                outputln!(preamble self.service_group,
+                          "Stopping the job run after its timeout of {}s",
+                          timeout);
+                run_state.job.time_out(now);
+                run_state.last_updated_at = now;
+                self.stop_job_run();
+            }
+        }
+
+        if !run_state.job.take_due(&job, Utc::now()) {
+            return;
+        }
+        if self.spec.job_on_leader && !self.is_group_leader(census_ring) {
+            debug!("Skipping the job run of {}; it only runs on the leader",
+                   self.service_group);
+            return;
+        }
+        if !pid_update.is_running() {
+            self.start_job_run(run_state, launcher);
+            return;
+        }
+        match self.spec.job_concurrency {
+            JobConcurrency::Forbid => {
+                outputln!(preamble self.service_group,
+                          "Skipping the job run; the previous run is still going")
+            }
+            JobConcurrency::Replace => {
+                if run_state.job.replace(now).is_some() {
+                    outputln!(preamble self.service_group,
+                              "Stopping the previous job run to replace it");
+                    run_state.last_updated_at = now;
+                    self.stop_job_run();
+                }
+                // The new run starts once the previous run has stopped
+                run_state.job.request();
+            }
+        }
+    }
+
+    /// Start a run of this job service. Unlike a long-running service, a job is not health
+    /// checked and has no `post-run` hook.
+    fn start_job_run(&mut self, run_state: &mut ServiceRunState, launcher: &LauncherCli) {
+        debug!("Starting a job run of {}", self.pkg.ident);
+        let result = self.supervisor
+                         .lock()
+                         .expect("Couldn't lock supervisor")
+                         .start(&self.pkg,
+                                &self.service_group,
+                                launcher,
+                                self.spec.svc_encrypted_password.as_deref());
+        match result {
+            Ok(_) => {
+                let now = SystemTime::now();
+                run_state.job.start(now);
+                run_state.last_updated_at = now;
+            }
+            Err(e) => {
+                outputln!(preamble self.service_group, "Job run failed to start: {}", e);
+            }
+        }
+    }
+
+    /// Stop the running job run, without stopping the service.
+    fn stop_job_run(&self) {
+        self.supervisor
+            .lock()
+            .expect("Couldn't lock supervisor")
+            .stop(ShutdownConfig::new(None, self));
+    }
+
     /// Run file-updated hook if present.
     fn file_updated(&self) -> bool {
         let _timer = hook_timer("file-updated");
@@ -1447,18 +2027,24 @@ impl Service {
 
     /// Helper for constructing a new render context for the service.
     fn render_context<'a>(&'a self, census: &'a CensusRing) -> RenderContext<'a> {
+        self.render_context_for(&self.cfg, census)
+    }
+
+    /// Helper for constructing a render context for the service with a given configuration.
+    fn render_context_for<'a>(&'a self, cfg: &'a Cfg, census: &'a CensusRing) -> RenderContext<'a> {
         // Unsatisfied binds are filtered out; you only get bind
         // information in the render context if they actually satisfy
         // the contract!
         RenderContext::new(&self.service_group,
                            &self.sys,
                            &self.pkg,
-                           &self.cfg,
+                           cfg,
                            census,
                            self.spec
                                .binds
                                .iter()
-                               .filter(|b| !self.unsatisfied_binds.contains(b)))
+                               .filter(|b| !self.unsatisfied_binds.contains(b)),
+                           &self.all_pkg_binds)
     }
 
     // Returns `false` if the write fails.
@@ -1549,6 +2135,42 @@ fn hook_timer(name: &str) -> HistogramTimer {
     HOOK_DURATION.with_label_values(&[name]).start_timer()
 }
 
+/// How a service finished draining.
+#[derive(Debug, PartialEq)]
+enum Drained<T> {
+    /// The `pre-stop` hook finished with this result
+    HookFinished(T),
+    /// The `pre-stop` hook was still running after this timeout
+    HookTimedOut(Duration),
+    /// The service had no `pre-stop` hook and waited out its drain timeout, if it has one
+    Waited,
+}
+
+/// Wait for a `pre_stop` hook run for at most the `drain_timeout`, or the `shutdown_timeout` when
+/// there is no drain timeout, so that a hung hook cannot keep a service from stopping. Without a
+/// hook, wait for the whole drain timeout.
+async fn drain_for<F>(pre_stop: Option<F>,
+                      drain_timeout: Option<Duration>,
+                      shutdown_timeout: Duration)
+                      -> Drained<F::Output>
+    where F: Future
+{
+    match (pre_stop, drain_timeout) {
+        (Some(hook_run), drain_timeout) => {
+            let timeout = drain_timeout.unwrap_or(shutdown_timeout);
+            match time::timeout(timeout, hook_run).await {
+                Ok(result) => Drained::HookFinished(result),
+                Err(_) => Drained::HookTimedOut(timeout),
+            }
+        }
+        (None, Some(drain_timeout)) => {
+            time::sleep(drain_timeout).await;
+            Drained::Waited
+        }
+        (None, None) => Drained::Waited,
+    }
+}
+
 /// This enum represents whether or not we want to render config information when we serialize this
 /// service via the ServiceProxy struct below. Choosing ConfigRendering::Full will render the
 /// config, and choosing ConfigRendering::Redacted will not render it. This matches up to the
@@ -1582,6 +2204,10 @@ impl TryFrom<SystemTime> for UnixTimestamp {
 #[derive(Debug, Clone, Serialize)]
 pub struct ServiceQueryModel {
     pub all_pkg_binds:          Vec<Bind>,
+    /// A newer release that is available to the service but not yet applied
+    #[serde(with = "biome_core::util::serde::option_string",
+            skip_serializing_if = "Option::is_none")]
+    pub available_update:       Option<PackageIdent>,
     pub binding_mode:           BindingMode,
     pub binds:                  Vec<ServiceBind>,
     pub bldr_url:               String,
@@ -1591,13 +2217,33 @@ pub struct ServiceQueryModel {
     pub config_from:            Option<PathBuf>,
     pub desired_state:          DesiredState,
     pub health_check:           HealthCheckResult,
+    /// The release the service is held at
+    #[serde(with = "biome_core::util::serde::option_string",
+            skip_serializing_if = "Option::is_none")]
+    pub held_at:                Option<PackageIdent>,
     pub hooks:                  HookTableQueryModel,
     pub initialized:            bool,
+    /// When the service runs as a job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job:                    Option<JobSchedule>,
+    /// The most recent runs of a job service, oldest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub job_runs:               Vec<JobRun>,
     pub last_election_status:   ElectionStatus,
     pub manager_fs_cfg:         Arc<FsCfg>,
     pub pkg:                    PkgQueryModel,
     pub process:                SupervisedProcessQueryModel,
+    /// Whether the service is ready to serve
+    pub ready:                  bool,
     pub last_process_state:     Option<LastProcessState>,
+    /// When the service's channel was last checked for a newer release, expressed as seconds
+    /// since epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_update_check:      Option<UnixTimestamp>,
+    /// The newest release found by the last check of the service's channel
+    #[serde(with = "biome_core::util::serde::option_string",
+            skip_serializing_if = "Option::is_none")]
+    pub latest_in_channel:      Option<PackageIdent>,
     pub next_restart_at:        Option<UnixTimestamp>,
     pub restart_count:          u64,
     pub restart_config:         ServiceRestartConfig,
@@ -1611,6 +2257,11 @@ pub struct ServiceQueryModel {
     pub topology:               Topology,
     pub update_strategy:        UpdateStrategy,
     pub update_condition:       UpdateCondition,
+    /// Progress of the canary or batched update the service group is going through
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_rollout:         Option<RolloutProgress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_window:          Option<Schedule>,
     pub user_config_updated:    bool,
 }
 
@@ -1619,7 +2270,17 @@ impl ServiceQueryModel {
                service_run_state: &ServiceRunState,
                config_rendering: ConfigRendering)
                -> Self {
+        // Read the process up front: `ready` locks the supervisor too, so it must not be called
+        // while the supervisor lock is held.
+        let supervised_process = SupervisedProcessQueryModel::new(service.supervisor
+                                                                         .lock()
+                                                                         .expect("Couldn't lock \
+                                                                                  supervisor for \
+                                                                                  serialization")
+                                                                         .deref());
+        let process_state = supervised_process.state;
         ServiceQueryModel { all_pkg_binds:          service.all_pkg_binds.clone(),
+                            available_update:       None,
                             binding_mode:           service.spec.binding_mode,
                             binds:                  service.spec.binds.clone(),
                             bldr_url:               service.spec.bldr_url.clone(),
@@ -1635,19 +2296,23 @@ impl ServiceQueryModel {
                                          .lock()
                                          .expect("Couldn't lock health check result for \
                                                   serialization")),
+                            held_at:                service.spec.hold.clone(),
                             hooks:                  HookTableQueryModel::new(&service.hooks),
                             initialized:            service.initialized(),
+                            job:                    service.spec.job.clone(),
+                            job_runs:               service_run_state.job
+                                                                     .runs()
+                                                                     .iter()
+                                                                     .cloned()
+                                                                     .collect(),
                             last_election_status:   service.last_election_status,
                             manager_fs_cfg:         service.manager_fs_cfg.clone(),
                             pkg:                    PkgQueryModel::new(&service.pkg),
-                            process:
-                                SupervisedProcessQueryModel::new(service.supervisor
-                                                                        .lock()
-                                                                        .expect("Couldn't lock \
-                                                                                 supervisor for \
-                                                                                 serialization")
-                                                                        .deref()),
+                            process:                supervised_process,
+                            ready:                  service.ready_with(process_state),
                             last_process_state:     service_run_state.last_process_state.clone(),
+                            last_update_check:      None,
+                            latest_in_channel:      None,
                             next_restart_at:
                                 service_run_state.restart_backoff
                                                  .duration_until_next_attempt_start()
@@ -1669,16 +2334,23 @@ impl ServiceQueryModel {
                             topology:               service.spec.topology,
                             update_strategy:        service.spec.update_strategy,
                             update_condition:       service.spec.update_condition,
+                            update_rollout:         None,
+                            update_window:          service.spec.update_window.clone(),
                             user_config_updated:    service.user_config_updated, }
     }
 }
 
 impl From<&ServiceQueryModel> for biome_sup_protocol::types::ServiceStatus {
     fn from(service: &ServiceQueryModel) -> Self {
-        Self { ident:         (*service.pkg.ident.as_ref()).clone().into(),
-               process:       Some((&service.process).into()),
-               service_group: service.service_group.clone().into(),
-               desired_state: Some(service.desired_state.into()), }
+        Self { ident:             (*service.pkg.ident.as_ref()).clone().into(),
+               process:           Some((&service.process).into()),
+               service_group:     service.service_group.clone().into(),
+               desired_state:     Some(service.desired_state.into()),
+               update_rollout:    service.update_rollout.as_ref().map(Into::into),
+               held_at:           service.held_at.clone().map(Into::into),
+               available_update:  service.available_update.clone().map(Into::into),
+               latest_in_channel: service.latest_in_channel.clone().map(Into::into),
+               last_update_check: service.last_update_check.as_ref().map(|t| t.0), }
     }
 }
 
@@ -1768,4 +2440,93 @@ mod tests {
                                                                    JSON but failed");
         assert_valid(&json_without_config, "http_gateway_services_schema.json");
     }
+
+    #[tokio::test]
+    async fn draining_services_are_not_ready() {
+        let mut service_wrapper = initialize_test_service().await;
+        let service = service_wrapper.service_mut().unwrap();
+        service.spec.job = Some(JobSchedule::Once);
+        *service.initialization_state.write() = InitializationState::Initialized;
+        assert!(service.ready());
+
+        // Without a `pre-stop` hook or a drain timeout there is nothing to drain
+        assert!(!service.start_draining());
+        assert!(service.ready());
+
+        service.spec.drain_timeout = Some(5);
+        assert!(service.start_draining());
+        assert!(!service.ready());
+        let proxy = ServiceQueryModel::new(service_wrapper.service().unwrap(),
+                                           service_wrapper.service_run_state(),
+                                           ConfigRendering::Redacted);
+        assert!(!proxy.ready);
+    }
+
+    #[tokio::test]
+    async fn drain_returns_the_result_of_a_finished_pre_stop_hook() {
+        let drained = drain_for(Some(future::ready(42)), None, Duration::from_secs(3600)).await;
+        assert_eq!(drained, Drained::HookFinished(42));
+    }
+
+    #[tokio::test]
+    async fn drain_bounds_the_pre_stop_hook_by_the_drain_timeout() {
+        let drained = drain_for(Some(future::pending::<()>()),
+                                Some(Duration::from_millis(10)),
+                                Duration::from_secs(3600)).await;
+        assert_eq!(drained, Drained::HookTimedOut(Duration::from_millis(10)));
+    }
+
+    #[tokio::test]
+    async fn drain_bounds_the_pre_stop_hook_by_the_shutdown_timeout_by_default() {
+        let drained = drain_for(Some(future::pending::<()>()),
+                                None,
+                                Duration::from_millis(10)).await;
+        assert_eq!(drained, Drained::HookTimedOut(Duration::from_millis(10)));
+    }
+
+    #[tokio::test]
+    async fn drain_waits_out_the_drain_timeout_without_a_pre_stop_hook() {
+        let started = std::time::Instant::now();
+        let drained = drain_for(None::<future::Pending<()>>,
+                                Some(Duration::from_millis(10)),
+                                Duration::from_secs(3600)).await;
+        assert_eq!(drained, Drained::Waited);
+        assert!(started.elapsed() >= Duration::from_millis(10));
+
+        let drained = drain_for(None::<future::Pending<()>>, None, Duration::from_secs(3600)).await;
+        assert_eq!(drained, Drained::Waited);
+    }
+
+    #[tokio::test]
+    async fn service_updates_report_the_latest_release_and_available_update() {
+        let service_wrapper = initialize_test_service().await;
+        let mut service = ServiceQueryModel::new(service_wrapper.service().unwrap(),
+                                                 service_wrapper.service_run_state(),
+                                                 ConfigRendering::Redacted);
+        let service_group = service.service_group.to_string();
+        let update_strategy = service.update_strategy.to_string();
+        let current = service.pkg.ident.to_string();
+
+        let unchecked =
+            serde_json::to_value(crate::http_gateway::service_updates(&[service.clone()])).unwrap();
+        assert_eq!(unchecked,
+                   serde_json::json!([{ "service_group": service_group,
+                                        "update_strategy": update_strategy,
+                                        "current": current,
+                                        "update_available": false }]));
+
+        let latest = PackageIdent::from_str("core/tree/9.9.9/20990101000000").unwrap();
+        service.latest_in_channel = Some(latest.clone());
+        service.available_update = Some(latest.clone());
+        service.last_update_check = Some(UnixTimestamp(1_600_000_000));
+        let checked =
+            serde_json::to_value(crate::http_gateway::service_updates(&[service.clone()])).unwrap();
+        assert_eq!(checked,
+                   serde_json::json!([{ "service_group": service_group,
+                                        "update_strategy": update_strategy,
+                                        "current": current,
+                                        "latest": latest.to_string(),
+                                        "last_checked": 1_600_000_000,
+                                        "update_available": true }]));
+    }
 }
@@ -0,0 +1,154 @@
+//! A bounded history of the configurations applied to a service through gossip.
+//!
+//! Every `ServiceConfig` rumor replaces the previous incarnation, so the Supervisor keeps the
+//! last few configurations it applied in order to show what changed and to allow rolling back to
+//! an earlier one.
+
+use biome_common::util::flatten::dotted_keys;
+use biome_sup_protocol as protocol;
+use std::{collections::VecDeque,
+          fmt,
+          time::{SystemTime,
+                 UNIX_EPOCH}};
+
+/// The number of gossiped configurations remembered for each service group.
+pub const CFG_HISTORY_SIZE: usize = 16;
+
+/// A change to a single key between two configurations. Keys of nested tables are dotted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CfgChange {
+    Added(String, toml::Value),
+    Removed(String, toml::Value),
+    Changed(String, toml::Value, toml::Value),
+}
+
+impl fmt::Display for CfgChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CfgChange::Added(key, value) => write!(f, "+ {} = {}", key, value),
+            CfgChange::Removed(key, value) => write!(f, "- {} = {}", key, value),
+            CfgChange::Changed(key, old, new) => write!(f, "~ {} = {} -> {}", key, old, new),
+        }
+    }
+}
+
+impl CfgChange {
+    fn key(&self) -> &str {
+        match self {
+            CfgChange::Added(key, _) | CfgChange::Removed(key, _) | CfgChange::Changed(key, ..) => {
+                key
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CfgHistoryEntry {
+    pub incarnation: u64,
+    /// The member id of the Supervisor that gossiped the configuration
+    pub from_id:     String,
+    pub applied_at:  SystemTime,
+    pub value:       toml::value::Table,
+    /// The changes from the configuration that was applied before this one
+    pub diff:        Vec<CfgChange>,
+}
+
+impl From<&CfgHistoryEntry> for protocol::types::ServiceCfgHistoryEntry {
+    fn from(entry: &CfgHistoryEntry) -> Self {
+        let applied_at = entry.applied_at
+                              .duration_since(UNIX_EPOCH)
+                              .map(|d| d.as_secs())
+                              .unwrap_or_default();
+        let cfg = toml::to_string_pretty(&toml::Value::Table(entry.value.clone()));
+        Self { incarnation: Some(entry.incarnation),
+               from_id:     Some(entry.from_id.clone()),
+               applied_at:  Some(applied_at),
+               cfg:         cfg.ok(),
+               diff:        entry.diff.iter().map(ToString::to_string).collect(), }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CfgHistory(VecDeque<CfgHistoryEntry>);
+
+impl CfgHistory {
+    /// Record a newly applied configuration, forgetting the oldest one if the history is full.
+    /// `previous` is the configuration it replaced, if any.
+    pub fn record(&mut self,
+                  incarnation: u64,
+                  from_id: &str,
+                  value: toml::value::Table,
+                  previous: Option<&toml::value::Table>) {
+        let diff = diff(previous.unwrap_or(&toml::value::Table::new()), &value);
+        if self.0.len() == CFG_HISTORY_SIZE {
+            self.0.pop_front();
+        }
+        self.0.push_back(CfgHistoryEntry { incarnation,
+                                           from_id: from_id.to_string(),
+                                           applied_at: SystemTime::now(),
+                                           value,
+                                           diff });
+    }
+
+    /// The recorded configurations, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &CfgHistoryEntry> { self.0.iter() }
+}
+
+/// Compute the changes between two configurations, ordered by key.
+pub fn diff(old: &toml::value::Table, new: &toml::value::Table) -> Vec<CfgChange> {
+    let old = dotted_keys(old);
+    let mut new = dotted_keys(new);
+    let mut changes = Vec::new();
+    for (key, old_value) in old {
+        match new.remove(&key) {
+            Some(new_value) if new_value != old_value => {
+                changes.push(CfgChange::Changed(key, old_value.clone(), new_value.clone()))
+            }
+            Some(_) => (),
+            None => changes.push(CfgChange::Removed(key, old_value.clone())),
+        }
+    }
+    changes.extend(new.into_iter()
+                      .map(|(key, value)| CfgChange::Added(key, value.clone())));
+    changes.sort_by(|a, b| a.key().cmp(b.key()));
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn table(content: &str) -> toml::value::Table { toml::from_str(content).expect("valid TOML") }
+
+    #[test]
+    fn diff_reports_changes_by_dotted_key() {
+        let old = table("port = 80\nremoved = true\n[tls]\ncert = \"a\"\nverify = true\n");
+        let new = table("port = 8080\nadded = \"yes\"\n[tls]\ncert = \"a\"\nverify = false\n");
+        let diff = diff(&old, &new).iter()
+                                   .map(ToString::to_string)
+                                   .collect::<Vec<_>>();
+        assert_eq!(diff,
+                   vec!["+ added = \"yes\"",
+                        "~ port = 80 -> 8080",
+                        "- removed = true",
+                        "~ tls.verify = true -> false"]);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = CfgHistory::default();
+        let mut previous = None;
+        for incarnation in 1..=(CFG_HISTORY_SIZE as u64 + 2) {
+            let value = table(&format!("incarnation = {}", incarnation));
+            history.record(incarnation, "member", value.clone(), previous.as_ref());
+            previous = Some(value);
+        }
+        let incarnations = history.entries().map(|e| e.incarnation).collect::<Vec<_>>();
+        assert_eq!(incarnations.len(), CFG_HISTORY_SIZE);
+        assert_eq!(incarnations.first(), Some(&3));
+        assert_eq!(history.entries().last().unwrap().diff,
+                   vec![CfgChange::Changed("incarnation".to_string(),
+                                           toml::Value::Integer(CFG_HISTORY_SIZE as i64 + 1),
+                                           toml::Value::Integer(CFG_HISTORY_SIZE as i64 + 2))]);
+    }
+}
@@ -34,6 +34,7 @@
 //! anything else, and so, they _can't_ be used for anything else.
 
 use crate::{census::{CensusGroup,
+                     CensusMember,
                      CensusMemberProxy,
                      CensusRing,
                      ElectionStatus},
@@ -41,7 +42,8 @@ use crate::{census::{CensusGroup,
 use biome_common::templating::{config::Cfg,
                                  package::{Env,
                                            Pkg}};
-use biome_core::{package::{FullyQualifiedPackageIdent,
+use biome_core::{package::{metadata::Bind,
+                             FullyQualifiedPackageIdent,
                              Identifiable,
                              PackageIdent},
                    service::{ServiceBind,
@@ -88,12 +90,16 @@ impl<'a> RenderContext<'a> {
     /// is already complex, and exactly what we need. Because of the
     /// nature of `Cfg`s behavior, we should be safe relying on that
     /// implementation for the foreseeable future.
+    ///
+    /// Members of bound groups are given the defaults of any optional
+    /// exports declared by the matching bind in `pkg_binds`.
     pub fn new<T>(service_group: &ServiceGroup,
                   sys: &'a Sys,
                   pkg: &'a Pkg,
                   cfg: &'a Cfg,
                   census: &'a CensusRing,
-                  bindings: T)
+                  bindings: T,
+                  pkg_binds: &'a [Bind])
                   -> RenderContext<'a>
         where T: Iterator<Item = &'a ServiceBind>
     {
@@ -103,7 +109,7 @@ impl<'a> RenderContext<'a> {
                         pkg:  Package::from_pkg(pkg),
                         cfg:  Cow::Borrowed(cfg),
                         svc:  Svc::new(census_group),
-                        bind: Binds::new(bindings, census), }
+                        bind: Binds::new(bindings, pkg_binds, census), }
     }
 
     // Exposed only for logging... can probably do this another way.
@@ -267,21 +273,14 @@ impl<'a> Svc<'a> {
         Svc { service_group:          Cow::Borrowed(&census_group.service_group),
               election_status:        Cow::Borrowed(&census_group.election_status),
               update_election_status: Cow::Borrowed(&census_group.update_election_status),
-              members:                census_group.active_members()
-                                                             .map(SvcMember::new)
-                                                             .collect(),
-              me:                     census_group.me()
-                                                             .map(SvcMember::new)
-                                                             .expect("Missing 'me'"),
-              leader:                 census_group.leader()
-                                                             .map(SvcMember::new),
-              update_leader:          census_group.update_leader()
-                                                             .map(SvcMember::new),
+              members:                census_group.active_members().map(SvcMember::new).collect(),
+              me:                     census_group.me().map(SvcMember::new).expect("Missing 'me'"),
+              leader:                 census_group.leader().map(SvcMember::new),
+              update_leader:          census_group.update_leader().map(SvcMember::new),
+              // `me` is always present and alive, so there is always a first member
               first:
-                  select_first(census_group).expect("First should always be present \
-                                                                on svc" /* i.e. `me` will
-                                                                         * always be
-                                                                         * here, and alive */), }
+                  select_first(census_group, SvcMember::new).expect("First should always be \
+                                                                     present on svc"), }
     }
 }
 
@@ -329,13 +328,14 @@ impl<'a> Serialize for Svc<'a> {
 struct Binds<'a>(BTreeMap<String, BindGroup<'a>>);
 
 impl<'a> Binds<'a> {
-    fn new<T>(bindings: T, census: &'a CensusRing) -> Self
+    fn new<T>(bindings: T, pkg_binds: &'a [Bind], census: &'a CensusRing) -> Self
         where T: Iterator<Item = &'a ServiceBind>
     {
         let mut map = BTreeMap::default();
         for bind in bindings {
             if let Some(group) = census.census_group_for(bind.service_group()) {
-                map.insert(bind.name().to_string(), BindGroup::new(group));
+                let pkg_bind = pkg_binds.iter().find(|b| b.service == bind.name());
+                map.insert(bind.name().to_string(), BindGroup::new(group, pkg_bind));
             }
         }
         Binds(map)
@@ -350,10 +350,16 @@ struct BindGroup<'a> {
 }
 
 impl<'a> BindGroup<'a> {
-    fn new(group: &'a CensusGroup) -> Self {
-        BindGroup { first:   select_first(group),
-                    leader:  group.leader().map(SvcMember::new),
-                    members: group.active_members().map(SvcMember::new).collect(), }
+    fn new(group: &'a CensusGroup, pkg_bind: Option<&'a Bind>) -> Self {
+        let member = |m: &'a CensusMember| {
+            match pkg_bind {
+                Some(pkg_bind) => SvcMember::with_defaults(m, pkg_bind.export_defaults()),
+                None => SvcMember::new(m),
+            }
+        };
+        BindGroup { first:   select_first(group, member),
+                    leader:  group.leader().map(member),
+                    members: group.active_members().map(member).collect(), }
     }
 }
 
@@ -370,10 +376,12 @@ impl<'a> BindGroup<'a> {
 /// it from *all* members, and not just active members. Users should
 /// move away from using `first`, and should instead just use
 /// `members[0]`, or `leader`.
-fn select_first(census_group: &CensusGroup) -> Option<SvcMember<'_>> {
+fn select_first<'a, F>(census_group: &'a CensusGroup, proxy: F) -> Option<SvcMember<'a>>
+    where F: Fn(&'a CensusMember) -> SvcMember<'a>
+{
     match census_group.leader() {
-        Some(member) => Some(SvcMember::new(member)),
-        None => census_group.members().next().map(SvcMember::new),
+        Some(member) => Some(proxy(member)),
+        None => census_group.members().next().map(proxy),
     }
 }
 
@@ -387,6 +395,7 @@ mod tests {
                 test_helpers::*};
     use biome_butterfly::rumor::service::SysInfo;
     use biome_common::templating::{config::PackageConfigPaths,
+                                     fixture::Fixture,
                                      TemplateRenderer};
     use biome_core::package::PackageIdent;
     use std::{fs,
@@ -478,7 +487,9 @@ two = 2
                                            suspect: false,
                                            confirmed: false,
                                            departed: false,
-                                           cfg: toml::value::Table::new(), };
+                                           ready: true,
+                                           cfg: toml::value::Table::new(),
+                                           rollout_control: None, };
         SvcMember::new_owned(census_member)
     }
 
@@ -628,6 +639,49 @@ two = 2
         assert_valid(&j, "render_context_schema.json");
     }
 
+    /// The census data `bio plan render` builds from a fixture must have the shape the
+    /// Supervisor gives it, or templates rendered offline won't behave the same once loaded.
+    #[test]
+    fn census_fixture_context_is_valid() {
+        let fixture: Fixture = toml::from_str(
+                                              r#"
+service_group = "test_pkg.prod"
+
+[sys]
+ip = "10.0.0.2"
+
+[[groups."test_pkg.prod"]]
+sys = { hostname = "web-0" }
+
+[[groups."test_pkg.prod"]]
+me = true
+leader = true
+
+[[groups."postgresql.prod"]]
+cfg = { port = 5432 }
+
+[[groups."postgresql.prod"]]
+health = "suspect"
+
+[[groups."postgresql.prod"]]
+health = "departed"
+
+[binds]
+database = "postgresql.prod"
+"#,
+        ).expect("valid fixture");
+        let ident = PackageIdent::new("core", "test_pkg", Some("1.0.0"), Some("20180321150416"));
+        let census = fixture.census_context(&ident)
+                            .expect("valid census context");
+
+        let mut j =
+            serde_json::to_value(&default_render_context()).expect("can't serialize to JSON");
+        for key in &["sys", "svc", "bind"] {
+            j[*key] = census[*key].clone();
+        }
+        assert_valid(&j.to_string(), "render_context_schema.json");
+    }
+
     #[test]
     fn binds_are_output_in_consistent_order() {
         let mut render_context = default_render_context();
@@ -1,6 +1,7 @@
 use crate::{error::Error,
             manager::service::{hook_runner,
-                               hooks::HealthCheckHook,
+                               hooks::{HealthCheckHook,
+                                       ReadyHook},
                                supervisor::Supervisor,
                                ProcessOutput,
                                ProcessState}};
@@ -16,7 +17,9 @@ use serde::Serialize;
 use std::{cmp,
           convert::TryFrom,
           fmt,
-          sync::{Arc,
+          sync::{atomic::{AtomicBool,
+                          Ordering},
+                 Arc,
                  Mutex},
           time::Duration};
 use tokio::{sync::mpsc::{self,
@@ -240,3 +243,43 @@ pub fn check_repeatedly(supervisor: Arc<Mutex<Supervisor>>,
 
     rx
 }
+
+/// Repeatedly run the ready hook of a service, if it has one, recording in `ready` whether the
+/// service reports that it is ready to serve. Until it is ready, the hook is run again after no
+/// more than the default health check interval.
+pub async fn check_readiness_repeatedly(hook: Option<Arc<ReadyHook>>,
+                                        nominal_interval: HealthCheckInterval,
+                                        service_group: ServiceGroup,
+                                        package: Pkg,
+                                        password: Option<String>,
+                                        ready: Arc<AtomicBool>) {
+    let hook = match hook {
+        Some(hook) => hook,
+        None => return,
+    };
+    loop {
+        let result = hook_runner::HookRunner::new(Arc::clone(&hook),
+                                                  service_group.clone(),
+                                                  package.clone(),
+                                                  password.clone()).into_future()
+                                                                   .await;
+        let is_ready = match result {
+            Ok((is_ready, _)) => is_ready,
+            Err(e) => {
+                error!("Error running ready hook for {}: {:?}", service_group, e);
+                false
+            }
+        };
+        if ready.swap(is_ready, Ordering::Relaxed) != is_ready {
+            outputln!(preamble service_group,
+                      "Service is {}",
+                      if is_ready { "ready" } else { "not ready" });
+        }
+        let interval = if is_ready {
+            nominal_interval
+        } else {
+            cmp::min(nominal_interval, HealthCheckInterval::default())
+        };
+        time::sleep(interval.into()).await;
+    }
+}
@@ -12,11 +12,13 @@ use biome_common::{error::Result,
                      FeatureFlag};
 #[cfg(windows)]
 use biome_core::os::process::windows_child::ExitStatus;
+use biome_core::util::text_diff;
 use log::debug;
 use serde::Serialize;
 #[cfg(not(windows))]
 use std::process::ExitStatus;
 use std::{self,
+          collections::HashMap,
           io::BufRead,
           path::{Path,
                  PathBuf},
@@ -471,6 +473,49 @@ impl Hook for SuitabilityHook {
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
 }
 
+#[derive(Debug, Serialize)]
+pub struct PreStopHook {
+    render_pair:     RenderPair,
+    stdout_log_path: PathBuf,
+    stderr_log_path: PathBuf,
+}
+
+impl Hook for PreStopHook {
+    type ExitValue = bool;
+
+    const FILE_NAME: &'static str = "pre-stop";
+
+    fn new(package_name: &str, pair: RenderPair, _feature_flags: FeatureFlag) -> Self {
+        PreStopHook { render_pair:     pair,
+                      stdout_log_path: hooks::stdout_log_path::<Self>(package_name),
+                      stderr_log_path: hooks::stderr_log_path::<Self>(package_name), }
+    }
+
+    fn handle_exit(&self, pkg: &Pkg, _: &HookOutput, status: ExitStatus) -> Self::ExitValue {
+        let pkg_name = &pkg.name;
+        match status.code() {
+            Some(0) => true,
+            Some(code) => {
+                outputln!(preamble pkg_name, "Pre stop failed! '{}' exited with \
+                    status code {}", Self::FILE_NAME, code);
+                false
+            }
+            None => {
+                Self::output_termination_message(pkg_name, status);
+                false
+            }
+        }
+    }
+
+    fn path(&self) -> &Path { &self.render_pair.path }
+
+    fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
+
+    fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
+
+    fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
+}
+
 #[derive(Debug, Serialize)]
 pub struct PostStopHook {
     render_pair:     RenderPair,
@@ -514,6 +559,130 @@ impl Hook for PostStopHook {
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
 }
 
+#[derive(Debug, Serialize)]
+pub struct PreUpdateHook {
+    render_pair:     RenderPair,
+    stdout_log_path: PathBuf,
+    stderr_log_path: PathBuf,
+}
+
+impl Hook for PreUpdateHook {
+    type ExitValue = bool;
+
+    const FILE_NAME: &'static str = "pre-update";
+
+    fn new(package_name: &str, pair: RenderPair, _feature_flags: FeatureFlag) -> Self {
+        PreUpdateHook { render_pair:     pair,
+                        stdout_log_path: hooks::stdout_log_path::<Self>(package_name),
+                        stderr_log_path: hooks::stderr_log_path::<Self>(package_name), }
+    }
+
+    fn handle_exit(&self, pkg: &Pkg, _: &HookOutput, status: ExitStatus) -> Self::ExitValue {
+        let pkg_name = &pkg.name;
+        match status.code() {
+            Some(0) => true,
+            Some(code) => {
+                outputln!(preamble pkg_name, "Update vetoed! '{}' exited with \
+                    status code {}", Self::FILE_NAME, code);
+                false
+            }
+            None => {
+                Self::output_termination_message(pkg_name, status);
+                false
+            }
+        }
+    }
+
+    fn path(&self) -> &Path { &self.render_pair.path }
+
+    fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
+
+    fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
+
+    fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostUpdateHook {
+    render_pair:     RenderPair,
+    stdout_log_path: PathBuf,
+    stderr_log_path: PathBuf,
+}
+
+impl Hook for PostUpdateHook {
+    type ExitValue = bool;
+
+    const FILE_NAME: &'static str = "post-update";
+
+    fn new(package_name: &str, pair: RenderPair, _feature_flags: FeatureFlag) -> Self {
+        PostUpdateHook { render_pair:     pair,
+                         stdout_log_path: hooks::stdout_log_path::<Self>(package_name),
+                         stderr_log_path: hooks::stderr_log_path::<Self>(package_name), }
+    }
+
+    fn handle_exit(&self, pkg: &Pkg, _: &HookOutput, status: ExitStatus) -> Self::ExitValue {
+        let pkg_name = &pkg.name;
+        match status.code() {
+            Some(0) => true,
+            Some(code) => {
+                outputln!(preamble pkg_name, "Post update failed! '{}' exited with \
+                    status code {}", Self::FILE_NAME, code);
+                false
+            }
+            None => {
+                Self::output_termination_message(pkg_name, status);
+                false
+            }
+        }
+    }
+
+    fn path(&self) -> &Path { &self.render_pair.path }
+
+    fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
+
+    fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
+
+    fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadyHook {
+    render_pair:     RenderPair,
+    stdout_log_path: PathBuf,
+    stderr_log_path: PathBuf,
+}
+
+impl Hook for ReadyHook {
+    type ExitValue = bool;
+
+    const FILE_NAME: &'static str = "ready";
+
+    fn new(package_name: &str, pair: RenderPair, _feature_flags: FeatureFlag) -> Self {
+        ReadyHook { render_pair:     pair,
+                    stdout_log_path: hooks::stdout_log_path::<Self>(package_name),
+                    stderr_log_path: hooks::stderr_log_path::<Self>(package_name), }
+    }
+
+    fn handle_exit(&self, pkg: &Pkg, _: &HookOutput, status: ExitStatus) -> Self::ExitValue {
+        match status.code() {
+            Some(0) => true,
+            Some(_) => false,
+            None => {
+                Self::output_termination_message(&pkg.name, status);
+                false
+            }
+        }
+    }
+
+    fn path(&self) -> &Path { &self.render_pair.path }
+
+    fn renderer(&self) -> &TemplateRenderer { &self.render_pair.renderer }
+
+    fn stdout_log_path(&self) -> &Path { &self.stdout_log_path }
+
+    fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
+}
+
 /// A lookup of hooks that have changed after compilation.
 #[derive(Default)]
 pub struct HookCompileTable {
@@ -526,6 +695,10 @@ pub struct HookCompileTable {
     run:          bool,
     post_run:     bool,
     post_stop:    bool,
+    pre_update:   bool,
+    post_update:  bool,
+    ready:        bool,
+    pre_stop:     bool,
 }
 
 impl HookCompileTable {
@@ -541,6 +714,39 @@ impl HookCompileTable {
 
     pub fn post_run_changed(&self) -> bool { self.post_run }
 
+    /// The file names of the hooks that have changed.
+    pub fn changed_hooks(&self) -> Vec<&'static str> {
+        let Self { health_check,
+                   init,
+                   file_updated,
+                   reload,
+                   reconfigure,
+                   suitability,
+                   run,
+                   post_run,
+                   post_stop,
+                   pre_update,
+                   post_update,
+                   ready,
+                   pre_stop, } = self;
+        [(*health_check, HealthCheckHook::FILE_NAME),
+         (*init, InitHook::FILE_NAME),
+         (*file_updated, FileUpdatedHook::FILE_NAME),
+         (*reload, ReloadHook::FILE_NAME),
+         (*reconfigure, ReconfigureHook::FILE_NAME),
+         (*suitability, SuitabilityHook::FILE_NAME),
+         (*run, RunHook::FILE_NAME),
+         (*post_run, PostRunHook::FILE_NAME),
+         (*post_stop, PostStopHook::FILE_NAME),
+         (*pre_update, PreUpdateHook::FILE_NAME),
+         (*post_update, PostUpdateHook::FILE_NAME),
+         (*ready, ReadyHook::FILE_NAME),
+         (*pre_stop, PreStopHook::FILE_NAME)].iter()
+                                             .filter(|(changed, _)| *changed)
+                                             .map(|(_, name)| *name)
+                                             .collect()
+    }
+
     pub fn changed(&self) -> bool {
         let Self { health_check,
                    init,
@@ -550,7 +756,11 @@ impl HookCompileTable {
                    suitability,
                    run,
                    post_run,
-                   post_stop, } = self;
+                   post_stop,
+                   pre_update,
+                   post_update,
+                   ready,
+                   pre_stop, } = self;
         *health_check
         || *init
         || *file_updated
@@ -560,6 +770,10 @@ impl HookCompileTable {
         || *run
         || *post_run
         || *post_stop
+        || *pre_update
+        || *post_update
+        || *ready
+        || *pre_stop
     }
 }
 
@@ -583,6 +797,10 @@ pub struct HookTableQueryModel {
     pub run:          Option<HookQueryModel>,
     pub post_run:     Option<HookQueryModel>,
     pub post_stop:    Option<HookQueryModel>,
+    pub pre_update:   Option<HookQueryModel>,
+    pub post_update:  Option<HookQueryModel>,
+    pub ready:        Option<HookQueryModel>,
+    pub pre_stop:     Option<HookQueryModel>,
 }
 
 impl HookTableQueryModel {
@@ -596,7 +814,11 @@ impl HookTableQueryModel {
             suitability: hook_table.suitability.as_ref().map(|hook| HookQueryModel { render_pair: hook.render_pair.path.clone(), stdout_log_path: hook.stdout_log_path.clone(), stderr_log_path: hook.stderr_log_path.clone() }),
             run: hook_table.run.as_ref().map(|hook| HookQueryModel { render_pair: hook.render_pair.path.clone(), stdout_log_path: hook.stdout_log_path.clone(), stderr_log_path: hook.stderr_log_path.clone() }),
             post_run: hook_table.post_run.as_ref().map(|hook| HookQueryModel { render_pair: hook.render_pair.path.clone(), stdout_log_path: hook.stdout_log_path.clone(), stderr_log_path: hook.stderr_log_path.clone() }),
-            post_stop: hook_table.post_stop.as_ref().map(|hook| HookQueryModel { render_pair: hook.render_pair.path.clone(), stdout_log_path: hook.stdout_log_path.clone(), stderr_log_path: hook.stderr_log_path.clone() })
+            post_stop: hook_table.post_stop.as_ref().map(|hook| HookQueryModel { render_pair: hook.render_pair.path.clone(), stdout_log_path: hook.stdout_log_path.clone(), stderr_log_path: hook.stderr_log_path.clone() }),
+            pre_update: hook_table.pre_update.as_ref().map(|hook| HookQueryModel { render_pair: hook.render_pair.path.clone(), stdout_log_path: hook.stdout_log_path.clone(), stderr_log_path: hook.stderr_log_path.clone() }),
+            post_update: hook_table.post_update.as_ref().map(|hook| HookQueryModel { render_pair: hook.render_pair.path.clone(), stdout_log_path: hook.stdout_log_path.clone(), stderr_log_path: hook.stderr_log_path.clone() }),
+            ready: hook_table.ready.as_ref().map(|hook| HookQueryModel { render_pair: hook.render_pair.path.clone(), stdout_log_path: hook.stdout_log_path.clone(), stderr_log_path: hook.stderr_log_path.clone() }),
+            pre_stop: hook_table.pre_stop.as_ref().map(|hook| HookQueryModel { render_pair: hook.render_pair.path.clone(), stdout_log_path: hook.stdout_log_path.clone(), stderr_log_path: hook.stderr_log_path.clone() })
         }
     }
 }
@@ -614,14 +836,21 @@ pub struct HookTable {
     pub run:          Option<RunHook>,
     pub post_run:     Option<Arc<PostRunHook>>,
     pub post_stop:    Option<Arc<PostStopHook>>,
+    pub pre_update:   Option<Arc<PreUpdateHook>>,
+    pub post_update:  Option<Arc<PostUpdateHook>>,
+    pub ready:        Option<Arc<ReadyHook>>,
+    pub pre_stop:     Option<Arc<PreStopHook>>,
 }
 
 impl HookTable {
     /// Read all available hook templates from the table's package directory into the table.
+    ///
+    /// Hooks of a `strict` table fail to render when they reference undefined variables.
     pub fn load<P, T>(package_name: &str,
                       templates: T,
                       hooks_path: P,
-                      feature_flags: FeatureFlag)
+                      feature_flags: FeatureFlag,
+                      strict: bool)
                       -> Self
         where P: AsRef<Path>,
               T: AsRef<Path>
@@ -629,28 +858,71 @@ impl HookTable {
         let mut table = HookTable::default();
         if let Ok(meta) = std::fs::metadata(templates.as_ref()) {
             if meta.is_dir() {
-                table.file_updated =
-                    FileUpdatedHook::load(package_name, &hooks_path, &templates, feature_flags);
-                table.health_check = HealthCheckHook::load(package_name,
-                                                           &hooks_path,
-                                                           &templates,
-                                                           feature_flags).map(Arc::new);
-                table.suitability =
-                    SuitabilityHook::load(package_name, &hooks_path, &templates, feature_flags);
-                table.init = InitHook::load(package_name, &hooks_path, &templates, feature_flags).map(Arc::new);
-                table.reload =
-                    ReloadHook::load(package_name, &hooks_path, &templates, feature_flags);
-                table.reconfigure =
-                    ReconfigureHook::load(package_name, &hooks_path, &templates, feature_flags);
-                table.run = RunHook::load(package_name, &hooks_path, &templates, feature_flags);
-                table.post_run = PostRunHook::load(package_name,
-                                                   &hooks_path,
-                                                   &templates,
-                                                   feature_flags).map(Arc::new);
-                table.post_stop = PostStopHook::load(package_name,
-                                                     &hooks_path,
-                                                     &templates,
-                                                     feature_flags).map(Arc::new);
+                table.file_updated = FileUpdatedHook::load_with_strictness(package_name,
+                                                                           &hooks_path,
+                                                                           &templates,
+                                                                           feature_flags,
+                                                                           strict);
+                table.health_check = HealthCheckHook::load_with_strictness(package_name,
+                                                                           &hooks_path,
+                                                                           &templates,
+                                                                           feature_flags,
+                                                                           strict).map(Arc::new);
+                table.suitability = SuitabilityHook::load_with_strictness(package_name,
+                                                                          &hooks_path,
+                                                                          &templates,
+                                                                          feature_flags,
+                                                                          strict);
+                table.init = InitHook::load_with_strictness(package_name,
+                                                            &hooks_path,
+                                                            &templates,
+                                                            feature_flags,
+                                                            strict).map(Arc::new);
+                table.reload = ReloadHook::load_with_strictness(package_name,
+                                                                &hooks_path,
+                                                                &templates,
+                                                                feature_flags,
+                                                                strict);
+                table.reconfigure = ReconfigureHook::load_with_strictness(package_name,
+                                                                          &hooks_path,
+                                                                          &templates,
+                                                                          feature_flags,
+                                                                          strict);
+                table.run = RunHook::load_with_strictness(package_name,
+                                                          &hooks_path,
+                                                          &templates,
+                                                          feature_flags,
+                                                          strict);
+                table.post_run = PostRunHook::load_with_strictness(package_name,
+                                                                   &hooks_path,
+                                                                   &templates,
+                                                                   feature_flags,
+                                                                   strict).map(Arc::new);
+                table.post_stop = PostStopHook::load_with_strictness(package_name,
+                                                                     &hooks_path,
+                                                                     &templates,
+                                                                     feature_flags,
+                                                                     strict).map(Arc::new);
+                table.pre_update = PreUpdateHook::load_with_strictness(package_name,
+                                                                       &hooks_path,
+                                                                       &templates,
+                                                                       feature_flags,
+                                                                       strict).map(Arc::new);
+                table.post_update = PostUpdateHook::load_with_strictness(package_name,
+                                                                         &hooks_path,
+                                                                         &templates,
+                                                                         feature_flags,
+                                                                         strict).map(Arc::new);
+                table.ready = ReadyHook::load_with_strictness(package_name,
+                                                              &hooks_path,
+                                                              &templates,
+                                                              feature_flags,
+                                                              strict).map(Arc::new);
+                table.pre_stop = PreStopHook::load_with_strictness(package_name,
+                                                                   &hooks_path,
+                                                                   &templates,
+                                                                   feature_flags,
+                                                                   strict).map(Arc::new);
             }
         }
         debug!("{}, Hooks loaded, destination={}, templates={}",
@@ -693,9 +965,196 @@ impl HookTable {
         if let Some(ref hook) = self.post_stop {
             changed.post_stop = self.compile_one(hook.as_ref(), service_group, ctx);
         }
+        if let Some(ref hook) = self.pre_update {
+            changed.pre_update = self.compile_one(hook.as_ref(), service_group, ctx);
+        }
+        if let Some(ref hook) = self.post_update {
+            changed.post_update = self.compile_one(hook.as_ref(), service_group, ctx);
+        }
+        if let Some(ref hook) = self.ready {
+            changed.ready = self.compile_one(hook.as_ref(), service_group, ctx);
+        }
+        if let Some(ref hook) = self.pre_stop {
+            changed.pre_stop = self.compile_one(hook.as_ref(), service_group, ctx);
+        }
+        changed
+    }
+
+    /// Render all loaded hooks from the table without writing them, failing as soon as any of
+    /// them fails to render.
+    pub fn render<T>(&self, ctx: &T) -> Result<RenderedHooks>
+        where T: Serialize
+    {
+        let mut rendered = RenderedHooks::default();
+        if let Some(ref hook) = self.file_updated {
+            rendered.render(hook, ctx)?;
+        }
+        if let Some(ref hook) = self.health_check {
+            rendered.render(hook.as_ref(), ctx)?;
+        }
+        if let Some(ref hook) = self.init {
+            rendered.render(hook.as_ref(), ctx)?;
+        }
+        if let Some(ref hook) = self.reload {
+            rendered.render(hook, ctx)?;
+        }
+        if let Some(ref hook) = self.reconfigure {
+            rendered.render(hook, ctx)?;
+        }
+        if let Some(ref hook) = self.suitability {
+            rendered.render(hook, ctx)?;
+        }
+        if let Some(ref hook) = self.run {
+            rendered.render(hook, ctx)?;
+        }
+        if let Some(ref hook) = self.post_run {
+            rendered.render(hook.as_ref(), ctx)?;
+        }
+        if let Some(ref hook) = self.post_stop {
+            rendered.render(hook.as_ref(), ctx)?;
+        }
+        if let Some(ref hook) = self.pre_update {
+            rendered.render(hook.as_ref(), ctx)?;
+        }
+        if let Some(ref hook) = self.post_update {
+            rendered.render(hook.as_ref(), ctx)?;
+        }
+        if let Some(ref hook) = self.ready {
+            rendered.render(hook.as_ref(), ctx)?;
+        }
+        if let Some(ref hook) = self.pre_stop {
+            rendered.render(hook.as_ref(), ctx)?;
+        }
+        Ok(rendered)
+    }
+
+    /// Write hooks rendered by `render` into their destination service directory.
+    pub fn write(&self, service_group: &str, rendered: &RenderedHooks) -> HookCompileTable {
+        let mut changed = HookCompileTable::new();
+        if let Some(ref hook) = self.file_updated {
+            changed.file_updated = Self::write_one(hook, service_group, rendered);
+        }
+        if let Some(ref hook) = self.health_check {
+            changed.health_check = Self::write_one(hook.as_ref(), service_group, rendered);
+        }
+        if let Some(ref hook) = self.init {
+            changed.init = Self::write_one(hook.as_ref(), service_group, rendered);
+        }
+        if let Some(ref hook) = self.reload {
+            changed.reload = Self::write_one(hook, service_group, rendered);
+        }
+        if let Some(ref hook) = self.reconfigure {
+            changed.reconfigure = Self::write_one(hook, service_group, rendered);
+        }
+        if let Some(ref hook) = self.suitability {
+            changed.suitability = Self::write_one(hook, service_group, rendered);
+        }
+        if let Some(ref hook) = self.run {
+            changed.run = Self::write_one(hook, service_group, rendered);
+        }
+        if let Some(ref hook) = self.post_run {
+            changed.post_run = Self::write_one(hook.as_ref(), service_group, rendered);
+        }
+        if let Some(ref hook) = self.post_stop {
+            changed.post_stop = Self::write_one(hook.as_ref(), service_group, rendered);
+        }
+        if let Some(ref hook) = self.pre_update {
+            changed.pre_update = Self::write_one(hook.as_ref(), service_group, rendered);
+        }
+        if let Some(ref hook) = self.post_update {
+            changed.post_update = Self::write_one(hook.as_ref(), service_group, rendered);
+        }
+        if let Some(ref hook) = self.ready {
+            changed.ready = Self::write_one(hook.as_ref(), service_group, rendered);
+        }
+        if let Some(ref hook) = self.pre_stop {
+            changed.pre_stop = Self::write_one(hook.as_ref(), service_group, rendered);
+        }
         changed
     }
 
+    /// Render all loaded hooks from the table without writing them.
+    ///
+    /// Returns the hooks that would change when compiled, along with a unified diff of each of
+    /// them against its compiled file.
+    pub fn dry_run<T>(&self, ctx: &T) -> Result<(HookCompileTable, Vec<String>)>
+        where T: Serialize
+    {
+        let mut changed = HookCompileTable::new();
+        let mut diffs = Vec::new();
+        if let Some(ref hook) = self.file_updated {
+            changed.file_updated = Self::dry_run_one(hook, ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.health_check {
+            changed.health_check = Self::dry_run_one(hook.as_ref(), ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.init {
+            changed.init = Self::dry_run_one(hook.as_ref(), ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.reload {
+            changed.reload = Self::dry_run_one(hook, ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.reconfigure {
+            changed.reconfigure = Self::dry_run_one(hook, ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.suitability {
+            changed.suitability = Self::dry_run_one(hook, ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.run {
+            changed.run = Self::dry_run_one(hook, ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.post_run {
+            changed.post_run = Self::dry_run_one(hook.as_ref(), ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.post_stop {
+            changed.post_stop = Self::dry_run_one(hook.as_ref(), ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.pre_update {
+            changed.pre_update = Self::dry_run_one(hook.as_ref(), ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.post_update {
+            changed.post_update = Self::dry_run_one(hook.as_ref(), ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.ready {
+            changed.ready = Self::dry_run_one(hook.as_ref(), ctx, &mut diffs)?;
+        }
+        if let Some(ref hook) = self.pre_stop {
+            changed.pre_stop = Self::dry_run_one(hook.as_ref(), ctx, &mut diffs)?;
+        }
+        Ok((changed, diffs))
+    }
+
+    fn dry_run_one<H, T>(hook: &H, ctx: &T, diffs: &mut Vec<String>) -> Result<bool>
+        where H: Hook,
+              T: Serialize
+    {
+        let content = hook.render(ctx)?;
+        match file_diff(&hook.compiled_path(), &content) {
+            Some(diff) => {
+                diffs.push(diff);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn write_one<H>(hook: &H, service_group: &str, rendered: &RenderedHooks) -> bool
+        where H: Hook
+    {
+        let content = match rendered.0.get(H::FILE_NAME) {
+            Some(content) => content,
+            None => return false,
+        };
+        match hook.write_compiled(service_group, content) {
+            Ok(status) => status,
+            Err(e) => {
+                outputln!(preamble service_group,
+                          "Failed to compile {} hook: {}", H::FILE_NAME, e);
+                false
+            }
+        }
+    }
+
     fn compile_one<H, T>(&self, hook: &H, service_group: &str, ctx: &T) -> bool
         where H: Hook,
               T: Serialize
@@ -711,6 +1170,31 @@ impl HookTable {
     }
 }
 
+/// The content of the hooks of a table, rendered but not written yet.
+#[derive(Debug, Default)]
+pub struct RenderedHooks(HashMap<&'static str, String>);
+
+impl RenderedHooks {
+    fn render<H, T>(&mut self, hook: &H, ctx: &T) -> Result<()>
+        where H: Hook,
+              T: Serialize
+    {
+        self.0.insert(H::FILE_NAME, hook.render(ctx)?);
+        Ok(())
+    }
+}
+
+/// Returns a unified diff of the file at `path` and its `rendered` replacement, or `None` if
+/// writing it would not change the lines of the file. Only use this for files that never hold
+/// the plaintext of a secret, such as hooks.
+fn file_diff(path: &Path, rendered: &str) -> Option<String> {
+    let path_name = path.display().to_string();
+    match std::fs::read_to_string(path) {
+        Ok(current) => text_diff::unified_diff(&current, rendered, &path_name, &path_name),
+        Err(_) => text_diff::unified_diff("", rendered, "/dev/null", &path_name),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{super::RenderContext,
@@ -773,7 +1257,11 @@ mod tests {
                       ReconfigureHook
                       RunHook
                       SuitabilityHook
-                      PostStopHook);
+                      PostStopHook
+                      PreUpdateHook
+                      PostUpdateHook
+                      ReadyHook
+                      PreStopHook);
 
     fn hook_templates_path() -> PathBuf {
         PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests")
@@ -858,7 +1346,7 @@ mod tests {
 
         let bindings = iter::empty::<&ServiceBind>();
 
-        RenderContext::new(service_group, sys, pkg, cfg, ring, bindings)
+        RenderContext::new(service_group, sys, pkg, cfg, ring, bindings, &[])
     }
 
     ////////////////////////////////////////////////////////////////////////
@@ -890,7 +1378,8 @@ mod tests {
         let hook_table = HookTable::load(&service_group,
                                          &template_path,
                                          &hooks_path,
-                                         FeatureFlag::empty());
+                                         FeatureFlag::empty(),
+                                         false);
         assert!(hook_table.compile(&service_group, &ctx).changed());
 
         // Verify init hook
@@ -908,6 +1397,24 @@ mod tests {
         let run_hook_content_normalized = run_hook_content.replace('\r', "");
         assert_eq!(run_hook_content_normalized, expected_run_hook);
 
+        // Verify pre-update hook
+        let pre_update_hook_content = file_content(hook_table.pre_update
+                                                             .as_ref()
+                                                             .map(convert::AsRef::as_ref)
+                                                             .expect("no pre-update hook??"));
+        assert_eq!(pre_update_hook_content.replace('\r', ""),
+                   "#!/bin/bash\n\necho \"Updating from $HAB_UPDATE_FROM to $HAB_UPDATE_TO\"\n");
+        assert!(hook_table.post_update.is_none());
+
+        // Verify ready hook
+        let ready_hook_content = file_content(hook_table.ready
+                                                        .as_ref()
+                                                        .map(convert::AsRef::as_ref)
+                                                        .expect("no ready hook??"));
+        assert_eq!(ready_hook_content.replace('\r', ""),
+                   format!("#!/bin/bash\n\ntest -f {}/ready\n",
+                           pkg.svc_var_path.display()));
+
         // Recompiling again results in no changes
         assert!(!hook_table.compile(&service_group, &ctx).changed());
 
@@ -925,6 +1432,39 @@ mod tests {
         assert_eq!(run_hook_content_normalized, expected_run_hook);
     }
 
+    #[test]
+    fn strict_hook_table_is_written_only_once_every_hook_renders() {
+        let tmp_root = rendered_hooks_path();
+        let hooks_path = tmp_root.path().join("hooks");
+        fs::create_dir_all(&hooks_path).unwrap();
+        let service_group = service_group();
+        let hook_table = HookTable::load(&service_group,
+                                         &hook_templates_path(),
+                                         &hooks_path,
+                                         FeatureFlag::empty(),
+                                         true);
+
+        let missing_message = serde_json::json!({ "cfg": {}, "pkg": { "svc_var_path": "/var" } });
+        assert!(hook_table.render(&missing_message).is_err());
+        assert_eq!(fs::read_dir(&hooks_path).unwrap().count(), 0);
+
+        let ctx = serde_json::json!({ "cfg": { "message": "Hello" },
+                                      "pkg": { "svc_var_path": "/var" } });
+        let rendered = hook_table.render(&ctx).expect("every hook renders");
+        assert_eq!(fs::read_dir(&hooks_path).unwrap().count(), 0);
+
+        let changed = hook_table.write(&service_group, &rendered);
+        assert!(changed.init_changed());
+        assert!(changed.run_changed());
+        let init_hook_content = file_content(hook_table.init
+                                                       .as_ref()
+                                                       .map(convert::AsRef::as_ref)
+                                                       .expect("no init hook??"));
+        assert_eq!(init_hook_content.replace('\r', ""),
+                   "#!/bin/bash\n\necho \"The message is Hello\"\n");
+        assert!(!hook_table.write(&service_group, &rendered).changed());
+    }
+
     #[test]
     fn parse_suitability() {
         #[allow(clippy::string_lit_as_bytes)]
@@ -0,0 +1,270 @@
+//! The runs of job services.
+//!
+//! A job service runs its `run` hook to completion instead of keeping it running. It runs once
+//! after the service starts, or at every minute matching its schedule, as well as whenever a run
+//! is requested on demand. The Supervisor keeps the last few runs of each job to report how they
+//! ended.
+
+use biome_core::service::JobSchedule;
+use chrono::{DateTime,
+             Utc};
+use serde::{ser::{Error as _,
+                  SerializeStruct},
+            Serialize,
+            Serializer};
+use std::{collections::VecDeque,
+          fmt,
+          result,
+          time::{Duration,
+                 SystemTime,
+                 UNIX_EPOCH}};
+
+/// The number of runs remembered for each job service.
+pub const JOB_HISTORY_LEN: usize = 10;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobOutcome {
+    Running,
+    /// The run exited with a code of zero
+    Succeeded,
+    /// The run exited with a non-zero code, or was killed by a signal
+    Failed,
+    /// The run was stopped because it exceeded the job timeout
+    TimedOut,
+    /// The run was stopped to start a newer run in its place
+    Replaced,
+}
+
+impl fmt::Display for JobOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = match self {
+            JobOutcome::Running => "running",
+            JobOutcome::Succeeded => "succeeded",
+            JobOutcome::Failed => "failed",
+            JobOutcome::TimedOut => "timed out",
+            JobOutcome::Replaced => "replaced",
+        };
+        write!(f, "{}", outcome)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobRun {
+    pub started_at:  SystemTime,
+    pub finished_at: Option<SystemTime>,
+    pub exit_code:   Option<i32>,
+    pub outcome:     JobOutcome,
+}
+
+impl Serialize for JobRun {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let since_epoch = |timestamp: SystemTime| {
+            timestamp.duration_since(UNIX_EPOCH)
+                     .map(|duration| duration.as_secs())
+                     .map_err(|err| {
+                         S::Error::custom(format!("System time should ALWAYS be after the UNIX \
+                                                   Epoch: {:?}",
+                                                  err))
+                     })
+        };
+        let mut strukt = serializer.serialize_struct("job_run", 4)?;
+        strukt.serialize_field("started_at", &since_epoch(self.started_at)?)?;
+        strukt.serialize_field("finished_at",
+                               &self.finished_at.map(since_epoch).transpose()?)?;
+        strukt.serialize_field("exit_code", &self.exit_code)?;
+        strukt.serialize_field("outcome", &self.outcome)?;
+        strukt.end()
+    }
+}
+
+/// When a job service runs, and how its most recent runs went.
+#[derive(Clone, Debug, Default)]
+pub struct JobState {
+    /// The most recent runs, oldest first
+    runs:            VecDeque<JobRun>,
+    /// Whether a run was requested outside of the job's schedule
+    requested:       bool,
+    /// The minute, counted from the UNIX Epoch, in which the schedule last made a run due. This
+    /// keeps a run from being due on every tick of a matching minute.
+    last_due_minute: Option<i64>,
+}
+
+impl JobState {
+    /// Request a run as soon as possible, regardless of the job's schedule.
+    pub fn request(&mut self) { self.requested = true; }
+
+    /// Whether a run is due at `now`, because it was requested or because `now` falls in a
+    /// minute matching the `schedule` for the first time. The request or the minute is
+    /// consumed, so the same run is not due again.
+    pub fn take_due(&mut self, schedule: &JobSchedule, now: DateTime<Utc>) -> bool {
+        let mut due = std::mem::take(&mut self.requested);
+        if let JobSchedule::Cron(schedule) = schedule {
+            let minute = now.timestamp().div_euclid(60);
+            if self.last_due_minute != Some(minute) && schedule.matches(now) {
+                self.last_due_minute = Some(minute);
+                due = true;
+            }
+        }
+        due
+    }
+
+    pub fn runs(&self) -> &VecDeque<JobRun> { &self.runs }
+
+    /// Whether the latest run is still going.
+    pub fn running(&self) -> bool { self.running_since().is_some() }
+
+    /// How long the latest run has been going at `now`, if it is still going.
+    pub fn running_for(&self, now: SystemTime) -> Option<Duration> {
+        self.running_since()
+            .map(|started_at| now.duration_since(started_at).unwrap_or_default())
+    }
+
+    fn running_since(&self) -> Option<SystemTime> {
+        self.runs
+            .back()
+            .filter(|run| run.outcome == JobOutcome::Running)
+            .map(|run| run.started_at)
+    }
+
+    /// Record the start of a run, forgetting the oldest runs beyond `JOB_HISTORY_LEN`.
+    pub fn start(&mut self, at: SystemTime) {
+        self.runs.push_back(JobRun { started_at:  at,
+                                     finished_at: None,
+                                     exit_code:   None,
+                                     outcome:     JobOutcome::Running, });
+        while self.runs.len() > JOB_HISTORY_LEN {
+            self.runs.pop_front();
+        }
+    }
+
+    /// Record that the latest run exited with `exit_code`. A missing exit code counts as a
+    /// failure. Returns the finished run, if a run was going.
+    pub fn finish(&mut self, exit_code: Option<i32>, at: SystemTime) -> Option<&JobRun> {
+        let outcome = if exit_code == Some(0) {
+            JobOutcome::Succeeded
+        } else {
+            JobOutcome::Failed
+        };
+        self.end(outcome, exit_code, at)
+    }
+
+    /// Record that the latest run was stopped for exceeding the job timeout.
+    pub fn time_out(&mut self, at: SystemTime) -> Option<&JobRun> {
+        self.end(JobOutcome::TimedOut, None, at)
+    }
+
+    /// Record that the latest run was stopped to start a newer run.
+    pub fn replace(&mut self, at: SystemTime) -> Option<&JobRun> {
+        self.end(JobOutcome::Replaced, None, at)
+    }
+
+    fn end(&mut self,
+           outcome: JobOutcome,
+           exit_code: Option<i32>,
+           at: SystemTime)
+           -> Option<&JobRun> {
+        let run = self.runs
+                      .back_mut()
+                      .filter(|run| run.outcome == JobOutcome::Running)?;
+        run.finished_at = Some(at);
+        run.exit_code = exit_code;
+        run.outcome = outcome;
+        Some(run)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, hour, minute, second)
+           .unwrap()
+    }
+
+    #[test]
+    fn once_is_only_due_when_requested() {
+        let mut state = JobState::default();
+        assert!(!state.take_due(&JobSchedule::Once, at(3, 0, 0)));
+        state.request();
+        assert!(state.take_due(&JobSchedule::Once, at(3, 0, 0)));
+        assert!(!state.take_due(&JobSchedule::Once, at(3, 0, 1)));
+    }
+
+    #[test]
+    fn cron_is_due_once_per_matching_minute() {
+        let schedule = JobSchedule::Cron("*/5 * * * *".parse().unwrap());
+        let mut state = JobState::default();
+        assert!(!state.take_due(&schedule, at(3, 4, 59)));
+        assert!(state.take_due(&schedule, at(3, 5, 0)));
+        assert!(!state.take_due(&schedule, at(3, 5, 30)));
+        assert!(!state.take_due(&schedule, at(3, 6, 0)));
+        assert!(state.take_due(&schedule, at(3, 10, 15)));
+
+        state.request();
+        assert!(state.take_due(&schedule, at(3, 11, 0)));
+    }
+
+    #[test]
+    fn finish_records_the_outcome_of_the_latest_run() {
+        let mut state = JobState::default();
+        assert!(state.finish(Some(0), SystemTime::now()).is_none());
+
+        let started_at = UNIX_EPOCH + Duration::from_secs(60);
+        state.start(started_at);
+        assert!(state.running());
+        assert_eq!(state.running_for(started_at + Duration::from_secs(5)),
+                   Some(Duration::from_secs(5)));
+        assert_eq!(state.finish(Some(0), started_at).unwrap().outcome,
+                   JobOutcome::Succeeded);
+        assert!(!state.running());
+
+        state.start(started_at);
+        assert_eq!(state.finish(Some(2), started_at).unwrap().outcome,
+                   JobOutcome::Failed);
+        state.start(started_at);
+        assert_eq!(state.finish(None, started_at).unwrap().outcome,
+                   JobOutcome::Failed);
+    }
+
+    #[test]
+    fn a_stopped_run_is_not_finished_again() {
+        let mut state = JobState::default();
+        state.start(SystemTime::now());
+        assert_eq!(state.time_out(SystemTime::now()).unwrap().outcome,
+                   JobOutcome::TimedOut);
+        assert!(state.finish(Some(0), SystemTime::now()).is_none());
+        assert_eq!(state.runs().back().unwrap().outcome, JobOutcome::TimedOut);
+
+        state.start(SystemTime::now());
+        assert_eq!(state.replace(SystemTime::now()).unwrap().outcome,
+                   JobOutcome::Replaced);
+    }
+
+    #[test]
+    fn history_keeps_the_most_recent_runs() {
+        let mut state = JobState::default();
+        for secs in 0..(JOB_HISTORY_LEN as u64 + 3) {
+            let started_at = UNIX_EPOCH + Duration::from_secs(secs);
+            state.start(started_at);
+            state.finish(Some(0), started_at);
+        }
+        assert_eq!(state.runs().len(), JOB_HISTORY_LEN);
+        assert_eq!(state.runs().front().unwrap().started_at,
+                   UNIX_EPOCH + Duration::from_secs(3));
+    }
+
+    #[test]
+    fn runs_serialize_timestamps_as_seconds_since_epoch() {
+        let run = JobRun { started_at:  UNIX_EPOCH + Duration::from_secs(60),
+                           finished_at: Some(UNIX_EPOCH + Duration::from_secs(90)),
+                           exit_code:   Some(1),
+                           outcome:     JobOutcome::Failed, };
+        assert_eq!(serde_json::to_string(&run).unwrap(),
+                   r#"{"started_at":60,"finished_at":90,"exit_code":1,"outcome":"failed"}"#);
+    }
+}
@@ -8,8 +8,12 @@ use biome_core::{fs::atomic_write,
                    os::process::ShutdownTimeout,
                    package::{PackageIdent,
                              PackageInstall},
+                   schedule::Schedule,
                    service::{HealthCheckInterval,
-                             ServiceBind},
+                             JobConcurrency,
+                             JobSchedule,
+                             ServiceBind,
+                             UpdateBatchSize},
                    url::DEFAULT_BLDR_URL,
                    util,
                    ChannelIdent};
@@ -99,6 +103,49 @@ pub struct ServiceSpec {
     pub desired_state:          DesiredState,
     pub shutdown_timeout:       Option<ShutdownTimeout>,
     pub svc_encrypted_password: Option<String>,
+    pub strict_templates:       bool,
+    /// How long, in seconds, the health of the service is watched after a package update
+    /// before the update is kept. The update is rolled back if the service turns critical
+    /// or crash loops within this window.
+    pub rollback_window:        Option<u32>,
+    /// How many members, including the update leader, a canary update updates before waiting
+    /// for the canaries to be approved.
+    pub canary_count:           Option<u32>,
+    /// How many members update together in each wave of a canary or batched update.
+    pub update_batch_size:      Option<UpdateBatchSize>,
+    /// How many members may be unavailable at once during a canary or batched update.
+    pub max_unavailable:        Option<u32>,
+    /// The minutes in which detected package updates are applied. Updates detected outside of
+    /// the window are deferred until it opens.
+    pub update_window:          Option<Schedule>,
+    /// The fully-qualified package the service is held at. Updates are not applied while a
+    /// service is held.
+    #[serde(with = "util::serde::option_string")]
+    pub hold:                   Option<PackageIdent>,
+    /// The fully-qualified package an update of the service was rolled back from. Updates
+    /// never install it again, even after the Supervisor restarts.
+    #[serde(with = "util::serde::option_string")]
+    pub rejected_release:       Option<PackageIdent>,
+    /// The fully-qualified package the service keeps running while an installed update of it
+    /// waits for the update window or for its `pre-update` hook, so that restarting the
+    /// Supervisor does not start the update early. Set and cleared by the Supervisor.
+    #[serde(with = "util::serde::option_string")]
+    pub pinned:                 Option<PackageIdent>,
+    /// Whether the post-update hook only runs on the leader of the service group, rather than
+    /// on every member that is updated.
+    pub post_update_on_leader:  bool,
+    /// How long, in seconds, the service is drained before it is stopped. The service is
+    /// reported as not ready while it drains, so that its peers stop sending work to it.
+    pub drain_timeout:          Option<u32>,
+    /// When the service runs as a job, running its `run` hook to completion once or on a
+    /// schedule instead of restarting it whenever it exits.
+    pub job:                    Option<JobSchedule>,
+    /// How long, in seconds, a job run may take before it is stopped.
+    pub job_timeout:            Option<u32>,
+    /// What a job does when a run is due while the previous run is still going.
+    pub job_concurrency:        JobConcurrency,
+    /// Whether the job only runs on the leader of the service group.
+    pub job_on_leader:          bool,
     // it is important that the health check interval
     // is the last field to be serialized because it
     // is serialized as a table. Individual values
@@ -125,7 +172,22 @@ impl ServiceSpec {
                desired_state: DesiredState::default(),
                health_check_interval: HealthCheckInterval::default(),
                svc_encrypted_password: None,
-               shutdown_timeout: None }
+               shutdown_timeout: None,
+               strict_templates: false,
+               rollback_window: None,
+               canary_count: None,
+               update_batch_size: None,
+               max_unavailable: None,
+               update_window: None,
+               hold: None,
+               rejected_release: None,
+               pinned: None,
+               post_update_on_leader: false,
+               drain_timeout: None,
+               job: None,
+               job_timeout: None,
+               job_concurrency: JobConcurrency::default(),
+               job_on_leader: false }
     }
 
     // This should only be used to provide a default value when deserializing. We intentially do not
@@ -175,13 +237,15 @@ impl ServiceSpec {
 
     pub fn file(&self) -> PathBuf { Self::ident_file(&self.ident) }
 
-    /// Validates that all required package binds are present in service binds and all remaining
-    /// service binds are optional package binds.
+    /// Validates that all required package binds are present in service binds, all remaining
+    /// service binds are optional package binds, and that every service bind is to a service
+    /// group the package bind's producer can run in.
     ///
     /// # Errors
     ///
     /// * If any required package binds are missing in service binds
     /// * If any given service binds are in neither required nor optional package binds
+    /// * If any service binds are to a service other than the package bind's required producer
     pub fn validate(&self, package: &PackageInstall) -> Result<()> {
         let mut svc_binds: HashSet<&str> = self.binds.iter().map(ServiceBind::name).collect();
         let mut missing_req_binds = Vec::new();
@@ -215,6 +279,24 @@ impl ServiceSpec {
                                                     .collect()));
         }
 
+        // A service group is named after the package its members run, so a service bind to a
+        // group of any other name can never be satisfied by the required producer.
+        let pkg_binds = package.all_binds()?;
+        let incompatible_binds =
+            self.binds
+                .iter()
+                .filter_map(|svc_bind| {
+                    pkg_binds.iter()
+                             .find(|b| b.service == svc_bind.name())
+                             .and_then(|b| b.producer.as_ref())
+                             .filter(|producer| producer.name != svc_bind.service_group().service())
+                             .map(|producer| format!("{} (requires {})", svc_bind, producer))
+                })
+                .collect::<Vec<_>>();
+        if !incompatible_binds.is_empty() {
+            return Err(Error::IncompatibleBinds(incompatible_binds));
+        }
+
         Ok(())
     }
 
@@ -282,6 +364,72 @@ impl ServiceSpec {
         if let Some(shutdown_timeout) = svc_load.shutdown_timeout {
             self.shutdown_timeout = Some(ShutdownTimeout::from(shutdown_timeout));
         }
+        if let Some(drain_timeout) = svc_load.drain_timeout {
+            // A timeout of zero turns off draining
+            self.drain_timeout = Some(drain_timeout).filter(|t| *t > 0);
+        }
+        if let Some(job) = svc_load.job {
+            // An empty schedule turns off job mode
+            if job.is_empty() {
+                self.job = None;
+            } else if let Ok(job) = JobSchedule::from_str(&job) {
+                self.job = Some(job);
+            } else {
+                warn!("Unable to parse job schedule value from SvcLoad protocol message; \
+                       ignoring: {}",
+                      job);
+            }
+        }
+        if let Some(job_timeout) = svc_load.job_timeout {
+            // A timeout of zero lets job runs take as long as they need
+            self.job_timeout = Some(job_timeout).filter(|t| *t > 0);
+        }
+        if let Some(job_concurrency) = svc_load.job_concurrency {
+            if let Ok(job_concurrency) = JobConcurrency::from_str(&job_concurrency) {
+                self.job_concurrency = job_concurrency;
+            } else {
+                warn!("Unable to parse job concurrency value from SvcLoad protocol message; \
+                       ignoring: {}",
+                      job_concurrency);
+            }
+        }
+        if let Some(job_on_leader) = svc_load.job_on_leader {
+            self.job_on_leader = job_on_leader;
+        }
+        if let Some(strict_templates) = svc_load.strict_templates {
+            self.strict_templates = strict_templates;
+        }
+        if let Some(post_update_on_leader) = svc_load.post_update_on_leader {
+            self.post_update_on_leader = post_update_on_leader;
+        }
+        if let Some(rollback_window) = svc_load.rollback_window {
+            // A window of zero turns off health-gated rollbacks
+            self.rollback_window = Some(rollback_window).filter(|w| *w > 0);
+        }
+        if let Some(canary_count) = svc_load.canary_count {
+            self.canary_count = Some(canary_count);
+        }
+        if let Some(update_batch_size) = svc_load.update_batch_size {
+            if let Ok(update_batch_size) = UpdateBatchSize::from_str(&update_batch_size) {
+                self.update_batch_size = Some(update_batch_size);
+            } else {
+                warn!("Unable to parse update batch size value from SvcLoad protocol message; \
+                       ignoring: {}",
+                      update_batch_size);
+            }
+        }
+        if let Some(max_unavailable) = svc_load.max_unavailable {
+            self.max_unavailable = Some(max_unavailable);
+        }
+        if let Some(update_window) = svc_load.update_window {
+            if let Ok(update_window) = Schedule::from_str(&update_window) {
+                self.update_window = Some(update_window);
+            } else {
+                warn!("Unable to parse update window value from SvcLoad protocol message; \
+                       ignoring: {}",
+                      update_window);
+            }
+        }
         Ok(self)
     }
 
@@ -343,6 +491,73 @@ impl ServiceSpec {
         if let Some(shutdown_timeout) = svc_update.shutdown_timeout {
             self.shutdown_timeout = Some(ShutdownTimeout::from(shutdown_timeout));
         }
+        if let Some(drain_timeout) = svc_update.drain_timeout {
+            // A timeout of zero turns off draining
+            self.drain_timeout = Some(drain_timeout).filter(|t| *t > 0);
+        }
+        if let Some(job) = svc_update.job {
+            // An empty schedule turns off job mode
+            if job.is_empty() {
+                self.job = None;
+            } else if let Ok(job) = JobSchedule::from_str(&job) {
+                self.job = Some(job);
+            } else {
+                warn!("Unable to parse job schedule value from SvcUpdate protocol message; \
+                       ignoring: {}",
+                      job);
+            }
+        }
+        if let Some(job_timeout) = svc_update.job_timeout {
+            // A timeout of zero lets job runs take as long as they need
+            self.job_timeout = Some(job_timeout).filter(|t| *t > 0);
+        }
+        if let Some(job_concurrency) = svc_update.job_concurrency {
+            if let Ok(job_concurrency) = JobConcurrency::from_str(&job_concurrency) {
+                self.job_concurrency = job_concurrency;
+            } else {
+                warn!("Unable to parse job concurrency value from SvcUpdate protocol message; \
+                       ignoring: {}",
+                      job_concurrency);
+            }
+        }
+        if let Some(job_on_leader) = svc_update.job_on_leader {
+            self.job_on_leader = job_on_leader;
+        }
+        if let Some(strict_templates) = svc_update.strict_templates {
+            self.strict_templates = strict_templates;
+        }
+        if let Some(post_update_on_leader) = svc_update.post_update_on_leader {
+            self.post_update_on_leader = post_update_on_leader;
+        }
+        if let Some(rollback_window) = svc_update.rollback_window {
+            // A window of zero turns off health-gated rollbacks
+            self.rollback_window = Some(rollback_window).filter(|w| *w > 0);
+        }
+        if let Some(canary_count) = svc_update.canary_count {
+            self.canary_count = Some(canary_count);
+        }
+        if let Some(update_batch_size) = svc_update.update_batch_size {
+            if let Ok(update_batch_size) = UpdateBatchSize::from_str(&update_batch_size) {
+                self.update_batch_size = Some(update_batch_size);
+            } else {
+                warn!("Unable to parse update batch size value from SvcUpdate protocol message; \
+                       ignoring: {}",
+                      update_batch_size);
+            }
+        }
+        if let Some(max_unavailable) = svc_update.max_unavailable {
+            self.max_unavailable = Some(max_unavailable);
+        }
+        // `hold` is resolved by the caller, which knows the package the service is running
+        if let Some(update_window) = svc_update.update_window {
+            if let Ok(update_window) = Schedule::from_str(&update_window) {
+                self.update_window = Some(update_window);
+            } else {
+                warn!("Unable to parse update window value from SvcUpdate protocol message; \
+                       ignoring: {}",
+                      update_window);
+            }
+        }
     }
 
     /// Given an `old` and a `new` spec, figure out what operations
@@ -434,6 +649,21 @@ impl ServiceSpec {
                         desired_state: _,
                         shutdown_timeout,
                         svc_encrypted_password,
+                        strict_templates,
+                        rollback_window,
+                        canary_count,
+                        update_batch_size,
+                        max_unavailable,
+                        update_window,
+                        hold,
+                        rejected_release,
+                        pinned,
+                        post_update_on_leader,
+                        drain_timeout,
+                        job,
+                        job_timeout,
+                        job_concurrency,
+                        job_on_leader,
                         health_check_interval,
                     } = &running_spec;
 
@@ -461,7 +691,14 @@ impl ServiceSpec {
                         || config_from != &disk_spec.config_from
                         // TODO (CM): This probably doesn't need to be here
                         || shutdown_timeout != &disk_spec.shutdown_timeout
+                        || job != &disk_spec.job
+                        || job_timeout != &disk_spec.job_timeout
+                        || job_concurrency != &disk_spec.job_concurrency
+                        || job_on_leader != &disk_spec.job_on_leader
                         || svc_encrypted_password != &disk_spec.svc_encrypted_password
+                        // Templates are registered with their renderer when the
+                        // service is created
+                        || strict_templates != &disk_spec.strict_templates
                         // TODO (CM): This probably doesn't need to be here, either
                         || health_check_interval != &disk_spec.health_check_interval
                     {
@@ -475,14 +712,28 @@ impl ServiceSpec {
                             || channel != &disk_spec.channel
                             || update_strategy != &disk_spec.update_strategy
                             || update_condition != &disk_spec.update_condition
+                            || rollback_window != &disk_spec.rollback_window
+                            || canary_count != &disk_spec.canary_count
+                            || update_batch_size != &disk_spec.update_batch_size
+                            || max_unavailable != &disk_spec.max_unavailable
+                            || update_window != &disk_spec.update_window
+                            || hold != &disk_spec.hold
+                            || rejected_release != &disk_spec.rejected_release
+                            || post_update_on_leader != &disk_spec.post_update_on_leader
                         {
                             ops.insert(RefreshOperation::RestartUpdater);
                         }
 
+                        // The drain timeout is read from the spec when the service stops and the
+                        // pinned package when it starts, so swapping in the new spec is all there
+                        // is to do.
+                        let swap_only = drain_timeout != &disk_spec.drain_timeout
+                                        || pinned != &disk_spec.pinned;
+
                         // We should have *something* to do down
                         // here, but if we don't, let's be explicit
                         // about it.
-                        if ops.is_empty() {
+                        if ops.is_empty() && !swap_only {
                             warn!("No refresh operations computed for {}!", ident);
                             None
                         } else {
@@ -601,6 +852,8 @@ mod test {
             update_condition = "latest"
             binds = ["cache:redis.cache@acmecorp", "db:postgres.app@acmecorp"]
             config_from = "/only/for/development"
+            job = "once"
+            job_concurrency = "replace"
 
             [health_check_interval]
             secs = 5
@@ -620,6 +873,8 @@ mod test {
                         ServiceBind::from_str("db:postgres.app@acmecorp").unwrap(),]);
         assert_eq!(spec.config_from,
                    Some(PathBuf::from("/only/for/development")));
+        assert_eq!(spec.job, Some(JobSchedule::Once));
+        assert_eq!(spec.job_concurrency, JobConcurrency::Replace);
         assert_eq!(spec.health_check_interval,
                    HealthCheckInterval::from_str("5").unwrap());
     }
@@ -696,7 +951,25 @@ mod test {
                           config_from:            Some(PathBuf::from("/only/for/development")),
                           desired_state:          DesiredState::Down,
                           svc_encrypted_password: None,
-                          shutdown_timeout:       Some(ShutdownTimeout::from_str("10").unwrap()), };
+                          shutdown_timeout:       Some(ShutdownTimeout::from_str("10").unwrap()),
+                          strict_templates:       true,
+                          rollback_window:        Some(300),
+                          canary_count:           Some(2),
+                          update_batch_size:      Some(UpdateBatchSize::Percent(25)),
+                          max_unavailable:        Some(1),
+                          update_window:          Some("* 2-4 * * SAT,SUN".parse().unwrap()),
+                          hold:                   Some(PackageIdent::from_str("origin/name/1.2.3/\
+                                                                               20170223130020").unwrap()),
+                          rejected_release:       Some(PackageIdent::from_str("origin/name/1.2.4/\
+                                                                               20170301130020").unwrap()),
+                          pinned:                 Some(PackageIdent::from_str("origin/name/1.2.3/\
+                                                                               20170223130020").unwrap()),
+                          post_update_on_leader:  true,
+                          drain_timeout:          Some(20),
+                          job:                    Some("0 3 * * *".parse().unwrap()),
+                          job_timeout:            Some(600),
+                          job_concurrency:        JobConcurrency::Replace,
+                          job_on_leader:          true, };
         let toml = spec.to_toml_string().unwrap();
 
         assert!(toml.contains(r#"ident = "origin/name/1.2.3/20170223130020""#,));
@@ -714,6 +987,21 @@ mod test {
         assert!(toml.contains(r#"secs = 123"#));
         assert!(toml.contains(r#"nanos = 0"#));
         assert!(toml.contains(r#"shutdown_timeout = 10"#));
+        assert!(toml.contains(r#"strict_templates = true"#));
+        assert!(toml.contains(r#"rollback_window = 300"#));
+        assert!(toml.contains(r#"canary_count = 2"#));
+        assert!(toml.contains(r#"update_batch_size = "25%""#));
+        assert!(toml.contains(r#"max_unavailable = 1"#));
+        assert!(toml.contains(r#"update_window = "* 2-4 * * SAT,SUN UTC""#));
+        assert!(toml.contains(r#"hold = "origin/name/1.2.3/20170223130020""#));
+        assert!(toml.contains(r#"rejected_release = "origin/name/1.2.4/20170301130020""#));
+        assert!(toml.contains(r#"pinned = "origin/name/1.2.3/20170223130020""#));
+        assert!(toml.contains(r#"post_update_on_leader = true"#));
+        assert!(toml.contains(r#"drain_timeout = 20"#));
+        assert!(toml.contains(r#"job = "0 3 * * * UTC""#));
+        assert!(toml.contains(r#"job_timeout = 600"#));
+        assert!(toml.contains(r#"job_concurrency = "replace""#));
+        assert!(toml.contains(r#"job_on_leader = true"#));
     }
 
     #[test]
@@ -865,7 +1153,22 @@ mod test {
                           config_from:            Some(PathBuf::from("/only/for/development")),
                           desired_state:          DesiredState::Down,
                           svc_encrypted_password: None,
-                          shutdown_timeout:       Some(ShutdownTimeout::default()), };
+                          shutdown_timeout:       Some(ShutdownTimeout::default()),
+                          strict_templates:       false,
+                          rollback_window:        None,
+                          canary_count:           None,
+                          update_batch_size:      None,
+                          max_unavailable:        None,
+                          update_window:          None,
+                          hold:                   None,
+                          rejected_release:       None,
+                          pinned:                 None,
+                          post_update_on_leader:  false,
+                          drain_timeout:          None,
+                          job:                    None,
+                          job_timeout:            None,
+                          job_concurrency:        JobConcurrency::Forbid,
+                          job_on_leader:          false, };
         spec.to_file(&path).unwrap();
         let toml = string_from_file(path);
 
@@ -1005,6 +1308,40 @@ mod test {
         }
     }
 
+    #[test]
+    /// Test when a bind is to a service group that can't run the required producer
+    fn service_spec_error_incompatible_bind() {
+        let tmpdir = TempDir::new().unwrap();
+        file_from_str(tmpdir.path().join("BINDS"),
+                      "database=port user?=admin pkg:core/postgresql version:>=11");
+        let ident = PackageIdent::from_str("acmecorp/app/1.0.0/20230101000000").unwrap();
+        let package = PackageInstall::new_from_parts(ident.clone(),
+                                                     tmpdir.path().to_path_buf(),
+                                                     tmpdir.path().to_path_buf(),
+                                                     tmpdir.path().to_path_buf());
+
+        let mut spec = ServiceSpec::new(ident);
+        spec.binds = vec![ServiceBind::from_str("database:postgresql.app@acmecorp").unwrap()];
+        if let Err(e) = spec.validate(&package) {
+            panic!("Unexpected error returned: {:?}", e);
+        }
+
+        spec.binds = vec![ServiceBind::from_str("database:mysql.app@acmecorp").unwrap()];
+        match spec.validate(&package) {
+            Err(e) => {
+                match e {
+                    IncompatibleBinds(b) => {
+                        assert_eq!(vec!["database:mysql.app@acmecorp (requires core/postgresql)"
+                                          .to_string()],
+                                   b)
+                    }
+                    wrong => panic!("Unexpected error returned: {:?}", wrong),
+                }
+            }
+            Ok(_) => panic!("Spec should not validate"),
+        }
+    }
+
     /// This is to support backward compatibility with the old
     /// application/environment functionality that is being removed.
     #[test]
@@ -1165,10 +1502,21 @@ mod test {
                    restart,
                    shutdown_timeout,
                    Some(10.into()));
+        reconcile!(job_causes_restart, restart, job, Some(JobSchedule::Once));
+        reconcile!(job_timeout_causes_restart, restart, job_timeout, Some(600));
+        reconcile!(job_concurrency_causes_restart,
+                   restart,
+                   job_concurrency,
+                   JobConcurrency::Replace);
+        reconcile!(job_on_leader_causes_restart, restart, job_on_leader, true);
         reconcile!(svc_encrypted_password_causes_restart,
                    restart,
                    svc_encrypted_password,
                    Some("monkeys".to_string()));
+        reconcile!(strict_templates_causes_restart,
+                   restart,
+                   strict_templates,
+                   true);
         reconcile!(health_check_interval_causes_restart,
                    restart,
                    health_check_interval,
@@ -1194,5 +1542,55 @@ mod test {
                    update_condition,
                    UpdateCondition::TrackChannel,
                    vec![RefreshOperation::RestartUpdater]);
+        reconcile!(rollback_window_causes_update,
+                   update,
+                   rollback_window,
+                   Some(300),
+                   vec![RefreshOperation::RestartUpdater]);
+        reconcile!(canary_count_causes_update,
+                   update,
+                   canary_count,
+                   Some(2),
+                   vec![RefreshOperation::RestartUpdater]);
+        reconcile!(update_batch_size_causes_update,
+                   update,
+                   update_batch_size,
+                   Some(UpdateBatchSize::Count(3)),
+                   vec![RefreshOperation::RestartUpdater]);
+        reconcile!(max_unavailable_causes_update,
+                   update,
+                   max_unavailable,
+                   Some(1),
+                   vec![RefreshOperation::RestartUpdater]);
+        reconcile!(update_window_causes_update,
+                   update,
+                   update_window,
+                   Some("0 3 * * *".parse().unwrap()),
+                   vec![RefreshOperation::RestartUpdater]);
+        reconcile!(post_update_on_leader_causes_update,
+                   update,
+                   post_update_on_leader,
+                   true,
+                   vec![RefreshOperation::RestartUpdater]);
+        reconcile!(drain_timeout_causes_update,
+                   update,
+                   drain_timeout,
+                   Some(20),
+                   vec![]);
+        reconcile!(hold_causes_update,
+                   update,
+                   hold,
+                   Some("core/redis/4.0.14/20190319155852".parse().unwrap()),
+                   vec![RefreshOperation::RestartUpdater]);
+        reconcile!(rejected_release_causes_update,
+                   update,
+                   rejected_release,
+                   Some("core/redis/4.0.14/20190319155852".parse().unwrap()),
+                   vec![RefreshOperation::RestartUpdater]);
+        reconcile!(pinned_causes_update,
+                   update,
+                   pinned,
+                   Some("core/redis/4.0.14/20190319155852".parse().unwrap()),
+                   vec![]);
     }
 }
@@ -1,10 +1,12 @@
 mod package_update_worker;
 mod rolling_update_worker;
+pub mod rollout;
 
 use self::{package_update_worker::PackageUpdateWorker,
            rolling_update_worker::RollingUpdateWorker};
 use crate::{census::CensusRing,
-            manager::service::{Service,
+            manager::service::{HealthCheckResult,
+                               Service,
                                UpdateStrategy}};
 use futures::future::{self,
                       AbortHandle};
@@ -16,14 +18,21 @@ use parking_lot::{Mutex,
                   RwLock};
 use std::{self,
           cmp::Ordering,
-          collections::HashMap,
+          collections::{HashMap,
+                        HashSet},
           fmt,
           future::Future,
           sync::Arc,
-          time::Duration};
+          time::{Duration,
+                 Instant,
+                 SystemTime}};
 
 static LOGKEY: &str = "SU";
 
+/// The number of times a service can restart within its rollback window before an update is
+/// considered to be crash looping. Restarting a service for an update resets its restart count.
+const CRASH_LOOP_RESTARTS: u64 = 3;
+
 /// A handle to an update worker that automatically aborts the worker when dropped.
 struct Worker(AbortHandle);
 
@@ -54,6 +63,104 @@ impl fmt::Display for IncarnatedPackageIdent {
     }
 }
 
+/// The last time an update worker checked the channel of a service for a newer release.
+#[derive(Clone, Debug)]
+pub struct UpdateCheck {
+    /// The newest release that was found
+    pub latest:     PackageIdent,
+    pub checked_at: SystemTime,
+}
+
+type UpdateChecks = Arc<Mutex<HashMap<ServiceGroup, UpdateCheck>>>;
+
+/// Why a package update was rolled back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RollbackReason {
+    HealthCheckCritical,
+    CrashLoop,
+}
+
+impl fmt::Display for RollbackReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollbackReason::HealthCheckCritical => write!(f, "the health check was critical"),
+            RollbackReason::CrashLoop => {
+                write!(f, "the service restarted {} times", CRASH_LOOP_RESTARTS)
+            }
+        }
+    }
+}
+
+/// The outcome of checking on a package update within its rollback window.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UpdateVerdict {
+    /// The rollback window is still open and the service is healthy so far.
+    Pending,
+    /// The rollback window closed without the service failing; the update is kept.
+    Confirmed,
+    /// The service failed; restart it with the `previous` package.
+    RollBack {
+        previous: PackageIdent,
+        failed:   PackageIdent,
+        reason:   RollbackReason,
+    },
+}
+
+/// A package update whose service is watched until its rollback window closes.
+struct UpdateWatch {
+    previous: PackageIdent,
+    updated:  PackageIdent,
+    deadline: Instant,
+}
+
+impl UpdateWatch {
+    fn verdict(&self,
+               health: HealthCheckResult,
+               restart_count: u64,
+               now: Instant)
+               -> UpdateVerdict {
+        let reason = if health == HealthCheckResult::Critical {
+            RollbackReason::HealthCheckCritical
+        } else if restart_count >= CRASH_LOOP_RESTARTS {
+            RollbackReason::CrashLoop
+        } else if now >= self.deadline {
+            return UpdateVerdict::Confirmed;
+        } else {
+            return UpdateVerdict::Pending;
+        };
+        UpdateVerdict::RollBack { previous: self.previous.clone(),
+                                  failed: self.updated.clone(),
+                                  reason }
+    }
+}
+
+/// The outcome of a service's `pre-update` hook for an update.
+#[derive(Clone, Debug)]
+enum PreUpdate {
+    Running(PackageIdent),
+    Approved(PackageIdent),
+    Vetoed(PackageIdent, Instant),
+}
+
+impl PreUpdate {
+    /// Whether the update to `update` may be applied. Returns `None` if the hook has to run (again)
+    /// for it. A vetoed update is retried once `retry_after` has elapsed.
+    fn allows(&self, update: &PackageIdent, retry_after: Duration, now: Instant) -> Option<bool> {
+        match self {
+            PreUpdate::Approved(ident) if ident == update => Some(true),
+            PreUpdate::Running(ident) if ident == update => Some(false),
+            PreUpdate::Vetoed(ident, vetoed_at) if ident == update => {
+                if now.duration_since(*vetoed_at) < retry_after {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
 /// The `ServiceUpdater` is in charge of updating a Service when a change in version of a package
 /// has been published to a depot channel or installed to the local package cache. To use an update
 /// strategy, the supervisor must be configured to watch a depot for new versions.
@@ -61,8 +168,18 @@ pub struct ServiceUpdater {
     butterfly:   biome_butterfly::Server,
     census_ring: Arc<RwLock<CensusRing>>,
     updates:     Arc<Mutex<HashMap<ServiceGroup, IncarnatedPackageIdent>>>,
+    /// The last check of each service's channel for a newer release
+    checks:      UpdateChecks,
     workers:     HashMap<ServiceGroup, Worker>,
     period:      Duration,
+    /// Updates waiting for their rollback window to close
+    watches:     HashMap<ServiceGroup, UpdateWatch>,
+    /// Updates waiting for their service's update window to open
+    deferred:    HashMap<ServiceGroup, PackageIdent>,
+    /// Services whose next update is applied regardless of their update window
+    forced:      HashSet<ServiceGroup>,
+    /// The `pre-update` hook outcomes of pending updates
+    pre_updates: Arc<Mutex<HashMap<ServiceGroup, PreUpdate>>>,
 }
 
 impl ServiceUpdater {
@@ -73,8 +190,13 @@ impl ServiceUpdater {
         ServiceUpdater { butterfly,
                          census_ring,
                          updates: Arc::default(),
+                         checks: Arc::default(),
                          workers: HashMap::new(),
-                         period }
+                         period,
+                         watches: HashMap::new(),
+                         deferred: HashMap::new(),
+                         forced: HashSet::new(),
+                         pre_updates: Arc::default() }
     }
 
     /// Register a service for updates. If the service has already
@@ -91,13 +213,20 @@ impl ServiceUpdater {
             UpdateStrategy::None => {
                 debug!("No updater registered for for {}", service);
             }
+            UpdateStrategy::Notify => {
+                debug!("Registering notify-only updater for {}", service);
+                let worker = self.notify_worker(service);
+                self.spawn_worker(service_group, worker);
+            }
             UpdateStrategy::AtOnce => {
                 debug!("Registering at-once updater for {}", service);
                 let worker = self.at_once_worker(service);
                 self.spawn_worker(service_group, worker);
             }
-            UpdateStrategy::Rolling => {
-                debug!("Registering rolling updater for {}", service);
+            UpdateStrategy::Rolling | UpdateStrategy::Canary | UpdateStrategy::Batched => {
+                debug!("Registering {} updater for {}",
+                       service.update_strategy(),
+                       service);
                 let worker = self.rolling_worker(service, Arc::clone(&self.census_ring));
                 self.spawn_worker(service_group, worker);
             }
@@ -108,6 +237,10 @@ impl ServiceUpdater {
     pub fn remove(&mut self, service_group: &ServiceGroup) {
         self.workers.remove(service_group);
         self.updates.lock().remove(service_group);
+        self.checks.lock().remove(service_group);
+        self.deferred.remove(service_group);
+        self.forced.remove(service_group);
+        self.pre_updates.lock().remove(service_group);
     }
 
     /// Check if this service has an update. If it does return the package ident of the update.
@@ -120,6 +253,151 @@ impl ServiceUpdater {
         self.updates.lock().get(service_group).cloned()
     }
 
+    /// The last time this service's channel was checked for a newer release, if it has been.
+    pub fn update_check(&self, service_group: &ServiceGroup) -> Option<UpdateCheck> {
+        self.checks.lock().get(service_group).cloned()
+    }
+
+    /// The release this service would be updated to, if it differs from the release the service
+    /// is running. Reported even while the update is deferred or held, and for services that are
+    /// only notified of updates.
+    pub fn available_update(&self, service: &Service) -> Option<PackageIdent> {
+        self.has_update(&service.service_group)
+            .map(|update| update.ident)
+            .or_else(|| {
+                self.update_check(&service.service_group)
+                    .map(|check| check.latest)
+            })
+            .filter(|ident| ident != service.pkg.ident.as_ref())
+    }
+
+    /// Check if this service has an update that may be applied now. Updates of a held service are
+    /// deferred until the hold is released. An update detected outside of the service's update
+    /// window is deferred until the window opens, unless the update was forced with
+    /// `force_update`. Finally, the update waits for the service's `pre-update` hook to allow it.
+    pub fn due_update(&mut self, service: &Service) -> Option<IncarnatedPackageIdent> {
+        let service_group = &service.service_group;
+        let update = self.has_update(service_group)?;
+        if let Some(held_at) = service.hold() {
+            if self.deferred.get(service_group) != Some(&update.ident) {
+                outputln!("Deferring update of {} to {} until it is released from its hold at {}",
+                          service_group,
+                          update.ident,
+                          held_at);
+                self.deferred
+                    .insert(service_group.clone(), update.ident.clone());
+            }
+            return None;
+        }
+        if let Some(window) = service.update_window() {
+            if !self.forced.contains(service_group) && !window.matches_now() {
+                if self.deferred.get(service_group) != Some(&update.ident) {
+                    outputln!("Deferring update of {} to {} until its update window '{}' opens",
+                              service_group,
+                              update.ident,
+                              window);
+                    self.deferred
+                        .insert(service_group.clone(), update.ident.clone());
+                }
+                return None;
+            }
+        }
+        if self.pre_update_allows(service, &update.ident) {
+            Some(update)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the `pre-update` hook of this service allows updating it to `update`. The hook is
+    /// started in the background the first time an update is due and the update waits until it
+    /// exits. An update the hook vetoed is offered to it again after the update period. The update
+    /// is already installed by then, so while the hook runs or its veto stands the manager pins
+    /// the service to the release it runs.
+    fn pre_update_allows(&self, service: &Service, update: &PackageIdent) -> bool {
+        if service.pkg.ident.as_ref() == update {
+            return true;
+        }
+        let service_group = &service.service_group;
+        let mut pre_updates = self.pre_updates.lock();
+        if let Some(allows) =
+            pre_updates.get(service_group)
+                       .and_then(|p| p.allows(update, self.period, Instant::now()))
+        {
+            return allows;
+        }
+        let hook = match service.pre_update(update) {
+            Some(hook) => hook,
+            None => return true,
+        };
+        outputln!("Running the pre-update hook of {} for its update to {}",
+                  service_group,
+                  update);
+        pre_updates.insert(service_group.clone(), PreUpdate::Running(update.clone()));
+        let pre_updates = Arc::clone(&self.pre_updates);
+        let service_group = service_group.clone();
+        let update = update.clone();
+        tokio::spawn(async move {
+            let outcome = if hook.await {
+                PreUpdate::Approved(update.clone())
+            } else {
+                PreUpdate::Vetoed(update.clone(), Instant::now())
+            };
+            let mut pre_updates = pre_updates.lock();
+            // The service may have been removed, or found a newer update, while the hook ran
+            if let Some(PreUpdate::Running(ident)) = pre_updates.get(&service_group) {
+                if ident == &update {
+                    pre_updates.insert(service_group, outcome);
+                }
+            }
+        });
+        false
+    }
+
+    /// Apply the next update of this service as soon as it is detected, even outside of the
+    /// service's update window. Returns the update if one is already waiting for the window.
+    pub fn force_update(&mut self, service_group: &ServiceGroup) -> Option<PackageIdent> {
+        self.forced.insert(service_group.clone());
+        self.deferred.get(service_group).cloned()
+    }
+
+    /// Start watching a service that is being restarted to update it to `updated`, if the
+    /// service has a rollback window. The package the service is currently running is kept
+    /// installed until the window closes so the update can be rolled back.
+    pub fn watch_update(&mut self, service: &Service, updated: &PackageIdent) {
+        if let Some(window) = service.rollback_window() {
+            debug!("'{}' watching update to '{}' for {}s",
+                   service.service_group,
+                   updated,
+                   window.as_secs());
+            self.watches.insert(service.service_group.clone(),
+                                UpdateWatch { previous: service.pkg.ident.as_ref().clone(),
+                                              updated:  updated.clone(),
+                                              deadline: Instant::now() + window, });
+        }
+    }
+
+    /// Whether an update of this service is still within its rollback window.
+    pub fn is_watching(&self, service_group: &ServiceGroup) -> bool {
+        self.watches.contains_key(service_group)
+    }
+
+    /// Check on a watched update of this service. Returns `None` if the service has no update in
+    /// its rollback window.
+    pub fn check_update(&mut self, service: &Service, restart_count: u64) -> Option<UpdateVerdict> {
+        let service_group = &service.service_group;
+        let verdict = self.watches
+                          .get(service_group)?
+                          .verdict(service.health_check_result(), restart_count, Instant::now());
+        match &verdict {
+            UpdateVerdict::Pending => {}
+            UpdateVerdict::Confirmed | UpdateVerdict::RollBack { .. } => {
+                self.watches.remove(service_group);
+            }
+        }
+        Some(verdict)
+    }
+
     fn at_once_worker(&mut self, service: &Service) -> impl Future<Output = ()> + Send + 'static {
         debug!("'{}' service updater spawning at-once worker watching for changes to '{}' from \
                 channel '{}'",
@@ -129,7 +407,10 @@ impl ServiceUpdater {
         let service_group = service.service_group.clone();
         let full_ident = service.pkg.ident.clone();
         let updates = Arc::clone(&self.updates);
-        let package_update_worker = PackageUpdateWorker::new(service, self.period);
+        let package_update_worker = PackageUpdateWorker::new(service,
+                                                             self.period,
+                                                             service.rejected_release().cloned(),
+                                                             Arc::clone(&self.checks));
         async move {
             let new_ident = package_update_worker.update().await;
             debug!("'{}' at-once updater found update from '{}' to '{}'",
@@ -151,8 +432,12 @@ impl ServiceUpdater {
         let service_group = service.service_group.clone();
         let full_ident = service.pkg.ident.clone();
         let updates = Arc::clone(&self.updates);
-        let worker =
-            RollingUpdateWorker::new(service, census_ring, self.butterfly.clone(), self.period);
+        let worker = RollingUpdateWorker::new(service,
+                                              census_ring,
+                                              self.butterfly.clone(),
+                                              self.period,
+                                              service.rejected_release().cloned(),
+                                              Arc::clone(&self.checks));
         async move {
             let new_ident = worker.run().await;
             debug!("'{}' rolling updater found update from '{}' to '{}'",
@@ -162,6 +447,17 @@ impl ServiceUpdater {
         }
     }
 
+    fn notify_worker(&mut self, service: &Service) -> impl Future<Output = ()> + Send + 'static {
+        debug!("'{}' service updater spawning notify-only worker watching for changes to '{}' \
+                from channel '{}'",
+               service.service_group,
+               service.spec_ident(),
+               service.channel());
+        let package_update_worker =
+            PackageUpdateWorker::new(service, self.period, None, Arc::clone(&self.checks));
+        async move { package_update_worker.notify().await }
+    }
+
     fn update_message(new_ident: &IncarnatedPackageIdent, current_ident: &PackageIdent) {
         match &new_ident.ident.cmp(current_ident) {
             Ordering::Greater => outputln!("Updating from {} to {}", current_ident, new_ident),
@@ -183,3 +479,82 @@ impl ServiceUpdater {
         tokio::spawn(worker);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn watch() -> UpdateWatch {
+        UpdateWatch { previous: "core/redis/4.0.14/20190319155852".parse().unwrap(),
+                      updated:  "core/redis/5.0.7/20200101000000".parse().unwrap(),
+                      deadline: Instant::now() + Duration::from_secs(60), }
+    }
+
+    #[test]
+    fn update_watch_is_pending_while_healthy_within_the_window() {
+        let watch = watch();
+        assert_eq!(watch.verdict(HealthCheckResult::Ok, 0, Instant::now()),
+                   UpdateVerdict::Pending);
+        assert_eq!(watch.verdict(HealthCheckResult::Warning,
+                                 CRASH_LOOP_RESTARTS - 1,
+                                 Instant::now()),
+                   UpdateVerdict::Pending);
+    }
+
+    #[test]
+    fn update_watch_is_confirmed_when_the_window_closes() {
+        let watch = watch();
+        assert_eq!(watch.verdict(HealthCheckResult::Unknown, 0, watch.deadline),
+                   UpdateVerdict::Confirmed);
+    }
+
+    #[test]
+    fn update_watch_rolls_back_a_critical_service() {
+        let watch = watch();
+        assert_eq!(watch.verdict(HealthCheckResult::Critical, 0, Instant::now()),
+                   UpdateVerdict::RollBack { previous: watch.previous.clone(),
+                                             failed:   watch.updated.clone(),
+                                             reason:   RollbackReason::HealthCheckCritical, });
+    }
+
+    #[test]
+    fn update_watch_rolls_back_a_crash_looping_service() {
+        let watch = watch();
+        assert_eq!(watch.verdict(HealthCheckResult::Ok, CRASH_LOOP_RESTARTS, Instant::now()),
+                   UpdateVerdict::RollBack { previous: watch.previous.clone(),
+                                             failed:   watch.updated.clone(),
+                                             reason:   RollbackReason::CrashLoop, });
+    }
+
+    #[test]
+    fn pre_update_holds_back_an_update_while_its_hook_runs() {
+        let update: PackageIdent = "core/redis/5.0.7/20200101000000".parse().unwrap();
+        let running = PreUpdate::Running(update.clone());
+        assert_eq!(running.allows(&update, Duration::from_secs(60), Instant::now()),
+                   Some(false));
+        let approved = PreUpdate::Approved(update.clone());
+        assert_eq!(approved.allows(&update, Duration::from_secs(60), Instant::now()),
+                   Some(true));
+    }
+
+    #[test]
+    fn pre_update_retries_a_vetoed_update_after_the_update_period() {
+        let update: PackageIdent = "core/redis/5.0.7/20200101000000".parse().unwrap();
+        let vetoed_at = Instant::now();
+        let vetoed = PreUpdate::Vetoed(update.clone(), vetoed_at);
+        assert_eq!(vetoed.allows(&update, Duration::from_secs(60), vetoed_at),
+                   Some(false));
+        assert_eq!(vetoed.allows(&update,
+                                 Duration::from_secs(60),
+                                 vetoed_at + Duration::from_secs(60)),
+                   None);
+    }
+
+    #[test]
+    fn pre_update_runs_again_for_a_newer_update() {
+        let approved = PreUpdate::Approved("core/redis/5.0.7/20200101000000".parse().unwrap());
+        let newer: PackageIdent = "core/redis/5.0.8/20200201000000".parse().unwrap();
+        assert_eq!(approved.allows(&newer, Duration::from_secs(60), Instant::now()),
+                   None);
+    }
+}
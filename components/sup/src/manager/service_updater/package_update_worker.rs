@@ -1,10 +1,16 @@
-use super::IncarnatedPackageIdent;
-use crate::{manager::service::Service,
+use super::{IncarnatedPackageIdent,
+            UpdateCheck,
+            UpdateChecks,
+            LOGKEY};
+use crate::{error::Result,
+            manager::service::Service,
             util};
+use biome_common::outputln;
 use biome_core::{self,
                    package::{FullyQualifiedPackageIdent,
                              Identifiable,
-                             PackageIdent},
+                             PackageIdent,
+                             PackageInstall},
                    service::ServiceGroup,
                    ChannelIdent};
 use biome_sup_protocol::types::UpdateCondition;
@@ -13,7 +19,8 @@ use log::{debug,
           warn};
 use rand::Rng;
 use std::{self,
-          time::Duration};
+          time::{Duration,
+                 SystemTime}};
 use tokio::{self,
             time};
 
@@ -63,17 +70,27 @@ pub struct PackageUpdateWorker {
     channel:          ChannelIdent,
     builder_url:      String,
     period:           Duration,
+    /// A release that was rolled back and is never updated to from the channel
+    rejected:         Option<PackageIdent>,
+    /// Where the outcome of each check for a newer release is recorded
+    checks:           UpdateChecks,
 }
 
 impl PackageUpdateWorker {
-    pub fn new(service: &Service, period: Duration) -> Self {
+    pub fn new(service: &Service,
+               period: Duration,
+               rejected: Option<PackageIdent>,
+               checks: UpdateChecks)
+               -> Self {
         Self { service_group: service.service_group.clone(),
                ident: service.spec_ident(),
                full_ident: service.pkg.ident.clone(),
                update_condition: service.update_condition(),
                channel: service.channel(),
                builder_url: service.bldr_url(),
-               period }
+               period,
+               rejected,
+               checks }
     }
 }
 
@@ -97,21 +114,20 @@ impl PackageUpdateWorker {
             // determines the new head.
             let package_result = if ident.ident.fully_qualified() {
                 util::pkg::install_no_ui(&self.builder_url, &install_source, &self.channel).await
+                                                                                         .map(Some)
             } else {
-                match self.update_condition {
-                    UpdateCondition::Latest => {
-                        let install_source = ident.ident.clone().into();
-                        util::pkg::install_no_ui(&self.builder_url, &install_source, &self.channel).await
-                    }
-                    UpdateCondition::TrackChannel => {
-                        util::pkg::install_channel_head(&self.builder_url,
-                                                        &ident.ident,
-                                                        &self.channel).await
-                    }
-                }
+                self.install_unless_rejected(&ident.ident).await
             };
             match package_result {
-                Ok(package) => {
+                Ok(None) => {
+                    trace!("'{}' package update worker ignoring the rolled back release of '{}' \
+                            at the head of channel '{}'",
+                           self.service_group,
+                           ident.ident,
+                           self.channel);
+                }
+                Ok(Some(package)) => {
+                    self.record_check(&package.ident);
                     // while this is likely a very slim edge case, if the fully qualified ident
                     // happens to be the same as the current service, go ahead and break out of
                     // the loop otherwise we will remain here forever and ever
@@ -147,6 +163,24 @@ impl PackageUpdateWorker {
         }
     }
 
+    /// Install the release `ident` resolves to in the service's channel, unless the head of the
+    /// channel is the rejected release. The head is checked before anything is installed, so
+    /// that a rolled back release never reaches the disk again while it is still in the channel.
+    async fn install_unless_rejected(&self,
+                                     ident: &PackageIdent)
+                                     -> Result<Option<PackageInstall>> {
+        let head = util::pkg::channel_head(&self.builder_url, ident, &self.channel).await?;
+        if self.rejected.as_ref() == Some(&head) {
+            return Ok(None);
+        }
+        let install_source = match self.update_condition {
+            UpdateCondition::Latest => ident.clone().into(),
+            UpdateCondition::TrackChannel => head.into(),
+        };
+        util::pkg::install_no_ui(&self.builder_url, &install_source, &self.channel).await
+                                                                                   .map(Some)
+    }
+
     /// Use the service spec's package ident to search for packages.
     /// This function is called by the at-once updater and by a rolling
     /// update leader. Delay for PackageUpdateWorkerPeriod before performing
@@ -164,4 +198,60 @@ impl PackageUpdateWorker {
         self.update_to(IncarnatedPackageIdent::new(ident, None))
             .await
     }
+
+    /// Check the head of the service's channel for a newer release on every period without
+    /// installing it, honoring the service's update condition. This is used by the notify update
+    /// strategy and never resolves.
+    pub async fn notify(&self) {
+        let period = PackageUpdateWorkerPeriod::get().unwrap_or(self.period);
+        let splay = Duration::from_secs(rand::thread_rng().gen_range(0..period.as_secs()));
+        debug!("Starting notify-only package update worker for {} in {}s",
+               self.ident,
+               splay.as_secs());
+        time::sleep(splay).await;
+        let mut notified = None;
+        loop {
+            match util::pkg::channel_head(&self.builder_url, &self.ident, &self.channel).await {
+                Ok(head) => {
+                    let latest = self.release_to_update_to(head);
+                    self.record_check(&latest);
+                    if &latest != self.full_ident.as_ref() && notified.as_ref() != Some(&latest) {
+                        outputln!("Update from {} to {} available for {} in channel '{}'",
+                                  self.full_ident,
+                                  latest,
+                                  self.service_group,
+                                  self.channel);
+                        notified = Some(latest);
+                    }
+                }
+                Err(err) => {
+                    warn!("'{}' package update worker failed to check '{}' in channel '{}', err: \
+                           {}",
+                          self.service_group, self.ident, self.channel, err)
+                }
+            }
+            trace!("Notify-only package update worker for {} delaying for {}s",
+                   self.ident,
+                   period.as_secs());
+            time::sleep(period).await;
+        }
+    }
+
+    /// The release the service would be updated to given the head of its channel. A service
+    /// updating to the latest release is never moved to a release older than the one it runs,
+    /// while a service tracking its channel follows the head wherever it goes.
+    fn release_to_update_to(&self, head: PackageIdent) -> PackageIdent {
+        match self.update_condition {
+            UpdateCondition::Latest if &head < self.full_ident.as_ref() => {
+                self.full_ident.as_ref().clone()
+            }
+            UpdateCondition::Latest | UpdateCondition::TrackChannel => head,
+        }
+    }
+
+    fn record_check(&self, latest: &PackageIdent) {
+        self.checks.lock().insert(self.service_group.clone(),
+                                  UpdateCheck { latest:     latest.clone(),
+                                                checked_at: SystemTime::now(), });
+    }
 }
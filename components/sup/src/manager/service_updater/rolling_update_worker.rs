@@ -1,11 +1,16 @@
 use super::{package_update_worker::PackageUpdateWorker,
-            IncarnatedPackageIdent};
+            rollout::{Rollout,
+                      RolloutPlan,
+                      Turn},
+            IncarnatedPackageIdent,
+            UpdateChecks};
 use crate::{census::{CensusGroup,
                      CensusRing},
             manager::service::{Service,
                                Topology}};
 use biome_common::owning_refs::RwLockReadGuardRef;
-use biome_core::service::ServiceGroup;
+use biome_core::{package::PackageIdent,
+                   service::ServiceGroup};
 use log::{debug,
           error,
           trace,
@@ -13,7 +18,8 @@ use log::{debug,
 use parking_lot::RwLock;
 use std::{self,
           sync::Arc,
-          time::Duration};
+          time::{Duration,
+                 Instant}};
 use tokio::{self,
             time};
 
@@ -54,25 +60,33 @@ enum FollowerUpdateTurnEvent {
 ///
 /// The basic behavior of the update is to elect an update leader. The leader waits for an update.
 /// When an update is detected, the leader is updated and each follower takes a turn to update.
+/// Canary and batched updates let followers take their turn in waves instead (see `rollout`).
 pub struct RollingUpdateWorker {
     service_group:         ServiceGroup,
     topology:              Topology,
     package_update_worker: PackageUpdateWorker,
     census_ring:           Arc<RwLock<CensusRing>>,
     butterfly:             biome_butterfly::Server,
+    plan:                  Option<RolloutPlan>,
+    /// How long the canaries must keep an update before it is approved without an operator
+    canary_window:         Option<Duration>,
 }
 
 impl RollingUpdateWorker {
     pub fn new(service: &Service,
                census_ring: Arc<RwLock<CensusRing>>,
                butterfly: biome_butterfly::Server,
-               period: Duration)
+               period: Duration,
+               rejected: Option<PackageIdent>,
+               checks: UpdateChecks)
                -> Self {
         Self { service_group: service.service_group.clone(),
                topology: service.topology(),
-               package_update_worker: PackageUpdateWorker::new(service, period),
+               package_update_worker: PackageUpdateWorker::new(service, period, rejected, checks),
                census_ring,
-               butterfly }
+               butterfly,
+               plan: RolloutPlan::for_service(service),
+               canary_window: service.rollback_window() }
     }
 
     pub async fn run(self) -> IncarnatedPackageIdent {
@@ -90,7 +104,11 @@ impl RollingUpdateWorker {
             Role::Follower => {
                 // Wait till it is our turn to update. It is possible that while we are waiting
                 // the leader dies and we are promoted to update leader.
-                match self.follower_wait_for_update_turn().await {
+                let event = match self.plan {
+                    Some(plan) => self.follower_wait_for_wave_turn(&plan).await,
+                    None => self.follower_wait_for_update_turn().await,
+                };
+                match event {
                     FollowerUpdateTurnEvent::PromotedToLeader => {
                         // Wait for an update which will trigger follower updates through the
                         // census protocol
@@ -294,12 +312,11 @@ impl RollingUpdateWorker {
                                    leader.member_id,
                                    leader.pkg_incarnation,
                                    census_group.pkg_incarnation);
-                        } else if peer.pkg_incarnation == leader.pkg_incarnation {
-                            // It is now this followers turn. The previous peer is done updating.
-                            // The first time this condition is true the previous peer is the
-                            // rolling update leader making this condition trivially true. This
-                            // will trigger all the followers to start their updates one after
-                            // another.
+                        } else if peer.pkg_incarnation == leader.pkg_incarnation && peer.ready() {
+                            // It is now this followers turn. The previous peer is done updating
+                            // and ready to serve again. The first time this condition is true the
+                            // previous peer is the rolling update leader. This will trigger all
+                            // the followers to start their updates one after another.
                             debug!("'{}' is in a rolling update and it is this followers turn to \
                                     update: leader='{}/{}/{}' peer='{}/{}/{}' follower='{}/{}/{}'",
                                    self.service_group,
@@ -316,7 +333,7 @@ impl RollingUpdateWorker {
                             Some(leader.pkg_incarnation)));
                         } else {
                             // It is not this followers turn to update. The previous peer has not
-                            // updated yet.
+                            // updated or is not ready yet.
                             debug!("'{}' is in a rolling update but it is not this followers \
                                     turn to update: leader='{}/{}/{}' peer='{}/{}/{}' \
                                     follower='{}/{}/{}'",
@@ -344,6 +361,95 @@ impl RollingUpdateWorker {
         }
     }
 
+    /// Wait for the wave of this follower in a canary or batched update. The canaries of a canary
+    /// update are approved through the rollout controls or, if the service has a rollback window,
+    /// once all of them have kept the update for that long.
+    async fn follower_wait_for_wave_turn(&self, plan: &RolloutPlan) -> FollowerUpdateTurnEvent {
+        let mut update_to = match self.follower_wait_for_update_start().await {
+            FollowerUpdateStartEvent::PromotedToLeader => {
+                return FollowerUpdateTurnEvent::PromotedToLeader
+            }
+            FollowerUpdateStartEvent::UpdateTo(ident) => ident,
+        };
+        // The rollout and the time its canaries were first seen updated
+        let mut canaries_updated_since: Option<(u64, Instant)> = None;
+        let mut last_wait = None;
+        loop {
+            {
+                let census_group = self.census_group().await;
+                match (census_group.me(), Rollout::for_census_group(&census_group)) {
+                    (Some(me), Some(rollout)) => {
+                        if census_group.update_leader()
+                                       .map_or(false, |leader| leader.member_id == me.member_id)
+                        {
+                            debug!("'{}' {} update follower was promoted to the leader mid \
+                                    update. Immediately updating to '{}'.",
+                                   self.service_group, plan.strategy, update_to.ident);
+                            break FollowerUpdateTurnEvent::PromotedToLeaderMidUpdate(update_to);
+                        }
+                        if rollout.incarnation() > me.pkg_incarnation {
+                            // Follow the leader should it start a newer rollout
+                            update_to = IncarnatedPackageIdent::new(rollout.package().clone(),
+                                                                    Some(rollout.incarnation()));
+                        }
+
+                        let canaries_approved = match self.canary_window {
+                            Some(window) if rollout.canaries_updated(plan) => {
+                                match canaries_updated_since {
+                                    Some((incarnation, since))
+                                        if incarnation == rollout.incarnation() =>
+                                    {
+                                        since.elapsed() >= window
+                                    }
+                                    _ => {
+                                        canaries_updated_since =
+                                            Some((rollout.incarnation(), Instant::now()));
+                                        false
+                                    }
+                                }
+                            }
+                            _ => {
+                                canaries_updated_since = None;
+                                false
+                            }
+                        };
+
+                        match rollout.turn(plan, &me.member_id, canaries_approved) {
+                            Some(Turn::Update) => {
+                                debug!("'{}' is in a {} update and it is this followers turn to \
+                                        update to '{}'",
+                                       self.service_group, plan.strategy, update_to);
+                                break FollowerUpdateTurnEvent::UpdateTo(update_to);
+                            }
+                            Some(Turn::Wait(reason)) => {
+                                if last_wait != Some(reason) {
+                                    debug!("'{}' is in a {} update but it is not this followers \
+                                            turn to update because {}",
+                                           self.service_group, plan.strategy, reason);
+                                    last_wait = Some(reason);
+                                }
+                            }
+                            None => {
+                                trace!("'{}' follower '{}' has no part in the {} update to '{}'",
+                                       self.service_group,
+                                       me.member_id,
+                                       plan.strategy,
+                                       rollout.package());
+                            }
+                        }
+                    }
+                    _ => {
+                        error!("The census group for '{}' is in a bad state. It could not \
+                                determine the update leader or its own identity.",
+                               self.service_group);
+                        debug_assert!(false);
+                    }
+                }
+            }
+            time::sleep(DELAY).await;
+        }
+    }
+
     /// Returns a reference to the services census group. The reference is behind a read write lock
     /// so the lifetime of the reference should be minimized to avoid uneccesarily holding the lock.
     async fn census_group(&self) -> RwLockReadGuardRef<'_, CensusRing, CensusGroup> {
@@ -0,0 +1,540 @@
+//! Planning for canary and batched rolling updates.
+//!
+//! Like a rolling update, the update leader updates first and its package incarnation marks the
+//! rollout. The leader and its followers, ordered by member id, are then split into waves. A
+//! member updates once every member of the earlier waves has updated. The first wave of a canary
+//! update holds the canaries and the rest of the service group waits for them to be approved.
+
+use crate::{census::{CensusGroup,
+                     CensusMember,
+                     CensusRing},
+            manager::service::{Service,
+                               UpdateStrategy}};
+use biome_butterfly::rumor::service::RolloutControl;
+use biome_core::{package::PackageIdent,
+                   service::UpdateBatchSize};
+use serde::Serialize;
+use std::fmt;
+
+/// How a canary or batched rolling update makes its way through a service group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RolloutPlan {
+    pub strategy:        UpdateStrategy,
+    pub canary_count:    u32,
+    pub batch_size:      UpdateBatchSize,
+    pub max_unavailable: Option<u32>,
+}
+
+impl RolloutPlan {
+    /// The plan for a service, or `None` if its update strategy does not update in waves.
+    pub fn for_service(service: &Service) -> Option<Self> {
+        match service.update_strategy() {
+            strategy @ (UpdateStrategy::Canary | UpdateStrategy::Batched) => {
+                Some(RolloutPlan { strategy,
+                                   canary_count: service.canary_count(),
+                                   batch_size: service.update_batch_size(),
+                                   max_unavailable: service.max_unavailable() })
+            }
+            _ => None,
+        }
+    }
+
+    fn is_canary(&self) -> bool { self.strategy == UpdateStrategy::Canary }
+
+    /// The number of members in the first wave, which always includes the update leader.
+    fn first_wave_size(&self, group_size: usize) -> usize {
+        if self.is_canary() {
+            (self.canary_count as usize).max(1)
+        } else {
+            self.batch_size.members(group_size)
+        }
+    }
+}
+
+/// Why a member is not updating yet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitReason {
+    Paused,
+    Aborted,
+    RolledBack,
+    AwaitingApproval,
+    PreviousWave,
+    MaxUnavailable,
+}
+
+impl fmt::Display for WaitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            WaitReason::Paused => "the rollout is paused",
+            WaitReason::Aborted => "the rollout was aborted",
+            WaitReason::RolledBack => "a member rolled the update back",
+            WaitReason::AwaitingApproval => "the canaries are awaiting approval",
+            WaitReason::PreviousWave => "an earlier wave is still updating",
+            WaitReason::MaxUnavailable => "too many members are unavailable",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// Whether it is a member's turn to update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+    Update,
+    Wait(WaitReason),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RolloutState {
+    Updating,
+    AwaitingApproval,
+    Paused,
+    Aborted,
+    /// A member rolled the update back, so no further members update until the rollout is
+    /// aborted or the update leader starts a new one.
+    Halted,
+}
+
+impl RolloutState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RolloutState::Updating => "updating",
+            RolloutState::AwaitingApproval => "awaiting-approval",
+            RolloutState::Paused => "paused",
+            RolloutState::Aborted => "aborted",
+            RolloutState::Halted => "halted",
+        }
+    }
+}
+
+/// Progress of a rollout in progress, as reported by the HTTP gateway and `bio svc status`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RolloutProgress {
+    pub strategy: UpdateStrategy,
+    pub package:  PackageIdent,
+    /// The current wave, starting at 1
+    pub wave:     usize,
+    pub waves:    usize,
+    pub updated:  usize,
+    pub members:  usize,
+    pub state:    RolloutState,
+}
+
+impl RolloutProgress {
+    /// The progress of the canary or batched update of a service, or `None` if the service group
+    /// is not in the middle of one.
+    pub fn for_service(service: &Service, census_ring: &CensusRing) -> Option<Self> {
+        let plan = RolloutPlan::for_service(service)?;
+        census_ring.census_group_for(&service.service_group)
+                   .and_then(Rollout::for_census_group)
+                   .and_then(|rollout| rollout.progress(&plan))
+    }
+}
+
+impl From<&RolloutProgress> for biome_sup_protocol::types::UpdateRollout {
+    fn from(progress: &RolloutProgress) -> Self {
+        Self { strategy: progress.strategy.into(),
+               ident:    progress.package.clone().into(),
+               wave:     progress.wave as u32,
+               waves:    progress.waves as u32,
+               updated:  progress.updated as u32,
+               members:  progress.members as u32,
+               state:    progress.state.as_str().to_string(), }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RolloutMember {
+    member_id:   String,
+    updated:     bool,
+    rolled_back: bool,
+    ready:       bool,
+    suspect:     bool,
+}
+
+impl RolloutMember {
+    /// Whether the member updated and is ready to serve again.
+    fn done(&self) -> bool { self.updated && self.ready }
+}
+
+/// A snapshot of the rollout the update leader of a service group is driving.
+#[derive(Clone, Debug)]
+pub struct Rollout {
+    package:     PackageIdent,
+    incarnation: u64,
+    /// The update leader followed by its followers ordered by member id
+    members:     Vec<RolloutMember>,
+    control:     RolloutControl,
+}
+
+impl Rollout {
+    /// The rollout of a service group, or `None` if the update leader is not yet known.
+    pub fn for_census_group(census_group: &CensusGroup) -> Option<Self> {
+        census_group.update_leader().map(|leader| {
+                                        Self::new(leader,
+                                                  census_group.active_members(),
+                                                  census_group.rollout_control())
+                                    })
+    }
+
+    fn new<'a>(leader: &CensusMember,
+               members: impl Iterator<Item = &'a CensusMember>,
+               control: Option<RolloutControl>)
+               -> Self {
+        let package = leader.pkg.clone();
+        let incarnation = leader.pkg_incarnation;
+        let mut followers: Vec<_> =
+            members.filter(|cm| cm.member_id != leader.member_id)
+                   .map(|cm| {
+                       // A member that rolled the update back keeps the incarnation of the
+                       // rollout but not its package.
+                       let reached = cm.pkg_incarnation >= incarnation;
+                       RolloutMember { member_id:   cm.member_id.clone(),
+                                       updated:     reached && cm.pkg == package,
+                                       rolled_back: reached && cm.pkg != package,
+                                       ready:       cm.ready(),
+                                       suspect:     cm.suspect(), }
+                   })
+                   .collect();
+        followers.sort_by(|a, b| a.member_id.cmp(&b.member_id));
+        let mut members = vec![RolloutMember { member_id:   leader.member_id.clone(),
+                                               updated:     true,
+                                               rolled_back: false,
+                                               ready:       leader.ready(),
+                                               suspect:     leader.suspect(), }];
+        members.extend(followers);
+        Rollout { package,
+                  incarnation,
+                  members,
+                  control: control.unwrap_or_default() }
+    }
+
+    /// The package every member of the service group is updating to.
+    pub fn package(&self) -> &PackageIdent { &self.package }
+
+    /// The package incarnation of the update leader which identifies the rollout.
+    pub fn incarnation(&self) -> u64 { self.incarnation }
+
+    fn waves(&self, plan: &RolloutPlan) -> Vec<&[RolloutMember]> {
+        let group_size = self.members.len();
+        let first = plan.first_wave_size(group_size).min(group_size);
+        let (first, rest) = self.members.split_at(first);
+        let mut waves = vec![first];
+        waves.extend(rest.chunks(plan.batch_size.members(group_size)));
+        waves
+    }
+
+    /// The index of the first wave that has members left to update or that are not ready yet.
+    fn current_wave(waves: &[&[RolloutMember]]) -> Option<usize> {
+        waves.iter().position(|wave| wave.iter().any(|m| !m.done()))
+    }
+
+    /// Whether the canaries of a canary update were approved. Once any member past the canaries
+    /// has updated the rollout has been approved, however that came about.
+    fn approved(&self, waves: &[&[RolloutMember]]) -> bool {
+        self.control.approved
+        || waves.iter()
+                .skip(1)
+                .flat_map(|wave| wave.iter())
+                .any(|m| m.updated)
+    }
+
+    /// Whether a member rolled the update back, which halts the rollout.
+    fn halted(&self) -> bool { self.members.iter().any(|m| m.rolled_back) }
+
+    /// Whether every member of the first wave has updated and is ready.
+    pub fn canaries_updated(&self, plan: &RolloutPlan) -> bool {
+        self.waves(plan)[0].iter().all(RolloutMember::done)
+    }
+
+    /// Whether it is the turn of `member_id` to update. `canaries_approved` approves the
+    /// canaries on top of an approval issued through the rollout controls. Returns `None` if the
+    /// member is not part of the rollout or has already updated.
+    pub fn turn(&self,
+                plan: &RolloutPlan,
+                member_id: &str,
+                canaries_approved: bool)
+                -> Option<Turn> {
+        let waves = self.waves(plan);
+        let wave = waves.iter()
+                        .position(|wave| wave.iter().any(|m| m.member_id == member_id))?;
+        let position = waves[wave].iter().position(|m| m.member_id == member_id)?;
+        if waves[wave][position].updated {
+            return None;
+        }
+        let reason = if self.control.aborted {
+            WaitReason::Aborted
+        } else if self.halted() {
+            WaitReason::RolledBack
+        } else if self.control.paused {
+            WaitReason::Paused
+        } else if Self::current_wave(&waves).map_or(false, |current| current < wave) {
+            WaitReason::PreviousWave
+        } else if plan.is_canary() && wave > 0 && !(canaries_approved || self.approved(&waves)) {
+            WaitReason::AwaitingApproval
+        } else if let Some(max_unavailable) = plan.max_unavailable {
+            // Suspect members and members that are not ready are unavailable, as are the members
+            // ahead of this one in its wave that have not finished updating.
+            let unavailable = self.members
+                                  .iter()
+                                  .filter(|m| (m.suspect || !m.ready) && m.member_id != member_id)
+                                  .count();
+            let updating = waves[wave][..position].iter()
+                                                  .filter(|m| !m.updated && !m.suspect && m.ready)
+                                                  .count();
+            if unavailable + updating >= max_unavailable.max(1) as usize {
+                WaitReason::MaxUnavailable
+            } else {
+                return Some(Turn::Update);
+            }
+        } else {
+            return Some(Turn::Update);
+        };
+        Some(Turn::Wait(reason))
+    }
+
+    /// The progress of the rollout, or `None` if every member has updated.
+    pub fn progress(&self, plan: &RolloutPlan) -> Option<RolloutProgress> {
+        let waves = self.waves(plan);
+        let wave = Self::current_wave(&waves)?;
+        let state = if self.control.aborted {
+            RolloutState::Aborted
+        } else if self.halted() {
+            RolloutState::Halted
+        } else if self.control.paused {
+            RolloutState::Paused
+        } else if plan.is_canary() && wave > 0 && !self.approved(&waves) {
+            RolloutState::AwaitingApproval
+        } else {
+            RolloutState::Updating
+        };
+        Some(RolloutProgress { strategy: plan.strategy,
+                               package: self.package.clone(),
+                               wave: wave + 1,
+                               waves: waves.len(),
+                               updated: self.members.iter().filter(|m| m.updated).count(),
+                               members: self.members.len(),
+                               state })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const OLD: &str = "core/redis/4.0.14/20190319155852";
+    const NEW: &str = "core/redis/5.0.7/20200101000000";
+
+    fn member(id: &str, pkg: &str, pkg_incarnation: u64) -> CensusMember {
+        CensusMember { member_id: id.to_string(),
+                       pkg: pkg.parse().unwrap(),
+                       pkg_incarnation,
+                       alive: true,
+                       ready: true,
+                       ..Default::default() }
+    }
+
+    /// A service group of a leader that updated to `NEW` and the followers `a` to `f`, of which
+    /// the first `updated` have updated too.
+    fn rollout(updated: usize, control: Option<RolloutControl>) -> Rollout {
+        let leader = member("leader", NEW, 1);
+        let mut members = vec![leader.clone()];
+        for (i, id) in ["a", "b", "c", "d", "e", "f"].iter().enumerate() {
+            if i < updated {
+                members.push(member(id, NEW, 1));
+            } else {
+                members.push(member(id, OLD, 0));
+            }
+        }
+        Rollout::new(&leader, members.iter(), control)
+    }
+
+    fn plan(strategy: UpdateStrategy) -> RolloutPlan {
+        RolloutPlan { strategy,
+                      canary_count: 2,
+                      batch_size: UpdateBatchSize::Count(2),
+                      max_unavailable: None }
+    }
+
+    fn turns(rollout: &Rollout, plan: &RolloutPlan, approved: bool) -> Vec<Option<Turn>> {
+        ["a", "b", "c", "d", "e", "f"].iter()
+                                      .map(|id| rollout.turn(plan, id, approved))
+                                      .collect()
+    }
+
+    #[test]
+    fn canary_waves_start_with_the_canaries() {
+        let canary = plan(UpdateStrategy::Canary);
+        let sizes: Vec<_> = rollout(0, None).waves(&canary)
+                                            .iter()
+                                            .map(|wave| wave.len())
+                                            .collect();
+        assert_eq!(sizes, vec![2, 2, 2, 1]);
+
+        let batched = RolloutPlan { batch_size: UpdateBatchSize::Percent(50),
+                                    ..plan(UpdateStrategy::Batched) };
+        let sizes: Vec<_> = rollout(0, None).waves(&batched)
+                                            .iter()
+                                            .map(|wave| wave.len())
+                                            .collect();
+        assert_eq!(sizes, vec![4, 3]);
+    }
+
+    #[test]
+    fn canaries_update_with_the_leader() {
+        let plan = plan(UpdateStrategy::Canary);
+        let rollout = rollout(0, None);
+        let wait = Some(Turn::Wait(WaitReason::PreviousWave));
+        assert_eq!(turns(&rollout, &plan, false),
+                   vec![Some(Turn::Update), wait, wait, wait, wait, wait]);
+        assert!(!rollout.canaries_updated(&plan));
+        assert_eq!(rollout.progress(&plan).unwrap().state,
+                   RolloutState::Updating);
+    }
+
+    #[test]
+    fn rollout_waits_for_the_canaries_to_be_approved() {
+        let plan = plan(UpdateStrategy::Canary);
+        let rollout = rollout(1, None);
+        assert!(rollout.canaries_updated(&plan));
+        assert_eq!(rollout.turn(&plan, "b", false),
+                   Some(Turn::Wait(WaitReason::AwaitingApproval)));
+        assert_eq!(rollout.turn(&plan, "b", true), Some(Turn::Update));
+
+        let progress = rollout.progress(&plan).unwrap();
+        assert_eq!(progress.state, RolloutState::AwaitingApproval);
+        assert_eq!((progress.wave, progress.waves), (2, 4));
+        assert_eq!((progress.updated, progress.members), (2, 7));
+
+        let approved = RolloutControl { rollout: 1,
+                                        approved: true,
+                                        ..Default::default() };
+        let rollout = Rollout { control: approved,
+                                ..rollout };
+        assert_eq!(rollout.turn(&plan, "b", false), Some(Turn::Update));
+        assert_eq!(rollout.turn(&plan, "c", false), Some(Turn::Update));
+        assert_eq!(rollout.turn(&plan, "d", false),
+                   Some(Turn::Wait(WaitReason::PreviousWave)));
+    }
+
+    #[test]
+    fn an_updated_member_past_the_canaries_approves_them() {
+        let plan = plan(UpdateStrategy::Canary);
+        let rollout = rollout(2, None);
+        assert_eq!(rollout.turn(&plan, "c", false), Some(Turn::Update));
+        assert_eq!(rollout.turn(&plan, "b", false), None);
+    }
+
+    #[test]
+    fn batched_rollout_does_not_wait_for_approval() {
+        let plan = plan(UpdateStrategy::Batched);
+        let rollout = rollout(1, None);
+        assert_eq!(rollout.turn(&plan, "b", false), Some(Turn::Update));
+        assert_eq!(rollout.turn(&plan, "c", false), Some(Turn::Update));
+        assert_eq!(rollout.turn(&plan, "d", false),
+                   Some(Turn::Wait(WaitReason::PreviousWave)));
+    }
+
+    #[test]
+    fn max_unavailable_limits_updates_within_a_wave() {
+        let plan = RolloutPlan { batch_size: UpdateBatchSize::Count(3),
+                                 max_unavailable: Some(1),
+                                 ..plan(UpdateStrategy::Batched) };
+        let rollout = rollout(2, None);
+        assert_eq!(rollout.turn(&plan, "c", false), Some(Turn::Update));
+        assert_eq!(rollout.turn(&plan, "d", false),
+                   Some(Turn::Wait(WaitReason::MaxUnavailable)));
+
+        let mut leader = member("leader", NEW, 1);
+        leader.alive = false;
+        leader.suspect = true;
+        let members = vec![leader.clone(), member("a", OLD, 0)];
+        let rollout = Rollout::new(&leader, members.iter(), None);
+        assert_eq!(rollout.turn(&plan, "a", false),
+                   Some(Turn::Wait(WaitReason::MaxUnavailable)));
+    }
+
+    #[test]
+    fn members_that_are_not_ready_hold_back_the_rollout() {
+        let plan = plan(UpdateStrategy::Batched);
+        let leader = member("leader", NEW, 1);
+        let mut starting = member("a", NEW, 1);
+        starting.ready = false;
+        let mut members = vec![leader.clone(),
+                               starting,
+                               member("b", OLD, 0),
+                               member("c", OLD, 0)];
+        let rollout = Rollout::new(&leader, members.iter(), None);
+        assert!(!rollout.canaries_updated(&plan));
+        assert_eq!(rollout.turn(&plan, "b", false),
+                   Some(Turn::Wait(WaitReason::PreviousWave)));
+
+        members[1].ready = true;
+        let rollout = Rollout::new(&leader, members.iter(), None);
+        assert_eq!(rollout.turn(&plan, "b", false), Some(Turn::Update));
+
+        members[1].ready = false;
+        let plan = RolloutPlan { batch_size: UpdateBatchSize::Count(3),
+                                 max_unavailable: Some(1),
+                                 ..plan };
+        let rollout = Rollout::new(&leader, members.iter(), None);
+        assert_eq!(rollout.turn(&plan, "b", false),
+                   Some(Turn::Wait(WaitReason::MaxUnavailable)));
+    }
+
+    #[test]
+    fn paused_and_aborted_rollouts_wait() {
+        let plan = plan(UpdateStrategy::Batched);
+        let paused = RolloutControl { rollout: 1,
+                                      paused: true,
+                                      ..Default::default() };
+        let rollout = rollout(1, Some(paused));
+        assert_eq!(rollout.turn(&plan, "b", false),
+                   Some(Turn::Wait(WaitReason::Paused)));
+        assert_eq!(rollout.progress(&plan).unwrap().state, RolloutState::Paused);
+
+        let aborted = RolloutControl { aborted: true,
+                                       ..paused };
+        let rollout = Rollout { control: aborted,
+                                ..rollout };
+        assert_eq!(rollout.turn(&plan, "b", false),
+                   Some(Turn::Wait(WaitReason::Aborted)));
+        assert_eq!(rollout.progress(&plan).unwrap().state,
+                   RolloutState::Aborted);
+    }
+
+    #[test]
+    fn rolled_back_members_halt_the_rollout() {
+        let plan = plan(UpdateStrategy::Canary);
+        let leader = member("leader", NEW, 1);
+        let members = vec![leader.clone(), member("a", OLD, 1), member("b", OLD, 0)];
+        let rollout = Rollout::new(&leader, members.iter(), None);
+        assert_eq!(rollout.turn(&plan, "a", true),
+                   Some(Turn::Wait(WaitReason::RolledBack)));
+        assert_eq!(rollout.turn(&plan, "b", true),
+                   Some(Turn::Wait(WaitReason::RolledBack)));
+        assert_eq!(rollout.progress(&plan).unwrap().state, RolloutState::Halted);
+
+        let aborted = RolloutControl { rollout: 1,
+                                       aborted: true,
+                                       ..Default::default() };
+        let rollout = Rollout::new(&leader, members.iter(), Some(aborted));
+        assert_eq!(rollout.progress(&plan).unwrap().state,
+                   RolloutState::Aborted);
+
+        // A newer rollout of the update leader starts over
+        let leader = member("leader", NEW, 2);
+        let rollout = Rollout::new(&leader, members.iter(), None);
+        assert_eq!(rollout.turn(&plan, "a", true), Some(Turn::Update));
+        assert_eq!(rollout.progress(&plan).unwrap().state,
+                   RolloutState::Updating);
+    }
+
+    #[test]
+    fn finished_rollouts_have_no_progress() {
+        let plan = plan(UpdateStrategy::Batched);
+        assert!(rollout(6, None).progress(&plan).is_none());
+        assert_eq!(rollout(6, None).turn(&plan, "f", false), None);
+        assert_eq!(rollout(0, None).turn(&plan, "unknown", false), None);
+    }
+}
@@ -7,7 +7,8 @@ use bio::{command::pkg::{self,
                                      UninstallHookMode,
                                      UninstallSafety}},
           error::Result as BioResult};
-use biome_api_client::BuilderAPIClient;
+use biome_api_client::{self as api_client,
+                         PackageSource};
 use biome_common::{cli_config::CliConfig,
                      command::package::install::{self as install_cmd,
                                                  InstallHookMode,
@@ -63,6 +64,26 @@ pub async fn install<T>(ui: &mut T,
                                                .map_err(Error::from)
 }
 
+/// Helper function for use in the Supervisor to install packages from a signed bundle without
+/// contacting Builder.
+pub async fn install_from_bundle<T>(ui: &mut T,
+                                    bundle_path: &Path,
+                                    idents: &[PackageIdent])
+                                    -> Result<Vec<PackageInstall>>
+    where T: UIWriter
+{
+    let fs_root_path = Path::new(&*FS_ROOT_PATH);
+    install_cmd::start_from_bundle(ui,
+                                   bundle_path,
+                                   idents,
+                                   PRODUCT,
+                                   VERSION,
+                                   fs_root_path,
+                                   &fs::cache_artifact_path(None::<String>),
+                                   InstallHookMode::Ignore).await
+                                                           .map_err(Error::from)
+}
+
 // `install` but with no ui output and the benefit of thread safety
 pub async fn install_no_ui(url: &str,
                            install_source: &InstallSource,
@@ -113,17 +134,25 @@ pub async fn install_channel_head(url: &str,
                                   ident: impl AsRef<PackageIdent>,
                                   channel: &ChannelIdent)
                                   -> Result<PackageInstall> {
+    let channel_latest_ident = channel_head(url, ident, channel).await?;
+    // Ensure the latest package from the channel is installed
+    install_no_ui(url, &channel_latest_ident.into(), channel).await
+}
+
+/// Returns the identifier of the package at the head of a channel without installing it.
+pub async fn channel_head(url: &str,
+                          ident: impl AsRef<PackageIdent>,
+                          channel: &ChannelIdent)
+                          -> Result<PackageIdent> {
     let fs_root_path = Path::new(&*FS_ROOT_PATH);
     let auth_token = get_auth_token();
-    let api_client = BuilderAPIClient::new(url, PRODUCT, VERSION, Some(fs_root_path))?;
-    // Get the latest package identifier from the channel
+    let api_client = api_client::package_source(url, PRODUCT, VERSION, Some(fs_root_path))?;
     let channel_latest_ident = api_client.show_package((ident.as_ref(),
                                                         PackageTarget::active_target()),
                                                        channel,
                                                        auth_token.as_deref())
                                          .await?;
-    // Ensure the latest package from the channel is installed
-    install_no_ui(url, &channel_latest_ident.into(), channel).await
+    Ok(channel_latest_ident)
 }
 
 pub async fn uninstall_all_but_latest(ident: impl AsRef<PackageIdent>,